@@ -1,8 +1,17 @@
 -- MMO Game Microtransaction Schema
 -- Compatible with both Node.js and Rust versions
+--
+-- Idempotent: safe to apply to databases where this file was run by hand
+-- before embedded migrations tracked versions.
 
 -- Create custom enum type for transaction status
-CREATE TYPE transaction_status AS ENUM ('pending', 'completed', 'failed', 'refunded');
+DO $$
+BEGIN
+    CREATE TYPE transaction_status AS ENUM ('pending', 'completed', 'failed', 'refunded');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
 
 -- Main transactions table
 CREATE TABLE IF NOT EXISTS microtransactions (
@@ -36,10 +45,10 @@ CREATE TABLE IF NOT EXISTS microtransactions (
 );
 
 -- Indexes for common query patterns
-CREATE INDEX idx_microtx_player_id ON microtransactions(player_id);
-CREATE INDEX idx_microtx_player_created ON microtransactions(player_id, created_at DESC);
-CREATE INDEX idx_microtx_status ON microtransactions(status) WHERE status = 'pending';
-CREATE INDEX idx_microtx_processor_id ON microtransactions(processor_id) WHERE processor_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_microtx_player_id ON microtransactions(player_id);
+CREATE INDEX IF NOT EXISTS idx_microtx_player_created ON microtransactions(player_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_microtx_status ON microtransactions(status) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_microtx_processor_id ON microtransactions(processor_id) WHERE processor_id IS NOT NULL;
 
 -- Trigger for updated_at
 CREATE OR REPLACE FUNCTION update_updated_at_column()
@@ -50,6 +59,7 @@ BEGIN
 END;
 $$ language 'plpgsql';
 
+DROP TRIGGER IF EXISTS update_microtransactions_updated_at ON microtransactions;
 CREATE TRIGGER update_microtransactions_updated_at
     BEFORE UPDATE ON microtransactions
     FOR EACH ROW
@@ -0,0 +1,9 @@
+-- Correlation ID of the API request that created the transaction
+--
+-- Support tickets quote the ID the game client shows; this column finds
+-- the row without searching logs.
+
+ALTER TABLE microtransactions ADD COLUMN IF NOT EXISTS request_id VARCHAR(128);
+
+CREATE INDEX IF NOT EXISTS idx_microtx_request_id ON microtransactions(request_id)
+    WHERE request_id IS NOT NULL;
@@ -0,0 +1,70 @@
+-- Virtual currency wallets
+--
+-- Every balance change is one row in wallet_ledger; rows are never updated
+-- or deleted. A player's balance is the balance_after of their latest entry
+-- in that currency, and always equals SUM(amount) - see wallet_balances.
+
+DO $$
+BEGIN
+    CREATE TYPE virtual_currency AS ENUM ('gems', 'gold');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+DO $$
+BEGIN
+    CREATE TYPE ledger_entry_kind AS ENUM ('top_up', 'spend');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+CREATE TABLE IF NOT EXISTS wallet_ledger (
+    entry_id UUID PRIMARY KEY,
+    player_id UUID NOT NULL,
+    currency virtual_currency NOT NULL,
+    
+    -- Gapless per player and currency; the unique constraint rejects a
+    -- second writer that read the same previous entry
+    sequence BIGINT NOT NULL CHECK (sequence > 0),
+    
+    amount BIGINT NOT NULL CHECK (amount <> 0),
+    balance_after BIGINT NOT NULL CHECK (balance_after >= 0),
+    
+    kind ledger_entry_kind NOT NULL,
+    -- Transaction ID for top-ups, client idempotency key for spends
+    reference VARCHAR(255) NOT NULL,
+    item_id VARCHAR(255),
+    request_id VARCHAR(128),
+    
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    
+    UNIQUE (player_id, currency, sequence),
+    UNIQUE (player_id, kind, reference)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_ledger_player_created ON wallet_ledger(player_id, created_at DESC);
+
+-- Append-only: corrections are new entries, never edits
+CREATE OR REPLACE FUNCTION reject_ledger_mutation()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION '% is append-only', TG_TABLE_NAME;
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS wallet_ledger_append_only ON wallet_ledger;
+CREATE TRIGGER wallet_ledger_append_only
+    BEFORE UPDATE OR DELETE ON wallet_ledger
+    FOR EACH ROW
+    EXECUTE FUNCTION reject_ledger_mutation();
+
+-- Balances recomputed from the ledger, for reconciliation
+CREATE OR REPLACE VIEW wallet_balances AS
+    SELECT player_id, currency, SUM(amount) AS balance
+    FROM wallet_ledger
+    GROUP BY player_id, currency;
+
+COMMENT ON TABLE wallet_ledger IS 'Append-only virtual currency ledger';
+COMMENT ON COLUMN wallet_ledger.balance_after IS 'Balance after this entry - equals the running SUM(amount)';
@@ -0,0 +1,118 @@
+-- Double-entry journal
+--
+-- Every status change that moves money, and every wallet ledger entry, is
+-- recorded as one journal entry whose postings sum to zero per currency.
+-- Postings are signed: debits positive, credits negative.
+
+-- Not usable until this migration commits, and nothing here uses it
+ALTER TYPE transaction_status ADD VALUE IF NOT EXISTS 'charged_back';
+
+DO $$
+BEGIN
+    CREATE TYPE account_type AS ENUM ('asset', 'liability', 'revenue', 'expense');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+DO $$
+BEGIN
+    CREATE TYPE journal_entry_kind AS ENUM (
+        'purchase', 'refund', 'chargeback', 'wallet_top_up', 'wallet_spend'
+    );
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+CREATE TABLE IF NOT EXISTS ledger_accounts (
+    account_code VARCHAR(255) PRIMARY KEY,
+    account_type account_type NOT NULL,
+    -- ISO 4217 code for money, virtual currency name otherwise
+    currency VARCHAR(16) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS journal_entries (
+    journal_id UUID PRIMARY KEY,
+    kind journal_entry_kind NOT NULL,
+    -- Transaction ID for purchases, refunds and chargebacks; wallet ledger
+    -- entry ID for wallet movements
+    reference VARCHAR(255) NOT NULL,
+    player_id UUID,
+    request_id VARCHAR(128),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- One entry per business event, however often the write is retried
+    UNIQUE (kind, reference)
+);
+
+CREATE INDEX IF NOT EXISTS idx_journal_player_created ON journal_entries(player_id, created_at DESC)
+    WHERE player_id IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS journal_postings (
+    posting_id BIGSERIAL PRIMARY KEY,
+    journal_id UUID NOT NULL REFERENCES journal_entries(journal_id),
+    account_code VARCHAR(255) NOT NULL REFERENCES ledger_accounts(account_code),
+    currency VARCHAR(16) NOT NULL,
+    amount BIGINT NOT NULL CHECK (amount <> 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_journal_postings_journal ON journal_postings(journal_id);
+CREATE INDEX IF NOT EXISTS idx_journal_postings_account ON journal_postings(account_code);
+
+-- Checked at commit, once every posting of the entry is in
+CREATE OR REPLACE FUNCTION check_journal_balanced()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF (SELECT COUNT(*) FROM journal_postings WHERE journal_id = NEW.journal_id) < 2 THEN
+        RAISE EXCEPTION 'journal entry % needs at least two postings', NEW.journal_id;
+    END IF;
+
+    IF EXISTS (
+        SELECT 1 FROM journal_postings
+        WHERE journal_id = NEW.journal_id
+        GROUP BY currency
+        HAVING SUM(amount) <> 0
+    ) THEN
+        RAISE EXCEPTION 'journal entry % is unbalanced', NEW.journal_id;
+    END IF;
+
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS journal_postings_balanced ON journal_postings;
+CREATE CONSTRAINT TRIGGER journal_postings_balanced
+    AFTER INSERT ON journal_postings
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW
+    EXECUTE FUNCTION check_journal_balanced();
+
+-- Append-only, like wallet_ledger: corrections are reversing entries
+DROP TRIGGER IF EXISTS journal_entries_append_only ON journal_entries;
+CREATE TRIGGER journal_entries_append_only
+    BEFORE UPDATE OR DELETE ON journal_entries
+    FOR EACH ROW
+    EXECUTE FUNCTION reject_ledger_mutation();
+
+DROP TRIGGER IF EXISTS journal_postings_append_only ON journal_postings;
+CREATE TRIGGER journal_postings_append_only
+    BEFORE UPDATE OR DELETE ON journal_postings
+    FOR EACH ROW
+    EXECUTE FUNCTION reject_ledger_mutation();
+
+-- Debit and credit totals per account; debits equal credits per currency
+CREATE OR REPLACE VIEW trial_balance AS
+    SELECT
+        p.account_code,
+        a.account_type,
+        p.currency,
+        COALESCE(SUM(p.amount) FILTER (WHERE p.amount > 0), 0)::BIGINT AS debits,
+        COALESCE(-SUM(p.amount) FILTER (WHERE p.amount < 0), 0)::BIGINT AS credits
+    FROM journal_postings p
+    JOIN ledger_accounts a USING (account_code)
+    GROUP BY p.account_code, a.account_type, p.currency;
+
+COMMENT ON TABLE journal_entries IS 'Append-only double-entry journal';
+COMMENT ON COLUMN journal_postings.amount IS 'Debit positive, credit negative';
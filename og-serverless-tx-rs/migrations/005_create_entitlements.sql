@@ -0,0 +1,43 @@
+-- Entitlements granted by completed purchases
+--
+-- Written in the same database transaction as the status change that
+-- causes it: granted when a purchase completes, revoked when it is
+-- refunded or charged back. Revoked rows are kept for reconciliation.
+
+DO $$
+BEGIN
+    CREATE TYPE entitlement_kind AS ENUM ('consumable', 'durable');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+DO $$
+BEGIN
+    CREATE TYPE entitlement_status AS ENUM ('active', 'revoked');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+CREATE TABLE IF NOT EXISTS entitlements (
+    entitlement_id UUID PRIMARY KEY,
+    player_id UUID NOT NULL,
+    item_id VARCHAR(255) NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    kind entitlement_kind NOT NULL,
+    status entitlement_status NOT NULL DEFAULT 'active',
+
+    -- A purchase grants at most once, however often completion is retried
+    source_transaction_id UUID NOT NULL UNIQUE REFERENCES microtransactions(transaction_id),
+
+    granted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ,
+
+    CHECK ((status = 'revoked') = (revoked_at IS NOT NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_entitlements_player_item ON entitlements(player_id, item_id)
+    WHERE status = 'active';
+
+COMMENT ON TABLE entitlements IS 'Items owned by players, one row per granting purchase';
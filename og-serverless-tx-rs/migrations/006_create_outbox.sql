@@ -0,0 +1,41 @@
+-- Transactional outbox for purchase events
+--
+-- Rows are written in the same database transaction as the status change
+-- they announce, so an event exists exactly when the change committed.
+-- The dispatcher claims due rows, publishes them and records the outcome;
+-- delivery is at-least-once and consumers deduplicate on event_id.
+
+DO $$
+BEGIN
+    CREATE TYPE outbox_status AS ENUM ('pending', 'delivered', 'dead');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+CREATE TABLE IF NOT EXISTS outbox_events (
+    event_id UUID PRIMARY KEY,
+    event_type VARCHAR(64) NOT NULL,
+    aggregate_id UUID NOT NULL REFERENCES microtransactions(transaction_id),
+    payload JSONB NOT NULL,
+
+    status outbox_status NOT NULL DEFAULT 'pending',
+    -- Claims so far; a claim also leases the row until next_attempt_at
+    attempts INTEGER NOT NULL DEFAULT 0 CHECK (attempts >= 0),
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ,
+
+    CHECK ((status = 'delivered') = (delivered_at IS NOT NULL))
+);
+
+-- Dispatcher scan: due pending rows, oldest first
+CREATE INDEX IF NOT EXISTS idx_outbox_events_due ON outbox_events(next_attempt_at, created_at)
+    WHERE status = 'pending';
+
+CREATE INDEX IF NOT EXISTS idx_outbox_events_dead ON outbox_events(created_at)
+    WHERE status = 'dead';
+
+COMMENT ON TABLE outbox_events IS 'Purchase events awaiting publication, written with the change they announce';
@@ -0,0 +1,58 @@
+-- Per-title webhook subscriptions and their deliveries
+--
+-- A purchase whose metadata names a title_id fans out one delivery per
+-- matching subscription, written in the same database transaction as the
+-- status change. Deliveries double as the delivery log: each keeps its
+-- attempt count and the outcome of the last attempt.
+
+DO $$
+BEGIN
+    CREATE TYPE webhook_delivery_status AS ENUM ('pending', 'delivered', 'dead');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+CREATE TABLE IF NOT EXISTS webhook_subscriptions (
+    subscription_id UUID PRIMARY KEY,
+    title_id VARCHAR(255) NOT NULL,
+    url TEXT NOT NULL,
+    -- HMAC-SHA256 signing key, shown to the subscriber once at creation
+    secret TEXT NOT NULL,
+    -- JSON array of event type names, e.g. ["purchase.completed"]
+    event_types JSONB NOT NULL CHECK (jsonb_typeof(event_types) = 'array'),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_subscriptions_title ON webhook_subscriptions(title_id);
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    delivery_id UUID PRIMARY KEY,
+    subscription_id UUID NOT NULL REFERENCES webhook_subscriptions(subscription_id) ON DELETE CASCADE,
+    event_id UUID NOT NULL REFERENCES outbox_events(event_id),
+    event_type VARCHAR(64) NOT NULL,
+    payload JSONB NOT NULL,
+
+    status webhook_delivery_status NOT NULL DEFAULT 'pending',
+    -- Claims so far; a claim also leases the row until next_attempt_at
+    attempts INTEGER NOT NULL DEFAULT 0 CHECK (attempts >= 0),
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_status_code INTEGER,
+    last_error TEXT,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ,
+
+    -- An event reaches each subscription once; redelivery reuses the row
+    UNIQUE (subscription_id, event_id),
+    CHECK ((status = 'delivered') = (delivered_at IS NOT NULL))
+);
+
+-- Dispatcher scan: due pending rows, oldest first
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(next_attempt_at, created_at)
+    WHERE status = 'pending';
+
+-- Delivery log: a subscription's deliveries, newest first
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_subscription ON webhook_deliveries(subscription_id, created_at DESC);
+
+COMMENT ON TABLE webhook_deliveries IS 'Signed purchase event deliveries to title webhooks, with retry state';
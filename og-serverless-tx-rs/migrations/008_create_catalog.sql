@@ -0,0 +1,43 @@
+-- Catalog items: kind and limited stock
+--
+-- Purchases of catalogued items lock the item's row while they are
+-- checked and inserted, so concurrent Lambdas cannot both take the last
+-- unit or both sell a player the same non-consumable.
+
+DO $$
+BEGIN
+    CREATE TYPE item_kind AS ENUM ('consumable', 'non_consumable', 'subscription');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+CREATE TABLE IF NOT EXISTS catalog_items (
+    item_id VARCHAR(255) PRIMARY KEY,
+    kind item_kind NOT NULL,
+
+    -- NULL means unlimited
+    stock_limit INTEGER CHECK (stock_limit >= 0),
+    stock_reserved INTEGER NOT NULL DEFAULT 0 CHECK (stock_reserved >= 0),
+    stock_sold INTEGER NOT NULL DEFAULT 0 CHECK (stock_sold >= 0),
+
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CHECK (stock_limit IS NULL OR stock_reserved + stock_sold <= stock_limit)
+);
+
+-- Units held by pending purchases; deleted when the purchase settles
+CREATE TABLE IF NOT EXISTS stock_reservations (
+    transaction_id UUID PRIMARY KEY REFERENCES microtransactions(transaction_id),
+    item_id VARCHAR(255) NOT NULL REFERENCES catalog_items(item_id),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Duplicate-ownership check: does the player have this item in flight?
+CREATE INDEX IF NOT EXISTS idx_microtransactions_pending_player_item
+    ON microtransactions(player_id, item_id)
+    WHERE status = 'pending';
+
+COMMENT ON TABLE catalog_items IS 'Item kinds and stock counters; uncatalogued items are unrestricted';
+COMMENT ON TABLE stock_reservations IS 'Stock held by pending purchases';
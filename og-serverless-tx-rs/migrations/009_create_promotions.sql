@@ -0,0 +1,49 @@
+-- Promotions: scheduled discounts and bundles applied at pricing time
+--
+-- Purchases copy the promotions they used onto their own row, so editing
+-- or ending a promotion never rewrites what a player was charged.
+
+DO $$
+BEGIN
+    CREATE TYPE promotion_kind AS ENUM ('percent_off', 'amount_off', 'bundle');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+CREATE TABLE IF NOT EXISTS promotions (
+    promotion_id UUID PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    kind promotion_kind NOT NULL,
+    value BIGINT NOT NULL CHECK (value > 0),
+    currency VARCHAR(3),
+
+    -- Empty arrays mean every item / every player
+    item_ids JSONB NOT NULL DEFAULT '[]',
+    segments JSONB NOT NULL DEFAULT '[]',
+    bundle_items JSONB NOT NULL DEFAULT '[]',
+
+    stackable BOOLEAN NOT NULL DEFAULT FALSE,
+    priority INTEGER NOT NULL DEFAULT 0,
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CHECK (ends_at > starts_at),
+    CHECK (kind <> 'percent_off' OR value <= 100),
+    CHECK (kind = 'percent_off' OR currency IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_promotions_window ON promotions(ends_at, starts_at);
+
+-- What the purchase would have cost, and why it did not
+ALTER TABLE microtransactions
+    ADD COLUMN IF NOT EXISTS original_price_cents BIGINT,
+    ADD COLUMN IF NOT EXISTS applied_promotions JSONB NOT NULL DEFAULT '[]';
+
+-- Bundles grant several items from one purchase
+ALTER TABLE entitlements DROP CONSTRAINT IF EXISTS entitlements_source_transaction_id_key;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_entitlements_source_item
+    ON entitlements(source_transaction_id, item_id);
+
+COMMENT ON TABLE promotions IS 'Live-ops discounts and bundles, matched by time window, item and player segment';
@@ -0,0 +1,53 @@
+-- Promo and gift codes
+--
+-- Every redemption is a microtransactions row - zero-amount for grant codes,
+-- discounted for purchase codes - linked from code_redemptions. Uses are
+-- counted from redemptions whose transaction did not fail.
+
+CREATE TABLE IF NOT EXISTS code_campaigns (
+    campaign_id UUID PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    reward JSONB NOT NULL,
+
+    -- NULL means any number of redemptions per code
+    max_uses INTEGER CHECK (max_uses > 0),
+    per_player_limit INTEGER NOT NULL DEFAULT 1 CHECK (per_player_limit > 0),
+    expires_at TIMESTAMPTZ,
+    code_count INTEGER NOT NULL CHECK (code_count > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Normalized: 16 Crockford base32 characters, the last a check character
+CREATE TABLE IF NOT EXISTS redeem_codes (
+    code CHAR(16) PRIMARY KEY,
+    campaign_id UUID NOT NULL REFERENCES code_campaigns(campaign_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_redeem_codes_campaign ON redeem_codes(campaign_id);
+
+CREATE TABLE IF NOT EXISTS code_redemptions (
+    redemption_id UUID PRIMARY KEY,
+    code CHAR(16) NOT NULL REFERENCES redeem_codes(code),
+    player_id UUID NOT NULL,
+    transaction_id UUID NOT NULL UNIQUE REFERENCES microtransactions(transaction_id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_code_redemptions_code ON code_redemptions(code, player_id);
+
+-- Failed redemption attempts, counted per player and per client address
+CREATE TABLE IF NOT EXISTS code_failures (
+    subject VARCHAR(255) NOT NULL,
+    failed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_code_failures_subject ON code_failures(subject, failed_at);
+
+-- Grant redemptions cost nothing
+ALTER TABLE microtransactions DROP CONSTRAINT IF EXISTS microtransactions_price_cents_check;
+ALTER TABLE microtransactions ADD CONSTRAINT microtransactions_price_cents_check
+    CHECK (price_cents >= 0 AND price_cents <= 99999999);
+
+ALTER TYPE ledger_entry_kind ADD VALUE IF NOT EXISTS 'code_grant';
+
+COMMENT ON TABLE code_redemptions IS 'One row per code use, linked to the transaction that recorded it';
@@ -0,0 +1,31 @@
+-- Player-to-player gifts
+--
+-- player_id stays the payer; recipient_id is set only on gifts, and owns
+-- the items. gift_status tracks the recipient's answer - items are granted
+-- on acceptance, and a declined gift is refunded.
+
+DO $$
+BEGIN
+    CREATE TYPE gift_status AS ENUM ('pending', 'accepted', 'declined');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+ALTER TABLE microtransactions
+    ADD COLUMN IF NOT EXISTS recipient_id UUID,
+    ADD COLUMN IF NOT EXISTS gift_status gift_status;
+
+ALTER TABLE microtransactions DROP CONSTRAINT IF EXISTS microtransactions_gift_check;
+ALTER TABLE microtransactions ADD CONSTRAINT microtransactions_gift_check CHECK (
+    (recipient_id IS NULL AND gift_status IS NULL)
+    OR (recipient_id IS NOT NULL AND recipient_id <> player_id AND gift_status IS NOT NULL)
+);
+
+-- Received gifts in the recipient's history, and daily gift limits
+CREATE INDEX IF NOT EXISTS idx_microtx_recipient_created
+    ON microtransactions(recipient_id, created_at DESC) WHERE recipient_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_microtx_gifts_sent
+    ON microtransactions(player_id, created_at) WHERE recipient_id IS NOT NULL;
+
+COMMENT ON COLUMN microtransactions.recipient_id IS 'Player a gift was bought for; NULL for purchases for oneself';
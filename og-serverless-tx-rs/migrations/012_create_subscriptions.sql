@@ -0,0 +1,76 @@
+-- Subscription plans, season passes and their subscribers
+--
+-- Every charge is a microtransactions row; its entitlement expires with
+-- the period it paid for. The renewal job picks up live subscriptions
+-- whose next_billing_at has passed. Saves compare and bump version, so
+-- the job and a player's cancel cannot overwrite each other.
+
+DO $$
+BEGIN
+    CREATE TYPE subscription_status AS ENUM ('incomplete', 'active', 'past_due', 'canceled', 'expired');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+CREATE TABLE IF NOT EXISTS subscription_plans (
+    plan_id VARCHAR(255) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    item_id VARCHAR(255) NOT NULL,
+    price_cents BIGINT NOT NULL CHECK (price_cents > 0),
+    currency CHAR(3) NOT NULL,
+
+    -- Recurring plans renew every period_days; season passes end at season_ends_at
+    period_days INTEGER CHECK (period_days > 0),
+    season_ends_at TIMESTAMPTZ,
+    grace_period_days INTEGER NOT NULL DEFAULT 0 CHECK (grace_period_days >= 0),
+
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CHECK ((period_days IS NULL) <> (season_ends_at IS NULL))
+);
+
+CREATE TABLE IF NOT EXISTS subscriptions (
+    subscription_id UUID PRIMARY KEY,
+    player_id UUID NOT NULL,
+    plan_id VARCHAR(255) NOT NULL REFERENCES subscription_plans(plan_id),
+    status subscription_status NOT NULL,
+    payment_method VARCHAR(255) NOT NULL,
+
+    current_period_start TIMESTAMPTZ NOT NULL,
+    current_period_end TIMESTAMPTZ NOT NULL,
+    cancel_at_period_end BOOLEAN NOT NULL DEFAULT FALSE,
+
+    -- Dunning: failed renewals since the last success, and the end of access
+    renewal_attempts INTEGER NOT NULL DEFAULT 0,
+    grace_ends_at TIMESTAMPTZ,
+    next_billing_at TIMESTAMPTZ,
+    credit_cents BIGINT NOT NULL DEFAULT 0 CHECK (credit_cents >= 0),
+
+    entitlement_transaction_id UUID NOT NULL,
+    renewal_transaction_id UUID,
+    ended_at TIMESTAMPTZ,
+
+    version BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CHECK (current_period_end > current_period_start)
+);
+
+-- One live subscription per player and plan
+CREATE UNIQUE INDEX IF NOT EXISTS idx_subscriptions_live_player_plan
+    ON subscriptions(player_id, plan_id) WHERE status IN ('active', 'past_due');
+
+-- Renewal job: live subscriptions by due time
+CREATE INDEX IF NOT EXISTS idx_subscriptions_due
+    ON subscriptions(next_billing_at) WHERE status IN ('active', 'past_due');
+
+CREATE INDEX IF NOT EXISTS idx_subscriptions_player ON subscriptions(player_id, created_at DESC);
+
+-- Subscription periods: access ends at expires_at; NULL is owned for good
+ALTER TABLE entitlements ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+
+COMMENT ON TABLE subscription_plans IS 'Recurring plans and season passes';
+COMMENT ON TABLE subscriptions IS 'Players'' subscriptions with billing and dunning state';
+COMMENT ON COLUMN entitlements.expires_at IS 'End of the subscription period the granting purchase paid for';
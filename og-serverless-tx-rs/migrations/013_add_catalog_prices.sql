@@ -0,0 +1,17 @@
+-- Catalog list prices
+--
+-- Priced items are charged what the catalog says, not what the client
+-- sends. Both columns are set together; unpriced items can only be bought
+-- through the v1 contract.
+
+ALTER TABLE catalog_items ADD COLUMN IF NOT EXISTS price_cents BIGINT CHECK (price_cents > 0);
+ALTER TABLE catalog_items ADD COLUMN IF NOT EXISTS currency VARCHAR(3);
+
+DO $$
+BEGIN
+    ALTER TABLE catalog_items ADD CONSTRAINT catalog_items_price_currency
+        CHECK ((price_cents IS NULL) = (currency IS NULL));
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
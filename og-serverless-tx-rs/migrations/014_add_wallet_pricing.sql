@@ -0,0 +1,50 @@
+-- Wallet pricing and reversals
+--
+-- Currency packs are catalog items that credit the wallet when bought, and
+-- items sold for virtual currency carry their wallet price, so neither a
+-- top-up nor a spend takes an amount from the client.
+--
+-- Refunding or charging back a top-up posts a reversal that takes the
+-- credit back. The money is already gone, so a reversal may leave the
+-- balance negative; only spends must stay covered.
+
+-- Not usable until this migration commits, and nothing here uses them
+ALTER TYPE ledger_entry_kind ADD VALUE IF NOT EXISTS 'reversal';
+ALTER TYPE journal_entry_kind ADD VALUE IF NOT EXISTS 'wallet_reversal';
+
+ALTER TABLE wallet_ledger DROP CONSTRAINT IF EXISTS wallet_ledger_balance_after_check;
+
+DO $$
+BEGIN
+    ALTER TABLE wallet_ledger ADD CONSTRAINT wallet_ledger_spend_covered
+        CHECK (kind <> 'spend' OR balance_after >= 0);
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+ALTER TABLE catalog_items ADD COLUMN IF NOT EXISTS credit_currency virtual_currency;
+ALTER TABLE catalog_items ADD COLUMN IF NOT EXISTS credit_amount BIGINT CHECK (credit_amount > 0);
+ALTER TABLE catalog_items ADD COLUMN IF NOT EXISTS wallet_price BIGINT CHECK (wallet_price > 0);
+ALTER TABLE catalog_items ADD COLUMN IF NOT EXISTS wallet_currency virtual_currency;
+
+DO $$
+BEGIN
+    ALTER TABLE catalog_items ADD CONSTRAINT catalog_items_pack_credit
+        CHECK ((credit_currency IS NULL) = (credit_amount IS NULL)
+            AND (credit_amount IS NULL OR price_cents IS NOT NULL));
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+DO $$
+BEGIN
+    ALTER TABLE catalog_items ADD CONSTRAINT catalog_items_wallet_price
+        CHECK ((wallet_price IS NULL) = (wallet_currency IS NULL));
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+COMMENT ON COLUMN wallet_ledger.balance_after IS 'Balance after this entry - equals the running SUM(amount); negative only after a reversal';
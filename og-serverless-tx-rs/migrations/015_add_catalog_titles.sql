@@ -0,0 +1,7 @@
+-- Game title of a catalog item
+--
+-- Purchases are stamped with their item's title when inserted, and the
+-- title's webhook subscriptions are told about them. Clients can no longer
+-- name a title in purchase metadata.
+
+ALTER TABLE catalog_items ADD COLUMN IF NOT EXISTS title_id VARCHAR(64) CHECK (title_id <> '');
@@ -0,0 +1,12 @@
+-- Player profiles: the segments promotions target
+--
+-- Segments are set by operators, never by the purchase request, so a
+-- client cannot claim a segment to unlock its promotions.
+
+CREATE TABLE IF NOT EXISTS player_profiles (
+    player_id UUID PRIMARY KEY,
+    segments JSONB NOT NULL DEFAULT '[]',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE player_profiles IS 'Operator-assigned player segments, matched against promotion segments';
@@ -0,0 +1,334 @@
+//! # Local Development Server
+//!
+//! Serves the Lambda `Router` over plain HTTP so game client builds can
+//! point at a laptop:
+//!
+//! ```text
+//! cargo run --bin dev-server
+//! curl -X POST localhost:3000/purchase -d @purchase.json
+//! ```
+//!
+//! `DEV_SERVER_ADDR` sets the listen address (default `127.0.0.1:3000`).
+//! Storage is in memory unless `DATABASE_BACKEND` is set, e.g.
+//! `DATABASE_BACKEND=postgres DATABASE_URL=postgresql://localhost/mmog`.
+//! Payments always use the mock strategy. Browser clients need their origin
+//! in `CORS_ALLOWED_ORIGINS`, e.g. `http://localhost:5173`.
+//! Admin routes under `/admin` are called as a local operator, standing in
+//! for the IAM auth API Gateway applies in front of them.
+//!
+//! ADVANTAGE: Same Router, handlers and validation as the deployed Lambda
+//! ADVANTAGE: No AWS account, Stripe key or database needed to get started
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use lambda_http::aws_lambda_events::apigw::ApiGatewayProxyRequestContext;
+use lambda_http::request::RequestContext;
+use lambda_http::{Body, Error, Request, RequestExt, Response};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
+
+use og_serverless_tx_rs::handlers::cors::CorsPolicy;
+use og_serverless_tx_rs::handlers::router::Router;
+use og_serverless_tx_rs::handlers::versioning::ApiVersion;
+use og_serverless_tx_rs::models::config::{CorsConfig, DatabaseConfig, ErrorFormat, Lifecycle};
+use og_serverless_tx_rs::services::{database, PaymentService};
+use og_serverless_tx_rs::strategies::payment::MockPaymentStrategy;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:3000";
+
+/// IAM identity given to `/admin` requests
+const LOCAL_OPERATOR_ARN: &str = "arn:aws:iam::000000000000:user/dev-server";
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| EnvFilter::new("info"))
+        )
+        .with_target(false)
+        .init();
+
+    let addr: SocketAddr = std::env::var("DEV_SERVER_ADDR")
+        .unwrap_or_else(|_| DEFAULT_ADDR.to_string())
+        .parse()
+        .map_err(|_| "DEV_SERVER_ADDR must be a socket address like 127.0.0.1:3000")?;
+
+    // ADVANTAGE: Explicit DATABASE_BACKEND wins, so local Postgres is one env var away
+    let database_config = match std::env::var("DATABASE_BACKEND") {
+        Ok(_) => DatabaseConfig::from_env()?,
+        Err(_) => DatabaseConfig::Memory,
+    };
+
+    let cors = CorsPolicy::new(CorsConfig::from_env()?);
+    let router = build_router(&database_config)
+        .await?
+        .with_cors(cors)
+        .with_error_format(ErrorFormat::from_env()?)
+        .with_lifecycle(ApiVersion::V1, Lifecycle::from_env("API_V1")?);
+    let router = Arc::new(router);
+    let listener = TcpListener::bind(addr).await?;
+
+    info!(addr = %listener.local_addr()?, "Dev server listening");
+    serve(listener, router).await
+}
+
+/// Router with mock payments over the configured storage
+async fn build_router(database_config: &DatabaseConfig) -> Result<Router, Error> {
+    let db = database::connect(database_config).await?;
+    let payment_service = Arc::new(PaymentService::new(Arc::new(MockPaymentStrategy::new())));
+    Ok(Router::new(db, payment_service))
+}
+
+/// Accept connections until the listener fails
+async fn serve(listener: TcpListener, router: Arc<Router>) -> Result<(), Error> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let router = Arc::clone(&router);
+
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                let router = Arc::clone(&router);
+                async move { handle(request, &router).await }
+            });
+
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                warn!(error = %e, "Connection closed with error");
+            }
+        });
+    }
+}
+
+/// Translate to a Lambda request, route it, and translate the response back
+async fn handle(
+    request: hyper::Request<Incoming>,
+    router: &Router,
+) -> Result<hyper::Response<Full<Bytes>>, hyper::Error> {
+    let request = into_lambda_request(request).await?;
+    let response = router.route(request).await;
+    Ok(from_lambda_response(response))
+}
+
+/// Buffer the body and attach query parameters the way API Gateway does
+async fn into_lambda_request(request: hyper::Request<Incoming>) -> Result<Request, hyper::Error> {
+    let (parts, body) = request.into_parts();
+    let bytes = body.collect().await?.to_bytes();
+
+    let body = if bytes.is_empty() {
+        Body::Empty
+    } else {
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(text) => Body::Text(text),
+            Err(e) => Body::Binary(e.into_bytes()),
+        }
+    };
+
+    let mut query: HashMap<String, Vec<String>> = HashMap::new();
+    if let Some(raw) = parts.uri.query() {
+        for (key, value) in url::form_urlencoded::parse(raw.as_bytes()) {
+            query.entry(key.into_owned()).or_default().push(value.into_owned());
+        }
+    }
+
+    let admin = parts.uri.path().starts_with("/admin/");
+    let request = Request::from_parts(parts, body).with_query_string_parameters(query);
+    Ok(if admin { as_local_operator(request) } else { request })
+}
+
+/// Attach the identity API Gateway would add after IAM auth
+fn as_local_operator(request: Request) -> Request {
+    let mut context = ApiGatewayProxyRequestContext::default();
+    context.identity.user_arn = Some(LOCAL_OPERATOR_ARN.to_string());
+    request.with_request_context(RequestContext::ApiGatewayV1(context))
+}
+
+fn from_lambda_response(response: Response<Body>) -> hyper::Response<Full<Bytes>> {
+    let (parts, body) = response.into_parts();
+
+    let bytes = match body {
+        Body::Empty => Bytes::new(),
+        Body::Text(text) => Bytes::from(text),
+        Body::Binary(binary) => Bytes::from(binary),
+    };
+
+    hyper::Response::from_parts(parts, Full::new(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn test_purchase_round_trip_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let v1_lifecycle = Lifecycle::parse("2026-11-01T00:00:00Z", "2027-05-01T00:00:00Z").unwrap();
+        let router = build_router(&DatabaseConfig::Memory).await.unwrap().with_lifecycle(ApiVersion::V1, Some(v1_lifecycle));
+        let router = Arc::new(router);
+        tokio::spawn(serve(listener, router));
+
+        let client = reqwest::Client::new();
+        let player_id = "550e8400-e29b-41d4-a716-446655440000";
+
+        let purchase = client
+            .post(format!("{}/purchase", base))
+            .header("X-Request-Id", "ticket-123")
+            .json(&json!({
+                "player_id": player_id,
+                "item_id": "sword_legendary_001",
+                "item_name": "Death",
+                "price_cents": 1999,
+                "currency": "USD",
+                "quantity": 1
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(purchase.status(), 201);
+        assert_eq!(purchase.headers()["x-request-id"], "ticket-123");
+        assert!(purchase.headers().contains_key("deprecation"));
+
+        let listing = json!({"kind": "consumable", "price_cents": 499, "currency": "USD"});
+        let public = client.put(format!("{}/v2/catalog/items/gem_pack", base)).json(&listing).send().await.unwrap();
+        assert_eq!(public.status(), 405);
+        let priced = client.put(format!("{}/admin/v2/catalog/items/gem_pack", base)).json(&listing).send().await.unwrap();
+        assert_eq!(priced.status(), 200);
+
+        let purchase_v2: Value = client
+            .post(format!("{}/v2/purchase", base))
+            .json(&json!({
+                "player_id": player_id,
+                "item_id": "gem_pack",
+                "item_name": "Gem Pack",
+                "quantity": 2
+            }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(purchase_v2["pricing"]["totalCents"], 998);
+        assert_eq!(purchase_v2["payment"]["result"], "approved");
+
+        let unknown: Value = client
+            .post(format!("{}/v9/purchase", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(unknown["code"], "UNSUPPORTED_API_VERSION");
+        assert_eq!(unknown["supportedVersions"], json!(["v1", "v2"]));
+
+        let problem = client
+            .post(format!("{}/v2/purchase", base))
+            .header("Accept", "application/problem+json")
+            .body(r#"{"player_id": 42}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(problem.headers()["content-type"], "application/problem+json");
+        let problem: Value = problem.json().await.unwrap();
+        assert_eq!(problem["status"], 400);
+        assert_eq!(problem["error_code"], "VALIDATION_ERROR");
+        assert_eq!(problem["instance"], "/v2/purchase");
+        assert_eq!(problem["errors"][0]["field"], "player_id");
+        assert!(problem["request_id"].is_string());
+
+        let listed: Value = client
+            .get(format!("{}/transactions/{}?limit=5", base, player_id))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(listed["transactions"].as_array().map(Vec::len), Some(2));
+        assert_eq!(listed["transactions"][1]["request_id"], "ticket-123");
+
+        // A currency pack, and a mount sold for gems
+        for (item_id, listing) in [
+            ("gems_500", json!({"kind": "consumable", "price_cents": 499, "currency": "USD", "credit_currency": "gems", "credit_amount": 500})),
+            ("dragon_mount", json!({"kind": "consumable", "wallet_price": 300, "wallet_currency": "gems"})),
+        ] {
+            let listed = client.put(format!("{}/admin/v2/catalog/items/{}", base, item_id)).json(&listing).send().await.unwrap();
+            assert_eq!(listed.status(), 200);
+        }
+
+        let top_up: Value = client
+            .post(format!("{}/v2/wallet/top-up", base))
+            .json(&json!({"player_id": player_id, "pack_id": "gems_500"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(top_up["status"], "completed");
+        assert_eq!(top_up["balance"], 500);
+
+        let spend = json!({"player_id": player_id, "item_id": "dragon_mount", "idempotency_key": "spend-1"});
+        let spent: Value = client.post(format!("{}/v2/wallet/spend", base)).json(&spend).send().await.unwrap().json().await.unwrap();
+        assert_eq!(spent["balance"], 200);
+        let retried: Value = client.post(format!("{}/v2/wallet/spend", base)).json(&spend).send().await.unwrap().json().await.unwrap();
+        assert_eq!(retried["entryId"], spent["entryId"]);
+
+        let overdraw = client
+            .post(format!("{}/v2/wallet/spend", base))
+            .json(&json!({"player_id": player_id, "item_id": "dragon_mount", "idempotency_key": "spend-2"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(overdraw.status(), 409);
+
+        let wallet: Value = client.get(format!("{}/v2/wallet/{}", base, player_id)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(wallet["balances"], json!([{"currency": "gems", "balance": 200}, {"currency": "gold", "balance": 0}]));
+
+        // Two purchases, the top-up purchase, its credit and one spend
+        let ledger: Value = client.get(format!("{}/v2/players/{}/ledger", base, player_id)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(ledger["count"], 5);
+        assert_eq!(ledger["entries"][0]["kind"], "wallet_spend");
+
+        // Both item purchases and the spend granted; the top-up credited the wallet instead
+        let owned: Value = client.get(format!("{}/v2/players/{}/entitlements", base, player_id)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(owned["count"], 3);
+        assert_eq!(owned["entitlements"][0]["itemId"], "dragon_mount");
+        assert_eq!(owned["entitlements"][1]["itemId"], "gem_pack");
+        assert_eq!(owned["entitlements"][1]["quantity"], 2);
+
+        let missing = client.get(format!("{}/nope", base)).send().await.unwrap();
+        assert_eq!(missing.status(), 404);
+
+        // ADVANTAGE: Endpoints newer than v2 are not added to the deprecated contract
+        let v1_wallet = client.get(format!("{}/v1/wallet/{}", base, player_id)).send().await.unwrap();
+        assert_eq!(v1_wallet.status(), 404);
+
+        let wrong_method = client.post(format!("{}/health", base)).send().await.unwrap();
+        assert_eq!(wrong_method.status(), 405);
+        assert_eq!(wrong_method.headers()["allow"], "GET, OPTIONS");
+
+        let live: serde_json::Value = client.get(format!("{}/health/live", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(live["status"], "healthy");
+        assert!(live["build"]["version"].is_string());
+        assert!(live.get("checks").is_none());
+
+        let ready: serde_json::Value = client.get(format!("{}/v2/health/ready", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(ready["status"], "healthy");
+        assert_eq!(ready["checks"]["migrations"]["status"], "healthy");
+        assert_eq!(ready["checks"]["circuit_breaker"]["status"], "healthy");
+        assert_eq!(ready["database"]["status"], "healthy");
+    }
+}
@@ -8,6 +8,9 @@
 use lambda_http::{Body, Response};
 use thiserror::Error;
 
+use crate::models::config::ErrorFormat;
+use crate::models::response::{ErrorResponse, FieldError, ProblemDetails};
+
 /// Application error type
 /// 
 /// ADVANTAGE: Each variant has specific, typed data
@@ -23,10 +26,18 @@ pub enum AppError {
     #[error("Validation error: {0}")]
     Validation(String),
     
+    /// Validation error with one entry per invalid field
+    #[error("Validation error: {} invalid field(s)", .0.len())]
+    InvalidFields(Vec<FieldError>),
+    
     /// Database error - connection or query failure
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
     
+    /// Aurora Data API error - request rejected or result could not be mapped
+    #[error("Data API error: {0}")]
+    DataApi(String),
+    
     /// Payment processing error
     #[error("Payment error: {0}")]
     Payment(String),
@@ -35,10 +46,50 @@ pub enum AppError {
     #[error("Not found: {0}")]
     NotFound(String),
     
+    /// Route exists but not for this HTTP method
+    #[error("Method not allowed: {0}")]
+    MethodNotAllowed(String),
+    
+    /// Admin endpoint called without an authenticated IAM identity
+    #[error("Unauthenticated: {0}")]
+    Unauthenticated(String),
+    
+    /// State-changing request from an origin outside the CORS allowlist
+    #[error("Origin not allowed: {0}")]
+    OriginNotAllowed(String),
+    
+    /// Path names an API version that is not served
+    #[error("Unsupported API version: {requested}")]
+    UnsupportedApiVersion { requested: String, supported: Vec<String> },
+    
+    /// Wallet balance too low for a debit
+    #[error("Insufficient funds: {0}")]
+    InsufficientFunds(String),
+    
+    /// Limited-stock item has too few units left
+    #[error("Out of stock: {0}")]
+    OutOfStock(String),
+    
+    /// Player already owns, or is buying, a one-per-player item
+    #[error("Already owned: {0}")]
+    AlreadyOwned(String),
+    
+    /// Payer or recipient has reached their daily gifting limit
+    #[error("Gift limit reached: {0}")]
+    GiftLimitReached(String),
+    
+    /// Code has expired or reached a usage limit
+    #[error("Code unavailable: {0}")]
+    CodeUnavailable(String),
+    
     /// Conflict - duplicate transaction, etc.
     #[error("Conflict: {0}")]
     Conflict(String),
     
+    /// Dependency is known to be failing - callers should retry later
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+    
     /// Rate limit exceeded
     #[error("Rate limit exceeded")]
     RateLimited,
@@ -60,11 +111,23 @@ impl AppError {
     pub const fn status_code(&self) -> u16 {
         match self {
             Self::Validation(_) => 400,
+            Self::InvalidFields(_) => 400,
             Self::Configuration(_) => 500,
             Self::Database(_) => 503,
+            Self::DataApi(_) => 503,
             Self::Payment(_) => 402,
             Self::NotFound(_) => 404,
+            Self::MethodNotAllowed(_) => 405,
+            Self::Unauthenticated(_) => 401,
+            Self::OriginNotAllowed(_) => 403,
+            Self::UnsupportedApiVersion { .. } => 404,
+            Self::InsufficientFunds(_) => 409,
+            Self::OutOfStock(_) => 409,
+            Self::AlreadyOwned(_) => 409,
+            Self::GiftLimitReached(_) => 429,
+            Self::CodeUnavailable(_) => 409,
             Self::Conflict(_) => 409,
+            Self::Unavailable(_) => 503,
             Self::RateLimited => 429,
             Self::Internal(_) => 500,
             Self::Json(_) => 400,
@@ -75,33 +138,129 @@ impl AppError {
     pub fn error_code(&self) -> &'static str {
         match self {
             Self::Validation(_) => "VALIDATION_ERROR",
+            Self::InvalidFields(_) => "VALIDATION_ERROR",
             Self::Configuration(_) => "CONFIGURATION_ERROR",
             Self::Database(_) => "DATABASE_ERROR",
+            Self::DataApi(_) => "DATABASE_ERROR",
             Self::Payment(_) => "PAYMENT_ERROR",
             Self::NotFound(_) => "NOT_FOUND",
+            Self::MethodNotAllowed(_) => "METHOD_NOT_ALLOWED",
+            Self::Unauthenticated(_) => "UNAUTHENTICATED",
+            Self::OriginNotAllowed(_) => "ORIGIN_NOT_ALLOWED",
+            Self::UnsupportedApiVersion { .. } => "UNSUPPORTED_API_VERSION",
+            Self::InsufficientFunds(_) => "INSUFFICIENT_FUNDS",
+            Self::OutOfStock(_) => "OUT_OF_STOCK",
+            Self::AlreadyOwned(_) => "ALREADY_OWNED",
+            Self::GiftLimitReached(_) => "GIFT_LIMIT_REACHED",
+            Self::CodeUnavailable(_) => "CODE_UNAVAILABLE",
             Self::Conflict(_) => "CONFLICT",
+            Self::Unavailable(_) => "SERVICE_UNAVAILABLE",
             Self::RateLimited => "RATE_LIMITED",
             Self::Internal(_) => "INTERNAL_ERROR",
             Self::Json(_) => "INVALID_JSON",
         }
     }
     
-    /// Convert error to HTTP response
+    /// Short, occurrence-independent summary (problem `title`)
+    pub const fn title(&self) -> &'static str {
+        match self {
+            Self::Validation(_) | Self::InvalidFields(_) => "Request validation failed",
+            Self::Configuration(_) | Self::Internal(_) => "Internal server error",
+            Self::Database(_) | Self::DataApi(_) | Self::Unavailable(_) => "Service unavailable",
+            Self::Payment(_) => "Payment failed",
+            Self::NotFound(_) => "Resource not found",
+            Self::MethodNotAllowed(_) => "Method not allowed",
+            Self::Unauthenticated(_) => "Authentication required",
+            Self::OriginNotAllowed(_) => "Origin not allowed",
+            Self::UnsupportedApiVersion { .. } => "Unsupported API version",
+            Self::InsufficientFunds(_) => "Insufficient funds",
+            Self::OutOfStock(_) => "Out of stock",
+            Self::AlreadyOwned(_) => "Item already owned",
+            Self::GiftLimitReached(_) => "Gifting limit reached",
+            Self::CodeUnavailable(_) => "Code unavailable",
+            Self::Conflict(_) => "Conflict",
+            Self::RateLimited => "Too many requests",
+            Self::Json(_) => "Malformed JSON body",
+        }
+    }
+    
+    /// Detail that is safe to send to clients
+    /// 
+    /// Database, configuration and internal errors carry SQL, hostnames and
+    /// ARNs in their `Display` output, so they are replaced by a generic
+    /// sentence. Use `Display` for logs.
+    /// 
+    /// ADVANTAGE: Leaking internals is opt-in per variant, not opt-out
+    pub fn public_detail(&self) -> String {
+        match self {
+            Self::Configuration(_) | Self::Internal(_) => {
+                "The server encountered an internal error".to_string()
+            }
+            Self::Database(_) | Self::DataApi(_) => {
+                "Storage is temporarily unavailable, please retry".to_string()
+            }
+            _ => self.to_string(),
+        }
+    }
+    
+    /// Convert error to HTTP response in the original JSON format
     /// 
     /// ADVANTAGE: Error -> Response conversion is guaranteed to succeed
     pub fn into_response(self) -> Response<Body> {
-        use crate::models::response::ErrorResponse;
-        
+        self.render(ErrorFormat::Json, None, None)
+    }
+    
+    /// Convert error to HTTP response in `format`
+    /// 
+    /// `instance` identifies this occurrence, e.g. the request path;
+    /// `request_id` is the correlation ID echoed for support tickets.
+    pub fn render(
+        self,
+        format: ErrorFormat,
+        instance: Option<&str>,
+        request_id: Option<&str>,
+    ) -> Response<Body> {
         let status = self.status_code();
-        let error_response = ErrorResponse::new(self.to_string())
-            .with_code(self.error_code());
+        let code = self.error_code();
+        let title = self.title();
+        let detail = self.public_detail();
+        
+        let (details, supported_versions) = match self {
+            Self::InvalidFields(fields) => (fields, Vec::new()),
+            Self::UnsupportedApiVersion { supported, .. } => (Vec::new(), supported),
+            _ => (Vec::new(), Vec::new()),
+        };
+        
+        let (content_type, body) = match format {
+            ErrorFormat::Json => {
+                let error_response = ErrorResponse::new(detail)
+                    .with_code(code)
+                    .with_details(details)
+                    .with_supported_versions(supported_versions)
+                    .with_request_id(request_id.map(str::to_string));
+                ("application/json", serde_json::to_string(&error_response))
+            }
+            ErrorFormat::Problem => {
+                let problem = ProblemDetails {
+                    problem_type: format!("/problems/{}", code.to_lowercase().replace('_', "-")),
+                    title: title.to_string(),
+                    status,
+                    detail,
+                    instance: instance.map(str::to_string),
+                    error_code: code.to_string(),
+                    errors: details,
+                    supported_versions,
+                    request_id: request_id.map(str::to_string),
+                };
+                ("application/problem+json", serde_json::to_string(&problem))
+            }
+        };
         
-        let body = serde_json::to_string(&error_response)
-            .unwrap_or_else(|_| r#"{"error":"Internal error"}"#.to_string());
+        let body = body.unwrap_or_else(|_| r#"{"error":"Internal error"}"#.to_string());
         
         Response::builder()
             .status(status)
-            .header("Content-Type", "application/json")
+            .header("Content-Type", content_type)
             .body(Body::from(body))
             .unwrap()  // ADVANTAGE: Builder pattern can't fail with valid inputs
     }
@@ -117,18 +276,104 @@ pub type AppResult<T> = Result<T, AppError>;
 // ============================================================================
 
 impl From<validator::ValidationErrors> for AppError {
+    /// One `FieldError` per failed rule, with nested and list paths expanded
     fn from(err: validator::ValidationErrors) -> Self {
-        let messages: Vec<String> = err
-            .field_errors()
-            .iter()
-            .flat_map(|(field, errors)| {
-                errors.iter().map(move |e| {
-                    format!("{}: {}", field, e.message.as_ref().map(|m| m.to_string()).unwrap_or_else(|| "invalid".to_string()))
-                })
-            })
-            .collect();
-        
-        Self::Validation(messages.join(", "))
+        let mut fields = Vec::new();
+        collect_field_errors("", &err, &mut fields);
+        fields.sort_by(|a, b| a.field.cmp(&b.field).then_with(|| a.code.cmp(&b.code)));
+        
+        Self::InvalidFields(fields)
+    }
+}
+
+/// Walk nested validator errors, building `parent.child[index]` paths
+fn collect_field_errors(prefix: &str, errors: &validator::ValidationErrors, out: &mut Vec<FieldError>) {
+    use validator::ValidationErrorsKind;
+    
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            (*field).to_string()
+        } else {
+            format!("{}.{}", prefix, field)
+        };
+        
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                out.extend(errors.iter().map(|e| field_error(&path, e)));
+            }
+            ValidationErrorsKind::Struct(nested) => collect_field_errors(&path, nested, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect_field_errors(&format!("{}[{}]", path, index), nested, out);
+                }
+            }
+        }
+    }
+}
+
+fn field_error(path: &str, error: &validator::ValidationError) -> FieldError {
+    // The rejected value is the client's own input - echoing it adds nothing
+    let params: serde_json::Map<String, serde_json::Value> = error.params
+        .iter()
+        .filter(|(name, _)| *name != "value")
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect();
+    
+    let message = match &error.message {
+        Some(message) => message.to_string(),
+        None => default_message(&error.code, &params),
+    };
+    
+    FieldError {
+        field: path.to_string(),
+        code: error.code.to_string(),
+        message,
+        params,
+    }
+}
+
+/// Readable message for built-in validator codes
+fn default_message(code: &str, params: &serde_json::Map<String, serde_json::Value>) -> String {
+    let bound = |name: &str| params.get(name).map(|v| v.to_string());
+    
+    match (code, bound("min"), bound("max"), bound("equal")) {
+        ("length", _, _, Some(equal)) => format!("must be exactly {} characters", equal),
+        ("length", Some(min), Some(max), _) => format!("must be {} to {} characters", min, max),
+        ("range", Some(min), Some(max), _) => format!("must be between {} and {}", min, max),
+        ("range", Some(min), None, _) => format!("must be at least {}", min),
+        ("range", None, Some(max), _) => format!("must be at most {}", max),
+        _ => "is invalid".to_string(),
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_json::Error>> for AppError {
+    /// Type and missing-field errors become a `FieldError` with the JSON path;
+    /// syntax errors stay `Json`, since no field is at fault
+    fn from(err: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        use serde_json::error::Category;
+        
+        let path = err.path().to_string();
+        let inner = err.into_inner();
+        
+        if !matches!(inner.classify(), Category::Data) {
+            return Self::Json(inner);
+        }
+        
+        // serde_json appends " at line X column Y" - positions mean nothing to clients
+        let full = inner.to_string();
+        let message = full.rsplit_once(" at line ").map_or(full.as_str(), |(m, _)| m);
+        let parent = if path == "." { String::new() } else { path };
+        
+        let field_error = match message
+            .strip_prefix("missing field `")
+            .and_then(|rest| rest.strip_suffix('`'))
+        {
+            Some(missing) if parent.is_empty() => FieldError::new(missing, "required", "is required"),
+            Some(missing) => FieldError::new(format!("{}.{}", parent, missing), "required", "is required"),
+            None => FieldError::new(parent, "invalid_type", message),
+        };
+        
+        Self::InvalidFields(vec![field_error])
     }
 }
 
@@ -150,6 +395,67 @@ mod tests {
         assert_eq!(AppError::RateLimited.status_code(), 429);
     }
 
+    #[test]
+    fn test_field_errors_carry_path_code_and_params() {
+        use validator::Validate;
+        
+        let request: crate::models::PurchaseRequest = serde_json::from_value(serde_json::json!({
+            "player_id": uuid::Uuid::new_v4(),
+            "item_id": "",
+            "item_name": "Sword",
+            "price_cents": 0,
+            "currency": "USD"
+        }))
+        .unwrap();
+        
+        let AppError::InvalidFields(fields) = AppError::from(request.validate().unwrap_err()) else {
+            panic!("expected field errors");
+        };
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].field, "item_id");
+        assert_eq!(fields[0].code, "length");
+        assert_eq!(fields[1].field, "price_cents");
+        assert_eq!(fields[1].params["min"], 1);
+        assert_eq!(fields[1].message, "must be between 1 and 99999999");
+        
+        let json = r#"{"player_id": "not-a-uuid", "item_id": "x"}"#;
+        let err: AppError = serde_path_to_error::deserialize::<_, crate::models::PurchaseRequest>(
+            &mut serde_json::Deserializer::from_str(json),
+        )
+        .unwrap_err()
+        .into();
+        let AppError::InvalidFields(fields) = err else {
+            panic!("expected field errors");
+        };
+        assert_eq!(fields[0].field, "player_id");
+        assert_eq!(fields[0].code, "invalid_type");
+        assert!(!fields[0].message.contains("line"));
+    }
+
+    #[test]
+    fn test_internal_detail_is_never_rendered() {
+        let error = AppError::Database(sqlx::Error::Configuration(
+            "connect to db.internal.example:5432 failed".into(),
+        ));
+        assert!(error.to_string().contains("db.internal.example"));
+        
+        let response = error.render(ErrorFormat::Problem, Some("/purchase"), Some("req-1"));
+        assert_eq!(response.status(), 503);
+        assert_eq!(response.headers()["Content-Type"], "application/problem+json");
+        
+        let Body::Text(body) = response.body() else { panic!("expected text body") };
+        let problem: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(problem["type"], "/problems/database-error");
+        assert_eq!(problem["error_code"], "DATABASE_ERROR");
+        assert_eq!(problem["instance"], "/purchase");
+        assert_eq!(problem["request_id"], "req-1");
+        assert!(!body.contains("db.internal.example"));
+        
+        let response = AppError::Configuration("DATABASE_URL not set".into()).into_response();
+        let Body::Text(body) = response.body() else { panic!("expected text body") };
+        assert!(!body.contains("DATABASE_URL"));
+    }
+
     #[test]
     fn test_error_conversion() {
         // ADVANTAGE: Error conversion is type-checked at compile time
@@ -0,0 +1,142 @@
+//! # Catalog Handlers
+//!
+//! - `PUT /catalog/items/{itemId}` - set an item's kind, stock limit and price
+//! - `GET /catalog/items/{itemId}` - kind and stock counters
+//!
+//! The `PUT` is served only under `/admin/v2`, to IAM-signed operator requests.
+//!
+//! ADVANTAGE: Ownership and stock rules come from the catalog, never from
+//! the purchase request
+
+use lambda_http::{Body, Request, Response};
+use tracing::{info, instrument};
+
+use crate::errors::AppError;
+use crate::metrics::Metrics;
+use crate::models::{CatalogItemRequest, CatalogItemResponse, FieldError};
+use crate::services::Database;
+use super::purchase::parse_body;
+use super::router::json_response;
+
+/// Handle catalog item upsert - stock counters survive a kind or limit change
+#[instrument(skip(request, db, metrics))]
+pub async fn handle_put_catalog_item(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+    item_id: &str,
+) -> Result<Response<Body>, AppError> {
+    check_item_id(item_id)?;
+    let update: CatalogItemRequest = parse_body(&request)?;
+    update.check_price()?;
+
+    let item = metrics
+        .time_db("upsert_catalog_item", db.upsert_catalog_item(item_id, &update))
+        .await?;
+
+    info!(kind = item.kind.as_str(), stock_limit = ?item.stock_limit, "Catalog item saved");
+    Ok(json_response(200, &CatalogItemResponse::from(item)))
+}
+
+/// Handle get catalog item request
+#[instrument(skip(db, metrics))]
+pub async fn handle_get_catalog_item(
+    db: &dyn Database,
+    metrics: &Metrics,
+    item_id: &str,
+) -> Result<Response<Body>, AppError> {
+    check_item_id(item_id)?;
+
+    let item = metrics
+        .time_db("get_catalog_item", db.get_catalog_item(item_id))
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Item {} is not in the catalog", item_id)))?;
+
+    Ok(json_response(200, &CatalogItemResponse::from(item)))
+}
+
+/// Same bound as `PurchaseRequest::item_id`
+fn check_item_id(item_id: &str) -> Result<(), AppError> {
+    if item_id.is_empty() || item_id.len() > 255 {
+        return Err(AppError::InvalidFields(vec![
+            FieldError::new("itemId", "length", "must be 1 to 255 characters"),
+        ]));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use uuid::Uuid;
+    use crate::models::{NewTransaction, TransactionStatus};
+    use crate::services::InMemoryDatabase;
+
+    fn put(body: Value) -> Request {
+        lambda_http::http::Request::builder()
+            .method("PUT")
+            .body(Body::Text(body.to_string()))
+            .unwrap()
+    }
+
+    fn cape(player_id: Uuid) -> NewTransaction {
+        NewTransaction::new(player_id, "founders_cape".into(), "Founder's Cape".into(), 1999, "USD".into(), 1, json!({}))
+    }
+
+    #[tokio::test]
+    async fn test_limited_non_consumable_drop() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let unpriced = handle_put_catalog_item(put(json!({"kind": "non_consumable", "price_cents": 1999})), &db, &metrics, "founders_cape").await;
+        assert!(matches!(unpriced, Err(AppError::Validation(_))));
+        handle_put_catalog_item(
+            put(json!({"kind": "non_consumable", "stock_limit": 2, "price_cents": 1999, "currency": "USD"})),
+            &db,
+            &metrics,
+            "founders_cape",
+        )
+        .await
+        .unwrap();
+
+        // A second purchase while the first is pending is already-owned, not a double sale
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let first = db.insert_transaction(&cape(alice)).await.unwrap();
+        assert_eq!(first.metadata["consumable"], false);
+        assert!(matches!(db.insert_transaction(&cape(alice)).await, Err(AppError::AlreadyOwned(_))));
+
+        // A failed purchase gives its unit back; a completed one keeps it
+        let declined = db.insert_transaction(&cape(bob)).await.unwrap();
+        assert!(matches!(db.insert_transaction(&cape(carol)).await, Err(AppError::OutOfStock(_))));
+        db.update_transaction_status(declined.transaction_id, TransactionStatus::Failed, None).await.unwrap();
+        db.update_transaction_status(first.transaction_id, TransactionStatus::Completed, Some("pi_1")).await.unwrap();
+        assert!(matches!(db.insert_transaction(&cape(alice)).await, Err(AppError::AlreadyOwned(_))));
+        db.insert_transaction(&cape(carol)).await.unwrap();
+
+        let response = handle_get_catalog_item(&db, &metrics, "founders_cape").await.unwrap();
+        let Body::Text(body) = response.body() else { panic!("expected a text body") };
+        let body: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body, json!({
+            "itemId": "founders_cape",
+            "kind": "non_consumable",
+            "stockLimit": 2,
+            "stockReserved": 1,
+            "stockSold": 1,
+            "priceCents": 1999,
+            "currency": "USD",
+            "creditCurrency": null,
+            "creditAmount": null,
+            "walletPrice": null,
+            "walletCurrency": null,
+            "titleId": null,
+            "updatedAt": body["updatedAt"],
+            "stockRemaining": 0,
+        }));
+
+        // The limit cannot drop below what is already committed
+        let shrink = handle_put_catalog_item(put(json!({"kind": "non_consumable", "stock_limit": 1})), &db, &metrics, "founders_cape").await;
+        assert!(matches!(shrink, Err(AppError::Conflict(_))));
+        assert_eq!(AppError::OutOfStock(String::new()).error_code(), "OUT_OF_STOCK");
+        assert_eq!(AppError::AlreadyOwned(String::new()).error_code(), "ALREADY_OWNED");
+    }
+}
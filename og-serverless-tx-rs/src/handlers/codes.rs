@@ -0,0 +1,400 @@
+//! # Code Handlers
+//!
+//! - `POST /codes/campaigns` - generate a batch of promo or gift codes
+//! - `POST /codes/redeem` - redeem a gift code for items and/or wallet credit
+//!
+//! Discount codes are entered as `promo_code` on a purchase instead.
+//! Campaigns are created only under `/admin/v2`, by IAM-signed operators.
+//!
+//! ADVANTAGE: Every redemption is a transaction row, so player history stays complete
+//! ADVANTAGE: Guessing is throttled per player and per client address
+
+use chrono::Utc;
+use lambda_http::request::RequestContext;
+use lambda_http::{Body, Request, RequestExt, Response};
+use serde_json::json;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::metrics::Metrics;
+use crate::models::code::{normalize_code, CODE_GRANT_METADATA_KEY, FAILED_ATTEMPT_WINDOW, MAX_FAILED_ATTEMPTS, NO_CURRENCY};
+use crate::models::entitlement::WALLET_TOP_UP_METADATA_KEY;
+use crate::models::{
+    AppliedPromotion, CodeCampaign, CodeCampaignCreatedResponse, CodeRedemptionResponse, CodeReward,
+    CreateCodeCampaignRequest, NewCodeCampaign, NewLedgerEntry, NewTransaction, PromotionKind,
+    RedeemCodeRequest, TransactionStatus,
+};
+use crate::services::Database;
+use super::purchase::parse_body;
+use super::request_id::RequestId;
+use super::router::json_response;
+
+/// Handle create code campaign request
+#[instrument(skip(request, db, metrics))]
+pub async fn handle_create_code_campaign(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+) -> Result<Response<Body>, AppError> {
+    let create: CreateCodeCampaignRequest = parse_body(&request)?;
+    create.check()?;
+
+    let campaign = NewCodeCampaign::new(create);
+    let created = metrics
+        .time_db("create_code_campaign", db.create_code_campaign(&campaign))
+        .await?;
+
+    info!(campaign_id = %created.campaign_id, codes = created.code_count, "Code campaign created");
+    Ok(json_response(201, &CodeCampaignCreatedResponse::new(created, &campaign.codes)))
+}
+
+/// Handle redeem code request
+///
+/// The redemption is a zero-amount transaction, completed straight away so
+/// the usual completion effects grant the items and credit the wallet.
+#[instrument(skip(request, db, metrics))]
+pub async fn handle_redeem_code(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+) -> Result<Response<Body>, AppError> {
+    let redeem: RedeemCodeRequest = parse_body(&request)?;
+    let request_id = RequestId::of(&request);
+
+    let attempt = CodeAttempt::of(&request, redeem.player_id);
+    let (code, campaign) = attempt.resolve(&redeem.code, db, metrics).await?;
+    let CodeReward::Grant { items, wallet_credit } = campaign.reward.clone() else {
+        return Err(AppError::Validation("Discount codes are entered as promo_code on a purchase".into()));
+    };
+
+    let mut metadata = json!({ CODE_GRANT_METADATA_KEY: campaign.campaign_id });
+    if let Some(credit) = wallet_credit {
+        metadata[WALLET_TOP_UP_METADATA_KEY] = json!(credit);
+    }
+    let mut new_tx = NewTransaction::new(
+        redeem.player_id,
+        format!("code:{}", campaign.campaign_id),
+        campaign.name.clone(),
+        0,
+        NO_CURRENCY.to_string(),
+        1,
+        metadata,
+    )
+    .with_code(code.clone());
+    new_tx.applied_promotions = vec![AppliedPromotion {
+        promotion_id: campaign.campaign_id,
+        name: campaign.name.clone(),
+        kind: PromotionKind::Bundle,
+        discount_cents: 0,
+        bundle_items: items.clone(),
+        code: Some(code),
+    }];
+    if let Some(id) = request_id {
+        new_tx = new_tx.with_request_id(id.as_str());
+    }
+
+    let tx = attempt
+        .check(metrics.time_db("insert_transaction", db.insert_transaction(&new_tx)).await, db, metrics)
+        .await?;
+    let tx = metrics
+        .time_db(
+            "update_transaction_status",
+            db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, None),
+        )
+        .await?;
+
+    // The completion credited the wallet; replaying returns that entry
+    let ledger_entry = match NewLedgerEntry::credit_for(&tx) {
+        Some(entry) => Some(metrics.time_db("append_ledger_entry", db.append_ledger_entry(&entry)).await?),
+        None => None,
+    };
+
+    info!(transaction_id = %tx.transaction_id, campaign_id = %campaign.campaign_id, "Code redeemed");
+    Ok(json_response(201, &CodeRedemptionResponse {
+        transaction_id: tx.transaction_id,
+        status: tx.status,
+        campaign_id: campaign.campaign_id,
+        items,
+        wallet_credit,
+        ledger_entry,
+    }))
+}
+
+/// Who is trying a code - the player and, behind API Gateway, the client
+/// address - for brute-force throttling
+pub(super) struct CodeAttempt {
+    player_id: Uuid,
+    subjects: Vec<String>,
+}
+
+impl CodeAttempt {
+    pub(super) fn of(request: &Request, player_id: Uuid) -> Self {
+        let source_ip = match request.request_context_ref() {
+            Some(RequestContext::ApiGatewayV1(context)) => context.identity.source_ip.clone(),
+            Some(RequestContext::ApiGatewayV2(context)) => context.http.source_ip.clone(),
+            _ => None,
+        };
+
+        let mut subjects = vec![format!("player:{}", player_id)];
+        subjects.extend(source_ip.map(|ip| format!("ip:{}", ip)));
+        Self { player_id, subjects }
+    }
+
+    /// Normalized code and campaign for `input`
+    ///
+    /// Usage limits are checked when the redeeming transaction is inserted;
+    /// this only turns away malformed, unknown and expired codes - each a
+    /// failed attempt - and subjects that have failed too often.
+    pub(super) async fn resolve(
+        &self,
+        input: &str,
+        db: &dyn Database,
+        metrics: &Metrics,
+    ) -> Result<(String, CodeCampaign), AppError> {
+        let since = Utc::now() - FAILED_ATTEMPT_WINDOW;
+        for subject in &self.subjects {
+            let failures = metrics
+                .time_db("count_code_failures", db.count_code_failures(subject, since))
+                .await?;
+            if failures >= MAX_FAILED_ATTEMPTS {
+                // The subject holds the raw player ID or address; log the
+                // player ID under the field the privacy layer hashes instead
+                let throttled_by = subject.split(':').next().unwrap_or_default();
+                warn!(player_id = %self.player_id, throttled_by, failures, "Code redemption throttled");
+                return Err(AppError::RateLimited);
+            }
+        }
+
+        let found = match normalize_code(input) {
+            Some(code) => metrics
+                .time_db("get_code_campaign", db.get_code_campaign(&code))
+                .await?
+                .map(|campaign| (code, campaign)),
+            None => None,
+        };
+        let resolved = found
+            .ok_or_else(|| AppError::NotFound("Code not found".into()))
+            .and_then(|(code, campaign)| {
+                // No uses yet stands in for the real counts - only expiry can fail
+                campaign.check_redemption(0, 0, Utc::now()).map(|()| (code, campaign))
+            });
+        self.check(resolved, db, metrics).await
+    }
+
+    /// Pass `result` through, recording a failed attempt if the code was
+    /// unknown or refused
+    ///
+    /// Only code failures count: a sold-out item or an owned one says
+    /// nothing about whether the player is guessing codes.
+    pub(super) async fn check<T>(
+        &self,
+        result: Result<T, AppError>,
+        db: &dyn Database,
+        metrics: &Metrics,
+    ) -> Result<T, AppError> {
+        if matches!(result, Err(AppError::NotFound(_) | AppError::CodeUnavailable(_))) {
+            for subject in &self.subjects {
+                metrics
+                    .time_db("record_code_failure", db.record_code_failure(subject))
+                    .await?;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use lambda_http::aws_lambda_events::apigw::ApiGatewayProxyRequestContext;
+    use serde_json::Value;
+    use std::sync::Arc;
+    use crate::handlers::purchase::handle_purchase;
+    use crate::handlers::versioning::ApiVersion;
+    use crate::models::{CatalogItemRequest, VirtualCurrency};
+    use crate::services::{InMemoryDatabase, PaymentService};
+    use crate::strategies::payment::MockPaymentStrategy;
+    use crate::test_support::{body, post};
+
+    async fn campaign(db: &InMemoryDatabase, request: Value) -> Vec<String> {
+        let created = handle_create_code_campaign(post(request), db, &Metrics::default()).await.unwrap();
+        assert_eq!(created.status(), 201);
+        serde_json::from_value(body(&created)["codes"].take()).unwrap()
+    }
+
+    /// A one-code grant campaign with `extra` settings
+    async fn cape_code(db: &InMemoryDatabase, extra: Value) -> String {
+        let mut request = json!({
+            "name": "Launch gift",
+            "reward": {"type": "grant", "items": [{"item_id": "founder_cape", "quantity": 1}]},
+            "count": 1
+        });
+        request.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        campaign(db, request).await.remove(0)
+    }
+
+    fn redeem(player_id: Uuid, code: &str) -> Request {
+        post(json!({"player_id": player_id, "code": code}))
+    }
+
+    fn from_address(request: Request, source_ip: &str) -> Request {
+        let mut context = ApiGatewayProxyRequestContext::default();
+        context.identity.source_ip = Some(source_ip.to_string());
+        request.with_request_context(RequestContext::ApiGatewayV1(context))
+    }
+
+    #[tokio::test]
+    async fn test_gift_code_grants_items_and_credit() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let codes = campaign(&db, json!({
+            "name": "Launch gift",
+            "reward": {
+                "type": "grant",
+                "items": [{"item_id": "founder_cape", "quantity": 1}],
+                "wallet_credit": {"currency": "gems", "amount": 500}
+            },
+            "count": 3
+        })).await;
+        assert_eq!(codes.len(), 3);
+
+        let player_id = Uuid::new_v4();
+        let redeemed = body(&handle_redeem_code(redeem(player_id, &codes[0].to_lowercase()), &db, &metrics).await.unwrap());
+        assert_eq!(redeemed["status"], "completed");
+        assert_eq!(redeemed["ledgerEntry"]["balance_after"], 500);
+
+        let owned = db.get_player_entitlements(player_id, false).await.unwrap();
+        assert_eq!(owned.iter().map(|e| e.item_id.as_str()).collect::<Vec<_>>(), vec!["founder_cape"]);
+        let wallet = db.get_wallet(player_id).await.unwrap();
+        assert!(wallet.iter().any(|b| b.currency == VirtualCurrency::Gems && b.balance == 500));
+
+        let history = db.get_player_transactions(player_id, 10, None).await.unwrap();
+        assert_eq!((history[0].price_cents, history[0].status), (0, TransactionStatus::Completed));
+        let journal = db.get_player_journal(player_id, 10).await.unwrap();
+        assert_eq!(
+            journal.iter().map(|e| e.kind.as_str()).collect::<Vec<_>>(),
+            vec!["wallet_top_up"],
+            "the zero-amount redemption moves no money"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_use_limits_are_enforced() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let code = cape_code(&db, json!({"max_uses": 2})).await;
+        let (first, second, third) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        handle_redeem_code(redeem(first, &code), &db, &metrics).await.unwrap();
+        assert!(
+            matches!(handle_redeem_code(redeem(first, &code), &db, &metrics).await, Err(AppError::CodeUnavailable(_))),
+            "once per player"
+        );
+        handle_redeem_code(redeem(second, &code), &db, &metrics).await.unwrap();
+        assert!(
+            matches!(handle_redeem_code(redeem(third, &code), &db, &metrics).await, Err(AppError::CodeUnavailable(_))),
+            "max_uses reached"
+        );
+        assert!(db.get_player_transactions(third, 10, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expired_code_is_refused_and_counted() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let code = cape_code(&db, json!({"expires_at": Utc::now() - Duration::hours(1)})).await;
+        let player_id = Uuid::new_v4();
+
+        assert!(matches!(handle_redeem_code(redeem(player_id, &code), &db, &metrics).await, Err(AppError::CodeUnavailable(_))));
+        assert!(db.get_player_entitlements(player_id, false).await.unwrap().is_empty());
+        let since = Utc::now() - FAILED_ATTEMPT_WINDOW;
+        assert_eq!(db.count_code_failures(&format!("player:{}", player_id), since).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_discount_code_prices_catalog_purchases() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+        let codes = campaign(&db, json!({
+            "name": "Streamer 20",
+            "reward": {"type": "discount", "kind": "percent_off", "value": 20},
+            "count": 1
+        })).await;
+        let listing: CatalogItemRequest =
+            serde_json::from_value(json!({"kind": "consumable", "price_cents": 1000, "currency": "USD"})).unwrap();
+        db.upsert_catalog_item("shield", &listing).await.unwrap();
+
+        let purchase = |item_id: &str| post(json!({
+            "player_id": Uuid::new_v4(),
+            "item_id": item_id,
+            "item_name": "Shield",
+            "price_cents": 1000,
+            "currency": "USD",
+            "promo_code": codes[0]
+        }));
+        let bought = body(&handle_purchase(ApiVersion::V1, purchase("shield"), &db, &payments, &metrics).await.unwrap());
+        assert_eq!(bought["payment"]["amountCents"], 800);
+        assert_eq!(bought["promotions"][0]["code"], codes[0].replace('-', ""));
+
+        assert!(matches!(
+            handle_purchase(ApiVersion::V1, purchase("unlisted_shield"), &db, &payments, &metrics).await,
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            handle_redeem_code(redeem(Uuid::new_v4(), &codes[0]), &db, &metrics).await,
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_guessing_locks_out_the_player_and_the_address() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let code = cape_code(&db, json!({})).await;
+        let guesser = Uuid::new_v4();
+
+        for _ in 0..MAX_FAILED_ATTEMPTS {
+            assert!(matches!(
+                handle_redeem_code(from_address(redeem(guesser, "AAAA-BBBB-CCCC-DDDD"), "198.51.100.7"), &db, &metrics).await,
+                Err(AppError::NotFound(_))
+            ));
+        }
+
+        // Even a valid code is refused to the player, from anywhere, and to the address
+        for request in [
+            from_address(redeem(guesser, &code), "203.0.113.9"),
+            from_address(redeem(Uuid::new_v4(), &code), "198.51.100.7"),
+        ] {
+            assert!(matches!(handle_redeem_code(request, &db, &metrics).await, Err(AppError::RateLimited)));
+        }
+        handle_redeem_code(from_address(redeem(Uuid::new_v4(), &code), "203.0.113.9"), &db, &metrics).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_purchases_without_a_code_are_not_guesses() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+        let code = cape_code(&db, json!({})).await;
+        let listing: CatalogItemRequest =
+            serde_json::from_value(json!({"kind": "consumable", "stock_limit": 0, "price_cents": 500, "currency": "USD"})).unwrap();
+        db.upsert_catalog_item("sold_out_shield", &listing).await.unwrap();
+        let player_id = Uuid::new_v4();
+
+        for _ in 0..MAX_FAILED_ATTEMPTS + 1 {
+            let purchase = post(json!({"player_id": player_id, "item_id": "sold_out_shield", "item_name": "Shield"}));
+            let purchase = from_address(purchase, "198.51.100.7");
+            assert!(matches!(
+                handle_purchase(ApiVersion::V2, purchase, &db, &payments, &metrics).await,
+                Err(AppError::OutOfStock(_))
+            ));
+        }
+
+        handle_redeem_code(from_address(redeem(player_id, &code), "198.51.100.7"), &db, &metrics).await.unwrap();
+        let since = Utc::now() - FAILED_ATTEMPT_WINDOW;
+        assert_eq!(db.count_code_failures(&format!("player:{}", player_id), since).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,158 @@
+//! # CORS Policy
+//!
+//! ADVANTAGE: Headers are applied after routing, so errors carry them too
+//! ADVANTAGE: State-changing requests from disallowed origins are refused
+//! server-side, not just hidden from the browser
+
+use lambda_http::{Body, Request, Response};
+use lambda_http::http::{HeaderMap, HeaderValue, Method};
+
+use crate::models::config::CorsConfig;
+
+/// Result of checking a request's `Origin` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OriginCheck {
+    /// No `Origin` header - not a cross-origin browser request
+    Absent,
+    Allowed,
+    Denied,
+}
+
+/// Applies a `CorsConfig` to responses
+#[derive(Debug, Clone, Default)]
+pub struct CorsPolicy {
+    config: CorsConfig,
+}
+
+impl CorsPolicy {
+    pub fn new(config: CorsConfig) -> Self {
+        Self { config }
+    }
+
+    /// Check the request's `Origin` against the allowlist
+    pub fn check(&self, request: &Request) -> OriginCheck {
+        match origin(request.headers()) {
+            None => OriginCheck::Absent,
+            Some(o) if self.config.allows_origin(o) => OriginCheck::Allowed,
+            Some(_) => OriginCheck::Denied,
+        }
+    }
+
+    /// Whether `method` may change state and must come from an allowed origin
+    pub fn requires_allowed_origin(method: &Method) -> bool {
+        !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+    }
+
+    /// Add CORS headers to an actual (non-preflight) response
+    ///
+    /// Disallowed origins get no CORS headers, so the browser blocks the read.
+    pub fn apply(&self, request_headers: &HeaderMap, response: &mut Response<Body>) {
+        let request_origin = origin(request_headers);
+        let Some(allow_origin) = self.allow_origin_value(request_origin) else {
+            return;
+        };
+
+        let headers = response.headers_mut();
+        insert(headers, "Access-Control-Allow-Origin", &allow_origin);
+
+        if allow_origin != "*" {
+            headers.append("Vary", HeaderValue::from_static("Origin"));
+        }
+        if self.config.allow_credentials {
+            insert(headers, "Access-Control-Allow-Credentials", "true");
+        }
+        if !self.config.exposed_headers.is_empty() {
+            insert(headers, "Access-Control-Expose-Headers", &self.config.exposed_headers.join(", "));
+        }
+    }
+
+    /// Preflight response for a path serving `allow_methods`
+    pub fn preflight(&self, request_headers: &HeaderMap, allow_methods: &str) -> Response<Body> {
+        let mut response = Response::builder()
+            .status(204)
+            .header("Allow", allow_methods)
+            .body(Body::Empty)
+            .unwrap();
+
+        if self.allow_origin_value(origin(request_headers)).is_some() {
+            let headers = response.headers_mut();
+            insert(headers, "Access-Control-Allow-Methods", allow_methods);
+            insert(headers, "Access-Control-Allow-Headers", &self.config.allowed_headers.join(", "));
+            insert(headers, "Access-Control-Max-Age", &self.config.max_age_seconds.to_string());
+        }
+
+        self.apply(request_headers, &mut response);
+        response
+    }
+
+    /// `Access-Control-Allow-Origin` value, `None` if the origin is not allowed
+    fn allow_origin_value(&self, request_origin: Option<&str>) -> Option<String> {
+        if self.config.allows_any_origin() && !self.config.allow_credentials {
+            return Some("*".to_string());
+        }
+
+        request_origin
+            .filter(|o| self.config.allows_origin(o))
+            .map(str::to_string)
+    }
+}
+
+fn origin(headers: &HeaderMap) -> Option<&str> {
+    headers.get("Origin").and_then(|v| v.to_str().ok())
+}
+
+fn insert(headers: &mut HeaderMap, name: &'static str, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shop_policy() -> CorsPolicy {
+        CorsPolicy::new(CorsConfig {
+            allowed_origins: vec!["https://shop.example.com".to_string()],
+            allow_credentials: true,
+            ..CorsConfig::default()
+        })
+    }
+
+    fn headers_with_origin(origin: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("Origin", HeaderValue::from_str(origin).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_allowed_origin_is_echoed_with_credentials() {
+        let policy = shop_policy();
+        let mut response = Response::new(Body::Empty);
+        policy.apply(&headers_with_origin("https://shop.example.com"), &mut response);
+
+        let headers = response.headers();
+        assert_eq!(headers["Access-Control-Allow-Origin"], "https://shop.example.com");
+        assert_eq!(headers["Access-Control-Allow-Credentials"], "true");
+        assert_eq!(headers["Vary"], "Origin");
+        assert!(headers["Access-Control-Expose-Headers"].to_str().unwrap().contains("Retry-After"));
+
+        let mut response = Response::new(Body::Empty);
+        policy.apply(&headers_with_origin("https://evil.example"), &mut response);
+        assert!(response.headers().get("Access-Control-Allow-Origin").is_none());
+    }
+
+    #[test]
+    fn test_preflight_only_advertises_to_allowed_origins() {
+        let policy = shop_policy();
+
+        let allowed = policy.preflight(&headers_with_origin("https://shop.example.com"), "POST, OPTIONS");
+        assert_eq!(allowed.status(), 204);
+        assert_eq!(allowed.headers()["Access-Control-Allow-Methods"], "POST, OPTIONS");
+        assert_eq!(allowed.headers()["Access-Control-Max-Age"], "600");
+
+        let denied = policy.preflight(&headers_with_origin("https://evil.example"), "POST, OPTIONS");
+        assert!(denied.headers().get("Access-Control-Allow-Methods").is_none());
+        assert!(denied.headers().get("Access-Control-Allow-Origin").is_none());
+    }
+}
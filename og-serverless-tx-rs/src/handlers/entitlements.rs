@@ -0,0 +1,92 @@
+//! # Entitlements Handler
+//!
+//! `GET /players/{playerId}/entitlements?includeRevoked=` - items the
+//! player owns, for game servers to reconcile inventories against.
+//!
+//! ADVANTAGE: Ownership comes from completed purchases, not the client's word
+
+use lambda_http::{Body, Request, Response};
+use tracing::{info, instrument};
+
+use crate::errors::AppError;
+use crate::metrics::Metrics;
+use crate::models::EntitlementListResponse;
+use crate::services::Database;
+use super::router::json_response;
+use super::transactions::parse_query_params;
+use super::wallet::parse_player_id;
+
+/// Handle get entitlements request - active only unless `includeRevoked=true`
+#[instrument(skip(request, db, metrics, player_id_str), fields(player_id = %player_id_str))]
+pub async fn handle_get_entitlements(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+    player_id_str: &str,
+) -> Result<Response<Body>, AppError> {
+    let player_id = parse_player_id(player_id_str)?;
+
+    let include_revoked = parse_query_params(request.uri().query().unwrap_or(""))
+        .get("includeRevoked")
+        .is_some_and(|v| v == "true");
+
+    let entitlements = metrics
+        .time_db("get_player_entitlements", db.get_player_entitlements(player_id, include_revoked))
+        .await?;
+
+    info!(count = entitlements.len(), include_revoked, "Retrieved entitlements");
+    Ok(json_response(200, &EntitlementListResponse::new(player_id, entitlements)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use uuid::Uuid;
+    use crate::models::{EntitlementKind, EntitlementStatus, NewTransaction, TransactionStatus};
+    use crate::services::InMemoryDatabase;
+
+    #[tokio::test]
+    async fn test_grant_on_completion_and_revoke_on_refund() {
+        let db = InMemoryDatabase::new();
+        let player_id = Uuid::new_v4();
+
+        let skin = db.insert_transaction(&NewTransaction::new(
+            player_id, "skin_gold".into(), "Gold Skin".into(), 999, "USD".into(), 1, json!({}),
+        )).await.unwrap();
+        let potions = db.insert_transaction(&NewTransaction::new(
+            player_id, "potion".into(), "Potion".into(), 199, "USD".into(), 5, json!({"consumable": true}),
+        )).await.unwrap();
+        let declined = db.insert_transaction(&NewTransaction::new(
+            player_id, "mount".into(), "Mount".into(), 4999, "USD".into(), 1, json!({}),
+        )).await.unwrap();
+
+        for tx in [&skin, &potions] {
+            db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, Some("pi")).await.unwrap();
+            db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, Some("pi")).await.unwrap();
+        }
+        db.update_transaction_status(declined.transaction_id, TransactionStatus::Failed, None).await.unwrap();
+
+        let owned = db.get_player_entitlements(player_id, false).await.unwrap();
+        assert_eq!(owned.len(), 2);
+        assert_eq!(owned[0].kind, EntitlementKind::Consumable);
+        assert_eq!(owned[0].quantity, 5);
+        assert_eq!(owned[1].kind, EntitlementKind::Durable);
+
+        db.update_transaction_status(skin.transaction_id, TransactionStatus::Refunded, Some("pi")).await.unwrap();
+        assert_eq!(db.get_player_entitlements(player_id, false).await.unwrap().len(), 1);
+
+        let request: Request = lambda_http::http::Request::builder()
+            .uri("/players/x/entitlements?includeRevoked=true")
+            .body(Body::Empty)
+            .unwrap();
+        let response = handle_get_entitlements(request, &db, &Metrics::default(), &player_id.to_string())
+            .await
+            .unwrap();
+        let Body::Text(body) = response.body() else { panic!("expected a text body") };
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["count"], 2);
+        assert_eq!(body["entitlements"][1]["status"], json!(EntitlementStatus::Revoked));
+        assert!(body["entitlements"][1]["revokedAt"].is_string());
+    }
+}
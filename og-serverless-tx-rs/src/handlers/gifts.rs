@@ -0,0 +1,175 @@
+//! # Gift Handlers
+//!
+//! - `POST /gifts/{transactionId}/accept` - grant a gift's items to its recipient
+//! - `POST /gifts/{transactionId}/decline` - refund the payer instead
+//!
+//! Gifts are bought through the purchase endpoint with a `recipient_id`.
+//!
+//! ADVANTAGE: Nothing reaches the recipient's inventory without their consent
+
+use lambda_http::{Body, Request, Response};
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::metrics::Metrics;
+use crate::models::{gift, FieldError, GiftDecisionRequest};
+use crate::services::{gifts, Database, PaymentService};
+use super::purchase::parse_body;
+use super::router::json_response;
+
+/// Handle accept or decline gift request
+///
+/// Declining refunds the payer and declines the gift in one transition. A
+/// decline retried after a processor outage re-sends the refund under the
+/// same idempotency key, so the payer is never refunded twice.
+#[instrument(skip(request, db, payment_service, metrics))]
+pub async fn handle_answer_gift(
+    request: Request,
+    db: &dyn Database,
+    payment_service: &PaymentService,
+    metrics: &Metrics,
+    transaction_id_str: &str,
+    accept: bool,
+) -> Result<Response<Body>, AppError> {
+    let transaction_id: Uuid = transaction_id_str
+        .parse()
+        .map_err(|_| AppError::InvalidFields(vec![
+            FieldError::new("transactionId", "invalid_uuid", "must be a UUID"),
+        ]))?;
+    let decision: GiftDecisionRequest = parse_body(&request)?;
+
+    let tx = if accept {
+        metrics
+            .time_db("accept_gift", db.accept_gift(transaction_id, decision.player_id))
+            .await?
+    } else {
+        let gift = metrics
+            .time_db("get_transaction", db.get_transaction(transaction_id))
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Gift {} not found", transaction_id)))?;
+        match gift::answer(&gift, decision.player_id, false)? {
+            Some(_) => gifts::refund_gift(db, payment_service, &gift).await?,
+            None => gift,
+        }
+    };
+
+    info!(transaction_id = %transaction_id, gift_status = ?tx.gift_status, "Gift answered");
+    Ok(json_response(200, &tx.seen_by(decision.player_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+    use crate::handlers::purchase::handle_purchase;
+    use crate::handlers::versioning::ApiVersion;
+    use crate::models::gift::MAX_GIFTS_SENT_PER_DAY;
+    use crate::models::JournalKind;
+    use crate::services::InMemoryDatabase;
+    use crate::strategies::payment::MockPaymentStrategy;
+    use crate::test_support::{body, post};
+
+    fn gift(payer_id: Uuid, recipient_id: Uuid, item_id: &str) -> Request {
+        post(json!({
+            "player_id": payer_id,
+            "recipient_id": recipient_id,
+            "item_id": item_id,
+            "item_name": "Gift",
+            "price_cents": 500,
+            "currency": "USD"
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_gift_is_granted_to_recipient_only_once_accepted() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+        let (payer_id, recipient_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let bought = body(&handle_purchase(ApiVersion::V1, gift(payer_id, recipient_id, "cape"), &db, &payments, &metrics).await.unwrap());
+        assert_eq!(bought["gift"]["status"], "pending");
+        let transaction_id: Uuid = serde_json::from_value(bought["transactionId"].clone()).unwrap();
+        assert!(db.get_player_entitlements(recipient_id, false).await.unwrap().is_empty());
+
+        let received = db.get_player_transactions(recipient_id, 10, None).await.unwrap();
+        assert_eq!(received.len(), 1);
+        assert!(received[0].clone().seen_by(recipient_id).processor_id.is_none());
+        assert_eq!(db.get_player_transactions(payer_id, 10, None).await.unwrap().len(), 1);
+
+        let id = transaction_id.to_string();
+        assert!(matches!(
+            handle_answer_gift(post(json!({"player_id": payer_id})), &db, &payments, &metrics, &id, true).await,
+            Err(AppError::NotFound(_))
+        ));
+        let accepted = handle_answer_gift(post(json!({"player_id": recipient_id})), &db, &payments, &metrics, &id, true)
+            .await
+            .unwrap();
+        assert_eq!(body(&accepted)["gift_status"], "accepted");
+
+        let owned = db.get_player_entitlements(recipient_id, false).await.unwrap();
+        assert_eq!(owned.iter().map(|e| e.item_id.as_str()).collect::<Vec<_>>(), vec!["cape"]);
+        assert!(db.get_player_entitlements(payer_id, false).await.unwrap().is_empty());
+        assert!(matches!(
+            handle_answer_gift(post(json!({"player_id": recipient_id})), &db, &payments, &metrics, &id, false).await,
+            Err(AppError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_declined_gift_is_refunded_once_in_the_declining_transition() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+        let (payer_id, recipient_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let bought = body(&handle_purchase(ApiVersion::V1, gift(payer_id, recipient_id, "hat"), &db, &payments, &metrics).await.unwrap());
+        let id = bought["transactionId"].as_str().unwrap().to_string();
+        assert!(matches!(
+            handle_answer_gift(post(json!({"player_id": payer_id})), &db, &payments, &metrics, &id, false).await,
+            Err(AppError::NotFound(_))
+        ));
+
+        for _ in 0..2 {
+            let declined = body(&handle_answer_gift(post(json!({"player_id": recipient_id})), &db, &payments, &metrics, &id, false)
+                .await
+                .unwrap());
+            assert_eq!((declined["status"].as_str(), declined["gift_status"].as_str()), (Some("refunded"), Some("declined")));
+        }
+
+        let refunds = db.get_player_journal(payer_id, 10).await.unwrap()
+            .into_iter()
+            .filter(|e| e.kind == JournalKind::Refund)
+            .count();
+        assert_eq!(refunds, 1, "a repeated decline does not refund again");
+        assert!(db.get_player_entitlements(recipient_id, false).await.unwrap().is_empty());
+        assert!(matches!(
+            handle_answer_gift(post(json!({"player_id": recipient_id})), &db, &payments, &metrics, &id, true).await,
+            Err(AppError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_daily_gift_limit_applies() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+        let payer_id = Uuid::new_v4();
+
+        for n in 0..MAX_GIFTS_SENT_PER_DAY {
+            handle_purchase(ApiVersion::V1, gift(payer_id, Uuid::new_v4(), &format!("item_{}", n)), &db, &payments, &metrics)
+                .await
+                .unwrap();
+        }
+        assert!(matches!(
+            handle_purchase(ApiVersion::V1, gift(payer_id, Uuid::new_v4(), "one_more"), &db, &payments, &metrics).await,
+            Err(AppError::GiftLimitReached(_))
+        ));
+        assert!(matches!(
+            handle_purchase(ApiVersion::V1, gift(payer_id, payer_id, "self"), &db, &payments, &metrics).await,
+            Err(AppError::Validation(_))
+        ));
+    }
+}
@@ -1,44 +1,53 @@
-//! # Health Handler
+//! # Health Handlers
+//!
+//! - Liveness (`/health/live`) answers from memory - the function runs
+//! - Readiness (`/health/ready`, and `/health`) runs every component check
+//!
+//! ADVANTAGE: Liveness never touches a dependency, so an outage can't fail it
+//! ADVANTAGE: Readiness is 200 while degraded and 503 only when unhealthy
 
 use lambda_http::{Body, Response};
+use std::sync::Arc;
 use tracing::{info, warn};
 
-use crate::models::response::{HealthResponse, HealthStatus, ComponentHealth};
-use crate::services::PostgresDatabase;
+use crate::models::response::{HealthResponse, HealthStatus};
+use crate::services::health::{build_info, overall_status, run_checks, HealthCheck};
 use super::router::json_response;
 
-/// Handle health check
-pub async fn handle_health(db: &PostgresDatabase) -> Response<Body> {
-    let timestamp = chrono::Utc::now().to_rfc3339();
-    
-    // Check database health
-    let db_health = match db.health_check().await {
-        Ok(latency) => {
-            info!(latency_ms = latency.as_millis(), "Database healthy");
-            ComponentHealth {
-                status: HealthStatus::Healthy,
-                latency_ms: Some(latency.as_millis() as u64),
-            }
-        }
-        Err(e) => {
-            warn!(error = %e, "Database unhealthy");
-            ComponentHealth {
-                status: HealthStatus::Unhealthy,
-                latency_ms: None,
-            }
-        }
+/// Shallow check - no dependencies are called
+pub fn handle_liveness() -> Response<Body> {
+    let response = HealthResponse {
+        status: HealthStatus::Healthy,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        build: build_info(),
+        database: None,
+        checks: Default::default(),
     };
     
-    let overall_status = match db_health.status {
-        HealthStatus::Healthy => HealthStatus::Healthy,
-        HealthStatus::Degraded => HealthStatus::Degraded,
-        HealthStatus::Unhealthy => HealthStatus::Unhealthy,
-    };
+    json_response(200, &response)
+}
+
+/// Deep check - every component in `checks`
+pub async fn handle_readiness(checks: &[Arc<dyn HealthCheck>]) -> Response<Body> {
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let results = run_checks(checks).await;
+    let status = overall_status(results.values());
+    
+    match status {
+        HealthStatus::Healthy => info!("All components healthy"),
+        _ => warn!(
+            status = ?status,
+            components = ?results.iter().filter(|(_, c)| c.status != HealthStatus::Healthy).map(|(name, _)| name).collect::<Vec<_>>(),
+            "Components not healthy"
+        ),
+    }
     
     let response = HealthResponse {
-        status: overall_status,
+        status,
         timestamp,
-        database: Some(db_health),
+        build: build_info(),
+        database: results.get("database").cloned(),
+        checks: results,
     };
     
     let status_code = match response.status {
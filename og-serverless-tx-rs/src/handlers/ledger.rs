@@ -0,0 +1,93 @@
+//! # Player Ledger Handler
+//!
+//! `GET /players/{playerId}/ledger?limit=` - the player's double-entry
+//! journal entries with their postings, newest first.
+//!
+//! ADVANTAGE: Support and finance see the same entries the trial balance sums
+
+use lambda_http::{Body, Request, Response};
+use tracing::{info, instrument};
+
+use crate::errors::AppError;
+use crate::metrics::Metrics;
+use crate::models::PlayerLedgerResponse;
+use crate::services::Database;
+use super::router::json_response;
+use super::transactions::parse_query_params;
+use super::wallet::parse_player_id;
+
+/// Handle get player ledger request
+#[instrument(skip(request, db, metrics, player_id_str), fields(player_id = %player_id_str))]
+pub async fn handle_get_player_ledger(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+    player_id_str: &str,
+) -> Result<Response<Body>, AppError> {
+    let player_id = parse_player_id(player_id_str)?;
+
+    let limit = parse_query_params(request.uri().query().unwrap_or(""))
+        .get("limit")
+        .and_then(|v| v.parse::<i32>().ok())
+        .unwrap_or(100)
+        .clamp(1, 1000);
+
+    let entries = metrics
+        .time_db("get_player_journal", db.get_player_journal(player_id, limit))
+        .await?;
+
+    info!(count = entries.len(), "Retrieved player ledger");
+    Ok(json_response(200, &PlayerLedgerResponse::new(player_id, entries)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use uuid::Uuid;
+    use crate::models::{JournalKind, NewLedgerEntry, NewTransaction, TransactionStatus, VirtualCurrency};
+    use crate::services::InMemoryDatabase;
+
+    #[tokio::test]
+    async fn test_every_money_movement_is_journaled_once() {
+        let db = InMemoryDatabase::new();
+        let player_id = Uuid::new_v4();
+
+        let tx = db.insert_transaction(&NewTransaction::new(
+            player_id, "sword".into(), "Sword".into(), 1999, "USD".into(), 1, json!({}),
+        )).await.unwrap();
+        db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, Some("pi_1")).await.unwrap();
+        // A retried update is accepted but journals nothing new
+        db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, Some("pi_1")).await.unwrap();
+        db.update_transaction_status(tx.transaction_id, TransactionStatus::Refunded, Some("pi_1")).await.unwrap();
+        assert!(matches!(
+            db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, None).await,
+            Err(AppError::Conflict(_))
+        ));
+
+        db.append_ledger_entry(&NewLedgerEntry::top_up(player_id, VirtualCurrency::Gold, 50, tx.transaction_id))
+            .await
+            .unwrap();
+
+        let entries = db.get_player_journal(player_id, 10).await.unwrap();
+        let kinds: Vec<JournalKind> = entries.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![JournalKind::WalletTopUp, JournalKind::Refund, JournalKind::Purchase]);
+
+        let trial = db.trial_balance().await.unwrap();
+        assert!(trial.is_balanced());
+
+        let request = Request::default();
+        let response = handle_get_player_ledger(request, &db, &Metrics::default(), &player_id.to_string())
+            .await
+            .unwrap();
+        let Body::Text(body) = response.body() else { panic!("expected a text body") };
+        let body: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["count"], 3);
+        assert_eq!(body["entries"][2]["postings"][0], json!({
+            "accountCode": "processor_clearing:USD",
+            "accountType": "asset",
+            "currency": "USD",
+            "amount": 1999
+        }));
+    }
+}
@@ -3,9 +3,22 @@
 //! ADVANTAGE: Handlers are strongly typed
 //! ADVANTAGE: Request/Response types are known at compile time
 
+pub mod cors;
+pub mod request_id;
 pub mod router;
+pub mod routes;
 pub mod purchase;
 pub mod transactions;
+pub mod wallet;
+pub mod ledger;
+pub mod entitlements;
+pub mod webhooks;
+pub mod catalog;
+pub mod promotions;
+pub mod codes;
+pub mod gifts;
+pub mod subscriptions;
 pub mod health;
+pub mod versioning;
 
 pub use router::Router;
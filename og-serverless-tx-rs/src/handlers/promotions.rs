@@ -0,0 +1,240 @@
+//! # Promotion Handlers
+//!
+//! - `POST /promotions` - schedule a discount or bundle
+//! - `GET /promotions?limit=` - promotions, latest starting first
+//! - `PUT /players/{playerId}/profile` - set the segments promotions target
+//!
+//! Scheduling and segmenting are served only under `/admin/v2`, to
+//! IAM-signed operator requests.
+//!
+//! ADVANTAGE: Sales go live on their schedule - no client patch, no deploy
+
+use lambda_http::{Body, Request, Response};
+use tracing::{info, instrument};
+
+use crate::errors::AppError;
+use crate::metrics::Metrics;
+use crate::models::{CreatePromotionRequest, NewPromotion, PlayerProfileRequest, PromotionListResponse};
+use crate::services::Database;
+use super::purchase::parse_body;
+use super::router::json_response;
+use super::transactions::parse_query_params;
+use super::wallet::parse_player_id;
+
+/// Handle create promotion request
+#[instrument(skip(request, db, metrics))]
+pub async fn handle_create_promotion(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+) -> Result<Response<Body>, AppError> {
+    let create: CreatePromotionRequest = parse_body(&request)?;
+    create.check()?;
+
+    let promotion = metrics
+        .time_db("create_promotion", db.create_promotion(&NewPromotion::new(create)))
+        .await?;
+
+    info!(promotion_id = %promotion.promotion_id, kind = promotion.kind.as_str(), "Promotion created");
+    Ok(json_response(201, &promotion))
+}
+
+/// Handle list promotions request
+#[instrument(skip(request, db, metrics))]
+pub async fn handle_list_promotions(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+) -> Result<Response<Body>, AppError> {
+    let limit = parse_query_params(request.uri().query().unwrap_or(""))
+        .get("limit")
+        .and_then(|v| v.parse::<i32>().ok())
+        .unwrap_or(100)
+        .clamp(1, 1000);
+
+    let promotions = metrics
+        .time_db("get_promotions", db.get_promotions(limit))
+        .await?;
+
+    Ok(json_response(200, &PromotionListResponse::new(promotions)))
+}
+
+/// Handle put player profile request - replaces the player's segments
+#[instrument(skip(request, db, metrics, player_id_str), fields(player_id = %player_id_str))]
+pub async fn handle_put_player_profile(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+    player_id_str: &str,
+) -> Result<Response<Body>, AppError> {
+    let player_id = parse_player_id(player_id_str)?;
+    let put: PlayerProfileRequest = parse_body(&request)?;
+
+    let profile = metrics
+        .time_db("put_player_profile", db.put_player_profile(player_id, &put.segments))
+        .await?;
+
+    info!(segments = profile.segments.len(), "Player profile updated");
+    Ok(json_response(200, &profile))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use uuid::Uuid;
+    use crate::handlers::purchase::handle_purchase;
+    use crate::handlers::versioning::ApiVersion;
+    use crate::models::CatalogItemRequest;
+    use crate::services::{InMemoryDatabase, PaymentService};
+    use crate::strategies::payment::MockPaymentStrategy;
+    use crate::test_support::{body, post};
+
+    /// `body` with a window running from an hour ago to two days out
+    fn running(mut body: Value) -> Request {
+        let window = json!({
+            "starts_at": Utc::now() - Duration::hours(1),
+            "ends_at": Utc::now() + Duration::days(2)
+        });
+        body.as_object_mut().unwrap().extend(window.as_object().unwrap().clone());
+        post(body)
+    }
+
+    async fn list(db: &InMemoryDatabase, item_id: &str, price_cents: i64) {
+        let listing: CatalogItemRequest =
+            serde_json::from_value(json!({"kind": "consumable", "price_cents": price_cents, "currency": "USD"})).unwrap();
+        db.upsert_catalog_item(item_id, &listing).await.unwrap();
+    }
+
+    fn buy_v1(player_id: Uuid, item_id: &str, extra: Value) -> Request {
+        let mut purchase = json!({
+            "player_id": player_id,
+            "item_id": item_id,
+            "item_name": "Item",
+            "price_cents": 1,
+            "currency": "USD"
+        });
+        purchase.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        post(purchase)
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_bundle_is_priced_recorded_and_granted() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+
+        let created = handle_create_promotion(running(json!({
+            "name": "Starter pack",
+            "kind": "bundle",
+            "value": 1500,
+            "currency": "USD",
+            "item_ids": ["starter_pack"],
+            "bundle_items": [
+                {"item_id": "sword", "quantity": 1},
+                {"item_id": "potion", "quantity": 5, "consumable": true}
+            ]
+        })), &db, &metrics).await.unwrap();
+        assert_eq!(created.status(), 201);
+        handle_create_promotion(running(json!({
+            "name": "Weekend sale", "kind": "percent_off", "value": 25
+        })), &db, &metrics).await.unwrap();
+        assert!(matches!(
+            handle_create_promotion(running(json!({"name": "Broken", "kind": "amount_off", "value": 100})), &db, &metrics).await,
+            Err(AppError::Validation(_))
+        ));
+
+        let player_id = Uuid::new_v4();
+        list(&db, "starter_pack", 2000).await;
+        let bought = handle_purchase(ApiVersion::V2, post(json!({
+            "player_id": player_id,
+            "item_id": "starter_pack",
+            "item_name": "Starter Pack"
+        })), &db, &payments, &metrics).await.unwrap();
+        let pricing = &body(&bought)["pricing"];
+        assert_eq!((pricing["totalCents"].as_i64(), pricing["originalTotalCents"].as_i64()), (Some(1500), Some(2000)));
+        assert_eq!(pricing["promotions"][0]["kind"], "bundle");
+        assert_eq!(pricing["promotions"].as_array().unwrap().len(), 1, "bundles do not stack");
+
+        let mut owned: Vec<_> = db.get_player_entitlements(player_id, false).await.unwrap()
+            .into_iter()
+            .map(|e| (e.item_id, e.quantity))
+            .collect();
+        owned.sort();
+        assert_eq!(owned, vec![("potion".to_string(), 5), ("sword".to_string(), 1)]);
+
+        // v1 is discounted from the catalog price, not the one it sends
+        list(&db, "shield", 2000).await;
+        let bought = body(&handle_purchase(ApiVersion::V1, buy_v1(player_id, "shield", json!({})), &db, &payments, &metrics).await.unwrap());
+        assert_eq!(bought["payment"]["amountCents"], 1500);
+        assert_eq!(bought["payment"]["originalAmountCents"], 2000);
+        assert_eq!(bought["promotions"][0]["name"], "Weekend sale");
+
+        let stored = db.get_player_transactions(player_id, 10, None).await.unwrap();
+        assert_eq!(stored[0].original_price_cents, Some(2000));
+        assert_eq!(stored[0].applied_promotions[0].discount_cents, 500);
+
+        // An item without a catalog price is charged as sent, undiscounted
+        let bought = body(&handle_purchase(ApiVersion::V1, buy_v1(player_id, "helmet", json!({"price_cents": 800})), &db, &payments, &metrics).await.unwrap());
+        assert_eq!(bought["payment"]["amountCents"], 800);
+        assert!(bought["promotions"].as_array().is_none_or(|p| p.is_empty()));
+
+        let listed = body(&handle_list_promotions(post(Value::Null), &db, &metrics).await.unwrap());
+        assert_eq!(listed["count"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_segments_come_from_the_player_profile() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+        handle_create_promotion(running(json!({
+            "name": "VIP sale", "kind": "percent_off", "value": 50, "segments": ["vip"]
+        })), &db, &metrics).await.unwrap();
+        list(&db, "shield", 2000).await;
+        let player_id = Uuid::new_v4();
+
+        // Claiming a segment in the body is no longer possible
+        let claimed = post(json!({"player_id": player_id, "item_id": "shield", "item_name": "Shield", "segments": ["vip"]}));
+        assert!(matches!(handle_purchase(ApiVersion::V2, claimed, &db, &payments, &metrics).await, Err(AppError::InvalidFields(_))));
+        let bought = body(&handle_purchase(ApiVersion::V1, buy_v1(player_id, "shield", json!({"segments": ["vip"]})), &db, &payments, &metrics).await.unwrap());
+        assert_eq!(bought["payment"]["amountCents"], 2000);
+
+        let put = handle_put_player_profile(post(json!({"segments": ["vip"]})), &db, &metrics, &player_id.to_string()).await.unwrap();
+        assert_eq!(body(&put)["segments"], json!(["vip"]));
+        let bought = body(&handle_purchase(ApiVersion::V1, buy_v1(player_id, "shield", json!({})), &db, &payments, &metrics).await.unwrap());
+        assert_eq!(bought["payment"]["amountCents"], 1000);
+
+        assert!(matches!(
+            handle_put_player_profile(post(json!({"segments": []})), &db, &metrics, "not-a-uuid").await,
+            Err(AppError::InvalidFields(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_bundle_above_list_price_discounts_nothing() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+        handle_create_promotion(running(json!({
+            "name": "Overpriced pack",
+            "kind": "bundle",
+            "value": 5000,
+            "currency": "USD",
+            "item_ids": ["starter_pack"],
+            "bundle_items": [{"item_id": "sword", "quantity": 1}]
+        })), &db, &metrics).await.unwrap();
+        list(&db, "starter_pack", 2000).await;
+
+        let bought = handle_purchase(ApiVersion::V2, post(json!({
+            "player_id": Uuid::new_v4(),
+            "item_id": "starter_pack",
+            "item_name": "Starter Pack"
+        })), &db, &payments, &metrics).await.unwrap();
+        let pricing = &body(&bought)["pricing"];
+        assert_eq!(pricing["totalCents"], 2000, "never charged above the list price");
+        assert_eq!(pricing["promotions"][0]["discountCents"], 0);
+    }
+}
@@ -1,120 +1,510 @@
 //! # Purchase Handler
-//! 
+//!
 //! ADVANTAGE: Request processing is typed end-to-end
 //! ADVANTAGE: Error handling with ? operator - no try/catch nesting
+//! ADVANTAGE: API versions share one settlement pipeline, only shapes differ
 
+use chrono::Utc;
 use lambda_http::{Body, Request, Response};
-use tracing::{info, error, instrument};
+use serde::de::DeserializeOwned;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
 use validator::Validate;
 
 use crate::errors::AppError;
-use crate::models::{PurchaseRequest, PurchaseResponse, NewTransaction, TransactionStatus};
-use crate::services::{PostgresDatabase, PaymentService};
+use crate::metrics::Metrics;
+use crate::models::{
+    PurchaseRequest, PurchaseRequestV2, PurchaseResponse, PurchaseResponseV2,
+    CatalogItem, CodeCampaign, FieldError, NewTransaction, Pricing, Transaction, TransactionStatus,
+};
+use crate::models::transaction::SERVER_METADATA_KEYS;
+use crate::services::{Database, PaymentService};
+use crate::services::payment::purchase_key;
+use crate::strategies::payment::PaymentResult;
+use super::codes::CodeAttempt;
+use super::request_id::RequestId;
 use super::router::json_response;
+use super::versioning::ApiVersion;
 
-/// Handle purchase request
-/// 
+/// Handle purchase request for `version`
+///
 /// ADVANTAGE: Full request pipeline with type safety
 /// ADVANTAGE: Each step returns Result - errors bubble up automatically
-#[instrument(skip(request, db, payment_service))]
+/// to the router, which renders them in the negotiated format
+#[instrument(skip(request, db, payment_service, metrics))]
 pub async fn handle_purchase(
+    version: ApiVersion,
     request: Request,
-    db: &PostgresDatabase,
+    db: &dyn Database,
     payment_service: &PaymentService,
-) -> Response<Body> {
-    match process_purchase(request, db, payment_service).await {
-        Ok(response) => json_response(201, &response),
-        Err(e) => {
-            error!(error = %e, "Purchase failed");
-            e.into_response()
-        }
+    metrics: &Metrics,
+) -> Result<Response<Body>, AppError> {
+    match version {
+        ApiVersion::V1 => process_purchase(request, db, payment_service, metrics)
+            .await
+            .map(|response| json_response(201, &response)),
+        ApiVersion::V2 => process_purchase_v2(request, db, payment_service, metrics)
+            .await
+            .map(|response| json_response(201, &response)),
     }
 }
 
-/// Process purchase - separated for cleaner error handling
+/// Process v1 purchase - a catalog-priced item is charged its catalog
+/// price after running promotions; any other item the client-supplied price
 async fn process_purchase(
     request: Request,
-    db: &PostgresDatabase,
+    db: &dyn Database,
     payment_service: &PaymentService,
+    metrics: &Metrics,
 ) -> Result<PurchaseResponse, AppError> {
-    // STEP 1: Parse request body
-    // ADVANTAGE: JSON parsing errors are typed
-    let body = request.body();
-    let body_str = match body {
-        Body::Text(s) => s.clone(),
-        Body::Binary(b) => String::from_utf8(b.to_vec())
-            .map_err(|_| AppError::Validation("Invalid UTF-8 in body".into()))?,
-        Body::Empty => return Err(AppError::Validation("Request body required".into())),
-    };
-    
-    // STEP 2: Deserialize to typed struct
-    // ADVANTAGE: Invalid JSON shape fails here, not later
-    let purchase_req: PurchaseRequest = serde_json::from_str(&body_str)?;
-    
-    // STEP 3: Validate request
-    // ADVANTAGE: Validation rules are enforced by the type system
-    purchase_req.validate()
-        .map_err(AppError::from)?;
-    
+    // STEP 1-3: Parse, deserialize and validate
+    let purchase_req: PurchaseRequest = parse_body(&request)?;
+    let request_id = RequestId::of(&request);
+
     info!(
         player_id = %purchase_req.player_id,
         item_id = %purchase_req.item_id,
         amount = purchase_req.price_cents,
         "Processing purchase"
     );
-    
+
     // STEP 4: Create transaction record
+    let item = metrics
+        .time_db("get_catalog_item", db.get_catalog_item(&purchase_req.item_id))
+        .await?
+        .filter(|item| item.price_cents.is_some());
+    let (price_cents, currency) = match &item {
+        Some(item) => (item.total_price_cents(purchase_req.quantity)?, item.unit_price()?.1.to_string()),
+        None => (purchase_req.price_cents, purchase_req.currency.clone()),
+    };
     let new_tx = NewTransaction::new(
         purchase_req.player_id,
         purchase_req.item_id.clone(),
         purchase_req.item_name.clone(),
-        purchase_req.price_cents,
-        purchase_req.currency.clone(),
+        price_cents,
+        currency,
         purchase_req.quantity,
-        purchase_req.metadata.clone().unwrap_or(serde_json::Value::Null),
+        client_metadata(purchase_req.metadata.as_ref())?,
+    );
+    let new_tx = with_gift(new_tx, purchase_req.recipient_id)?;
+    let attempt = CodeAttempt::of(&request, purchase_req.player_id);
+    let code = match &purchase_req.promo_code {
+        Some(input) => Some(attempt.resolve(input, db, metrics).await?),
+        None => None,
+    };
+    let new_tx = apply_promotions(new_tx, item.as_ref(), code.as_ref(), db, metrics).await?;
+
+    let settled = settle(new_tx, request_id, db, payment_service, metrics).await;
+    let (updated_tx, payment_result) = match code {
+        Some(_) => attempt.check(settled, db, metrics).await?,
+        None => settled?,
+    };
+
+    // STEP 7: Build response
+    // ADVANTAGE: Response structure is compile-time guaranteed
+    Ok(PurchaseResponse::from_transaction(
+        &updated_tx,
+        Some(payment_result.processor_id),
+    ))
+}
+
+/// Process v2 purchase - the total is the catalog price times quantity
+async fn process_purchase_v2(
+    request: Request,
+    db: &dyn Database,
+    payment_service: &PaymentService,
+    metrics: &Metrics,
+) -> Result<PurchaseResponseV2, AppError> {
+    let purchase_req: PurchaseRequestV2 = parse_body(&request)?;
+    let request_id = RequestId::of(&request);
+
+    let item = metrics
+        .time_db("get_catalog_item", db.get_catalog_item(&purchase_req.item_id))
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Item {} is not in the catalog", purchase_req.item_id)))?;
+    let (unit_price_cents, currency) = item.unit_price()?;
+    let total_cents = item.total_price_cents(purchase_req.quantity)?;
+
+    info!(
+        player_id = %purchase_req.player_id,
+        item_id = %purchase_req.item_id,
+        amount = total_cents,
+        "Processing purchase"
     );
+
+    let new_tx = NewTransaction::new(
+        purchase_req.player_id,
+        purchase_req.item_id.clone(),
+        purchase_req.item_name.clone(),
+        total_cents,
+        currency.to_string(),
+        purchase_req.quantity,
+        client_metadata(purchase_req.metadata.as_ref())?,
+    );
+    let new_tx = with_gift(new_tx, purchase_req.recipient_id)?;
+    let attempt = CodeAttempt::of(&request, purchase_req.player_id);
+    let code = match &purchase_req.promo_code {
+        Some(input) => Some(attempt.resolve(input, db, metrics).await?),
+        None => None,
+    };
+    let new_tx = apply_promotions(new_tx, Some(&item), code.as_ref(), db, metrics).await?;
+
+    let settled = settle(new_tx, request_id, db, payment_service, metrics).await;
+    let (updated_tx, payment_result) = match code {
+        Some(_) => attempt.check(settled, db, metrics).await?,
+        None => settled?,
+    };
+
+    Ok(PurchaseResponseV2::from_transaction(
+        &updated_tx,
+        unit_price_cents,
+        &payment_result,
+    ))
+}
+
+/// Price `new_tx` with the promotions running now, then any discount code
+/// the player entered
+///
+/// Only a catalog list price is discounted, for the segments on the payer's
+/// profile; an item without one is charged as priced and refuses codes.
+///
+/// ADVANTAGE: Both API versions price through the same engine, and the
+/// result is stored on the transaction rather than recomputed later
+async fn apply_promotions(
+    new_tx: NewTransaction,
+    item: Option<&CatalogItem>,
+    code: Option<&(String, CodeCampaign)>,
+    db: &dyn Database,
+    metrics: &Metrics,
+) -> Result<NewTransaction, AppError> {
+    if item.is_none_or(|item| item.price_cents.is_none()) {
+        return match code {
+            Some(_) => Err(AppError::Validation(format!("Item {} has no catalog price to discount", new_tx.item_id))),
+            None => Ok(new_tx),
+        };
+    }
+
+    let now = Utc::now();
+    let promotions = metrics
+        .time_db("get_active_promotions", db.get_active_promotions(&new_tx.item_id, now))
+        .await?;
+    let segments = metrics
+        .time_db("get_player_profile", db.get_player_profile(new_tx.player_id))
+        .await?
+        .map(|profile| profile.segments)
+        .unwrap_or_default();
+
+    let mut pricing = Pricing::evaluate(&new_tx, &segments, &promotions, now);
+    if let Some((code, campaign)) = code {
+        let discount = campaign
+            .discount()
+            .ok_or_else(|| AppError::Validation("Gift codes are redeemed at /codes/redeem".into()))?;
+        if !discount.applies_to(&new_tx, &segments, now) {
+            return Err(AppError::Validation("Code does not apply to this purchase".into()));
+        }
+        pricing = pricing.with_code(&discount, new_tx.quantity, code);
+    }
+    if !pricing.applied.is_empty() {
+        info!(
+            original = pricing.original_price_cents,
+            price = pricing.price_cents,
+            promotions = pricing.applied.len(),
+            "Promotions applied"
+        );
+    }
+
+    let new_tx = new_tx.with_pricing(pricing);
+    Ok(match code {
+        Some((code, _)) => new_tx.with_code(code.clone()),
+        None => new_tx,
+    })
+}
+
+/// Purchase metadata as sent, refusing keys the server owns
+///
+/// ADVANTAGE: A purchase cannot credit a wallet or extend access by naming
+/// the metadata that does so
+fn client_metadata(metadata: Option<&serde_json::Value>) -> Result<serde_json::Value, AppError> {
+    let Some(metadata) = metadata else {
+        return Ok(serde_json::Value::Null);
+    };
+    if let Some(key) = SERVER_METADATA_KEYS.iter().find(|key| metadata.get(**key).is_some()) {
+        return Err(AppError::InvalidFields(vec![FieldError::new(
+            format!("metadata.{}", key),
+            "reserved",
+            "is set by the server",
+        )]));
+    }
+    Ok(metadata.clone())
+}
+
+/// Make `new_tx` a gift when the request names a recipient
+///
+/// The recipient's limits and ownership are checked by the database when
+/// the transaction is inserted.
+fn with_gift(new_tx: NewTransaction, recipient_id: Option<Uuid>) -> Result<NewTransaction, AppError> {
+    match recipient_id {
+        Some(recipient_id) if recipient_id == new_tx.player_id => {
+            Err(AppError::Validation("A gift needs a recipient other than the payer".into()))
+        }
+        Some(recipient_id) => Ok(new_tx.with_recipient(recipient_id)),
+        None => Ok(new_tx),
+    }
+}
+
+/// Read, deserialize and validate a JSON body
+///
+/// ADVANTAGE: Invalid JSON shape fails here, not later
+pub(super) fn parse_body<T: DeserializeOwned + Validate>(request: &Request) -> Result<T, AppError> {
+    let body_str = match request.body() {
+        Body::Text(s) => s.clone(),
+        Body::Binary(b) => String::from_utf8(b.to_vec())
+            .map_err(|_| AppError::Validation("Invalid UTF-8 in body".into()))?,
+        Body::Empty => return Err(AppError::Validation("Request body required".into())),
+    };
+
+    // ADVANTAGE: Type errors report the JSON path of the offending field
+    let parsed: T = serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(&body_str))?;
+
+    // ADVANTAGE: Validation rules are enforced by the type system
+    parsed.validate().map_err(AppError::from)?;
+
+    Ok(parsed)
+}
+
+/// Record the transaction, charge it, and store the outcome
+///
+/// ADVANTAGE: The request ID is stored on the row and sent to the processor
+pub(super) async fn settle(
+    new_tx: NewTransaction,
+    request_id: Option<&RequestId>,
+    db: &dyn Database,
+    payment_service: &PaymentService,
+    metrics: &Metrics,
+) -> Result<(Transaction, PaymentResult), AppError> {
+    let new_tx = match request_id {
+        Some(id) => new_tx.with_request_id(id.as_str()),
+        None => new_tx,
+    };
     
+    // STEP 4: Insert pending transaction
     // ADVANTAGE: Transaction ID is generated and typed
-    let tx = db.insert_transaction(&new_tx).await?;
-    
+    let tx = metrics
+        .time_db("insert_transaction", db.insert_transaction(&new_tx))
+        .await?;
+
     // STEP 5: Process payment via strategy
     // ADVANTAGE: Payment service handles strategy selection
-    let payment_result = payment_service
+    let payment_result = match payment_service
         .process_purchase(
             tx.transaction_id,
             tx.player_id,
             tx.price_cents,
             &tx.currency,
+            tx.request_id.as_deref(),
         )
-        .await?;
-    
+        .await
+    {
+        Ok(result) => result,
+        Err(e) => recover_unsettled(&tx, e, db, payment_service, metrics).await?,
+    };
+
     // STEP 6: Update transaction status
     let final_status = if payment_result.success {
         TransactionStatus::Completed
     } else {
         TransactionStatus::Failed
     };
-    
-    let updated_tx = db
-        .update_transaction_status(
-            tx.transaction_id,
-            final_status,
-            Some(&payment_result.processor_id),
+
+    let updated_tx = metrics
+        .time_db(
+            "update_transaction_status",
+            db.update_transaction_status(
+                tx.transaction_id,
+                final_status,
+                Some(&payment_result.processor_id),
+            ),
         )
         .await?;
     
-    // STEP 7: Build response
-    // ADVANTAGE: Response structure is compile-time guaranteed
-    let response = PurchaseResponse::from_transaction(
-        &updated_tx,
-        Some(payment_result.processor_id),
-    );
-    
+    // ADVANTAGE: Business metrics come from the stored row, not the request
+    metrics.record_purchase(&updated_tx, payment_service.strategy_name(), &payment_result);
+
     info!(
         transaction_id = %updated_tx.transaction_id,
         status = ?final_status,
         "Purchase completed"
     );
-    
-    Ok(response)
+
+    Ok((updated_tx, payment_result))
+}
+
+/// Find out what became of `tx`'s charge after the processor errored
+///
+/// The processor is asked for the charge sent under the purchase's
+/// idempotency key, and a charge that went through settles the purchase
+/// anyway. Only a charge it never received, or one the service refused to
+/// send, fails the transaction and releases its stock. When even the lookup
+/// errors the row is left pending for the `reap-pending` job, and the
+/// processor's error is handed back.
+async fn recover_unsettled(
+    tx: &Transaction,
+    error: AppError,
+    db: &dyn Database,
+    payment_service: &PaymentService,
+    metrics: &Metrics,
+) -> Result<PaymentResult, AppError> {
+    if !matches!(error, AppError::Validation(_)) {
+        warn!(transaction_id = %tx.transaction_id, error = %error, "Payment errored; looking up the charge");
+        match payment_service.find_charge(&purchase_key(tx.transaction_id)).await {
+            Ok(Some(result)) => return Ok(result),
+            Ok(None) => {}
+            Err(e) => {
+                warn!(transaction_id = %tx.transaction_id, error = %e, "Charge outcome unknown; transaction left pending");
+                return Err(error);
+            }
+        }
+    }
+
+    let failed = metrics
+        .time_db(
+            "update_transaction_status",
+            db.update_transaction_status(tx.transaction_id, TransactionStatus::Failed, None),
+        )
+        .await;
+    if let Err(e) = failed {
+        warn!(transaction_id = %tx.transaction_id, error = %e, "Transaction left pending");
+    }
+    Err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+    use crate::models::CatalogItemRequest;
+    use crate::services::InMemoryDatabase;
+    use crate::strategies::payment::{MockPaymentStrategy, PaymentRequest, PaymentStrategy};
+    use crate::test_support::post;
+
+    /// Processor that never answers
+    struct UnreachableStrategy;
+
+    #[async_trait::async_trait]
+    impl PaymentStrategy for UnreachableStrategy {
+        async fn process_payment(&self, _: PaymentRequest) -> Result<PaymentResult, AppError> {
+            Err(AppError::Unavailable("processor timed out".into()))
+        }
+
+        async fn refund_payment(&self, _: &str, _: i64, _: &str) -> Result<PaymentResult, AppError> {
+            Err(AppError::Unavailable("processor timed out".into()))
+        }
+
+        fn name(&self) -> &'static str {
+            "unreachable"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_cannot_send_server_metadata() {
+        let db = InMemoryDatabase::new();
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+        let player_id = Uuid::new_v4();
+        let purchase = post(json!({
+            "player_id": player_id,
+            "item_id": "sword",
+            "item_name": "Sword",
+            "price_cents": 1,
+            "currency": "USD",
+            "metadata": {"wallet_top_up": {"currency": "gems", "amount": 1000000}}
+        }));
+
+        let Err(AppError::InvalidFields(fields)) =
+            handle_purchase(ApiVersion::V1, purchase, &db, &payments, &Metrics::default()).await
+        else {
+            panic!("expected reserved metadata to be refused");
+        };
+        assert_eq!(fields[0].field, "metadata.wallet_top_up");
+        assert!(db.get_player_transactions(player_id, 10, None).await.unwrap().is_empty());
+    }
+
+    /// Processor whose every charge times out, after going through when `charges`
+    struct TimeoutStrategy {
+        mock: MockPaymentStrategy,
+        charges: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl PaymentStrategy for TimeoutStrategy {
+        async fn process_payment(&self, request: PaymentRequest) -> Result<PaymentResult, AppError> {
+            if self.charges {
+                self.mock.process_payment(request).await?;
+            }
+            Err(AppError::Unavailable("processor timed out".into()))
+        }
+
+        async fn refund_payment(&self, processor_id: &str, amount: i64, key: &str) -> Result<PaymentResult, AppError> {
+            self.mock.refund_payment(processor_id, amount, key).await
+        }
+
+        async fn find_payment(&self, idempotency_key: &str) -> Result<Option<PaymentResult>, AppError> {
+            self.mock.find_payment(idempotency_key).await
+        }
+
+        fn name(&self) -> &'static str {
+            "timeout"
+        }
+    }
+
+    async fn list_cape(db: &InMemoryDatabase) {
+        let listing: CatalogItemRequest = serde_json::from_value(json!({
+            "kind": "non_consumable", "stock_limit": 1, "price_cents": 999, "currency": "USD"
+        })).unwrap();
+        db.upsert_catalog_item("founders_cape", &listing).await.unwrap();
+    }
+
+    fn buy_cape(player_id: Uuid) -> Request {
+        post(json!({"player_id": player_id, "item_id": "founders_cape", "item_name": "Founders Cape"}))
+    }
+
+    #[tokio::test]
+    async fn test_unknown_charge_outcome_leaves_the_purchase_pending() {
+        let db = InMemoryDatabase::new();
+        let payments = PaymentService::new(Arc::new(UnreachableStrategy));
+        list_cape(&db).await;
+        let player_id = Uuid::new_v4();
+
+        let result = handle_purchase(ApiVersion::V2, buy_cape(player_id), &db, &payments, &Metrics::default()).await;
+        assert!(matches!(result, Err(AppError::Unavailable(_))));
+
+        // The charge may have gone through, so neither the row nor its stock is given up
+        let stored = db.get_player_transactions(player_id, 10, None).await.unwrap();
+        assert_eq!(stored[0].status, TransactionStatus::Pending);
+        assert_eq!(db.get_catalog_item("founders_cape").await.unwrap().unwrap().stock_reserved, 1);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_charge_settles_to_what_the_processor_reports() {
+        let db = InMemoryDatabase::new();
+        list_cape(&db).await;
+
+        // Never received: failed, and the stock is free to sell again
+        let unsent = PaymentService::new(Arc::new(TimeoutStrategy { mock: MockPaymentStrategy::new(), charges: false }));
+        let player_id = Uuid::new_v4();
+        let result = handle_purchase(ApiVersion::V2, buy_cape(player_id), &db, &unsent, &Metrics::default()).await;
+        assert!(matches!(result, Err(AppError::Unavailable(_))));
+        let stored = db.get_player_transactions(player_id, 10, None).await.unwrap();
+        assert_eq!(stored[0].status, TransactionStatus::Failed);
+        assert_eq!(db.get_catalog_item("founders_cape").await.unwrap().unwrap().stock_reserved, 0);
+
+        // Charged before the timeout: the purchase completes
+        let charged = PaymentService::new(Arc::new(TimeoutStrategy { mock: MockPaymentStrategy::new(), charges: true }));
+        let response = handle_purchase(ApiVersion::V2, buy_cape(player_id), &db, &charged, &Metrics::default()).await.unwrap();
+        assert_eq!(response.status(), 201);
+        let stored = db.get_player_transactions(player_id, 10, None).await.unwrap();
+        let completed = stored.iter().find(|tx| tx.status == TransactionStatus::Completed).unwrap();
+        assert!(completed.processor_id.as_deref().is_some_and(|id| id.starts_with("mock_")));
+        assert_eq!(db.get_catalog_item("founders_cape").await.unwrap().unwrap().stock_sold, 1);
+    }
 }
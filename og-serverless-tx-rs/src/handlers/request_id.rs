@@ -0,0 +1,96 @@
+//! # Request Correlation IDs
+//!
+//! Every request gets one ID, chosen in this order:
+//!
+//! 1. An inbound `X-Request-Id` header - game clients generate it and show
+//!    it to players, so support tickets quote it
+//! 2. The API Gateway request ID
+//! 3. A fresh UUID (dev server, direct invokes)
+//!
+//! ADVANTAGE: One ID ties the client, the logs, the row and the charge together
+//! ADVANTAGE: Inbound IDs are validated - clients can't inject log noise
+
+use lambda_http::request::RequestContext;
+use lambda_http::{Request, RequestExt};
+use std::fmt;
+use uuid::Uuid;
+
+/// Header carrying the ID on requests and responses
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Longest inbound ID that is accepted as-is
+const MAX_LEN: usize = 128;
+
+/// Correlation ID for one request
+///
+/// Stored in the request extensions by the router, so handlers read it
+/// with `RequestId::of`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Pick the ID for `request`
+    pub fn from_request(request: &Request) -> Self {
+        let inbound = request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| is_valid(v));
+
+        if let Some(id) = inbound {
+            return Self(id.to_string());
+        }
+
+        let gateway = match request.request_context_ref() {
+            Some(RequestContext::ApiGatewayV1(context)) => context.request_id.clone(),
+            Some(RequestContext::ApiGatewayV2(context)) => context.request_id.clone(),
+            _ => None,
+        };
+
+        Self(gateway.unwrap_or_else(|| Uuid::new_v4().to_string()))
+    }
+
+    /// ID attached to `request` by the router, if any
+    pub fn of(request: &Request) -> Option<&Self> {
+        request.extensions().get::<Self>()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Short, header-safe token - letters, digits and `-_.:`
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LEN
+        && id.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_.:".contains(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    fn test_inbound_header_wins_when_valid() {
+        let request: Request = lambda_http::http::Request::builder()
+            .header(REQUEST_ID_HEADER, "client-7f3a:42")
+            .body(Body::Empty)
+            .unwrap();
+        assert_eq!(RequestId::from_request(&request).as_str(), "client-7f3a:42");
+
+        let request: Request = lambda_http::http::Request::builder()
+            .header(REQUEST_ID_HEADER, "bad id\twith spaces")
+            .body(Body::Empty)
+            .unwrap();
+        let generated = RequestId::from_request(&request);
+        assert!(generated.as_str().parse::<Uuid>().is_ok());
+    }
+}
@@ -1,108 +1,612 @@
 //! # Request Router
 //! 
-//! ADVANTAGE: Pattern matching ensures all routes are handled
-//! ADVANTAGE: Exhaustive matching prevents forgotten endpoints
-//! ADVANTAGE: No runtime string comparisons for routing
+//! ADVANTAGE: Routes live in one declarative table
+//! ADVANTAGE: Exhaustive matching on `Endpoint` prevents forgotten handlers
+//! ADVANTAGE: 405 and OPTIONS responses come from the table, not hand-written cases
 
-use lambda_http::{Body, Request, Response, http::Method};
+use lambda_http::{Body, Request, RequestExt, Response, http::Method};
+use lambda_http::request::RequestContext;
 use std::sync::Arc;
-use tracing::{info, warn};
+use std::time::{Duration, Instant};
+use lambda_http::http::{HeaderMap, HeaderValue};
+use tracing::{error, info, info_span, warn, Instrument};
 
-use crate::services::{PostgresDatabase, PaymentService};
+use crate::services::{Database, PaymentService};
+use crate::services::health::{
+    CircuitBreakerCheck, DatabaseCheck, HealthCheck, MigrationCheck, PaymentProcessorCheck,
+    DEFAULT_DB_DEGRADED_AFTER,
+};
 use crate::errors::AppError;
+use crate::metrics::Metrics;
+use crate::models::config::ErrorFormat;
+use crate::telemetry;
 
-use super::{purchase, transactions, health};
+use super::cors::{CorsPolicy, OriginCheck};
+use super::request_id::{RequestId, REQUEST_ID_HEADER};
+use super::routes::{allow_header, strip_stage, PathParams, RouteMatch, RouteTable};
+use super::versioning::{self, ApiVersion, Lifecycle};
+use super::{purchase, transactions, health, wallet, ledger, entitlements, webhooks, catalog, promotions, codes, gifts, subscriptions};
+
+/// Handler selected by the route table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Endpoint {
+    Purchase,
+    Transactions,
+    /// Readiness - every component check
+    Health,
+    /// Liveness - no dependencies called
+    Liveness,
+    Wallet,
+    WalletTopUp,
+    WalletSpend,
+    /// Player's double-entry journal
+    PlayerLedger,
+    /// Items the player owns
+    Entitlements,
+    /// A title's webhook subscriptions
+    Webhooks,
+    CreateWebhook,
+    /// One subscription's delivery log
+    WebhookDeliveries,
+    WebhookRedeliver,
+    /// Item kind and stock counters
+    CatalogItem,
+    PutCatalogItem,
+    /// Scheduled discounts and bundles
+    Promotions,
+    CreatePromotion,
+    /// Segments a player's promotions target
+    PutPlayerProfile,
+    CreateCodeCampaign,
+    RedeemCode,
+    /// Recipient's answer to a gift
+    AcceptGift,
+    DeclineGift,
+    /// Subscription plans and season passes
+    PutSubscriptionPlan,
+    Subscribe,
+    PlayerSubscriptions,
+    CancelSubscription,
+    ResumeSubscription,
+    ChangeSubscriptionPlan,
+}
+
+impl Endpoint {
+    /// First version serving this endpoint
+    ///
+    /// Endpoints added after v2 shipped are mounted on v2 only, so the
+    /// deprecated v1 contract stops growing.
+    const fn since(&self) -> ApiVersion {
+        match self {
+            Self::Purchase
+            | Self::Transactions
+            | Self::Health
+            | Self::Liveness => ApiVersion::V1,
+            Self::Wallet
+            | Self::WalletTopUp
+            | Self::WalletSpend
+            | Self::PlayerLedger
+            | Self::Entitlements
+            | Self::Webhooks
+            | Self::CreateWebhook
+            | Self::WebhookDeliveries
+            | Self::WebhookRedeliver
+            | Self::CatalogItem
+            | Self::PutCatalogItem
+            | Self::Promotions
+            | Self::CreatePromotion
+            | Self::PutPlayerProfile
+            | Self::CreateCodeCampaign
+            | Self::RedeemCode
+            | Self::AcceptGift
+            | Self::DeclineGift
+            | Self::PutSubscriptionPlan
+            | Self::Subscribe
+            | Self::PlayerSubscriptions
+            | Self::CancelSubscription
+            | Self::ResumeSubscription
+            | Self::ChangeSubscriptionPlan => ApiVersion::V2,
+        }
+    }
+    
+    /// Operator endpoints, served only under `/admin` to IAM-signed callers
+    ///
+    /// ADVANTAGE: Game clients cannot reach them on any public prefix
+    const fn is_admin(&self) -> bool {
+        matches!(
+            self,
+            Self::Webhooks
+                | Self::CreateWebhook
+                | Self::WebhookDeliveries
+                | Self::WebhookRedeliver
+                | Self::PutCatalogItem
+                | Self::CreatePromotion
+                | Self::PutPlayerProfile
+                | Self::CreateCodeCampaign
+                | Self::PutSubscriptionPlan
+        )
+    }
+    
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Purchase => "purchase",
+            Self::Transactions => "transactions",
+            Self::Health => "health",
+            Self::Liveness => "liveness",
+            Self::Wallet => "wallet",
+            Self::WalletTopUp => "wallet_top_up",
+            Self::WalletSpend => "wallet_spend",
+            Self::PlayerLedger => "player_ledger",
+            Self::Entitlements => "entitlements",
+            Self::Webhooks => "webhooks",
+            Self::CreateWebhook => "create_webhook",
+            Self::WebhookDeliveries => "webhook_deliveries",
+            Self::WebhookRedeliver => "webhook_redeliver",
+            Self::CatalogItem => "catalog_item",
+            Self::PutCatalogItem => "put_catalog_item",
+            Self::Promotions => "promotions",
+            Self::CreatePromotion => "create_promotion",
+            Self::PutPlayerProfile => "put_player_profile",
+            Self::CreateCodeCampaign => "create_code_campaign",
+            Self::RedeemCode => "redeem_code",
+            Self::AcceptGift => "accept_gift",
+            Self::DeclineGift => "decline_gift",
+            Self::PutSubscriptionPlan => "put_subscription_plan",
+            Self::Subscribe => "subscribe",
+            Self::PlayerSubscriptions => "player_subscriptions",
+            Self::CancelSubscription => "cancel_subscription",
+            Self::ResumeSubscription => "resume_subscription",
+            Self::ChangeSubscriptionPlan => "change_subscription_plan",
+        }
+    }
+}
 
 /// HTTP request router
 /// 
 /// ADVANTAGE: Dependencies are injected at construction
 /// ADVANTAGE: Router is stateless - services are shared via Arc
 pub struct Router {
-    db: Arc<PostgresDatabase>,
+    db: Arc<dyn Database>,
     payment_service: Arc<PaymentService>,
+    routes: RouteTable<(ApiVersion, Endpoint)>,
+    cors: CorsPolicy,
+    error_format: ErrorFormat,
+    metrics: Metrics,
+    /// Checks beyond the built-in database, schema and payment checks
+    health_checks: Vec<Arc<dyn HealthCheck>>,
+    db_degraded_after: Duration,
+    /// Deprecation schedules of superseded versions
+    lifecycles: Vec<(ApiVersion, Lifecycle)>,
 }
 
 impl Router {
-    pub fn new(db: Arc<PostgresDatabase>, payment_service: Arc<PaymentService>) -> Self {
-        Self { db, payment_service }
+    pub fn new(db: Arc<dyn Database>, payment_service: Arc<PaymentService>) -> Self {
+        use ApiVersion::{V1, V2};
+        
+        // Declared once and mounted per version; unversioned paths are the
+        // original contract and stay on v1. Admin endpoints are mounted only
+        // under /admin, which API Gateway fronts with IAM auth
+        let api = RouteTable::new()
+            .route(Method::POST, "/purchase", Endpoint::Purchase)
+            .route(Method::GET, "/transactions/{playerId}", Endpoint::Transactions)
+            .route(Method::GET, "/health", Endpoint::Health)
+            .route(Method::GET, "/health/ready", Endpoint::Health)
+            .route(Method::GET, "/health/live", Endpoint::Liveness)
+            .route(Method::GET, "/wallet/{playerId}", Endpoint::Wallet)
+            .route(Method::POST, "/wallet/top-up", Endpoint::WalletTopUp)
+            .route(Method::POST, "/wallet/spend", Endpoint::WalletSpend)
+            .route(Method::GET, "/players/{playerId}/ledger", Endpoint::PlayerLedger)
+            .route(Method::GET, "/players/{playerId}/entitlements", Endpoint::Entitlements)
+            .route(Method::GET, "/titles/{titleId}/webhooks", Endpoint::Webhooks)
+            .route(Method::POST, "/titles/{titleId}/webhooks", Endpoint::CreateWebhook)
+            .route(Method::GET, "/webhooks/{subscriptionId}/deliveries", Endpoint::WebhookDeliveries)
+            .route(Method::POST, "/webhooks/deliveries/{deliveryId}/redeliver", Endpoint::WebhookRedeliver)
+            .route(Method::GET, "/catalog/items/{itemId}", Endpoint::CatalogItem)
+            .route(Method::PUT, "/catalog/items/{itemId}", Endpoint::PutCatalogItem)
+            .route(Method::GET, "/promotions", Endpoint::Promotions)
+            .route(Method::POST, "/promotions", Endpoint::CreatePromotion)
+            .route(Method::PUT, "/players/{playerId}/profile", Endpoint::PutPlayerProfile)
+            .route(Method::POST, "/codes/campaigns", Endpoint::CreateCodeCampaign)
+            .route(Method::POST, "/codes/redeem", Endpoint::RedeemCode)
+            .route(Method::POST, "/gifts/{transactionId}/accept", Endpoint::AcceptGift)
+            .route(Method::POST, "/gifts/{transactionId}/decline", Endpoint::DeclineGift)
+            .route(Method::PUT, "/subscriptions/plans/{planId}", Endpoint::PutSubscriptionPlan)
+            .route(Method::POST, "/subscriptions", Endpoint::Subscribe)
+            .route(Method::GET, "/players/{playerId}/subscriptions", Endpoint::PlayerSubscriptions)
+            .route(Method::POST, "/subscriptions/{subscriptionId}/cancel", Endpoint::CancelSubscription)
+            .route(Method::POST, "/subscriptions/{subscriptionId}/resume", Endpoint::ResumeSubscription)
+            .route(Method::POST, "/subscriptions/{subscriptionId}/change-plan", Endpoint::ChangeSubscriptionPlan);
+        let routes = [("", V1, false), ("/v1", V1, false), ("/v2", V2, false), ("/admin/v2", V2, true)]
+            .into_iter()
+            .fold(RouteTable::new(), |table, (prefix, version, admin)| {
+                table.mount(prefix, &api, |endpoint: Endpoint| {
+                    (endpoint.since() <= version && endpoint.is_admin() == admin).then_some((version, endpoint))
+                })
+            });
+        
+        Self {
+            db,
+            payment_service,
+            routes,
+            cors: CorsPolicy::default(),
+            error_format: ErrorFormat::default(),
+            metrics: Metrics::default(),
+            health_checks: Vec::new(),
+            db_degraded_after: DEFAULT_DB_DEGRADED_AFTER,
+            lifecycles: Vec::new(),
+        }
+    }
+    
+    /// Announce `version`'s deprecation schedule on its responses; `None`
+    /// leaves the version undeprecated
+    pub fn with_lifecycle(mut self, version: ApiVersion, lifecycle: Option<Lifecycle>) -> Self {
+        self.lifecycles.retain(|(v, _)| *v != version);
+        self.lifecycles.extend(lifecycle.map(|l| (version, l)));
+        self
+    }
+    
+    /// Replace the default CORS policy, which allows no browser origins
+    pub fn with_cors(mut self, cors: CorsPolicy) -> Self {
+        self.cors = cors;
+        self
+    }
+    
+    /// Report request, purchase and database metrics to `metrics`
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+    
+    /// Add a component to the readiness check
+    pub fn with_health_check(mut self, check: Arc<dyn HealthCheck>) -> Self {
+        self.health_checks.push(check);
+        self
+    }
+    
+    /// Database round trip above which readiness reports degraded
+    pub fn with_db_degraded_after(mut self, threshold: Duration) -> Self {
+        self.db_degraded_after = threshold;
+        self
+    }
+    
+    /// Error body format for clients that don't ask for problem+json
+    pub fn with_error_format(mut self, error_format: ErrorFormat) -> Self {
+        self.error_format = error_format;
+        self
     }
     
     /// Route incoming request to appropriate handler
     /// 
-    /// ADVANTAGE: Pattern matching on (Method, Path)
-    /// ADVANTAGE: Compiler warns about unhandled patterns
-    /// ADVANTAGE: No regex parsing overhead
-    pub async fn route(&self, request: Request) -> Response<Body> {
+    /// Runs inside a root `request` span carrying the correlation ID, which
+    /// is also echoed in the `X-Request-Id` response header.
+    /// 
+    /// ADVANTAGE: Every log line of a request shares one `request_id` field
+    pub async fn route(&self, mut request: Request) -> Response<Body> {
+        let request_id = RequestId::from_request(&request);
+        let span = info_span!("request", request_id = %request_id);
+        telemetry::set_remote_parent(&span, request.headers());
+        request.extensions_mut().insert(request_id.clone());
+        
+        let mut response = self.route_request(request, &request_id).instrument(span).await;
+        
+        if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+            response.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+        response
+    }
+    
+    /// Match the route table and dispatch
+    /// 
+    /// ADVANTAGE: Table lookup yields a typed `RouteMatch`
+    /// ADVANTAGE: Compiler warns about unhandled endpoints
+    async fn route_request(&self, request: Request, request_id: &RequestId) -> Response<Body> {
         let method = request.method().clone();
         let path = request.uri().path().to_string();
+        let stage = api_stage(&request);
         
-        info!(method = %method, path = %path, "Routing request");
+        let request_headers = request.headers().clone();
         
-        // ADVANTAGE: Exhaustive pattern matching
-        // The compiler ensures we handle all cases
-        match (method, path.as_str()) {
-            // Purchase endpoint
-            (Method::POST, "/purchase") => {
-                self.handle_purchase(request).await
+        // The concrete path carries player IDs the redaction layer cannot
+        // see, so only the matched route template is logged
+        let matched = self.routes.find(&method, &path, stage.as_deref());
+        let route = match &matched {
+            RouteMatch::Found { template, .. } => template.as_str(),
+            _ => "unmatched",
+        };
+        info!(method = %method, route, "Routing request");
+        
+        let mut response = match matched {
+            RouteMatch::Found { .. }
+                if CorsPolicy::requires_allowed_origin(&method)
+                    && self.cors.check(&request) == OriginCheck::Denied =>
+            {
+                warn!(method = %method, route, "Origin not allowed");
+                self.error_response(
+                    AppError::OriginNotAllowed("Origin is not allowed to call this API".into()),
+                    &request_headers,
+                    &path,
+                    request_id,
+                )
+            }
+            
+            RouteMatch::Found { target: (_, endpoint), .. }
+                if endpoint.is_admin() && iam_caller(&request).is_none() =>
+            {
+                warn!(method = %method, route, "Admin call without IAM identity");
+                self.error_response(
+                    AppError::Unauthenticated("Admin endpoints require a signed IAM request".into()),
+                    &request_headers,
+                    &path,
+                    request_id,
+                )
             }
             
-            // Get player transactions - with path parameter extraction
-            (Method::GET, path) if path.starts_with("/transactions/") => {
-                // ADVANTAGE: Path parsing is explicit and typed
-                let player_id = path.strip_prefix("/transactions/")
-                    .unwrap_or("")
-                    .trim_end_matches('/');
+            RouteMatch::Found { target: (version, endpoint), params, .. } => {
+                let start = Instant::now();
+                let mut response = match self.dispatch(version, endpoint, request, params).await {
+                    Ok(response) => response,
+                    Err(e) => self.error_response(e, &request_headers, &path, request_id),
+                };
                 
-                self.handle_get_transactions(request, player_id).await
+                let lifecycle = self.lifecycles.iter().find(|(v, _)| *v == version).map(|(_, l)| l);
+                versioning::apply_lifecycle_headers(lifecycle, &mut response);
+                self.metrics.record_request(
+                    endpoint.name(),
+                    version.as_str(),
+                    response.status().as_u16(),
+                    start.elapsed(),
+                );
+                response
             }
             
-            // Health check
-            (Method::GET, "/health") => {
-                self.handle_health(request).await
+            // CORS preflight - methods derived from the table
+            RouteMatch::Options { allowed } => {
+                return self.cors.preflight(&request_headers, &allow_header(&allowed));
             }
             
-            // CORS preflight
-            (Method::OPTIONS, _) => {
-                self.cors_response()
+            RouteMatch::MethodNotAllowed { allowed } => {
+                warn!(method = %method, "Method not allowed");
+                self.method_not_allowed(&method, &allow_header(&allowed), &request_headers, &path, request_id)
             }
             
             // Not found - ADVANTAGE: Explicit handling of unknown routes
-            _ => {
-                warn!(method = %method, path = %path, "Route not found");
-                self.not_found()
+            RouteMatch::NotFound => {
+                warn!(method = %method, "Route not found");
+                let error = match versioning::requested_version(strip_stage(&path, stage.as_deref())) {
+                    Some(Err(requested)) => AppError::UnsupportedApiVersion {
+                        requested,
+                        supported: ApiVersion::supported_names(),
+                    },
+                    _ => AppError::NotFound("Endpoint not found".into()),
+                };
+                self.error_response(error, &request_headers, &path, request_id)
+            }
+        };
+        
+        // ADVANTAGE: Success and error responses get identical CORS treatment
+        self.cors.apply(&request_headers, &mut response);
+        response
+    }
+    
+    /// Call the handler for a matched endpoint
+    async fn dispatch(
+        &self,
+        version: ApiVersion,
+        endpoint: Endpoint,
+        request: Request,
+        params: PathParams,
+    ) -> Result<Response<Body>, AppError> {
+        match endpoint {
+            Endpoint::Purchase => self.handle_purchase(version, request).await,
+            Endpoint::Transactions => {
+                let player_id = params.get("playerId").unwrap_or_default();
+                self.handle_get_transactions(request, player_id).await
+            }
+            Endpoint::Health => Ok(self.handle_health(request).await),
+            Endpoint::Liveness => Ok(health::handle_liveness()),
+            Endpoint::Wallet => {
+                let player_id = params.get("playerId").unwrap_or_default();
+                wallet::handle_get_wallet(self.db.as_ref(), &self.metrics, player_id).await
+            }
+            Endpoint::WalletTopUp => {
+                wallet::handle_top_up(request, self.db.as_ref(), &self.payment_service, &self.metrics).await
+            }
+            Endpoint::WalletSpend => wallet::handle_spend(request, self.db.as_ref(), &self.metrics).await,
+            Endpoint::PlayerLedger => {
+                let player_id = params.get("playerId").unwrap_or_default();
+                ledger::handle_get_player_ledger(request, self.db.as_ref(), &self.metrics, player_id).await
+            }
+            Endpoint::Entitlements => {
+                let player_id = params.get("playerId").unwrap_or_default();
+                entitlements::handle_get_entitlements(request, self.db.as_ref(), &self.metrics, player_id).await
+            }
+            Endpoint::Webhooks => {
+                let title_id = params.get("titleId").unwrap_or_default();
+                webhooks::handle_list_webhooks(self.db.as_ref(), &self.metrics, title_id).await
+            }
+            Endpoint::CreateWebhook => {
+                let title_id = params.get("titleId").unwrap_or_default();
+                webhooks::handle_create_webhook(request, self.db.as_ref(), &self.metrics, title_id).await
+            }
+            Endpoint::WebhookDeliveries => {
+                let subscription_id = params.get("subscriptionId").unwrap_or_default();
+                webhooks::handle_get_deliveries(request, self.db.as_ref(), &self.metrics, subscription_id).await
+            }
+            Endpoint::WebhookRedeliver => {
+                let delivery_id = params.get("deliveryId").unwrap_or_default();
+                webhooks::handle_redeliver(self.db.as_ref(), &self.metrics, delivery_id).await
+            }
+            Endpoint::CatalogItem => {
+                let item_id = params.get("itemId").unwrap_or_default();
+                catalog::handle_get_catalog_item(self.db.as_ref(), &self.metrics, item_id).await
+            }
+            Endpoint::PutCatalogItem => {
+                let item_id = params.get("itemId").unwrap_or_default();
+                catalog::handle_put_catalog_item(request, self.db.as_ref(), &self.metrics, item_id).await
+            }
+            Endpoint::Promotions => {
+                promotions::handle_list_promotions(request, self.db.as_ref(), &self.metrics).await
+            }
+            Endpoint::CreatePromotion => {
+                promotions::handle_create_promotion(request, self.db.as_ref(), &self.metrics).await
+            }
+            Endpoint::PutPlayerProfile => {
+                let player_id = params.get("playerId").unwrap_or_default();
+                promotions::handle_put_player_profile(request, self.db.as_ref(), &self.metrics, player_id).await
+            }
+            Endpoint::CreateCodeCampaign => {
+                codes::handle_create_code_campaign(request, self.db.as_ref(), &self.metrics).await
+            }
+            Endpoint::RedeemCode => {
+                codes::handle_redeem_code(request, self.db.as_ref(), &self.metrics).await
+            }
+            Endpoint::AcceptGift | Endpoint::DeclineGift => {
+                let transaction_id = params.get("transactionId").unwrap_or_default();
+                let accept = matches!(endpoint, Endpoint::AcceptGift);
+                gifts::handle_answer_gift(
+                    request,
+                    self.db.as_ref(),
+                    &self.payment_service,
+                    &self.metrics,
+                    transaction_id,
+                    accept,
+                )
+                .await
+            }
+            Endpoint::PutSubscriptionPlan => {
+                let plan_id = params.get("planId").unwrap_or_default();
+                subscriptions::handle_put_plan(request, self.db.as_ref(), &self.metrics, plan_id).await
+            }
+            Endpoint::Subscribe => {
+                subscriptions::handle_subscribe(request, self.db.as_ref(), &self.payment_service, &self.metrics).await
+            }
+            Endpoint::PlayerSubscriptions => {
+                let player_id = params.get("playerId").unwrap_or_default();
+                subscriptions::handle_get_player_subscriptions(self.db.as_ref(), &self.metrics, player_id).await
+            }
+            Endpoint::CancelSubscription | Endpoint::ResumeSubscription => {
+                let subscription_id = params.get("subscriptionId").unwrap_or_default();
+                let cancel = matches!(endpoint, Endpoint::CancelSubscription);
+                subscriptions::handle_cancel_subscription(request, self.db.as_ref(), &self.metrics, subscription_id, cancel)
+                    .await
+            }
+            Endpoint::ChangeSubscriptionPlan => {
+                let subscription_id = params.get("subscriptionId").unwrap_or_default();
+                subscriptions::handle_change_plan(
+                    request,
+                    self.db.as_ref(),
+                    &self.payment_service,
+                    &self.metrics,
+                    subscription_id,
+                )
+                .await
             }
         }
     }
     
     /// Handle purchase request
-    async fn handle_purchase(&self, request: Request) -> Response<Body> {
-        purchase::handle_purchase(request, &self.db, &self.payment_service).await
+    async fn handle_purchase(&self, version: ApiVersion, request: Request) -> Result<Response<Body>, AppError> {
+        purchase::handle_purchase(version, request, self.db.as_ref(), &self.payment_service, &self.metrics).await
     }
     
     /// Handle get transactions request
-    async fn handle_get_transactions(&self, request: Request, player_id: &str) -> Response<Body> {
-        transactions::handle_get_transactions(request, &self.db, player_id).await
+    async fn handle_get_transactions(&self, request: Request, player_id: &str) -> Result<Response<Body>, AppError> {
+        transactions::handle_get_transactions(request, self.db.as_ref(), &self.metrics, player_id).await
     }
     
-    /// Handle health check
+    /// Handle readiness check
     async fn handle_health(&self, _request: Request) -> Response<Body> {
-        health::handle_health(&self.db).await
+        health::handle_readiness(&self.readiness_checks()).await
+    }
+    
+    /// Built-in checks, then any added with `with_health_check`
+    /// 
+    /// Built per request so they pick up the final metrics handle.
+    fn readiness_checks(&self) -> Vec<Arc<dyn HealthCheck>> {
+        let mut checks: Vec<Arc<dyn HealthCheck>> = vec![
+            Arc::new(DatabaseCheck::new(Arc::clone(&self.db), self.db_degraded_after, self.metrics.clone())),
+            Arc::new(MigrationCheck::new(Arc::clone(&self.db))),
+            Arc::new(PaymentProcessorCheck::new(Arc::clone(&self.payment_service))),
+            Arc::new(CircuitBreakerCheck::new(self.payment_service.circuit_breaker())),
+        ];
+        checks.extend(self.health_checks.iter().cloned());
+        checks
     }
     
-    /// CORS preflight response
-    fn cors_response(&self) -> Response<Body> {
-        Response::builder()
-            .status(204)
-            .header("Access-Control-Allow-Origin", "*")
-            .header("Access-Control-Allow-Methods", "GET, POST, OPTIONS")
-            .header("Access-Control-Allow-Headers", "Content-Type, Authorization")
-            .body(Body::Empty)
-            .unwrap()
+    /// Method not allowed response with `Allow` header
+    fn method_not_allowed(
+        &self,
+        method: &Method,
+        allow: &str,
+        request_headers: &HeaderMap,
+        path: &str,
+        request_id: &RequestId,
+    ) -> Response<Body> {
+        let mut response = self.error_response(
+            AppError::MethodNotAllowed(format!("{} is not supported here", method)),
+            request_headers,
+            path,
+            request_id,
+        );
+        if let Ok(value) = allow.parse() {
+            response.headers_mut().insert("Allow", value);
+        }
+        response
     }
     
-    /// Not found response
-    fn not_found(&self) -> Response<Body> {
-        AppError::NotFound("Endpoint not found".into()).into_response()
+    /// Log the internal cause and render the public error body
+    /// 
+    /// ADVANTAGE: The only place errors become responses - nothing skips redaction
+    fn error_response(
+        &self,
+        error: AppError,
+        request_headers: &HeaderMap,
+        path: &str,
+        request_id: &RequestId,
+    ) -> Response<Body> {
+        let status = error.status_code();
+        if status >= 500 {
+            error!(status, code = error.error_code(), error = %error, "Request failed");
+        } else {
+            warn!(status, code = error.error_code(), error = %error, "Request rejected");
+        }
+        
+        error.render(
+            negotiate_error_format(request_headers, self.error_format),
+            Some(path),
+            Some(request_id.as_str()),
+        )
+    }
+}
+
+/// Problem+json when the client accepts it, otherwise the configured format
+fn negotiate_error_format(request_headers: &HeaderMap, default: ErrorFormat) -> ErrorFormat {
+    let wants_problem = request_headers
+        .get_all("Accept")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|media| media.split(';').next().is_some_and(|m| m.trim().eq_ignore_ascii_case("application/problem+json")));
+    
+    if wants_problem { ErrorFormat::Problem } else { default }
+}
+
+/// API Gateway stage, which lambda_http includes in REST API paths
+fn api_stage(request: &Request) -> Option<String> {
+    match request.request_context_ref()? {
+        RequestContext::ApiGatewayV1(context) => context.stage.clone(),
+        RequestContext::ApiGatewayV2(context) => context.stage.clone(),
+        _ => None,
+    }
+}
+
+/// ARN of the IAM principal API Gateway authenticated, if any
+///
+/// Only set when the route uses `AWS_IAM` auth, so a client cannot supply it.
+fn iam_caller(request: &Request) -> Option<String> {
+    match request.request_context_ref()? {
+        RequestContext::ApiGatewayV1(context) => context.identity.user_arn.clone(),
+        _ => None,
     }
 }
 
@@ -115,7 +619,61 @@ pub fn json_response<T: serde::Serialize>(status: u16, body: &T) -> Response<Bod
     Response::builder()
         .status(status)
         .header("Content-Type", "application/json")
-        .header("Access-Control-Allow-Origin", "*")
         .body(Body::from(json))
         .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::aws_lambda_events::apigw::ApiGatewayProxyRequestContext;
+    use crate::services::InMemoryDatabase;
+    use crate::strategies::payment::MockPaymentStrategy;
+
+    /// Every admin route, by method and path below the version prefix
+    const ADMIN_ROUTES: [(&str, &str); 9] = [
+        ("GET", "/titles/starfall/webhooks"),
+        ("POST", "/titles/starfall/webhooks"),
+        ("GET", "/webhooks/00000000-0000-0000-0000-000000000000/deliveries"),
+        ("POST", "/webhooks/deliveries/00000000-0000-0000-0000-000000000000/redeliver"),
+        ("PUT", "/catalog/items/founders_cape"),
+        ("POST", "/promotions"),
+        ("PUT", "/players/00000000-0000-0000-0000-000000000000/profile"),
+        ("POST", "/codes/campaigns"),
+        ("PUT", "/subscriptions/plans/vip"),
+    ];
+
+    fn router() -> Router {
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+        Router::new(Arc::new(InMemoryDatabase::new()), Arc::new(payments))
+    }
+
+    fn request(method: &str, path: &str) -> Request {
+        lambda_http::http::Request::builder().method(method).uri(path).body(Body::Empty).unwrap()
+    }
+
+    fn signed(request: Request, user_arn: &str) -> Request {
+        let mut context = ApiGatewayProxyRequestContext::default();
+        context.identity.user_arn = Some(user_arn.to_string());
+        request.with_request_context(RequestContext::ApiGatewayV1(context))
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoints_need_an_iam_identity() {
+        let router = router();
+
+        for (method, path) in ADMIN_ROUTES {
+            let admin = format!("/admin/v2{}", path);
+            assert_eq!(router.route(request(method, &admin)).await.status(), 401, "{} {}", method, admin);
+            for prefix in ["", "/v1", "/v2"] {
+                let public = format!("{}{}", prefix, path);
+                // 405 where the path serves other, public methods
+                let status = router.route(request(method, &public)).await.status();
+                assert!(matches!(status.as_u16(), 404 | 405), "{} {} is not served", method, public);
+            }
+        }
+
+        let operator = signed(request("GET", "/admin/v2/titles/starfall/webhooks"), "arn:aws:iam::123456789012:role/live-ops");
+        assert_eq!(router.route(operator).await.status(), 200);
+    }
+}
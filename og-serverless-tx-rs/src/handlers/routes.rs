@@ -0,0 +1,256 @@
+//! # Route Table
+//!
+//! ADVANTAGE: Routes are declared once, as data - methods, paths and handlers
+//! ADVANTAGE: `{param}` segments are extracted by the table, not by hand
+//! ADVANTAGE: 405 `Allow` and OPTIONS responses are derived, never out of sync
+
+use lambda_http::http::Method;
+
+/// One registered route
+///
+/// `pattern` is a path such as `/transactions/{playerId}`; each `{name}`
+/// segment matches exactly one non-empty path segment. `prefix` is the
+/// mount point (`/v2`) the pattern is matched under, empty when unmounted.
+#[derive(Debug, Clone)]
+pub struct Route<T> {
+    pub method: Method,
+    pub prefix: &'static str,
+    pub pattern: &'static str,
+    pub target: T,
+}
+
+/// Path parameters captured from `{name}` segments
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathParams {
+    params: Vec<(&'static str, String)>,
+}
+
+impl PathParams {
+    /// Value captured for `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Outcome of looking up a request
+#[derive(Debug, PartialEq, Eq)]
+pub enum RouteMatch<T> {
+    /// Route found for method and path; `template` is the matched prefix
+    /// and pattern, e.g. `/v2/players/{playerId}/ledger`, safe to log where
+    /// the concrete path is not
+    Found { target: T, params: PathParams, template: String },
+    /// Path exists, but not for this method
+    MethodNotAllowed { allowed: Vec<Method> },
+    /// CORS preflight or OPTIONS for a known path
+    Options { allowed: Vec<Method> },
+    /// No route has this path
+    NotFound,
+}
+
+/// Typed route table
+///
+/// ADVANTAGE: `T` is usually an enum, so dispatch stays an exhaustive `match`
+#[derive(Debug, Clone)]
+pub struct RouteTable<T> {
+    routes: Vec<Route<T>>,
+}
+
+impl<T: Copy> RouteTable<T> {
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Register `target` for `method` and `pattern`
+    pub fn route(mut self, method: Method, pattern: &'static str, target: T) -> Self {
+        self.routes.push(Route { method, prefix: "", pattern, target });
+        self
+    }
+
+    /// Register the routes of `routes` again under `prefix`
+    ///
+    /// `mount` maps each target into this table; routes it maps to `None`
+    /// are left out. Prefixes do not nest - `routes` should be unmounted.
+    ///
+    /// ADVANTAGE: A route table is declared once and served at many prefixes
+    pub fn mount<U: Copy>(mut self, prefix: &'static str, routes: &RouteTable<U>, mount: impl Fn(U) -> Option<T>) -> Self {
+        for route in &routes.routes {
+            if let Some(target) = mount(route.target) {
+                self.routes.push(Route { method: route.method.clone(), prefix, pattern: route.pattern, target });
+            }
+        }
+        self
+    }
+
+    /// Registered routes, in registration order
+    pub fn routes(&self) -> &[Route<T>] {
+        &self.routes
+    }
+
+    /// Look up `method` and `path`
+    ///
+    /// A leading `/{stage}` segment (API Gateway REST stage) is stripped
+    /// when `stage` is given, and trailing slashes are ignored. The first
+    /// registered route matching both method and path wins.
+    pub fn find(&self, method: &Method, path: &str, stage: Option<&str>) -> RouteMatch<T> {
+        let path = strip_stage(path, stage);
+        let segments: Vec<&str> = path
+            .trim_matches('/')
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let mut allowed = Vec::new();
+
+        for route in &self.routes {
+            let Some(params) = match_pattern(route.prefix, route.pattern, &segments) else {
+                continue;
+            };
+
+            if route.method == *method {
+                let template = format!("{}{}", route.prefix, route.pattern);
+                return RouteMatch::Found { target: route.target, params, template };
+            }
+
+            if !allowed.contains(&route.method) {
+                allowed.push(route.method.clone());
+            }
+        }
+
+        if allowed.is_empty() {
+            return RouteMatch::NotFound;
+        }
+
+        allowed.push(Method::OPTIONS);
+
+        if *method == Method::OPTIONS {
+            RouteMatch::Options { allowed }
+        } else {
+            RouteMatch::MethodNotAllowed { allowed }
+        }
+    }
+}
+
+impl<T: Copy> Default for RouteTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Comma-separated method list for `Allow` headers
+pub fn allow_header(methods: &[Method]) -> String {
+    methods
+        .iter()
+        .map(Method::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Remove a leading `/{stage}` segment added by API Gateway REST APIs
+pub fn strip_stage<'a>(path: &'a str, stage: Option<&str>) -> &'a str {
+    let Some(stage) = stage.filter(|s| !s.is_empty() && *s != "$default") else {
+        return path;
+    };
+
+    match path.strip_prefix('/').and_then(|p| p.strip_prefix(stage)) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+        _ => path,
+    }
+}
+
+fn match_pattern(prefix: &'static str, pattern: &'static str, segments: &[&str]) -> Option<PathParams> {
+    let pattern_segments: Vec<&'static str> = prefix
+        .split('/')
+        .chain(pattern.split('/'))
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    if pattern_segments.len() != segments.len() {
+        return None;
+    }
+
+    let mut params = PathParams::default();
+
+    for (expected, actual) in pattern_segments.iter().zip(segments) {
+        match expected.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(name) => params.params.push((name, (*actual).to_string())),
+            None if expected == actual => {}
+            None => return None,
+        }
+    }
+
+    Some(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Target {
+        Purchase,
+        Transactions,
+        Health,
+    }
+
+    fn table() -> RouteTable<Target> {
+        RouteTable::new()
+            .route(Method::POST, "/purchase", Target::Purchase)
+            .route(Method::GET, "/transactions/{playerId}", Target::Transactions)
+            .route(Method::GET, "/health", Target::Health)
+    }
+
+    #[test]
+    fn test_params_and_stage_prefix() {
+        let table = table();
+
+        let RouteMatch::Found { target, params, template } =
+            table.find(&Method::GET, "/prod/transactions/abc/", Some("prod"))
+        else {
+            panic!("expected match");
+        };
+        assert_eq!(target, Target::Transactions);
+        assert_eq!(params.get("playerId"), Some("abc"));
+        assert_eq!(template, "/transactions/{playerId}");
+
+        // ADVANTAGE: Extra segments no longer leak into the player ID
+        assert_eq!(table.find(&Method::GET, "/transactions/abc/extra", None), RouteMatch::NotFound);
+        assert_eq!(table.find(&Method::GET, "/transactions/", None), RouteMatch::NotFound);
+        assert!(matches!(table.find(&Method::GET, "/health", Some("prod")), RouteMatch::Found { .. }));
+    }
+
+    #[test]
+    fn test_method_not_allowed_and_options() {
+        let table = table();
+
+        assert_eq!(
+            table.find(&Method::POST, "/health", None),
+            RouteMatch::MethodNotAllowed { allowed: vec![Method::GET, Method::OPTIONS] }
+        );
+        assert_eq!(
+            table.find(&Method::OPTIONS, "/purchase", None),
+            RouteMatch::Options { allowed: vec![Method::POST, Method::OPTIONS] }
+        );
+        assert_eq!(table.find(&Method::OPTIONS, "/nope", None), RouteMatch::NotFound);
+        assert_eq!(allow_header(&[Method::GET, Method::OPTIONS]), "GET, OPTIONS");
+    }
+
+    #[test]
+    fn test_mount_serves_table_under_prefix() {
+        let table = RouteTable::new()
+            .mount("", &table(), |t| (t != Target::Health).then_some((1, t)))
+            .mount("/v2", &table(), |t| Some((2, t)));
+
+        let RouteMatch::Found { target, params, template } = table.find(&Method::GET, "/v2/transactions/abc", None) else {
+            panic!("expected match");
+        };
+        assert_eq!(target, (2, Target::Transactions));
+        assert_eq!(template, "/v2/transactions/{playerId}");
+        assert_eq!(params.get("playerId"), Some("abc"));
+        assert!(matches!(table.find(&Method::POST, "/purchase", None), RouteMatch::Found { target: (1, _), .. }));
+        assert_eq!(table.find(&Method::GET, "/health", None), RouteMatch::NotFound);
+        assert!(matches!(table.find(&Method::GET, "/v2/health", None), RouteMatch::Found { .. }));
+    }
+}
@@ -0,0 +1,406 @@
+//! # Subscription Handlers
+//!
+//! - `PUT /subscriptions/plans/{planId}` - create or update a plan or season pass
+//! - `POST /subscriptions` - subscribe, paying the first period now
+//! - `GET /players/{playerId}/subscriptions` - a player's subscriptions
+//! - `POST /subscriptions/{subscriptionId}/cancel` - stop at the end of the period
+//! - `POST /subscriptions/{subscriptionId}/resume` - undo a cancellation
+//! - `POST /subscriptions/{subscriptionId}/change-plan` - switch plan, prorated
+//!
+//! Plans are set only under `/admin/v2`, by IAM-signed operator requests.
+//! Renewals are charged by the `renew-subscriptions` scheduled job.
+//!
+//! ADVANTAGE: The pass entitlement expires with the paid period, so a lapsed
+//! subscription needs no cleanup job to revoke access
+
+use chrono::Utc;
+use lambda_http::{Body, Request, Response};
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::metrics::Metrics;
+use crate::models::{
+    ChangePlanRequest, FieldError, SubscribeRequest, Subscription, SubscriptionActionRequest,
+    SubscriptionListResponse, SubscriptionPlan, SubscriptionPlanRequest, Transaction, TransactionStatus,
+};
+use crate::services::subscriptions::charge_saved_method;
+use crate::services::{Database, PaymentService};
+use super::purchase::{parse_body, settle};
+use super::request_id::RequestId;
+use super::router::json_response;
+use super::wallet::parse_player_id;
+
+/// Handle plan upsert - existing subscribers renew at the new price
+#[instrument(skip(request, db, metrics))]
+pub async fn handle_put_plan(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+    plan_id: &str,
+) -> Result<Response<Body>, AppError> {
+    if plan_id.is_empty() || plan_id.len() > 255 {
+        return Err(AppError::InvalidFields(vec![
+            FieldError::new("planId", "length", "must be 1 to 255 characters"),
+        ]));
+    }
+    let update: SubscriptionPlanRequest = parse_body(&request)?;
+    update.check()?;
+
+    let plan = metrics
+        .time_db("upsert_subscription_plan", db.upsert_subscription_plan(&update.into_plan(plan_id)))
+        .await?;
+
+    info!(period_days = ?plan.period_days, season_ends_at = ?plan.season_ends_at, "Subscription plan saved");
+    Ok(json_response(200, &plan))
+}
+
+/// Handle subscribe request
+///
+/// The subscription is saved before the charge, so the one-live-subscription
+/// rule is enforced before the player pays; a declined first payment leaves
+/// it `incomplete`.
+#[instrument(skip(request, db, payment_service, metrics))]
+pub async fn handle_subscribe(
+    request: Request,
+    db: &dyn Database,
+    payment_service: &PaymentService,
+    metrics: &Metrics,
+) -> Result<Response<Body>, AppError> {
+    let subscribe: SubscribeRequest = parse_body(&request)?;
+    let plan = get_plan(db, metrics, &subscribe.plan_id).await?;
+
+    let now = Utc::now();
+    let subscription = Subscription::start(subscribe.player_id, &plan, subscribe.payment_method, Uuid::new_v4(), now)?;
+    let subscription = metrics
+        .time_db("insert_subscription", db.insert_subscription(&subscription))
+        .await?;
+
+    let mut new_tx = plan.charge(&subscription, plan.price_cents, subscription.current_period_end);
+    new_tx.transaction_id = subscription.entitlement_transaction_id;
+    let settled = settle(new_tx, RequestId::of(&request), db, payment_service, metrics).await;
+
+    let subscription = if settled.as_ref().is_ok_and(|(tx, _)| tx.status == TransactionStatus::Completed) {
+        subscription
+    } else {
+        warn!(subscription_id = %subscription.subscription_id, "First subscription payment failed");
+        metrics
+            .time_db("update_subscription", db.update_subscription(&subscription.incomplete(now)))
+            .await?
+    };
+    // A processor error is reported once the subscription is marked incomplete
+    settled?;
+
+    info!(subscription_id = %subscription.subscription_id, status = subscription.status.as_str(), "Subscribed");
+    Ok(json_response(201, &subscription))
+}
+
+/// Handle list player subscriptions request
+#[instrument(skip(db, metrics, player_id_str), fields(player_id = %player_id_str))]
+pub async fn handle_get_player_subscriptions(
+    db: &dyn Database,
+    metrics: &Metrics,
+    player_id_str: &str,
+) -> Result<Response<Body>, AppError> {
+    let player_id = parse_player_id(player_id_str)?;
+
+    let subscriptions = metrics
+        .time_db("get_player_subscriptions", db.get_player_subscriptions(player_id))
+        .await?;
+
+    Ok(json_response(200, &SubscriptionListResponse::new(player_id, subscriptions)))
+}
+
+/// Handle cancel or resume request
+#[instrument(skip(request, db, metrics))]
+pub async fn handle_cancel_subscription(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+    subscription_id_str: &str,
+    cancel: bool,
+) -> Result<Response<Body>, AppError> {
+    let action: SubscriptionActionRequest = parse_body(&request)?;
+    let subscription = get_owned(db, metrics, subscription_id_str, action.player_id).await?;
+
+    let changed = if cancel { subscription.cancel(Utc::now()) } else { subscription.resume()? };
+    let saved = metrics
+        .time_db("update_subscription", db.update_subscription(&changed))
+        .await?;
+
+    info!(subscription_id = %saved.subscription_id, cancel, status = saved.status.as_str(), "Subscription updated");
+    Ok(json_response(200, &saved))
+}
+
+/// Handle change plan request
+///
+/// An upgrade charges the prorated difference to the saved payment method;
+/// a downgrade credits it against the next renewal. Either way the new
+/// plan's item is granted for the rest of the current period. A change that
+/// cannot be saved afterwards, because the subscription changed meanwhile,
+/// is refunded and its grant revoked.
+#[instrument(skip(request, db, payment_service, metrics))]
+pub async fn handle_change_plan(
+    request: Request,
+    db: &dyn Database,
+    payment_service: &PaymentService,
+    metrics: &Metrics,
+    subscription_id_str: &str,
+) -> Result<Response<Body>, AppError> {
+    let change: ChangePlanRequest = parse_body(&request)?;
+    let subscription = get_owned(db, metrics, subscription_id_str, change.player_id).await?;
+    let current = get_plan(db, metrics, &subscription.plan_id).await?;
+    let next = get_plan(db, metrics, &change.plan_id).await?;
+
+    let proration = subscription.prorate(&current, &next, Utc::now())?;
+    let new_tx = next.charge(&subscription, proration.charge_cents, subscription.current_period_end);
+    let new_tx = match RequestId::of(&request) {
+        Some(id) => new_tx.with_request_id(id.as_str()),
+        None => new_tx,
+    };
+    let tx = metrics
+        .time_db("insert_transaction", db.insert_transaction(&new_tx))
+        .await?;
+    let tx = charge_saved_method(db, payment_service, tx, &subscription.payment_method).await?;
+    if tx.status != TransactionStatus::Completed {
+        warn!(subscription_id = %subscription.subscription_id, "Plan change payment declined");
+        return Err(AppError::Payment("The saved payment method was declined".into()));
+    }
+
+    let subscription_id = subscription.subscription_id;
+    let changed = subscription.changed_plan(&next, &tx, proration);
+    let saved = match metrics.time_db("update_subscription", db.update_subscription(&changed)).await {
+        Ok(saved) => saved,
+        Err(e) => {
+            warn!(subscription_id = %subscription_id, error = %e, "Plan change not saved; refunding it");
+            if let Err(refund_error) = refund_plan_change(db, payment_service, &tx).await {
+                error!(transaction_id = %tx.transaction_id, error = %refund_error, "Unsaved plan change not refunded");
+            }
+            return Err(e);
+        }
+    };
+
+    info!(
+        subscription_id = %saved.subscription_id,
+        plan_id = %saved.plan_id,
+        charged = proration.charge_cents,
+        credited = proration.credit_cents,
+        "Subscription plan changed"
+    );
+    Ok(json_response(200, &saved))
+}
+
+/// Refund `tx`, the charge for a plan change that was not saved, revoking
+/// the new plan's item in the same transition
+///
+/// The refund is sent under the transaction's idempotency key, so a retried
+/// refund never pays out twice; a change covered by credit has nothing to
+/// refund.
+async fn refund_plan_change(db: &dyn Database, payments: &PaymentService, tx: &Transaction) -> Result<Transaction, AppError> {
+    let processor_id = tx.processor_id.as_deref();
+    if let Some(processor_id) = processor_id.filter(|_| tx.price_cents > 0) {
+        let refund = payments.process_refund(tx.transaction_id, processor_id, tx.price_cents).await?;
+        if !refund.success {
+            return Err(AppError::Payment(refund.error_message.unwrap_or_else(|| "Refund declined".into())));
+        }
+    }
+
+    db.update_transaction_status(tx.transaction_id, TransactionStatus::Refunded, processor_id).await
+}
+
+async fn get_plan(db: &dyn Database, metrics: &Metrics, plan_id: &str) -> Result<SubscriptionPlan, AppError> {
+    metrics
+        .time_db("get_subscription_plan", db.get_subscription_plan(plan_id))
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Plan {} not found", plan_id)))
+}
+
+/// `subscription_id_str` if `player_id` holds it
+async fn get_owned(
+    db: &dyn Database,
+    metrics: &Metrics,
+    subscription_id_str: &str,
+    player_id: Uuid,
+) -> Result<Subscription, AppError> {
+    let subscription_id: Uuid = subscription_id_str
+        .parse()
+        .map_err(|_| AppError::InvalidFields(vec![
+            FieldError::new("subscriptionId", "invalid_uuid", "must be a UUID"),
+        ]))?;
+
+    let subscription = metrics
+        .time_db("get_subscription", db.get_subscription(subscription_id))
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Subscription {} not found", subscription_id)))?;
+    subscription.check_owner(player_id)?;
+    Ok(subscription)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+    use crate::models::SubscriptionStatus;
+    use crate::services::InMemoryDatabase;
+    use crate::services::subscriptions::{BillingReport, SubscriptionBiller};
+    use crate::strategies::payment::{
+        MockPaymentStrategy, PaymentRequest, PaymentResult, PaymentStrategy, MOCK_DECLINING_PAYMENT_METHOD,
+    };
+
+    fn request(method: &str, body: Value) -> Request {
+        lambda_http::http::Request::builder()
+            .method(method)
+            .body(Body::Text(body.to_string()))
+            .unwrap()
+    }
+
+    fn subscription(response: &Response<Body>) -> Subscription {
+        let Body::Text(body) = response.body() else { panic!("expected a text body") };
+        serde_json::from_str(body).unwrap()
+    }
+
+    async fn setup() -> (Arc<InMemoryDatabase>, Arc<PaymentService>, Metrics) {
+        let db = Arc::new(InMemoryDatabase::new());
+        let metrics = Metrics::default();
+        for (plan_id, price_cents) in [("vip", 1000), ("vip_plus", 3000)] {
+            let plan = json!({
+                "name": "VIP",
+                "item_id": plan_id,
+                "price_cents": price_cents,
+                "currency": "USD",
+                "period_days": 30,
+                "grace_period_days": 7
+            });
+            handle_put_plan(request("PUT", plan), db.as_ref(), &metrics, plan_id).await.unwrap();
+        }
+        (db, Arc::new(PaymentService::new(Arc::new(MockPaymentStrategy::new()))), metrics)
+    }
+
+    async fn subscribe(db: &InMemoryDatabase, payments: &PaymentService, metrics: &Metrics, payment_method: &str) -> Subscription {
+        let body = json!({"player_id": Uuid::new_v4(), "plan_id": "vip", "payment_method": payment_method});
+        subscription(&handle_subscribe(request("POST", body), db, payments, metrics).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_pass_renews_and_expires_after_declined_dunning() {
+        let (db, payments, metrics) = setup().await;
+        let biller = SubscriptionBiller::new(db.clone(), payments.clone());
+
+        let renewing = subscribe(&db, &payments, &metrics, "pm_card_visa").await;
+        let lapsing = subscribe(&db, &payments, &metrics, MOCK_DECLINING_PAYMENT_METHOD).await;
+        assert_eq!(renewing.status, SubscriptionStatus::Active);
+        let owned = db.get_player_entitlements(renewing.player_id, false).await.unwrap();
+        assert_eq!(owned[0].expires_at, Some(renewing.current_period_end));
+
+        // Subscribed a moment later, so both are due by then
+        let due = lapsing.current_period_end;
+        let report = biller.bill_once(due).await.unwrap();
+        assert_eq!(report, BillingReport { due: 2, renewed: 1, retried: 1, ..Default::default() });
+
+        let renewed = db.get_subscription(renewing.subscription_id).await.unwrap().unwrap();
+        assert_eq!(renewed.current_period_start, renewing.current_period_end);
+        let owned = db.get_player_entitlements(renewing.player_id, true).await.unwrap();
+        assert_eq!(owned.iter().filter_map(|e| e.expires_at).max(), Some(renewed.current_period_end));
+
+        // Dunning retries on days 1 and 4, then the 7-day grace period runs out
+        for day in [1, 4, 7] {
+            biller.bill_once(due + Duration::days(day)).await.unwrap();
+        }
+        let expired = db.get_subscription(lapsing.subscription_id).await.unwrap().unwrap();
+        assert_eq!(expired.status, SubscriptionStatus::Expired);
+        let owned = db.get_player_entitlements(lapsing.player_id, true).await.unwrap();
+        assert_eq!(owned[0].expires_at, Some(due + Duration::days(7)));
+        assert!(!owned[0].is_current(due + Duration::days(7)));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_resume_and_upgrade() {
+        let (db, payments, metrics) = setup().await;
+        let sub = subscribe(&db, &payments, &metrics, "pm_card_visa").await;
+        let id = sub.subscription_id.to_string();
+        let owner = json!({"player_id": sub.player_id});
+
+        let duplicate = json!({"player_id": sub.player_id, "plan_id": "vip", "payment_method": "pm_card_visa"});
+        assert!(matches!(
+            handle_subscribe(request("POST", duplicate), db.as_ref(), &payments, &metrics).await,
+            Err(AppError::Conflict(_))
+        ));
+        assert!(matches!(
+            handle_cancel_subscription(request("POST", json!({"player_id": Uuid::new_v4()})), db.as_ref(), &metrics, &id, true).await,
+            Err(AppError::NotFound(_))
+        ));
+
+        let canceled = subscription(&handle_cancel_subscription(request("POST", owner.clone()), db.as_ref(), &metrics, &id, true).await.unwrap());
+        assert!(canceled.cancel_at_period_end);
+        let resumed = subscription(&handle_cancel_subscription(request("POST", owner), db.as_ref(), &metrics, &id, false).await.unwrap());
+        assert!(!resumed.cancel_at_period_end);
+
+        let upgrade = json!({"player_id": sub.player_id, "plan_id": "vip_plus"});
+        let upgraded = subscription(&handle_change_plan(request("POST", upgrade), db.as_ref(), &payments, &metrics, &id).await.unwrap());
+        assert_eq!(upgraded.plan_id, "vip_plus");
+        let owned = db.get_player_entitlements(sub.player_id, false).await.unwrap();
+        assert_eq!(owned.iter().map(|e| e.item_id.as_str()).collect::<Vec<_>>(), vec!["vip_plus"]);
+    }
+
+    /// Mock strategy that cancels `subscription_id` while charging it, and
+    /// records the idempotency key of every refund
+    struct RacingStrategy {
+        db: Arc<InMemoryDatabase>,
+        subscription_id: Uuid,
+        mock: MockPaymentStrategy,
+        refund_keys: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl PaymentStrategy for RacingStrategy {
+        async fn process_payment(&self, request: PaymentRequest) -> Result<PaymentResult, AppError> {
+            self.mock.process_payment(request).await
+        }
+
+        async fn charge_off_session(&self, payment_method: &str, request: PaymentRequest) -> Result<PaymentResult, AppError> {
+            let subscription = self.db.get_subscription(self.subscription_id).await?.unwrap();
+            self.db.update_subscription(&subscription.cancel(Utc::now())).await?;
+            self.mock.charge_off_session(payment_method, request).await
+        }
+
+        async fn refund_payment(&self, processor_id: &str, amount_cents: i64, idempotency_key: &str) -> Result<PaymentResult, AppError> {
+            self.refund_keys.lock().unwrap().push(idempotency_key.to_string());
+            self.mock.refund_payment(processor_id, amount_cents, idempotency_key).await
+        }
+
+        fn name(&self) -> &'static str {
+            "racing"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unsaved_plan_change_is_refunded() {
+        let (db, payments, metrics) = setup().await;
+        let sub = subscribe(&db, &payments, &metrics, "pm_card_visa").await;
+        let strategy = Arc::new(RacingStrategy {
+            db: db.clone(),
+            subscription_id: sub.subscription_id,
+            mock: MockPaymentStrategy::new(),
+            refund_keys: Mutex::default(),
+        });
+        let racing = PaymentService::new(strategy.clone());
+
+        let upgrade = json!({"player_id": sub.player_id, "plan_id": "vip_plus"});
+        let id = sub.subscription_id.to_string();
+        assert!(matches!(
+            handle_change_plan(request("POST", upgrade), db.as_ref(), &racing, &metrics, &id).await,
+            Err(AppError::Conflict(_))
+        ));
+
+        let stored = db.get_subscription(sub.subscription_id).await.unwrap().unwrap();
+        assert_eq!((stored.plan_id.as_str(), stored.cancel_at_period_end), ("vip", true));
+        let history = db.get_player_transactions(sub.player_id, 10, None).await.unwrap();
+        let change = history.iter().find(|tx| tx.item_id == "vip_plus").unwrap();
+        assert_eq!(change.status, TransactionStatus::Refunded);
+        assert_eq!(*strategy.refund_keys.lock().unwrap(), vec![format!("refund_{}", change.transaction_id)]);
+        let owned = db.get_player_entitlements(sub.player_id, false).await.unwrap();
+        assert_eq!(owned.iter().map(|e| e.item_id.as_str()).collect::<Vec<_>>(), vec!["vip"]);
+    }
+}
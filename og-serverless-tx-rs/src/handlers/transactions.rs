@@ -4,39 +4,40 @@
 //! ADVANTAGE: Pagination is safe by default
 
 use lambda_http::{Body, Request, Response};
-use tracing::{info, error, instrument};
+use tracing::{info, instrument};
 use uuid::Uuid;
 
 use crate::errors::AppError;
-use crate::models::TransactionListResponse;
-use crate::services::PostgresDatabase;
+use crate::models::{FieldError, TransactionListResponse};
+use crate::metrics::Metrics;
+use crate::services::Database;
 use super::router::json_response;
 
 /// Handle get transactions request
-#[instrument(skip(request, db))]
+#[instrument(skip(request, db, metrics, player_id_str), fields(player_id = %player_id_str))]
 pub async fn handle_get_transactions(
     request: Request,
-    db: &PostgresDatabase,
+    db: &dyn Database,
+    metrics: &Metrics,
     player_id_str: &str,
-) -> Response<Body> {
-    match get_transactions(request, db, player_id_str).await {
-        Ok(response) => json_response(200, &response),
-        Err(e) => {
-            error!(error = %e, "Get transactions failed");
-            e.into_response()
-        }
-    }
+) -> Result<Response<Body>, AppError> {
+    get_transactions(request, db, metrics, player_id_str)
+        .await
+        .map(|response| json_response(200, &response))
 }
 
 async fn get_transactions(
     request: Request,
-    db: &PostgresDatabase,
+    db: &dyn Database,
+    metrics: &Metrics,
     player_id_str: &str,
 ) -> Result<TransactionListResponse, AppError> {
     // ADVANTAGE: UUID parsing is explicit - invalid UUIDs rejected
     let player_id: Uuid = player_id_str
         .parse()
-        .map_err(|_| AppError::Validation(format!("Invalid player ID: {}", player_id_str)))?;
+        .map_err(|_| AppError::InvalidFields(vec![
+            FieldError::new("playerId", "invalid_uuid", "must be a UUID"),
+        ]))?;
     
     // ADVANTAGE: Query params extraction with typed defaults
     let query_string = request.uri().query().unwrap_or("");
@@ -60,23 +61,29 @@ async fn get_transactions(
         "Fetching player transactions"
     );
     
-    let transactions = db.get_player_transactions(player_id, limit, cursor).await?;
+    let transactions = metrics
+        .time_db("get_player_transactions", db.get_player_transactions(player_id, limit, cursor))
+        .await?;
     
     info!(count = transactions.len(), "Retrieved transactions");
     
+    // ADVANTAGE: Received gifts never expose the payer's payment details
+    let transactions = transactions
+        .into_iter()
+        .map(|tx| tx.seen_by(player_id))
+        .collect();
+    
     Ok(TransactionListResponse::new(transactions))
 }
 
 /// Parse query string into key-value pairs
 /// 
 /// ADVANTAGE: Simple, safe parsing - no complex regex
-fn parse_query_params(query: &str) -> std::collections::HashMap<String, String> {
+pub(super) fn parse_query_params(query: &str) -> std::collections::HashMap<String, String> {
     query
         .split('&')
         .filter_map(|pair| {
-            let mut parts = pair.splitn(2, '=');
-            let key = parts.next()?;
-            let value = parts.next()?;
+            let (key, value) = pair.split_once('=')?;
             Some((key.to_string(), value.to_string()))
         })
         .collect()
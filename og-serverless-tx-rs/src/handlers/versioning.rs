@@ -0,0 +1,111 @@
+//! # API Versioning
+//!
+//! Paths may start with a version segment (`/v1/purchase`, `/v2/purchase`).
+//! Unversioned paths are the original contract and are served as v1, so
+//! shipped game clients keep working.
+//!
+//! ADVANTAGE: Versions are an enum - handlers match exhaustively on them
+//! ADVANTAGE: Deprecation and sunset dates are deployment config, not code
+//! ADVANTAGE: Old clients are told when to upgrade via standard headers
+
+use lambda_http::{Body, Response};
+use lambda_http::http::HeaderValue;
+
+pub use crate::models::config::Lifecycle;
+
+/// Supported API version, ordered oldest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    /// Every version still served, oldest first
+    pub const SUPPORTED: [Self; 2] = [Self::V1, Self::V2];
+
+    /// Newest version
+    pub const LATEST: Self = Self::V2;
+
+    /// Path segment for this version
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+        }
+    }
+
+    /// Supported version names, for error bodies
+    pub fn supported_names() -> Vec<String> {
+        Self::SUPPORTED.iter().map(|v| v.as_str().to_string()).collect()
+    }
+}
+
+/// Version segment requested by `path`, if it starts with one
+///
+/// Returns `Some(Err(segment))` for a well-formed but unsupported version
+/// such as `/v9/purchase`, so it can be rejected with the supported list.
+pub fn requested_version(path: &str) -> Option<Result<ApiVersion, String>> {
+    let segment = path.trim_start_matches('/').split('/').next()?;
+    let digits = segment.strip_prefix('v')?;
+
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(
+        ApiVersion::SUPPORTED
+            .into_iter()
+            .find(|v| v.as_str() == segment)
+            .ok_or_else(|| segment.to_string()),
+    )
+}
+
+/// Add `Deprecation`, `Sunset` and successor `Link` headers for a
+/// version with a deprecation schedule
+pub fn apply_lifecycle_headers(lifecycle: Option<&Lifecycle>, response: &mut Response<Body>) {
+    let Some(lifecycle) = lifecycle else {
+        return;
+    };
+
+    let headers = response.headers_mut();
+    let values = [
+        ("Deprecation", format!("@{}", lifecycle.deprecated_at.timestamp())),
+        ("Sunset", lifecycle.sunset_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+        ("Link", format!("</{}>; rel=\"successor-version\"", ApiVersion::LATEST.as_str())),
+    ];
+
+    for (name, value) in values {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requested_version() {
+        assert_eq!(requested_version("/v2/purchase"), Some(Ok(ApiVersion::V2)));
+        assert_eq!(requested_version("/v9/purchase"), Some(Err("v9".to_string())));
+        assert_eq!(requested_version("/purchase"), None);
+        assert_eq!(requested_version("/vip/status"), None);
+    }
+
+    #[test]
+    fn test_lifecycle_headers_only_on_deprecated_versions() {
+        let lifecycle = Lifecycle::parse("2026-11-01T00:00:00Z", "2027-05-01T00:00:00Z").unwrap();
+        let mut response = Response::new(Body::Empty);
+        apply_lifecycle_headers(Some(&lifecycle), &mut response);
+        assert_eq!(response.headers()["Deprecation"], "@1793491200");
+        assert_eq!(response.headers()["Sunset"], "Sat, 01 May 2027 00:00:00 GMT");
+
+        let mut response = Response::new(Body::Empty);
+        apply_lifecycle_headers(None, &mut response);
+        assert!(response.headers().get("Deprecation").is_none());
+
+        assert!(Lifecycle::parse("2027-05-01T00:00:00Z", "2026-11-01T00:00:00Z").is_err());
+    }
+}
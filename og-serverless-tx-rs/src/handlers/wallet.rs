@@ -0,0 +1,424 @@
+//! # Wallet Handlers
+//!
+//! - `GET /wallet/{playerId}` - balances
+//! - `POST /wallet/top-up` - buy a catalog currency pack with real money
+//! - `POST /wallet/spend` - buy an item at its catalog wallet price
+//!
+//! A spend is a purchase paid from the wallet: its debit, grant, stock sale
+//! and journal entry are all written as its transaction completes.
+//!
+//! ADVANTAGE: Top-ups reuse the purchase settlement pipeline unchanged
+//! ADVANTAGE: Balances only move through ledger entries
+//! ADVANTAGE: Every price and credit comes from the catalog, never the client
+
+use lambda_http::{Body, Request, Response};
+use serde_json::json;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::metrics::Metrics;
+use crate::models::code::NO_CURRENCY;
+use crate::models::entitlement::WALLET_TOP_UP_METADATA_KEY;
+use crate::models::wallet::WALLET_SPEND_METADATA_KEY;
+use crate::models::{
+    CatalogItem, FieldError, NewLedgerEntry, NewTransaction, SpendRequest, SpendResponse, TopUpRequest,
+    TopUpResponse, Transaction, TransactionStatus, WalletDebit, WalletResponse,
+};
+use crate::services::{Database, PaymentService};
+use super::purchase::{parse_body, settle};
+use super::request_id::RequestId;
+use super::router::json_response;
+
+/// Handle get wallet request
+#[instrument(skip(db, metrics, player_id_str), fields(player_id = %player_id_str))]
+pub async fn handle_get_wallet(
+    db: &dyn Database,
+    metrics: &Metrics,
+    player_id_str: &str,
+) -> Result<Response<Body>, AppError> {
+    let player_id = parse_player_id(player_id_str)?;
+    let balances = metrics.time_db("get_wallet", db.get_wallet(player_id)).await?;
+
+    Ok(json_response(200, &WalletResponse::new(player_id, &balances)))
+}
+
+/// Handle top-up request
+///
+/// The pack is charged at its list price like any other purchase; the
+/// wallet credit is an effect of the transaction completing, written in
+/// the same database transaction, so a completed top-up is never uncredited.
+#[instrument(skip(request, db, payment_service, metrics))]
+pub async fn handle_top_up(
+    request: Request,
+    db: &dyn Database,
+    payment_service: &PaymentService,
+    metrics: &Metrics,
+) -> Result<Response<Body>, AppError> {
+    let top_up: TopUpRequest = parse_body(&request)?;
+    let pack = catalog_item(db, metrics, &top_up.pack_id).await?;
+    let credit = pack.pack_credit()?;
+    let (price_cents, currency) = pack.unit_price()?;
+
+    info!(
+        player_id = %top_up.player_id,
+        pack_id = %top_up.pack_id,
+        currency = credit.currency.as_str(),
+        amount = credit.amount,
+        "Processing wallet top-up"
+    );
+
+    let new_tx = NewTransaction::new(
+        top_up.player_id,
+        pack.item_id.clone(),
+        format!("{} {}", credit.amount, credit.currency.as_str()),
+        price_cents,
+        currency.to_string(),
+        1,
+        pack.stamp(&json!({ WALLET_TOP_UP_METADATA_KEY: credit })),
+    );
+
+    let (tx, payment_result) = settle(new_tx, RequestId::of(&request), db, payment_service, metrics).await?;
+
+    // Replaying the credit returns the entry the completion wrote
+    let ledger_entry = match NewLedgerEntry::credit_for(&tx) {
+        Some(entry) if tx.status == TransactionStatus::Completed => Some(
+            metrics.time_db("append_ledger_entry", db.append_ledger_entry(&entry)).await?,
+        ),
+        _ => None,
+    };
+
+    let balance = match &ledger_entry {
+        Some(entry) => entry.balance_after,
+        None => {
+            let balances = metrics.time_db("get_wallet", db.get_wallet(top_up.player_id)).await?;
+            balances.iter().find(|b| b.currency == credit.currency).map(|b| b.balance).unwrap_or(0)
+        }
+    };
+
+    Ok(json_response(201, &TopUpResponse {
+        transaction_id: tx.transaction_id,
+        status: tx.status,
+        processor_id: Some(payment_result.processor_id),
+        currency: credit.currency,
+        amount: credit.amount,
+        balance,
+        ledger_entry,
+    }))
+}
+
+/// Handle spend request
+///
+/// The item is bought in a wallet-funded transaction recorded at zero, like
+/// a gift code grant. Completing it debits the wallet, grants the item and
+/// sells its stock together; a debit the balance cannot cover fails the
+/// transaction instead, releasing its stock. Ownership and stock are
+/// checked when the transaction is recorded, as for any purchase.
+///
+/// ADVANTAGE: Insufficient funds is a typed 409, decided inside the ledger lock
+#[instrument(skip(request, db, metrics))]
+pub async fn handle_spend(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+) -> Result<Response<Body>, AppError> {
+    let spend: SpendRequest = parse_body(&request)?;
+    let (price, currency) = catalog_item(db, metrics, &spend.item_id).await?.wallet_unit_price()?;
+
+    info!(
+        player_id = %spend.player_id,
+        currency = currency.as_str(),
+        amount = price,
+        item_id = %spend.item_id,
+        "Processing wallet spend"
+    );
+
+    // A retried key finds the purchase it made the first time
+    let transaction_id = WalletDebit::transaction_id(spend.player_id, &spend.idempotency_key);
+    let tx = match metrics.time_db("get_transaction", db.get_transaction(transaction_id)).await? {
+        Some(tx) => tx,
+        None => {
+            let debit = WalletDebit { currency, amount: price, idempotency_key: spend.idempotency_key.clone() };
+            let mut new_tx = NewTransaction::new(
+                spend.player_id,
+                spend.item_id.clone(),
+                spend.item_id.clone(),
+                0,
+                NO_CURRENCY.to_string(),
+                1,
+                json!({ WALLET_SPEND_METADATA_KEY: debit }),
+            );
+            new_tx.transaction_id = transaction_id;
+            if let Some(id) = RequestId::of(&request) {
+                new_tx = new_tx.with_request_id(id.as_str());
+            }
+            metrics.time_db("insert_transaction", db.insert_transaction(&new_tx)).await?
+        }
+    };
+
+    let tx = complete_spend(db, metrics, tx).await?;
+
+    // Replaying the debit returns the entry the completion wrote
+    let debit = NewLedgerEntry::debit_for(&tx)
+        .ok_or_else(|| AppError::Internal("Wallet spend has no debit".into()))?;
+    let entry = metrics
+        .time_db("append_ledger_entry", db.append_ledger_entry(&debit))
+        .await?;
+
+    Ok(json_response(201, &SpendResponse::new(&tx, entry)))
+}
+
+/// Complete the pending spend `tx`, or fail it when the debit is refused
+async fn complete_spend(db: &dyn Database, metrics: &Metrics, tx: Transaction) -> Result<Transaction, AppError> {
+    match tx.status {
+        TransactionStatus::Pending => {}
+        TransactionStatus::Completed => return Ok(tx),
+        _ => {
+            return Err(AppError::Conflict(format!(
+                "Spend {} was {}; retry with a new idempotency_key",
+                tx.transaction_id,
+                tx.status.as_str()
+            )));
+        }
+    }
+
+    let completed = metrics
+        .time_db(
+            "update_transaction_status",
+            db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, None),
+        )
+        .await;
+    let Err(error) = completed else { return completed };
+
+    // Nothing was debited or granted; release the stock the purchase holds
+    let failed = metrics
+        .time_db(
+            "update_transaction_status",
+            db.update_transaction_status(tx.transaction_id, TransactionStatus::Failed, None),
+        )
+        .await;
+    if let Err(e) = failed {
+        warn!(transaction_id = %tx.transaction_id, error = %e, "Spend left pending");
+    }
+    Err(error)
+}
+
+async fn catalog_item(db: &dyn Database, metrics: &Metrics, item_id: &str) -> Result<CatalogItem, AppError> {
+    metrics
+        .time_db("get_catalog_item", db.get_catalog_item(item_id))
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Item {} is not in the catalog", item_id)))
+}
+
+pub(super) fn parse_player_id(player_id: &str) -> Result<Uuid, AppError> {
+    player_id.parse().map_err(|_| AppError::InvalidFields(vec![
+        FieldError::new("playerId", "invalid_uuid", "must be a UUID"),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::sync::Arc;
+    use crate::models::{CatalogItemRequest, JournalKind, LedgerEntryKind, NewJournalEntry, VirtualCurrency, WalletBalance};
+    use crate::services::InMemoryDatabase;
+    use crate::strategies::payment::MockPaymentStrategy;
+    use crate::test_support::{body, post};
+
+    fn gems(balance: i64) -> Vec<WalletBalance> {
+        vec![WalletBalance { currency: VirtualCurrency::Gems, balance }]
+    }
+
+    /// A 500-gem pack for $4.99 and a 300-gem sword
+    async fn catalog(db: &InMemoryDatabase) {
+        for (item_id, listing) in [
+            ("gems_500", json!({"kind": "consumable", "price_cents": 499, "currency": "USD", "credit_currency": "gems", "credit_amount": 500})),
+            ("sword", json!({"kind": "consumable", "wallet_price": 300, "wallet_currency": "gems"})),
+        ] {
+            let listing: CatalogItemRequest = serde_json::from_value(listing).unwrap();
+            listing.check_price().unwrap();
+            db.upsert_catalog_item(item_id, &listing).await.unwrap();
+        }
+    }
+
+    async fn top_up(db: &InMemoryDatabase, player_id: Uuid) -> Value {
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+        let response = handle_top_up(post(json!({"player_id": player_id, "pack_id": "gems_500"})), db, &payments, &Metrics::default())
+            .await
+            .unwrap();
+        body(&response)
+    }
+
+    #[tokio::test]
+    async fn test_top_up_and_spend_are_priced_by_the_catalog() {
+        let db = InMemoryDatabase::new();
+        catalog(&db).await;
+        let player_id = Uuid::new_v4();
+
+        let topped_up = top_up(&db, player_id).await;
+        assert_eq!((topped_up["status"].as_str(), topped_up["amount"].as_i64()), (Some("completed"), Some(500)));
+        assert_eq!(topped_up["balance"], 500);
+        let tx = db.get_transaction(topped_up["transactionId"].as_str().unwrap().parse().unwrap()).await.unwrap().unwrap();
+        assert_eq!((tx.price_cents, tx.currency.as_str()), (499, "USD"));
+
+        // A client-chosen price or amount is rejected outright
+        let payments = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+        let priced = post(json!({"player_id": player_id, "pack_id": "gems_500", "price_cents": 1}));
+        assert!(handle_top_up(priced, &db, &payments, &Metrics::default()).await.is_err());
+        let cheap = post(json!({"player_id": player_id, "item_id": "sword", "amount": 1, "idempotency_key": "k"}));
+        assert!(handle_spend(cheap, &db, &Metrics::default()).await.is_err());
+        let not_a_pack = post(json!({"player_id": player_id, "pack_id": "sword"}));
+        assert!(matches!(handle_top_up(not_a_pack, &db, &payments, &Metrics::default()).await, Err(AppError::Validation(_))));
+
+        let spent = handle_spend(post(json!({"player_id": player_id, "item_id": "sword", "idempotency_key": "k"})), &db, &Metrics::default())
+            .await
+            .unwrap();
+        assert_eq!((body(&spent)["amount"].as_i64(), body(&spent)["balance"].as_i64()), (Some(300), Some(200)));
+    }
+
+    #[tokio::test]
+    async fn test_spend_grants_the_item_once_and_sells_its_stock() {
+        let db = InMemoryDatabase::new();
+        catalog(&db).await;
+        let listing: CatalogItemRequest = serde_json::from_value(json!({
+            "kind": "non_consumable", "stock_limit": 10, "wallet_price": 200, "wallet_currency": "gems"
+        })).unwrap();
+        db.upsert_catalog_item("founders_cape", &listing).await.unwrap();
+        let player_id = Uuid::new_v4();
+        top_up(&db, player_id).await;
+
+        let spend = |key: &str| post(json!({"player_id": player_id, "item_id": "founders_cape", "idempotency_key": key}));
+        let spent = body(&handle_spend(spend("k1"), &db, &Metrics::default()).await.unwrap());
+        assert_eq!((spent["amount"].as_i64(), spent["balance"].as_i64()), (Some(200), Some(300)));
+
+        let owned = db.get_player_entitlements(player_id, false).await.unwrap();
+        assert_eq!(owned.iter().map(|e| e.item_id.as_str()).collect::<Vec<_>>(), vec!["founders_cape"]);
+        let cape = db.get_catalog_item("founders_cape").await.unwrap().unwrap();
+        assert_eq!((cape.stock_reserved, cape.stock_sold), (0, 1));
+        let journal = db.get_player_journal(player_id, 10).await.unwrap();
+        assert_eq!(journal.iter().filter(|e| e.kind == JournalKind::WalletSpend).count(), 1);
+
+        // A retry replays the first purchase; a second purchase is refused
+        let replayed = body(&handle_spend(spend("k1"), &db, &Metrics::default()).await.unwrap());
+        assert_eq!((&replayed["transactionId"], &replayed["entryId"]), (&spent["transactionId"], &spent["entryId"]));
+        let again = handle_spend(spend("k2"), &db, &Metrics::default()).await;
+        assert!(matches!(&again, Err(AppError::AlreadyOwned(_))));
+        assert_eq!(again.unwrap_err().status_code(), 409);
+        assert_eq!(db.get_wallet(player_id).await.unwrap(), gems(300));
+        assert!(db.trial_balance().await.unwrap().is_balanced());
+    }
+
+    #[tokio::test]
+    async fn test_unaffordable_spend_fails_and_releases_its_stock() {
+        let db = InMemoryDatabase::new();
+        let listing: CatalogItemRequest = serde_json::from_value(json!({
+            "kind": "consumable", "stock_limit": 1, "wallet_price": 200, "wallet_currency": "gems"
+        })).unwrap();
+        db.upsert_catalog_item("elixir", &listing).await.unwrap();
+        let player_id = Uuid::new_v4();
+
+        let spend = post(json!({"player_id": player_id, "item_id": "elixir", "idempotency_key": "k1"}));
+        assert!(matches!(handle_spend(spend, &db, &Metrics::default()).await, Err(AppError::InsufficientFunds(_))));
+        let elixir = db.get_catalog_item("elixir").await.unwrap().unwrap();
+        assert_eq!((elixir.stock_reserved, elixir.stock_sold), (0, 0));
+        assert!(db.get_player_entitlements(player_id, false).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_credit_leaves_the_top_up_pending() {
+        let db = InMemoryDatabase::new();
+        catalog(&db).await;
+        let player_id = Uuid::new_v4();
+        let tx = db.insert_transaction(&NewTransaction::new(
+            player_id,
+            "gems_500".into(),
+            "500 gems".into(),
+            499,
+            "USD".into(),
+            1,
+            json!({ WALLET_TOP_UP_METADATA_KEY: {"currency": "gems", "amount": 500} }),
+        )).await.unwrap();
+
+        // A conflicting entry under the same reference makes the credit fail
+        db.append_ledger_entry(&NewLedgerEntry::top_up(player_id, VirtualCurrency::Gems, 1, tx.transaction_id))
+            .await
+            .unwrap();
+        let completed = db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, Some("pi_1")).await;
+        assert!(matches!(completed, Err(AppError::Conflict(_))));
+
+        // Neither the status nor the purchase journal entry was written
+        let tx = db.get_transaction(tx.transaction_id).await.unwrap().unwrap();
+        assert_eq!(tx.status, TransactionStatus::Pending);
+        let journal = db.get_player_journal(player_id, 10).await.unwrap();
+        assert!(journal.iter().all(|e| e.kind != JournalKind::Purchase));
+        assert_eq!(db.get_wallet(player_id).await.unwrap(), gems(1));
+    }
+
+    #[tokio::test]
+    async fn test_refund_and_chargeback_take_the_credit_back() {
+        let db = InMemoryDatabase::new();
+        catalog(&db).await;
+        let player_id = Uuid::new_v4();
+
+        let refunded = top_up(&db, player_id).await;
+        let refunded_id: Uuid = refunded["transactionId"].as_str().unwrap().parse().unwrap();
+        db.update_transaction_status(refunded_id, TransactionStatus::Refunded, None).await.unwrap();
+        assert_eq!(db.get_wallet(player_id).await.unwrap(), gems(0));
+
+        // A chargeback after the gems were spent leaves the player in debt
+        let charged_back = top_up(&db, player_id).await;
+        let charged_back_id: Uuid = charged_back["transactionId"].as_str().unwrap().parse().unwrap();
+        handle_spend(post(json!({"player_id": player_id, "item_id": "sword", "idempotency_key": "k"})), &db, &Metrics::default())
+            .await
+            .unwrap();
+        db.update_transaction_status(charged_back_id, TransactionStatus::ChargedBack, None).await.unwrap();
+        assert_eq!(db.get_wallet(player_id).await.unwrap(), gems(-300));
+
+        let again = post(json!({"player_id": player_id, "item_id": "sword", "idempotency_key": "k2"}));
+        assert!(matches!(handle_spend(again, &db, &Metrics::default()).await, Err(AppError::InsufficientFunds(_))));
+
+        let reversal = NewLedgerEntry::reversal_for(&db.get_transaction(charged_back_id).await.unwrap().unwrap()).unwrap();
+        let replayed = db.append_ledger_entry(&reversal).await.unwrap();
+        assert_eq!((replayed.kind, replayed.amount, replayed.balance_after), (LedgerEntryKind::Reversal, -500, -300));
+        let journaled = NewJournalEntry::for_ledger_entry(&replayed).unwrap();
+        assert_eq!(journaled.kind, JournalKind::WalletReversal);
+        assert!(db.trial_balance().await.unwrap().is_balanced());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_spends_never_overdraw() {        let db = Arc::new(InMemoryDatabase::new());
+        let player_id = Uuid::new_v4();
+        db.append_ledger_entry(&NewLedgerEntry::top_up(player_id, VirtualCurrency::Gems, 500, Uuid::new_v4()))
+            .await
+            .unwrap();
+
+        let spends = (0..10).map(|i| {
+            let db = Arc::clone(&db);
+            tokio::spawn(async move {
+                db.append_ledger_entry(&NewLedgerEntry::spend(player_id, VirtualCurrency::Gems, 100, format!("key-{}", i), "potion"))
+                    .await
+            })
+        }).collect::<Vec<_>>();
+
+        let mut succeeded = Vec::new();
+        for spend in spends {
+            match spend.await.unwrap() {
+                Ok(entry) => succeeded.push(entry),
+                Err(e) => assert!(matches!(e, AppError::InsufficientFunds(_))),
+            }
+        }
+        assert_eq!(succeeded.len(), 5);
+
+        // Replaying a key returns the original entry and moves nothing
+        let first = &succeeded[0];
+        let replay = db
+            .append_ledger_entry(&NewLedgerEntry::spend(player_id, VirtualCurrency::Gems, 100, first.reference.clone(), "potion"))
+            .await
+            .unwrap();
+        assert_eq!(replay.entry_id, first.entry_id);
+
+        let wallet = db.get_wallet(player_id).await.unwrap();
+        assert_eq!(wallet, vec![crate::models::WalletBalance { currency: VirtualCurrency::Gems, balance: 0 }]);
+    }
+}
@@ -0,0 +1,208 @@
+//! # Webhook Handlers
+//!
+//! - `POST /titles/{titleId}/webhooks` - subscribe a game server
+//! - `GET /titles/{titleId}/webhooks` - list a title's subscriptions
+//! - `GET /webhooks/{subscriptionId}/deliveries?limit=` - delivery log
+//! - `POST /webhooks/deliveries/{deliveryId}/redeliver` - send again
+//!
+//! Served only under `/admin/v2`, to IAM-signed operator requests.
+//!
+//! ADVANTAGE: The signing secret is returned once, at creation, and never listed
+
+use lambda_http::{Body, Request, Response};
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::metrics::Metrics;
+use crate::models::{
+    CreateWebhookRequest, FieldError, NewWebhookSubscription, WebhookCreatedResponse,
+    WebhookDeliveryListResponse, WebhookListResponse,
+};
+use crate::services::Database;
+use super::purchase::parse_body;
+use super::router::json_response;
+use super::transactions::parse_query_params;
+
+/// Handle create webhook subscription request
+#[instrument(skip(request, db, metrics))]
+pub async fn handle_create_webhook(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+    title_id: &str,
+) -> Result<Response<Body>, AppError> {
+    let title_id = parse_title_id(title_id)?;
+    let create: CreateWebhookRequest = parse_body(&request)?;
+    create.check_url().await?;
+
+    let subscription = NewWebhookSubscription::new(title_id, create);
+    let subscription = metrics
+        .time_db("create_webhook_subscription", db.create_webhook_subscription(&subscription))
+        .await?;
+
+    Ok(json_response(201, &WebhookCreatedResponse::new(subscription)))
+}
+
+/// Handle list webhook subscriptions request
+#[instrument(skip(db, metrics))]
+pub async fn handle_list_webhooks(
+    db: &dyn Database,
+    metrics: &Metrics,
+    title_id: &str,
+) -> Result<Response<Body>, AppError> {
+    let title_id = parse_title_id(title_id)?;
+    let subscriptions = metrics
+        .time_db("get_webhook_subscriptions", db.get_webhook_subscriptions(&title_id))
+        .await?;
+
+    Ok(json_response(200, &WebhookListResponse::new(title_id, subscriptions)))
+}
+
+/// Handle delivery log request - 404 for unknown subscriptions
+#[instrument(skip(request, db, metrics))]
+pub async fn handle_get_deliveries(
+    request: Request,
+    db: &dyn Database,
+    metrics: &Metrics,
+    subscription_id: &str,
+) -> Result<Response<Body>, AppError> {
+    let subscription_id = parse_id("subscriptionId", subscription_id)?;
+
+    let limit = parse_query_params(request.uri().query().unwrap_or(""))
+        .get("limit")
+        .and_then(|v| v.parse::<i32>().ok())
+        .unwrap_or(100)
+        .clamp(1, 1000);
+
+    metrics
+        .time_db("get_webhook_subscription", db.get_webhook_subscription(subscription_id))
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Webhook subscription {} not found", subscription_id)))?;
+
+    let deliveries = metrics
+        .time_db("get_webhook_deliveries", db.get_webhook_deliveries(subscription_id, limit))
+        .await?;
+
+    info!(count = deliveries.len(), "Retrieved webhook deliveries");
+    Ok(json_response(200, &WebhookDeliveryListResponse::new(subscription_id, deliveries)))
+}
+
+/// Handle redeliver request - the delivery is queued, not sent inline
+///
+/// Works on delivered and dead deliveries alike; the receiver sees the same
+/// `eventId` again.
+#[instrument(skip(db, metrics))]
+pub async fn handle_redeliver(
+    db: &dyn Database,
+    metrics: &Metrics,
+    delivery_id: &str,
+) -> Result<Response<Body>, AppError> {
+    let delivery_id = parse_id("deliveryId", delivery_id)?;
+    let delivery = metrics
+        .time_db("redeliver_webhook", db.redeliver_webhook(delivery_id))
+        .await?;
+
+    info!(delivery_id = %delivery_id, "Webhook delivery queued again");
+    Ok(json_response(202, &delivery))
+}
+
+/// Title IDs are short slugs: letters, digits, `-` and `_`
+fn parse_title_id(title_id: &str) -> Result<String, AppError> {
+    let valid = (1..=64).contains(&title_id.len())
+        && title_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if !valid {
+        return Err(AppError::InvalidFields(vec![FieldError::new(
+            "titleId",
+            "invalid_format",
+            "must be 1-64 letters, digits, '-' or '_'",
+        )]));
+    }
+    Ok(title_id.to_string())
+}
+
+fn parse_id(field: &str, id: &str) -> Result<Uuid, AppError> {
+    id.parse().map_err(|_| AppError::InvalidFields(vec![
+        FieldError::new(field, "invalid_uuid", "must be a UUID"),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::models::{CatalogItemRequest, NewTransaction, TransactionStatus};
+    use crate::services::InMemoryDatabase;
+    use crate::test_support::{body, post};
+
+    fn subscribe(url: &str) -> Request {
+        post(json!({"url": url, "event_types": ["purchase.completed", "purchase.refunded"]}))
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_list_log_and_redeliver() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+
+        let created = handle_create_webhook(subscribe("https://203.0.113.10/hooks"), &db, &metrics, "starfall").await.unwrap();
+        assert_eq!(created.status(), 201);
+        let created = body(&created);
+        assert!(created["secret"].as_str().unwrap().starts_with("whsec_"));
+        let subscription_id = created["subscriptionId"].as_str().unwrap().to_string();
+
+        let listed = body(&handle_list_webhooks(&db, &metrics, "starfall").await.unwrap());
+        assert_eq!(listed["count"], 1);
+        assert!(listed["subscriptions"][0].get("secret").is_none());
+
+        // The title comes from the catalog
+        let listing: CatalogItemRequest =
+            serde_json::from_value(json!({"kind": "consumable", "title_id": "starfall"})).unwrap();
+        db.upsert_catalog_item("sword", &listing).await.unwrap();
+        let tx = db.insert_transaction(&NewTransaction::new(
+            Uuid::new_v4(), "sword".into(), "Sword".into(), 999, "USD".into(), 1, serde_json::Value::Null,
+        )).await.unwrap();
+        db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, Some("pi_1")).await.unwrap();
+
+        let log_request = || lambda_http::http::Request::builder()
+            .uri("/webhooks/x/deliveries?limit=5")
+            .body(Body::Empty)
+            .unwrap();
+        let log = body(&handle_get_deliveries(log_request(), &db, &metrics, &subscription_id).await.unwrap());
+        assert_eq!(log["count"], 1);
+        assert_eq!(log["deliveries"][0]["status"], "pending");
+        assert_eq!(log["deliveries"][0]["payload"]["transaction"]["transactionId"], tx.transaction_id.to_string());
+
+        let delivery_id = log["deliveries"][0]["deliveryId"].as_str().unwrap();
+        let redelivered = handle_redeliver(&db, &metrics, delivery_id).await.unwrap();
+        assert_eq!(redelivered.status(), 202);
+    }
+
+    #[tokio::test]
+    async fn test_internal_destinations_are_refused() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+
+        for url in ["http://203.0.113.10/hooks", "https://10.0.0.5/hooks", "https://169.254.169.254/latest", "https://[::1]/"] {
+            assert!(matches!(
+                handle_create_webhook(subscribe(url), &db, &metrics, "starfall").await,
+                Err(AppError::Validation(_))
+            ), "{} is refused", url);
+        }
+        assert!(db.get_webhook_subscriptions("starfall").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_ids_are_not_found() {
+        let db = InMemoryDatabase::new();
+        let metrics = Metrics::default();
+        let unknown = Uuid::new_v4().to_string();
+
+        assert!(matches!(handle_redeliver(&db, &metrics, &unknown).await, Err(AppError::NotFound(_))));
+        let log_request = lambda_http::http::Request::builder().uri("/webhooks/x/deliveries").body(Body::Empty).unwrap();
+        assert!(matches!(
+            handle_get_deliveries(log_request, &db, &metrics, &unknown).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,17 @@
+//! # MMO Game Micro-transaction Library
+//!
+//! ADVANTAGE: Handlers, services and strategies compile once and are shared
+//! by every binary target
+//! ADVANTAGE: Public API is type-checked independently of the Lambda entrypoint
+
+pub mod errors;
+pub mod handlers;
+pub mod metrics;
+pub mod models;
+pub mod privacy;
+pub mod services;
+pub mod strategies;
+pub mod telemetry;
+
+#[cfg(test)]
+mod test_support;
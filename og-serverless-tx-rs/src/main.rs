@@ -14,19 +14,30 @@
 //! 10. **Fearless concurrency** - Safe parallel processing
 
 use lambda_http::{run, service_fn, Body, Error, Request, Response};
+use lambda_runtime::LambdaEvent;
+use serde::Deserialize;
 use std::sync::Arc;
 use tracing::info;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
 
-mod errors;
-mod handlers;
-mod models;
-mod services;
-mod strategies;
-
-use handlers::router::Router;
-use services::{database::PostgresDatabase, payment::PaymentService};
-use strategies::payment::{StripePaymentStrategy, MockPaymentStrategy};
+use og_serverless_tx_rs::handlers::cors::CorsPolicy;
+use og_serverless_tx_rs::handlers::router::Router;
+use og_serverless_tx_rs::handlers::versioning::ApiVersion;
+use og_serverless_tx_rs::metrics::{EmfSink, Metrics};
+use og_serverless_tx_rs::models;
+use og_serverless_tx_rs::privacy::{RedactingFields, RedactingJson, RedactionPolicy};
+use og_serverless_tx_rs::services::database::{self, MigrationReport};
+use og_serverless_tx_rs::services::gifts::GiftExpirer;
+use og_serverless_tx_rs::services::health::SecretsCheck;
+use og_serverless_tx_rs::services::outbox::{self, OutboxDispatcher};
+use og_serverless_tx_rs::services::reaper::PendingReaper;
+use og_serverless_tx_rs::services::subscriptions::SubscriptionBiller;
+use og_serverless_tx_rs::services::webhooks::WebhookDispatcher;
+use og_serverless_tx_rs::services::{Database, PaymentService, SecretHandle, SecretStore};
+use og_serverless_tx_rs::strategies::{self, payment::{StripePaymentStrategy, MockPaymentStrategy}};
+use og_serverless_tx_rs::telemetry;
 
 /// Application state - shared across Lambda invocations (warm starts)
 /// 
@@ -36,27 +47,201 @@ struct AppState {
     router: Router,
 }
 
+/// Most batches one `dispatch-outbox`, `dispatch-webhooks`,
+/// `renew-subscriptions`, `reap-pending` or `expire-gifts` run handles
+/// before yielding
+const MAX_DISPATCH_BATCHES: usize = 20;
+
+/// Payload of the `migrate` Lambda entrypoint
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct MigrateEvent {
+    dry_run: bool,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     // ADVANTAGE: Structured logging with compile-time format strings
-    tracing_subscriber::fmt()
-        .with_env_filter(
+    // Player IDs are hashed and configured fields masked before any line is written
+    let redaction = Arc::new(RedactionPolicy::from_env()?);
+    let subscriber = tracing_subscriber::registry()
+        .with(
             EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| EnvFilter::new("info"))
         )
-        .json()
-        .with_target(false)
-        .with_current_span(false)
-        .init();
+        .with(
+            tracing_subscriber::fmt::layer()
+                .fmt_fields(RedactingFields::new(Arc::clone(&redaction)))
+                .event_format(RedactingJson::new(redaction))
+        );
+    
+    // ADVANTAGE: OTLP export is only compiled in with `--features otel`
+    #[cfg(feature = "otel")]
+    let subscriber = subscriber.with(telemetry::layer(env!("CARGO_PKG_NAME"))?);
+    
+    subscriber.init();
 
     info!("Initializing MMO Microtransaction Lambda (Rust)");
 
     // ADVANTAGE: Configuration validated at startup, not per-request
-    let config = models::config::Config::from_env()?;
+    // Secret references are resolved once here and cached for warm invocations
+    let secrets = Arc::new(SecretStore::from_env()?);
+    let config = models::config::Config::from_env()?
+        .resolve_secrets(&secrets)
+        .await?;
+    
+    // ADVANTAGE: Database backend created once, reused across warm invocations
+    // The Data API backend needs no VPC and no connection pool
+    let db = database::connect(&config.database).await?;
+    
+    // ADVANTAGE: EMF lines on stdout become CloudWatch metrics - no API calls
+    let metrics = Metrics::new(Arc::new(EmfSink::new(config.metrics_namespace.clone())));
+    
+    // Migrations run from `migrate [--dry-run]` locally, or from the
+    // Lambda whose handler is `migrate` (the custom runtime sets `_HANDLER`)
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("migrate") => {
+            let dry_run = args.any(|arg| arg == "--dry-run");
+            let report = db.run_migrations(dry_run).await?;
+            print_migration_report(&report)?;
+            return Ok(());
+        }
+        // Finance reconciliation: account totals, failing if any currency is off
+        Some("trial-balance") => {
+            let trial_balance = db.trial_balance().await?;
+            println!("{}", serde_json::to_string_pretty(&trial_balance)?);
+            if !trial_balance.is_balanced() {
+                return Err("Trial balance does not balance".into());
+            }
+            return Ok(());
+        }
+        // Publish due outbox events once, e.g. to drain a backlog by hand
+        Some("dispatch-outbox") => {
+            let report = outbox_dispatcher(db)?.drain(MAX_DISPATCH_BATCHES).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        // Send due webhook deliveries once, e.g. against a local receiver
+        Some("dispatch-webhooks") => {
+            let report = webhook_dispatcher(db)?.drain(MAX_DISPATCH_BATCHES).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        // Charge due renewals once, e.g. against the mock strategy
+        Some("renew-subscriptions") => {
+            let biller = SubscriptionBiller::new(db, payment_service(&config, &secrets, &metrics));
+            let report = biller.drain(MAX_DISPATCH_BATCHES).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        // Settle purchases left pending by a crashed request to the processor's outcome
+        Some("reap-pending") => {
+            let reaper = PendingReaper::new(db, payment_service(&config, &secrets, &metrics));
+            let report = reaper.drain(MAX_DISPATCH_BATCHES).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        // Refund gifts left unanswered past the answer window
+        Some("expire-gifts") => {
+            let expirer = GiftExpirer::new(db, payment_service(&config, &secrets, &metrics));
+            let report = expirer.drain(MAX_DISPATCH_BATCHES).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        _ => {}
+    }
+    
+    if std::env::var("_HANDLER").as_deref() == Ok("migrate") {
+        return lambda_runtime::run(lambda_runtime::service_fn(|event: LambdaEvent<MigrateEvent>| {
+            let db = Arc::clone(&db);
+            async move { Ok::<_, Error>(db.run_migrations(event.payload.dry_run).await?) }
+        }))
+        .await;
+    }
+    
+    // Scheduled publisher for the transactional outbox; the event payload is ignored
+    if std::env::var("_HANDLER").as_deref() == Ok("dispatch-outbox") {
+        let dispatcher = Arc::new(outbox_dispatcher(db)?);
+        return lambda_runtime::run(lambda_runtime::service_fn(|_: LambdaEvent<serde_json::Value>| {
+            let dispatcher = Arc::clone(&dispatcher);
+            async move { Ok::<_, Error>(dispatcher.drain(MAX_DISPATCH_BATCHES).await?) }
+        }))
+        .await;
+    }
     
-    // ADVANTAGE: Database pool created once, reused across warm invocations
-    let db = Arc::new(PostgresDatabase::new(&config.database_url).await?);
+    // Scheduled sender for webhook deliveries; the event payload is ignored
+    if std::env::var("_HANDLER").as_deref() == Ok("dispatch-webhooks") {
+        let dispatcher = Arc::new(webhook_dispatcher(db)?);
+        return lambda_runtime::run(lambda_runtime::service_fn(|_: LambdaEvent<serde_json::Value>| {
+            let dispatcher = Arc::clone(&dispatcher);
+            async move { Ok::<_, Error>(dispatcher.drain(MAX_DISPATCH_BATCHES).await?) }
+        }))
+        .await;
+    }
     
+    // Scheduled renewal and dunning run for subscriptions; the event payload is ignored
+    if std::env::var("_HANDLER").as_deref() == Ok("renew-subscriptions") {
+        let biller = Arc::new(SubscriptionBiller::new(db, payment_service(&config, &secrets, &metrics)));
+        return lambda_runtime::run(lambda_runtime::service_fn(|_: LambdaEvent<serde_json::Value>| {
+            let biller = Arc::clone(&biller);
+            async move { Ok::<_, Error>(biller.drain(MAX_DISPATCH_BATCHES).await?) }
+        }))
+        .await;
+    }
+    
+    // Scheduled cleanup of timed-out pending purchases; the event payload is ignored
+    if std::env::var("_HANDLER").as_deref() == Ok("reap-pending") {
+        let reaper = Arc::new(PendingReaper::new(db, payment_service(&config, &secrets, &metrics)));
+        return lambda_runtime::run(lambda_runtime::service_fn(|_: LambdaEvent<serde_json::Value>| {
+            let reaper = Arc::clone(&reaper);
+            async move { Ok::<_, Error>(reaper.drain(MAX_DISPATCH_BATCHES).await?) }
+        }))
+        .await;
+    }
+    
+    // Scheduled refund of unanswered gifts; the event payload is ignored
+    if std::env::var("_HANDLER").as_deref() == Ok("expire-gifts") {
+        let expirer = Arc::new(GiftExpirer::new(db, payment_service(&config, &secrets, &metrics)));
+        return lambda_runtime::run(lambda_runtime::service_fn(|_: LambdaEvent<serde_json::Value>| {
+            let expirer = Arc::clone(&expirer);
+            async move { Ok::<_, Error>(expirer.drain(MAX_DISPATCH_BATCHES).await?) }
+        }))
+        .await;
+    }
+    
+    // ADVANTAGE: Advisory lock makes this safe when many containers start at once
+    if config.auto_migrate {
+        let report = db.run_migrations(false).await?;
+        info!(applied = ?report.applied, version = ?report.current_version, "Schema migrations checked");
+    }
+    
+    let payment_service = payment_service(&config, &secrets, &metrics);
+    
+    // ADVANTAGE: Router is statically typed - all routes validated at compile time
+    let router = Router::new(db, payment_service)
+        .with_cors(CorsPolicy::new(config.cors.clone()))
+        .with_error_format(config.error_format)
+        .with_metrics(metrics)
+        .with_db_degraded_after(config.health_db_degraded_after)
+        .with_lifecycle(ApiVersion::V1, config.api_v1_lifecycle)
+        .with_health_check(Arc::new(SecretsCheck::new(Arc::clone(&secrets))));
+    let state = Arc::new(AppState { router });
+
+    // ADVANTAGE: Lambda runtime is a thin wrapper, not a full interpreter
+    run(service_fn(|event: Request| {
+        let state = Arc::clone(&state);
+        async move { handle_request(event, state).await }
+    }))
+    .await
+}
+
+/// Payment service for the configured strategy
+fn payment_service(
+    config: &models::config::Config,
+    secrets: &Arc<SecretStore>,
+    metrics: &Metrics,
+) -> Arc<PaymentService> {
     // ADVANTAGE: Strategy pattern with compile-time polymorphism
     // The concrete strategy is selected at startup, not per-request
     let payment_strategy: Arc<dyn strategies::payment::PaymentStrategy> = 
@@ -65,21 +250,45 @@ async fn main() -> Result<(), Error> {
             Arc::new(MockPaymentStrategy::new())
         } else {
             info!("Using Stripe payment strategy");
-            Arc::new(StripePaymentStrategy::new(&config.stripe_api_key))
+            Arc::new(StripePaymentStrategy::new(SecretHandle::new(
+                "STRIPE_API_KEY",
+                config.stripe_api_key.expose().clone(),
+                Arc::clone(secrets),
+            )))
         };
     
-    let payment_service = Arc::new(PaymentService::new(payment_strategy));
+    Arc::new(PaymentService::new(payment_strategy).with_metrics(metrics.clone()))
+}
+
+/// Outbox dispatcher for the sink selected by `OUTBOX_SINK`
+fn outbox_dispatcher(db: Arc<dyn Database>) -> Result<OutboxDispatcher, Error> {
+    let config = models::config::OutboxConfig::from_env()?;
     
-    // ADVANTAGE: Router is statically typed - all routes validated at compile time
-    let router = Router::new(db, payment_service);
-    let state = Arc::new(AppState { router });
+    Ok(OutboxDispatcher::new(db, outbox::sink_for(&config.sink))
+        .with_batch_size(config.batch_size)
+        .with_max_attempts(config.max_attempts))
+}
 
-    // ADVANTAGE: Lambda runtime is a thin wrapper, not a full interpreter
-    run(service_fn(|event: Request| {
-        let state = Arc::clone(&state);
-        async move { handle_request(event, state).await }
-    }))
-    .await
+/// Webhook dispatcher with `WEBHOOK_*` settings
+fn webhook_dispatcher(db: Arc<dyn Database>) -> Result<WebhookDispatcher, Error> {
+    let config = models::config::WebhookConfig::from_env()?;
+    
+    Ok(WebhookDispatcher::new(db)
+        .with_max_attempts(config.max_attempts)
+        .with_timeout(config.timeout))
+}
+
+/// Print a migration report, including pending SQL for dry runs
+fn print_migration_report(report: &MigrationReport) -> Result<(), Error> {
+    if report.dry_run {
+        for migration in &report.pending {
+            println!("-- {:03}_{} ({})", migration.version, migration.name, migration.checksum);
+            println!("{}", migration.sql.unwrap_or_default());
+        }
+    }
+    
+    println!("{}", serde_json::to_string_pretty(report)?);
+    Ok(())
 }
 
 /// Handle incoming HTTP request
@@ -91,15 +300,12 @@ async fn handle_request(
     request: Request,
     state: Arc<AppState>,
 ) -> Result<Response<Body>, Error> {
-    // ADVANTAGE: Structured logging with typed fields
-    info!(
-        method = %request.method(),
-        path = %request.uri().path(),
-        "Processing request"
-    );
-
+    // ADVANTAGE: Router logs inside a span tagged with the request ID
     // ADVANTAGE: Router returns strongly-typed Response
     let response = state.router.route(request).await;
     
+    // ADVANTAGE: Spans are exported before Lambda freezes the container
+    telemetry::flush().await;
+    
     Ok(response)
 }
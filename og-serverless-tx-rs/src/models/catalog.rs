@@ -0,0 +1,305 @@
+//! Catalog models - item kinds and limited stock
+//!
+//! Items without a catalog entry behave as before: any quantity, any number
+//! of times. A catalog entry adds two rules, both enforced when the pending
+//! transaction is inserted:
+//!
+//! - non-consumable and subscription items cannot be bought while the
+//!   player owns one or has a purchase of it pending
+//! - items with a `stock_limit` reserve stock per pending purchase; the
+//!   reservation becomes a sale on completion and is released on failure.
+//!   Refunds do not return stock - a limited drop stays limited.
+//!
+//! A catalog entry may also carry a list price, which v2 purchases are
+//! charged instead of a client-supplied amount. Currency packs add the
+//! virtual currency a top-up credits, and items sold for virtual currency
+//! add their wallet price, so no wallet amount comes from the client.
+//!
+//! The game title an item belongs to is stamped on its purchases, so the
+//! title's webhooks fire for them whatever the client sends.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::errors::AppError;
+use super::entitlement::CONSUMABLE_METADATA_KEY;
+use super::transaction::Currency;
+use super::wallet::{VirtualCurrency, WalletCredit};
+use super::webhook::TITLE_METADATA_KEY;
+
+/// Metadata key recording the catalog kind an item was bought as
+pub const ITEM_KIND_METADATA_KEY: &str = "item_kind";
+
+/// How an item may be bought and owned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "item_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    /// Used up in play - bought any number of times
+    Consumable,
+    /// Owned once - skins, mounts
+    NonConsumable,
+    /// Owned once while active
+    Subscription,
+}
+
+impl ItemKind {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Consumable => "consumable",
+            Self::NonConsumable => "non_consumable",
+            Self::Subscription => "subscription",
+        }
+    }
+
+    /// A player may hold at most one
+    pub const fn is_unique(&self) -> bool {
+        !matches!(self, Self::Consumable)
+    }
+}
+
+/// Catalog entry for one item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogItem {
+    pub item_id: String,
+    pub kind: ItemKind,
+    /// Units that may ever be sold, `None` for unlimited
+    pub stock_limit: Option<i32>,
+    /// Units held by pending purchases
+    pub stock_reserved: i32,
+    pub stock_sold: i32,
+    /// List price of one unit in minor units, `None` if unpriced
+    pub price_cents: Option<i64>,
+    /// ISO code of `price_cents`
+    pub currency: Option<String>,
+    /// Virtual currency a top-up of this pack credits, `None` if not a pack
+    pub credit_currency: Option<VirtualCurrency>,
+    pub credit_amount: Option<i64>,
+    /// Price of one unit in virtual currency, `None` if not sold for it
+    pub wallet_price: Option<i64>,
+    pub wallet_currency: Option<VirtualCurrency>,
+    /// Game title whose webhooks hear of purchases, `None` for none
+    pub title_id: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CatalogItem {
+    /// List price of one unit and its currency
+    ///
+    /// ADVANTAGE: The client never names the amount it is charged
+    pub fn unit_price(&self) -> Result<(i64, &str), AppError> {
+        match (self.price_cents, self.currency.as_deref()) {
+            (Some(price_cents), Some(currency)) => Ok((price_cents, currency)),
+            _ => Err(AppError::Validation(format!("Item {} has no catalog price", self.item_id))),
+        }
+    }
+
+    /// Wallet credit bought by topping up with this pack
+    pub fn pack_credit(&self) -> Result<WalletCredit, AppError> {
+        match (self.credit_currency, self.credit_amount) {
+            (Some(currency), Some(amount)) => Ok(WalletCredit { currency, amount }),
+            _ => Err(AppError::Validation(format!("Item {} is not a currency pack", self.item_id))),
+        }
+    }
+
+    /// Price of one unit in virtual currency
+    pub fn wallet_unit_price(&self) -> Result<(i64, VirtualCurrency), AppError> {
+        match (self.wallet_price, self.wallet_currency) {
+            (Some(price), Some(currency)) => Ok((price, currency)),
+            _ => Err(AppError::Validation(format!("Item {} has no wallet price", self.item_id))),
+        }
+    }
+
+    /// List price of `quantity` units
+    ///
+    /// ADVANTAGE: Overflow is a validation error, never a panic
+    pub fn total_price_cents(&self, quantity: i32) -> Result<i64, AppError> {
+        self.unit_price()?
+            .0
+            .checked_mul(i64::from(quantity))
+            .ok_or_else(|| AppError::Validation("Total price is out of range".into()))
+    }
+
+    /// Units still available, `None` for unlimited
+    pub fn stock_remaining(&self) -> Option<i32> {
+        self.stock_limit
+            .map(|limit| (limit - self.stock_reserved - self.stock_sold).max(0))
+    }
+
+    /// Check a purchase of `quantity` against the item's rules
+    ///
+    /// `already_owned` is whether the player holds an active entitlement or
+    /// a pending purchase of the item. Returns the units to reserve, zero
+    /// for unlimited items.
+    pub fn check_purchase(&self, quantity: i32, already_owned: bool) -> Result<i32, AppError> {
+        if self.kind.is_unique() {
+            if quantity != 1 {
+                return Err(AppError::Validation(format!(
+                    "quantity must be 1 for {} items",
+                    self.kind.as_str()
+                )));
+            }
+            if already_owned {
+                return Err(AppError::AlreadyOwned(format!("Item {} is already owned", self.item_id)));
+            }
+        }
+
+        match self.stock_remaining() {
+            Some(remaining) if remaining < quantity => Err(AppError::OutOfStock(format!(
+                "Item {} has {} left, {} requested",
+                self.item_id, remaining, quantity
+            ))),
+            Some(_) => Ok(quantity),
+            None => Ok(0),
+        }
+    }
+
+    /// `metadata` with the catalog kind and title recorded, so entitlements
+    /// are granted and webhooks sent as the catalog says rather than as the
+    /// client says
+    ///
+    /// Metadata that is neither an object nor null is left as sent.
+    pub fn stamp(&self, metadata: &Value) -> Value {
+        let mut stamped = match metadata {
+            Value::Null => serde_json::Map::new(),
+            Value::Object(map) => map.clone(),
+            other => return other.clone(),
+        };
+        stamped.insert(ITEM_KIND_METADATA_KEY.into(), Value::from(self.kind.as_str()));
+        stamped.insert(CONSUMABLE_METADATA_KEY.into(), Value::from(!self.kind.is_unique()));
+        match &self.title_id {
+            Some(title_id) => stamped.insert(TITLE_METADATA_KEY.into(), Value::from(title_id.as_str())),
+            None => stamped.remove(TITLE_METADATA_KEY),
+        };
+        Value::Object(stamped)
+    }
+}
+
+/// Body of `PUT /catalog/items/{itemId}`
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CatalogItemRequest {
+    pub kind: ItemKind,
+
+    /// Omit for unlimited stock; cannot drop below units reserved or sold
+    #[validate(range(min = 0, max = 100_000_000))]
+    #[serde(default)]
+    pub stock_limit: Option<i32>,
+
+    /// List price of one unit; omit, with `currency`, for an unpriced item
+    #[validate(range(min = 1, max = 99_999_999))]
+    #[serde(default)]
+    pub price_cents: Option<i64>,
+
+    #[serde(default)]
+    pub currency: Option<Currency>,
+
+    /// Virtual currency credited by a top-up; set, with `credit_amount`
+    /// and a list price, to make the item a currency pack
+    #[serde(default)]
+    pub credit_currency: Option<VirtualCurrency>,
+
+    #[validate(range(min = 1, max = 1_000_000))]
+    #[serde(default)]
+    pub credit_amount: Option<i64>,
+
+    /// Price of one unit in virtual currency; omit, with
+    /// `wallet_currency`, if it cannot be bought from the wallet
+    #[validate(range(min = 1, max = 1_000_000))]
+    #[serde(default)]
+    pub wallet_price: Option<i64>,
+
+    #[serde(default)]
+    pub wallet_currency: Option<VirtualCurrency>,
+
+    /// Game title the item belongs to, for webhooks
+    #[validate(length(min = 1, max = 64))]
+    #[serde(default)]
+    pub title_id: Option<String>,
+}
+
+impl CatalogItemRequest {
+    /// Every amount needs its currency and every currency an amount; a
+    /// currency pack also needs a list price
+    pub fn check_price(&self) -> Result<(), AppError> {
+        if self.price_cents.is_some() != self.currency.is_some() {
+            return Err(AppError::Validation("price_cents and currency must be set together".into()));
+        }
+        if self.credit_amount.is_some() != self.credit_currency.is_some() {
+            return Err(AppError::Validation("credit_amount and credit_currency must be set together".into()));
+        }
+        if self.credit_amount.is_some() && self.price_cents.is_none() {
+            return Err(AppError::Validation("A currency pack needs price_cents".into()));
+        }
+        if self.wallet_price.is_some() != self.wallet_currency.is_some() {
+            return Err(AppError::Validation("wallet_price and wallet_currency must be set together".into()));
+        }
+        Ok(())
+    }
+}
+
+/// How a status change settles the purchase's stock reservation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StockSettlement {
+    /// Completed - reserved units become sold
+    Commit,
+    /// Failed - reserved units return to stock
+    Release,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(kind: ItemKind, stock_limit: Option<i32>) -> CatalogItem {
+        CatalogItem {
+            item_id: "founders_cape".into(),
+            kind,
+            stock_limit,
+            stock_reserved: 3,
+            stock_sold: 5,
+            price_cents: Some(250),
+            currency: Some("USD".into()),
+            credit_currency: None,
+            credit_amount: None,
+            wallet_price: None,
+            wallet_currency: None,
+            title_id: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_ownership_and_stock_rules() {
+        let cape = item(ItemKind::NonConsumable, Some(10));
+        assert_eq!(cape.stock_remaining(), Some(2));
+        assert_eq!(cape.check_purchase(1, false).unwrap(), 1);
+        assert!(matches!(cape.check_purchase(1, true), Err(AppError::AlreadyOwned(_))));
+        assert!(matches!(cape.check_purchase(2, false), Err(AppError::Validation(_))));
+
+        let potion = item(ItemKind::Consumable, Some(10));
+        assert_eq!(potion.check_purchase(2, true).unwrap(), 2);
+        assert!(matches!(potion.check_purchase(3, false), Err(AppError::OutOfStock(_))));
+
+        assert_eq!(item(ItemKind::Consumable, None).check_purchase(50, false).unwrap(), 0);
+
+        assert_eq!(potion.total_price_cents(2).unwrap(), 500);
+        let unpriced = CatalogItem { price_cents: None, currency: None, ..potion };
+        assert!(matches!(unpriced.total_price_cents(1), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_stamp_overrides_client_consumable_flag() {
+        let stamped = item(ItemKind::NonConsumable, None).stamp(&json!({"consumable": true, "color": "red"}));
+        assert_eq!(stamped, json!({"consumable": false, "color": "red", "item_kind": "non_consumable"}));
+        assert_eq!(item(ItemKind::Consumable, None).stamp(&Value::Null)["consumable"], true);
+
+        let titled = CatalogItem { title_id: Some("starfall".into()), ..item(ItemKind::Consumable, None) };
+        assert_eq!(titled.stamp(&json!({"title_id": "moonrise"}))["title_id"], "starfall");
+        assert!(item(ItemKind::Consumable, None).stamp(&json!({"title_id": "moonrise"})).get("title_id").is_none());
+    }
+}
@@ -0,0 +1,336 @@
+//! Code models - promo codes and redeemable gift/key codes
+//!
+//! Codes are generated in bulk per campaign: 15 random Crockford base32
+//! characters (75 bits) and a check character, shown as
+//! `XXXX-XXXX-XXXX-XXXX`. The check character rejects typos before any
+//! lookup, and `I`/`L`/`O` are read as `1`/`1`/`0`.
+//!
+//! A campaign's reward is either a grant - items and/or wallet credit, no
+//! payment - or a discount applied to a purchase. Either way the redemption
+//! is a `microtransactions` row: zero-amount for grants, discounted for
+//! purchases. Uses are counted from redemptions whose transaction did not
+//! fail, so a declined card gives the use back.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::errors::AppError;
+use super::promotion::{BundleItem, Promotion, PromotionKind};
+use super::transaction::Currency;
+use super::wallet::WalletCredit;
+
+/// Crockford base32 - no `I`, `L`, `O` or `U`
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Random characters before the check character
+const BODY_LENGTH: usize = 15;
+
+/// Stored length of a normalized code
+pub const CODE_LENGTH: usize = BODY_LENGTH + 1;
+
+/// Failed redemptions a player or client address may make per window
+/// before further attempts are rate limited
+pub const MAX_FAILED_ATTEMPTS: i64 = 10;
+
+/// Window `MAX_FAILED_ATTEMPTS` is counted over
+pub const FAILED_ATTEMPT_WINDOW: chrono::Duration = chrono::Duration::minutes(15);
+
+/// Currency of zero-amount grant redemptions - ISO 4217 "no currency"
+pub const NO_CURRENCY: &str = "XXX";
+
+/// Metadata key recording which campaign a grant redemption came from
+pub const CODE_GRANT_METADATA_KEY: &str = "code_grant";
+
+fn digit(c: u8) -> Option<usize> {
+    ALPHABET.iter().position(|&a| a == c)
+}
+
+/// Luhn mod 32 check character for `body`
+fn check_character(body: &[u8]) -> u8 {
+    let sum: usize = body
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &c)| {
+            let value = digit(c).expect("body is in the alphabet");
+            let value = if i % 2 == 0 { value * 2 } else { value };
+            value / 32 + value % 32
+        })
+        .sum();
+    ALPHABET[(32 - sum % 32) % 32]
+}
+
+/// A fresh unguessable code, normalized
+pub fn generate_code() -> String {
+    // A v4 UUID has 122 random bits; skip the version and variant bits
+    let uuid = Uuid::new_v4().as_u128();
+    let mut random = ((uuid >> 80) << 56) | (uuid & ((1 << 56) - 1));
+
+    let mut code: Vec<u8> = (0..BODY_LENGTH)
+        .map(|_| {
+            let c = ALPHABET[(random & 31) as usize];
+            random >>= 5;
+            c
+        })
+        .collect();
+    code.push(check_character(&code));
+    String::from_utf8(code).expect("alphabet is ASCII")
+}
+
+/// The stored form of a code as typed, `None` unless its check character matches
+pub fn normalize_code(input: &str) -> Option<String> {
+    let code: Vec<u8> = input
+        .bytes()
+        .filter(|c| !matches!(c, b'-' | b' '))
+        .map(|c| match c.to_ascii_uppercase() {
+            b'O' => b'0',
+            b'I' | b'L' => b'1',
+            c => c,
+        })
+        .collect();
+
+    let valid = code.len() == CODE_LENGTH
+        && code.iter().all(|&c| digit(c).is_some())
+        && check_character(&code[..BODY_LENGTH]) == code[BODY_LENGTH];
+    valid.then(|| String::from_utf8(code).expect("alphabet is ASCII"))
+}
+
+/// `XXXX-XXXX-XXXX-XXXX` display form of a normalized code
+pub fn format_code(code: &str) -> String {
+    code.as_bytes()
+        .chunks(4)
+        .map(|chunk| std::str::from_utf8(chunk).expect("alphabet is ASCII"))
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// What redeeming a code gives
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CodeReward {
+    /// Items and/or wallet credit, without payment
+    Grant {
+        #[serde(default)]
+        items: Vec<BundleItem>,
+        #[serde(default)]
+        wallet_credit: Option<WalletCredit>,
+    },
+    /// Percent or amount off one purchase
+    Discount {
+        kind: PromotionKind,
+        value: i64,
+        #[serde(default)]
+        currency: Option<Currency>,
+        /// Items the code may be used on, empty for every item
+        #[serde(default)]
+        item_ids: Vec<String>,
+    },
+}
+
+/// A batch of codes sharing one reward and set of limits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeCampaign {
+    pub campaign_id: Uuid,
+    pub name: String,
+    #[sqlx(json)]
+    pub reward: CodeReward,
+    /// Redemptions allowed per code, `None` for unlimited
+    pub max_uses: Option<i32>,
+    /// Redemptions of one code allowed per player
+    pub per_player_limit: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub code_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CodeCampaign {
+    /// Check one more redemption of a code already redeemed `uses` times,
+    /// `player_uses` of them by this player
+    pub fn check_redemption(&self, uses: i64, player_uses: i64, now: DateTime<Utc>) -> Result<(), AppError> {
+        if self.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(AppError::CodeUnavailable("Code has expired".into()));
+        }
+        if self.max_uses.is_some_and(|max_uses| uses >= i64::from(max_uses)) {
+            return Err(AppError::CodeUnavailable("Code has been fully redeemed".into()));
+        }
+        if player_uses >= i64::from(self.per_player_limit) {
+            return Err(AppError::CodeUnavailable("Code already redeemed by this player".into()));
+        }
+        Ok(())
+    }
+
+    /// A discount code's reward as a stackable promotion, `None` for grants
+    pub fn discount(&self) -> Option<Promotion> {
+        let CodeReward::Discount { kind, value, currency, item_ids } = &self.reward else {
+            return None;
+        };
+        Some(Promotion {
+            promotion_id: self.campaign_id,
+            name: self.name.clone(),
+            kind: *kind,
+            value: *value,
+            currency: currency.map(|c| c.as_str().to_string()),
+            item_ids: item_ids.clone(),
+            segments: Vec::new(),
+            bundle_items: Vec::new(),
+            stackable: true,
+            priority: 0,
+            starts_at: self.created_at,
+            ends_at: self.expires_at.unwrap_or(DateTime::<Utc>::MAX_UTC),
+            created_at: self.created_at,
+        })
+    }
+}
+
+/// Body of `POST /codes/campaigns`
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateCodeCampaignRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+
+    pub reward: CodeReward,
+
+    /// Codes to generate
+    #[validate(range(min = 1, max = 10_000))]
+    pub count: i32,
+
+    /// 1 for single-use codes; omit for unlimited
+    #[validate(range(min = 1))]
+    #[serde(default)]
+    pub max_uses: Option<i32>,
+
+    #[validate(range(min = 1, max = 1000))]
+    #[serde(default = "default_per_player_limit")]
+    pub per_player_limit: i32,
+
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+const fn default_per_player_limit() -> i32 {
+    1
+}
+
+impl CreateCodeCampaignRequest {
+    /// Reward rules `Validate` cannot express
+    pub fn check(&self) -> Result<(), AppError> {
+        match &self.reward {
+            CodeReward::Grant { items, wallet_credit } => {
+                if items.is_empty() && wallet_credit.is_none() {
+                    return Err(AppError::Validation("grant codes need items or a wallet credit".into()));
+                }
+                if items.len() > 50 || items.iter().any(|i| i.validate().is_err()) {
+                    return Err(AppError::Validation("grant items must be 1-50 valid items".into()));
+                }
+                let distinct: BTreeSet<_> = items.iter().map(|i| &i.item_id).collect();
+                if distinct.len() != items.len() {
+                    return Err(AppError::Validation("grant items must be distinct".into()));
+                }
+                if wallet_credit.is_some_and(|credit| !(1..=1_000_000).contains(&credit.amount)) {
+                    return Err(AppError::Validation("wallet_credit amount must be 1 to 1000000".into()));
+                }
+                Ok(())
+            }
+            CodeReward::Discount { kind, value, currency, .. } => match kind {
+                PromotionKind::Bundle => Err(AppError::Validation("discount codes cannot be bundles".into())),
+                PromotionKind::PercentOff if !(1..=100).contains(value) => {
+                    Err(AppError::Validation("percent_off value must be 1 to 100".into()))
+                }
+                PromotionKind::AmountOff if currency.is_none() || !(1..=99_999_999).contains(value) => {
+                    Err(AppError::Validation("amount_off codes need a currency and a value of 1 to 99999999".into()))
+                }
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+/// Campaign to insert, with its generated codes
+#[derive(Debug, Clone)]
+pub struct NewCodeCampaign {
+    pub campaign_id: Uuid,
+    pub request: CreateCodeCampaignRequest,
+    /// Normalized, distinct
+    pub codes: Vec<String>,
+}
+
+impl NewCodeCampaign {
+    pub fn new(request: CreateCodeCampaignRequest) -> Self {
+        let mut codes = BTreeSet::new();
+        while codes.len() < request.count as usize {
+            codes.insert(generate_code());
+        }
+        Self { campaign_id: Uuid::new_v4(), request, codes: codes.into_iter().collect() }
+    }
+
+    /// The stored form of this campaign
+    pub fn into_campaign(self, created_at: DateTime<Utc>) -> CodeCampaign {
+        CodeCampaign {
+            campaign_id: self.campaign_id,
+            name: self.request.name,
+            reward: self.request.reward,
+            max_uses: self.request.max_uses,
+            per_player_limit: self.request.per_player_limit,
+            expires_at: self.request.expires_at,
+            code_count: self.codes.len() as i32,
+            created_at,
+        }
+    }
+}
+
+/// Body of `POST /codes/redeem`
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct RedeemCodeRequest {
+    pub player_id: Uuid,
+
+    #[validate(length(min = 1, max = 64))]
+    pub code: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_codes_carry_a_check_character() {
+        let code = generate_code();
+        assert_eq!(code.len(), CODE_LENGTH);
+        assert_ne!(code, generate_code());
+
+        let typed = format_code(&code).to_lowercase();
+        assert_eq!(typed.len(), 19);
+        assert_eq!(normalize_code(&typed), Some(code.clone()));
+
+        // Any single-character typo is caught
+        let mut typo = code.into_bytes();
+        typo[3] = if typo[3] == b'7' { b'8' } else { b'7' };
+        assert_eq!(normalize_code(std::str::from_utf8(&typo).unwrap()), None);
+        assert_eq!(normalize_code("not-a-code"), None);
+    }
+
+    #[test]
+    fn test_redemption_limits() {
+        let campaign = CodeCampaign {
+            campaign_id: Uuid::new_v4(),
+            name: "Gamescom".into(),
+            reward: CodeReward::Grant { items: Vec::new(), wallet_credit: None },
+            max_uses: Some(100),
+            per_player_limit: 1,
+            expires_at: Some(Utc::now() + chrono::Duration::days(1)),
+            code_count: 1,
+            created_at: Utc::now(),
+        };
+        let now = Utc::now();
+
+        assert!(campaign.check_redemption(99, 0, now).is_ok());
+        assert!(matches!(campaign.check_redemption(100, 0, now), Err(AppError::CodeUnavailable(_))));
+        assert!(matches!(campaign.check_redemption(5, 1, now), Err(AppError::CodeUnavailable(_))));
+        assert!(campaign.check_redemption(0, 0, now + chrono::Duration::days(2)).is_err());
+        assert!(campaign.discount().is_none());
+    }
+}
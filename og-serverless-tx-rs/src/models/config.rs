@@ -1,19 +1,35 @@
 //! Configuration model with compile-time type safety
 
 use crate::errors::AppError;
+use crate::privacy::Secret;
+use crate::services::secrets::SecretStore;
+use chrono::{DateTime, Utc};
 use std::env;
+use std::time::Duration;
 
 /// Application configuration
 /// 
 /// ADVANTAGE: Missing or invalid config is caught at startup, not runtime
 /// ADVANTAGE: All fields have explicit types - no string-to-number coercion bugs
+/// ADVANTAGE: Credentials are `Secret`s, so `{:?}` of the config is safe to log
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub database_url: String,
-    pub stripe_api_key: String,
+    pub database: DatabaseConfig,
+    pub stripe_api_key: Secret<String>,
     pub use_mock_payments: bool,
     pub max_transaction_cents: i64,
     pub max_quantity: i32,
+    /// Apply pending schema migrations at cold start
+    pub auto_migrate: bool,
+    pub cors: CorsConfig,
+    pub error_format: ErrorFormat,
+    /// CloudWatch namespace for EMF metrics
+    pub metrics_namespace: String,
+    /// Database round trip above which readiness reports degraded
+    pub health_db_degraded_after: Duration,
+    /// Deprecation schedule announced on API v1 responses, `None` while
+    /// v1 is not deprecated
+    pub api_v1_lifecycle: Option<Lifecycle>,
 }
 
 impl Config {
@@ -23,11 +39,12 @@ impl Config {
     /// ADVANTAGE: No silent defaults that hide misconfiguration
     pub fn from_env() -> Result<Self, AppError> {
         // ADVANTAGE: Each env var read returns Result, forcing error handling
-        let database_url = env::var("DATABASE_URL")
-            .map_err(|_| AppError::Configuration("DATABASE_URL not set".into()))?;
+        let database = DatabaseConfig::from_env()?;
+        let cors = CorsConfig::from_env()?;
+        let error_format = ErrorFormat::from_env()?;
         
-        let stripe_api_key = env::var("STRIPE_API_KEY")
-            .unwrap_or_else(|_| String::new());
+        let stripe_api_key = Secret::new(env::var("STRIPE_API_KEY")
+            .unwrap_or_else(|_| String::new()));
         
         // ADVANTAGE: Parse with explicit error handling - no NaN surprises
         let use_mock_payments = env::var("USE_MOCK_PAYMENTS")
@@ -41,6 +58,22 @@ impl Config {
                 "MAX_TRANSACTION_CENTS must be a valid integer".into()
             ))?;
         
+        let auto_migrate = env::var("AUTO_MIGRATE")
+            .map(|v| v.to_lowercase() == "true")
+            .unwrap_or(false);
+        
+        let metrics_namespace = env::var("METRICS_NAMESPACE")
+            .unwrap_or_else(|_| crate::metrics::DEFAULT_NAMESPACE.to_string());
+        
+        let health_db_degraded_after = match env::var("HEALTH_DB_DEGRADED_MS") {
+            Ok(v) => Duration::from_millis(v.parse::<u64>().map_err(|_| AppError::Configuration(
+                "HEALTH_DB_DEGRADED_MS must be a valid integer".into()
+            ))?),
+            Err(_) => crate::services::health::DEFAULT_DB_DEGRADED_AFTER,
+        };
+        
+        let api_v1_lifecycle = Lifecycle::from_env("API_V1")?;
+        
         let max_quantity = env::var("MAX_QUANTITY")
             .unwrap_or_else(|_| "100".to_string())
             .parse::<i32>()
@@ -56,19 +89,417 @@ impl Config {
         }
 
         Ok(Self {
-            database_url,
+            database,
             stripe_api_key,
             use_mock_payments,
             max_transaction_cents,
             max_quantity,
+            auto_migrate,
+            cors,
+            error_format,
+            metrics_namespace,
+            health_db_degraded_after,
+            api_v1_lifecycle,
+        })
+    }
+    
+    /// Resolve secret references (`secretsmanager:`, `ssm:`, `file:`)
+    /// 
+    /// `DATABASE_URL` is replaced by its resolved value. `STRIPE_API_KEY` is
+    /// resolved to fail fast and warm the cache, but kept as the reference so
+    /// the payment strategy can refresh it through a `SecretHandle`.
+    /// 
+    /// ADVANTAGE: Missing secrets fail the cold start, not the first purchase
+    pub async fn resolve_secrets(mut self, secrets: &SecretStore) -> Result<Self, AppError> {
+        if let DatabaseConfig::Postgres { url, .. } = &mut self.database {
+            *url = Secret::new(secrets.resolve("DATABASE_URL", url.expose()).await?);
+        }
+        
+        if !self.use_mock_payments {
+            secrets.resolve("STRIPE_API_KEY", self.stripe_api_key.expose()).await?;
+        }
+        
+        Ok(self)
+    }
+}
+
+/// Deprecation schedule of an API version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lifecycle {
+    /// `Deprecation` header (RFC 9745) - when the version was deprecated
+    pub deprecated_at: DateTime<Utc>,
+    /// `Sunset` header (RFC 8594) - when the version stops being served
+    pub sunset_at: DateTime<Utc>,
+}
+
+impl Lifecycle {
+    /// Parse RFC 3339 deprecation and sunset times
+    pub fn parse(deprecated_at: &str, sunset_at: &str) -> Result<Self, AppError> {
+        let parse = |value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|_| AppError::Configuration(format!("'{}' is not an RFC 3339 time", value)))
+        };
+        let lifecycle = Self { deprecated_at: parse(deprecated_at)?, sunset_at: parse(sunset_at)? };
+
+        if lifecycle.sunset_at <= lifecycle.deprecated_at {
+            return Err(AppError::Configuration("Sunset must come after deprecation".into()));
+        }
+        Ok(lifecycle)
+    }
+
+    /// Load `{prefix}_DEPRECATED_AT` and `{prefix}_SUNSET_AT`
+    ///
+    /// ADVANTAGE: Neither set means not deprecated; only one set is an error
+    pub fn from_env(prefix: &str) -> Result<Option<Self>, AppError> {
+        let deprecated_key = format!("{}_DEPRECATED_AT", prefix);
+        let sunset_key = format!("{}_SUNSET_AT", prefix);
+        // Empty counts as unset, as the template passes empty parameters through
+        let read = |key: &str| env::var(key).ok().filter(|v| !v.is_empty());
+
+        match (read(&deprecated_key), read(&sunset_key)) {
+            (Some(deprecated_at), Some(sunset_at)) => Self::parse(&deprecated_at, &sunset_at).map(Some),
+            (None, None) => Ok(None),
+            _ => Err(AppError::Configuration(format!(
+                "{} and {} must be set together",
+                deprecated_key, sunset_key
+            ))),
+        }
+    }
+}
+
+/// Database backend selection
+/// 
+/// ADVANTAGE: Each backend carries exactly the settings it needs
+/// ADVANTAGE: Unknown backend names fail at startup, not on first query
+#[derive(Debug, Clone)]
+pub enum DatabaseConfig {
+    /// Direct PostgreSQL connection pool (requires VPC access)
+    /// 
+    /// With `iam_auth`, connections authenticate with RDS IAM tokens instead
+    /// of the password in `url`.
+    Postgres { url: Secret<String>, iam_auth: bool },
+    /// Aurora Data API over HTTPS (no VPC required)
+    DataApi(DataApiConfig),
+    /// Process memory - local development only, data is lost on exit
+    Memory,
+}
+
+/// Aurora Data API connection settings
+#[derive(Debug, Clone)]
+pub struct DataApiConfig {
+    /// Aurora cluster ARN
+    pub resource_arn: String,
+    /// Secrets Manager ARN holding the database credentials
+    pub secret_arn: String,
+    /// Database name within the cluster
+    pub database: String,
+    /// Endpoint override for local stand-ins of the Data API
+    pub endpoint_url: Option<String>,
+}
+
+impl DatabaseConfig {
+    /// Load database settings selected by `DATABASE_BACKEND`
+    /// 
+    /// ADVANTAGE: `postgres` stays the default - existing deployments unchanged
+    pub fn from_env() -> Result<Self, AppError> {
+        let backend = env::var("DATABASE_BACKEND")
+            .unwrap_or_else(|_| "postgres".to_string());
+        
+        match backend.to_lowercase().as_str() {
+            "postgres" => {
+                let url = env::var("DATABASE_URL")
+                    .map_err(|_| AppError::Configuration("DATABASE_URL not set".into()))?;
+                let iam_auth = env::var("DATABASE_IAM_AUTH")
+                    .map(|v| v.to_lowercase() == "true")
+                    .unwrap_or(false);
+                Ok(Self::Postgres { url: Secret::new(url), iam_auth })
+            }
+            "data-api" => {
+                let resource_arn = env::var("DATA_API_RESOURCE_ARN")
+                    .map_err(|_| AppError::Configuration("DATA_API_RESOURCE_ARN not set".into()))?;
+                let secret_arn = env::var("DATA_API_SECRET_ARN")
+                    .map_err(|_| AppError::Configuration("DATA_API_SECRET_ARN not set".into()))?;
+                let database = env::var("DATABASE_NAME")
+                    .map_err(|_| AppError::Configuration("DATABASE_NAME not set".into()))?;
+                let endpoint_url = env::var("DATA_API_ENDPOINT_URL").ok();
+                
+                Ok(Self::DataApi(DataApiConfig {
+                    resource_arn,
+                    secret_arn,
+                    database,
+                    endpoint_url,
+                }))
+            }
+            "memory" => Ok(Self::Memory),
+            other => Err(AppError::Configuration(format!(
+                "DATABASE_BACKEND must be 'postgres', 'data-api' or 'memory', got '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Outbox dispatcher settings
+///
+/// Loaded only by the `dispatch-outbox` entrypoint - the API never publishes.
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    pub sink: OutboxSinkConfig,
+    /// Events claimed per batch
+    pub batch_size: i32,
+    /// Attempts before an event is marked dead
+    pub max_attempts: i32,
+}
+
+/// Where purchase events are published
+///
+/// ADVANTAGE: Each sink carries exactly the target it needs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutboxSinkConfig {
+    Sns { topic_arn: String, endpoint_url: Option<String> },
+    Sqs { queue_url: String, endpoint_url: Option<String> },
+    EventBridge { bus_name: String, endpoint_url: Option<String> },
+    /// Process memory - local development only, events are dropped on exit
+    Memory,
+}
+
+impl OutboxConfig {
+    /// Load the sink selected by `OUTBOX_SINK`, whose topic ARN, queue URL
+    /// or bus name is `OUTBOX_TARGET`
+    ///
+    /// ADVANTAGE: No default sink - a dispatcher cannot silently publish nowhere
+    pub fn from_env() -> Result<Self, AppError> {
+        let sink = env::var("OUTBOX_SINK")
+            .map_err(|_| AppError::Configuration("OUTBOX_SINK not set".into()))?;
+        let target = || env::var("OUTBOX_TARGET")
+            .map_err(|_| AppError::Configuration(format!("OUTBOX_TARGET required for the {} sink", sink)));
+
+        let sink = match sink.to_lowercase().as_str() {
+            "sns" => OutboxSinkConfig::Sns {
+                topic_arn: target()?,
+                endpoint_url: env::var("AWS_ENDPOINT_URL_SNS").ok(),
+            },
+            "sqs" => OutboxSinkConfig::Sqs {
+                queue_url: target()?,
+                endpoint_url: env::var("AWS_ENDPOINT_URL_SQS").ok(),
+            },
+            "eventbridge" => OutboxSinkConfig::EventBridge {
+                bus_name: target()?,
+                endpoint_url: env::var("AWS_ENDPOINT_URL_EVENTBRIDGE").ok(),
+            },
+            "memory" => OutboxSinkConfig::Memory,
+            other => return Err(AppError::Configuration(format!(
+                "OUTBOX_SINK must be 'sns', 'sqs', 'eventbridge' or 'memory', got '{}'",
+                other
+            ))),
+        };
+
+        let batch_size = env::var("OUTBOX_BATCH_SIZE")
+            .unwrap_or_else(|_| "50".to_string())
+            .parse::<i32>()
+            .ok()
+            .filter(|n| (1..=1000).contains(n))
+            .ok_or_else(|| AppError::Configuration(
+                "OUTBOX_BATCH_SIZE must be an integer from 1 to 1000".into()
+            ))?;
+
+        let max_attempts = env::var("OUTBOX_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<i32>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| AppError::Configuration(
+                "OUTBOX_MAX_ATTEMPTS must be a positive integer".into()
+            ))?;
+
+        Ok(Self { sink, batch_size, max_attempts })
+    }
+}
+
+/// Webhook dispatcher settings
+///
+/// Loaded only by the `dispatch-webhooks` entrypoint. Every setting has a
+/// default - subscriptions carry their own targets.
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// Attempts before a delivery is marked dead
+    pub max_attempts: i32,
+    /// How long a game server gets to answer
+    pub timeout: Duration,
+}
+
+impl WebhookConfig {
+    /// Load `WEBHOOK_MAX_ATTEMPTS` (default 10) and `WEBHOOK_TIMEOUT_MS`
+    pub fn from_env() -> Result<Self, AppError> {
+        let max_attempts = env::var("WEBHOOK_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<i32>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| AppError::Configuration(
+                "WEBHOOK_MAX_ATTEMPTS must be a positive integer".into()
+            ))?;
+
+        let timeout = match env::var("WEBHOOK_TIMEOUT_MS") {
+            Ok(v) => Duration::from_millis(v.parse::<u64>().map_err(|_| AppError::Configuration(
+                "WEBHOOK_TIMEOUT_MS must be a valid integer".into()
+            ))?),
+            Err(_) => crate::services::webhooks::DEFAULT_TIMEOUT,
+        };
+
+        Ok(Self { max_attempts, timeout })
+    }
+}
+
+/// Cross-origin policy for browser clients
+/// 
+/// ADVANTAGE: One policy applies to success, error and preflight responses
+/// ADVANTAGE: Origins are an allowlist - `*` must be configured explicitly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsConfig {
+    /// Exact origins, `*`, or `https://*.example.com` subdomain patterns
+    pub allowed_origins: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub exposed_headers: Vec<String>,
+    pub allow_credentials: bool,
+    /// Preflight cache lifetime in seconds
+    pub max_age_seconds: u32,
+}
+
+impl Default for CorsConfig {
+    /// No browser origins, no credentials - origins must be listed to be allowed
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_headers: split_list("Content-Type, Authorization, Idempotency-Key, X-Request-Id"),
+            exposed_headers: split_list("Idempotency-Key, Retry-After, X-Request-Id"),
+            allow_credentials: false,
+            max_age_seconds: 600,
+        }
+    }
+}
+
+impl CorsConfig {
+    /// Load from `CORS_*` variables, defaulting each unset one
+    /// 
+    /// `CORS_ALLOWED_ORIGINS`, `CORS_ALLOWED_HEADERS` and
+    /// `CORS_EXPOSED_HEADERS` are comma-separated lists.
+    pub fn from_env() -> Result<Self, AppError> {
+        let defaults = Self::default();
+        
+        let config = Self {
+            allowed_origins: env::var("CORS_ALLOWED_ORIGINS")
+                .map(|v| split_list(&v))
+                .unwrap_or(defaults.allowed_origins),
+            allowed_headers: env::var("CORS_ALLOWED_HEADERS")
+                .map(|v| split_list(&v))
+                .unwrap_or(defaults.allowed_headers),
+            exposed_headers: env::var("CORS_EXPOSED_HEADERS")
+                .map(|v| split_list(&v))
+                .unwrap_or(defaults.exposed_headers),
+            allow_credentials: env::var("CORS_ALLOW_CREDENTIALS")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(defaults.allow_credentials),
+            max_age_seconds: match env::var("CORS_MAX_AGE_SECONDS") {
+                Ok(v) => v.parse().map_err(|_| AppError::Configuration(
+                    "CORS_MAX_AGE_SECONDS must be a valid integer".into()
+                ))?,
+                Err(_) => defaults.max_age_seconds,
+            },
+        };
+        
+        // ADVANTAGE: Browsers reject `*` with credentials - fail at startup instead
+        if config.allow_credentials && config.allowed_origins.iter().any(|o| o == "*") {
+            return Err(AppError::Configuration(
+                "CORS_ALLOW_CREDENTIALS requires explicit CORS_ALLOWED_ORIGINS, not *".into()
+            ));
+        }
+        
+        Ok(config)
+    }
+    
+    /// Whether `origin` matches the allowlist
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins.iter().any(|pattern| {
+            if pattern == "*" {
+                return true;
+            }
+            
+            match pattern.split_once("://*.") {
+                Some((scheme, domain)) => origin
+                    .strip_prefix(scheme)
+                    .and_then(|rest| rest.strip_prefix("://"))
+                    .and_then(|host| host.strip_suffix(domain))
+                    .is_some_and(|sub| sub.ends_with('.') && sub.len() > 1),
+                None => pattern.eq_ignore_ascii_case(origin),
+            }
         })
     }
+    
+    /// Whether every origin is allowed
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|o| o == "*")
+    }
+}
+
+/// Body format for error responses
+/// 
+/// Clients sending `Accept: application/problem+json` always get
+/// `Problem`; this picks the format for everyone else.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// `{error, code, details}` - the original contract
+    #[default]
+    Json,
+    /// RFC 7807 `application/problem+json`
+    Problem,
+}
+
+impl ErrorFormat {
+    /// Load from `ERROR_FORMAT` (`json` or `problem`, default `json`)
+    pub fn from_env() -> Result<Self, AppError> {
+        match env::var("ERROR_FORMAT").map(|v| v.to_lowercase()).as_deref() {
+            Err(_) | Ok("json") => Ok(Self::Json),
+            Ok("problem") => Ok(Self::Problem),
+            Ok(other) => Err(AppError::Configuration(format!(
+                "ERROR_FORMAT must be 'json' or 'problem', got '{}'", other
+            ))),
+        }
+    }
+}
+
+/// Split a comma-separated list, dropping empty entries
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cors_origin_patterns() {
+        let cors = CorsConfig {
+            allowed_origins: split_list("https://shop.example.com, https://*.example.dev"),
+            ..CorsConfig::default()
+        };
+        
+        assert!(cors.allows_origin("https://shop.example.com"));
+        assert!(cors.allows_origin("https://staging.example.dev"));
+        assert!(!cors.allows_origin("https://example.dev"));
+        assert!(!cors.allows_origin("http://staging.example.dev"));
+        assert!(!cors.allows_origin("https://evil.com"));
+        assert!(!cors.allows_any_origin());
+        assert!(!CorsConfig::default().allows_origin("https://anything.test"));
+        assert!(!CorsConfig::default().allows_any_origin());
+    }
+
     #[test]
     fn test_config_validation() {
         // ADVANTAGE: Tests are compiled and type-checked
@@ -0,0 +1,151 @@
+//! Entitlement models - what a player owns because of a purchase
+//!
+//! Game servers read entitlements instead of trusting the purchase
+//! response the client saw.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::transaction::Transaction;
+
+/// Metadata flag marking a purchase as consumable
+pub const CONSUMABLE_METADATA_KEY: &str = "consumable";
+
+/// Metadata key the wallet sets on top-up purchases, which credit the
+/// wallet instead of granting an item
+pub const WALLET_TOP_UP_METADATA_KEY: &str = "wallet_top_up";
+
+/// Metadata key holding the RFC 3339 time a purchase's entitlement
+/// expires - set on subscription charges
+pub const ACCESS_EXPIRES_METADATA_KEY: &str = "access_expires_at";
+
+/// How the game treats an owned item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "entitlement_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EntitlementKind {
+    /// Used up in play - potions, boosts; `quantity` counts them
+    Consumable,
+    /// Owned for good - skins, mounts
+    Durable,
+}
+
+impl EntitlementKind {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Consumable => "consumable",
+            Self::Durable => "durable",
+        }
+    }
+}
+
+/// Whether the entitlement still counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "entitlement_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EntitlementStatus {
+    Active,
+    /// Source purchase was refunded or charged back
+    Revoked,
+}
+
+/// Item granted to a player by one purchase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Entitlement {
+    pub entitlement_id: Uuid,
+    pub player_id: Uuid,
+    pub item_id: String,
+    pub quantity: i32,
+    pub kind: EntitlementKind,
+    pub status: EntitlementStatus,
+    pub source_transaction_id: Uuid,
+    pub granted_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// End of a subscription period's access, `None` for owned for good
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Entitlement {
+    /// Active and not yet expired at `at`
+    pub fn is_current(&self, at: DateTime<Utc>) -> bool {
+        self.status == EntitlementStatus::Active && self.expires_at.is_none_or(|expires_at| expires_at > at)
+    }
+}
+
+/// Entitlement to grant
+///
+/// `(source_transaction_id, item_id)` is unique, so a purchase grants each
+/// item at most once.
+#[derive(Debug, Clone)]
+pub struct NewEntitlement {
+    pub entitlement_id: Uuid,
+    pub player_id: Uuid,
+    pub item_id: String,
+    pub quantity: i32,
+    pub kind: EntitlementKind,
+    pub source_transaction_id: Uuid,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl NewEntitlement {
+    /// Grants for a completed purchase, none for wallet top-ups
+    ///
+    /// Items go to the owner - the recipient of a gift.
+    /// A bundle or gift code grants its items instead of the SKU, even when
+    /// it also credits the wallet. Other purchases grant the item bought,
+    /// durable unless their metadata sets `consumable: true`. Subscription
+    /// charges expire at their metadata's `access_expires_at`.
+    pub fn for_transaction(transaction: &Transaction) -> Vec<Self> {
+        let expires_at = transaction
+            .metadata
+            .get(ACCESS_EXPIRES_METADATA_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok());
+        let grant = |item_id: &str, quantity: i32, consumable: bool| Self {
+            entitlement_id: Uuid::new_v4(),
+            player_id: transaction.owner_id(),
+            item_id: item_id.to_string(),
+            quantity,
+            kind: if consumable { EntitlementKind::Consumable } else { EntitlementKind::Durable },
+            source_transaction_id: transaction.transaction_id,
+            expires_at,
+        };
+
+        if let Some(bundle) = transaction.applied_promotions.iter().find(|p| !p.bundle_items.is_empty()) {
+            return bundle
+                .bundle_items
+                .iter()
+                .map(|item| grant(&item.item_id, item.quantity.saturating_mul(transaction.quantity), item.consumable))
+                .collect();
+        }
+        if transaction.metadata.get(WALLET_TOP_UP_METADATA_KEY).is_some() {
+            return Vec::new();
+        }
+
+        let consumable = transaction
+            .metadata
+            .get(CONSUMABLE_METADATA_KEY)
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false);
+
+        vec![grant(&transaction.item_id, transaction.quantity, consumable)]
+    }
+
+    /// The stored form of this grant
+    pub fn into_entitlement(self, granted_at: DateTime<Utc>) -> Entitlement {
+        Entitlement {
+            entitlement_id: self.entitlement_id,
+            player_id: self.player_id,
+            item_id: self.item_id,
+            quantity: self.quantity,
+            kind: self.kind,
+            status: EntitlementStatus::Active,
+            source_transaction_id: self.source_transaction_id,
+            granted_at,
+            revoked_at: None,
+            expires_at: self.expires_at,
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Purchase events and the transactional outbox that carries them
+//!
+//! An event is written to `outbox_events` in the same database transaction
+//! as the status change it announces, then published by the dispatcher.
+//! Delivery is at-least-once: consumers deduplicate on `eventId`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::transaction::{Transaction, TransactionStatus};
+
+/// Schema version of `PurchaseEvent` payloads
+///
+/// Bump it, and add `schemas/purchase-event.v{N}.json`, for any change that
+/// removes or retypes a field. Adding optional fields keeps the version.
+pub const PURCHASE_EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema of the current payload version
+pub const PURCHASE_EVENT_SCHEMA: &str = include_str!("../../schemas/purchase-event.v1.json");
+
+/// What happened to the purchase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventType {
+    #[serde(rename = "purchase.completed")]
+    PurchaseCompleted,
+    #[serde(rename = "purchase.refunded")]
+    PurchaseRefunded,
+    #[serde(rename = "purchase.charged_back")]
+    PurchaseChargedBack,
+}
+
+impl EventType {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::PurchaseCompleted => "purchase.completed",
+            Self::PurchaseRefunded => "purchase.refunded",
+            Self::PurchaseChargedBack => "purchase.charged_back",
+        }
+    }
+
+    /// Event announcing a move into `status`, if that status is announced
+    pub const fn for_status(status: TransactionStatus) -> Option<Self> {
+        match status {
+            TransactionStatus::Completed => Some(Self::PurchaseCompleted),
+            TransactionStatus::Refunded => Some(Self::PurchaseRefunded),
+            TransactionStatus::ChargedBack => Some(Self::PurchaseChargedBack),
+            TransactionStatus::Pending | TransactionStatus::Failed => None,
+        }
+    }
+}
+
+impl std::str::FromStr for EventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "purchase.completed" => Ok(Self::PurchaseCompleted),
+            "purchase.refunded" => Ok(Self::PurchaseRefunded),
+            "purchase.charged_back" => Ok(Self::PurchaseChargedBack),
+            _ => Err(format!("Invalid event type: {}", s)),
+        }
+    }
+}
+
+/// Published event body - versioned by `schemaVersion`
+///
+/// ADVANTAGE: Built from the stored `Transaction`, so every consumer sees
+/// the committed state rather than what the request asked for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurchaseEvent {
+    pub schema_version: u32,
+    pub event_id: Uuid,
+    pub event_type: EventType,
+    pub occurred_at: DateTime<Utc>,
+    pub transaction: PurchaseEventTransaction,
+}
+
+/// The transaction as announced - `Transaction` minus free-form metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurchaseEventTransaction {
+    pub transaction_id: Uuid,
+    pub player_id: Uuid,
+    pub item_id: String,
+    pub item_name: String,
+    pub price_cents: i64,
+    pub currency: String,
+    pub quantity: i32,
+    pub status: TransactionStatus,
+    pub processor_id: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<&Transaction> for PurchaseEventTransaction {
+    fn from(tx: &Transaction) -> Self {
+        Self {
+            transaction_id: tx.transaction_id,
+            player_id: tx.player_id,
+            item_id: tx.item_id.clone(),
+            item_name: tx.item_name.clone(),
+            price_cents: tx.price_cents,
+            currency: tx.currency.clone(),
+            quantity: tx.quantity,
+            status: tx.status,
+            processor_id: tx.processor_id.clone(),
+            request_id: tx.request_id.clone(),
+            created_at: tx.created_at,
+            updated_at: tx.updated_at,
+        }
+    }
+}
+
+/// Delivery state of an outbox row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "outbox_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum OutboxStatus {
+    /// Waiting for its first or next attempt
+    Pending,
+    Delivered,
+    /// Out of attempts - needs an operator
+    Dead,
+}
+
+impl OutboxStatus {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Dead => "dead",
+        }
+    }
+}
+
+/// Stored outbox row with its retry state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxEvent {
+    pub event_id: Uuid,
+    /// `EventType` name, kept as text so old rows survive type changes
+    pub event_type: String,
+    /// Transaction the event is about - sinks use it to keep order per purchase
+    pub aggregate_id: Uuid,
+    pub payload: serde_json::Value,
+    pub status: OutboxStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Outbox row to write alongside a status change
+#[derive(Debug, Clone)]
+pub struct NewOutboxEvent {
+    pub event_id: Uuid,
+    pub event_type: EventType,
+    pub aggregate_id: Uuid,
+    pub payload: serde_json::Value,
+}
+
+impl NewOutboxEvent {
+    /// Event for `transaction` having just moved into its current status
+    pub fn for_transaction(transaction: &Transaction) -> Option<Self> {
+        let event_type = EventType::for_status(transaction.status)?;
+        let event = PurchaseEvent {
+            schema_version: PURCHASE_EVENT_SCHEMA_VERSION,
+            event_id: Uuid::new_v4(),
+            event_type,
+            occurred_at: transaction.updated_at,
+            transaction: PurchaseEventTransaction::from(transaction),
+        };
+
+        Some(Self {
+            event_id: event.event_id,
+            event_type,
+            aggregate_id: transaction.transaction_id,
+            // Serializing plain fields cannot fail
+            payload: serde_json::to_value(&event).unwrap_or_default(),
+        })
+    }
+
+    /// The stored form of this event, due immediately
+    pub fn into_event(self, created_at: DateTime<Utc>) -> OutboxEvent {
+        OutboxEvent {
+            event_id: self.event_id,
+            event_type: self.event_type.as_str().to_string(),
+            aggregate_id: self.aggregate_id,
+            payload: self.payload,
+            status: OutboxStatus::Pending,
+            attempts: 0,
+            next_attempt_at: created_at,
+            last_error: None,
+            created_at,
+            delivered_at: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_payload_matches_published_schema() {
+        let now = Utc::now();
+        let transaction = Transaction {
+            transaction_id: Uuid::new_v4(),
+            player_id: Uuid::new_v4(),
+            item_id: "sword".into(),
+            item_name: "Sword".into(),
+            price_cents: 999,
+            currency: "USD".into(),
+            quantity: 1,
+            status: TransactionStatus::Refunded,
+            metadata: json!({"internal": "not published"}),
+            processor_id: Some("pi_1".into()),
+            request_id: None,
+            created_at: now,
+            updated_at: now,
+            original_price_cents: None,
+            applied_promotions: Vec::new(),
+            recipient_id: None,
+            gift_status: None,
+        };
+
+        let event = NewOutboxEvent::for_transaction(&transaction).unwrap();
+        assert_eq!(event.event_type, EventType::PurchaseRefunded);
+
+        // ADVANTAGE: The schema file and the Rust type cannot drift apart unnoticed
+        let schema: Value = serde_json::from_str(PURCHASE_EVENT_SCHEMA).unwrap();
+        assert_eq!(schema["properties"]["schemaVersion"]["const"], PURCHASE_EVENT_SCHEMA_VERSION);
+        for (object, schema) in [
+            (&event.payload, &schema),
+            (&event.payload["transaction"], &schema["properties"]["transaction"]),
+        ] {
+            let mut required: Vec<&str> = schema["required"].as_array().unwrap().iter().filter_map(Value::as_str).collect();
+            let mut actual: Vec<&str> = object.as_object().unwrap().keys().map(String::as_str).collect();
+            required.sort_unstable();
+            actual.sort_unstable();
+            assert_eq!(actual, required);
+        }
+
+        let mut pending = transaction;
+        pending.status = TransactionStatus::Failed;
+        assert!(NewOutboxEvent::for_transaction(&pending).is_none());
+    }
+}
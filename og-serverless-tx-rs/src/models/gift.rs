@@ -0,0 +1,121 @@
+//! Gift models - purchases paid by one player for another
+//!
+//! A gift is a purchase whose `recipient_id` differs from its payer
+//! (`player_id`). It is charged like any other, but its items are only
+//! granted once the recipient accepts; declining refunds the payer. A gift
+//! left unanswered for `GIFT_ANSWER_WINDOW` is declined and refunded by a
+//! scheduled job. Gifting is a common way to move stolen card value between
+//! accounts, so both the gifts a player sends and the gifts a player
+//! receives are capped per day.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::errors::AppError;
+use super::transaction::{Transaction, TransactionStatus};
+
+/// Gifts one player may send per `GIFT_LIMIT_WINDOW`
+pub const MAX_GIFTS_SENT_PER_DAY: i64 = 5;
+
+/// Gifts one player may receive per `GIFT_LIMIT_WINDOW`
+pub const MAX_GIFTS_RECEIVED_PER_DAY: i64 = 10;
+
+/// Window the gift limits are counted over
+pub const GIFT_LIMIT_WINDOW: chrono::Duration = chrono::Duration::days(1);
+
+/// Time a recipient has to answer before the gift is refunded
+pub const GIFT_ANSWER_WINDOW: chrono::Duration = chrono::Duration::days(14);
+
+/// Where a gift stands with its recipient
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "gift_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum GiftStatus {
+    /// Waiting for the recipient - nothing granted yet
+    Pending,
+    /// Items granted to the recipient
+    Accepted,
+    /// Payer refunded, nothing granted
+    Declined,
+}
+
+impl GiftStatus {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Accepted => "accepted",
+            Self::Declined => "declined",
+        }
+    }
+}
+
+/// Check a new gift against the payer's and recipient's gifts since
+/// `GIFT_LIMIT_WINDOW` ago, failed purchases excluded
+pub fn check_gift_limits(sent: i64, received: i64) -> Result<(), AppError> {
+    if sent >= MAX_GIFTS_SENT_PER_DAY {
+        return Err(AppError::GiftLimitReached(format!(
+            "At most {} gifts can be sent per day",
+            MAX_GIFTS_SENT_PER_DAY
+        )));
+    }
+    if received >= MAX_GIFTS_RECEIVED_PER_DAY {
+        return Err(AppError::GiftLimitReached(format!(
+            "The recipient cannot receive more than {} gifts per day",
+            MAX_GIFTS_RECEIVED_PER_DAY
+        )));
+    }
+    Ok(())
+}
+
+/// Gift status once `gift` moves to `next`
+///
+/// Refunding a pending gift declines it, so the payer's refund and the
+/// recipient's answer are written in the same transition.
+pub fn status_after(gift: &Transaction, next: TransactionStatus) -> Option<GiftStatus> {
+    match (gift.gift_status, next) {
+        (Some(GiftStatus::Pending), TransactionStatus::Refunded) => Some(GiftStatus::Declined),
+        (status, _) => status,
+    }
+}
+
+/// Gift status after `recipient_id` answers `gift`, `None` when they are
+/// repeating an earlier answer
+pub fn answer(gift: &Transaction, recipient_id: Uuid, accept: bool) -> Result<Option<GiftStatus>, AppError> {
+    // Anyone but the recipient is told the gift does not exist
+    if gift.recipient_id != Some(recipient_id) {
+        return Err(AppError::NotFound(format!("Gift {} not found", gift.transaction_id)));
+    }
+
+    let answer = if accept { GiftStatus::Accepted } else { GiftStatus::Declined };
+    match gift.gift_status {
+        Some(GiftStatus::Pending) if gift.status == TransactionStatus::Completed => Ok(Some(answer)),
+        Some(GiftStatus::Pending) => Err(AppError::Conflict(format!(
+            "Gift payment is {}",
+            gift.status.as_str()
+        ))),
+        Some(current) if current == answer => Ok(None),
+        Some(current) => Err(AppError::Conflict(format!("Gift already {}", current.as_str()))),
+        None => Err(AppError::Internal("Gift has no status".into())),
+    }
+}
+
+/// Body of `POST /gifts/{transactionId}/accept` and `/decline`
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct GiftDecisionRequest {
+    /// The recipient - only they may answer a gift
+    pub player_id: Uuid,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gift_limits() {
+        assert!(check_gift_limits(MAX_GIFTS_SENT_PER_DAY - 1, MAX_GIFTS_RECEIVED_PER_DAY - 1).is_ok());
+        assert!(matches!(check_gift_limits(MAX_GIFTS_SENT_PER_DAY, 0), Err(AppError::GiftLimitReached(_))));
+        assert!(matches!(check_gift_limits(0, MAX_GIFTS_RECEIVED_PER_DAY), Err(AppError::GiftLimitReached(_))));
+        assert_eq!(AppError::GiftLimitReached(String::new()).status_code(), 429);
+    }
+}
@@ -0,0 +1,407 @@
+//! Double-entry journal - every movement of money and virtual currency
+//!
+//! A journal entry is a set of postings whose signed amounts (debits
+//! positive, credits negative) sum to zero in each currency. Money is
+//! posted in minor units of the transaction currency and virtual currency
+//! in whole units, so the two are never added together.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+use super::transaction::{Transaction, TransactionStatus};
+use super::wallet::{LedgerEntry, LedgerEntryKind, VirtualCurrency};
+
+/// Account classification, which fixes the side an account normally sits on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "account_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AccountType {
+    Asset,
+    Liability,
+    Revenue,
+    Expense,
+}
+
+impl AccountType {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Asset => "asset",
+            Self::Liability => "liability",
+            Self::Revenue => "revenue",
+            Self::Expense => "expense",
+        }
+    }
+}
+
+/// A ledger account, identified by its code
+///
+/// ADVANTAGE: Accounts are only built through named constructors, so codes
+/// and types cannot drift between call sites
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub code: String,
+    pub account_type: AccountType,
+    pub currency: String,
+}
+
+impl Account {
+    fn new(code: String, account_type: AccountType, currency: &str) -> Self {
+        Self { code, account_type, currency: currency.to_string() }
+    }
+
+    /// Money collected by the payment processor and owed to us
+    pub fn processor_clearing(currency: &str) -> Self {
+        Self::new(format!("processor_clearing:{}", currency), AccountType::Asset, currency)
+    }
+
+    /// Completed purchases
+    pub fn sales_revenue(currency: &str) -> Self {
+        Self::new(format!("sales_revenue:{}", currency), AccountType::Revenue, currency)
+    }
+
+    /// Purchases returned to the player - contra-revenue
+    pub fn refunds(currency: &str) -> Self {
+        Self::new(format!("refunds:{}", currency), AccountType::Expense, currency)
+    }
+
+    /// Purchases reversed by the card issuer
+    pub fn chargebacks(currency: &str) -> Self {
+        Self::new(format!("chargebacks:{}", currency), AccountType::Expense, currency)
+    }
+
+    /// Virtual currency held by one player - owed to them
+    pub fn player_wallet(player_id: Uuid, currency: VirtualCurrency) -> Self {
+        Self::new(
+            format!("player_wallet:{}:{}", player_id, currency.as_str()),
+            AccountType::Liability,
+            currency.as_str(),
+        )
+    }
+
+    /// Virtual currency created by top-ups
+    pub fn currency_issued(currency: VirtualCurrency) -> Self {
+        Self::new(format!("currency_issued:{}", currency.as_str()), AccountType::Expense, currency.as_str())
+    }
+
+    /// Virtual currency spent on items
+    pub fn currency_redeemed(currency: VirtualCurrency) -> Self {
+        Self::new(format!("currency_redeemed:{}", currency.as_str()), AccountType::Revenue, currency.as_str())
+    }
+}
+
+/// One side of a journal entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Posting {
+    pub account_code: String,
+    pub account_type: AccountType,
+    pub currency: String,
+    /// Positive for debits, negative for credits
+    pub amount: i64,
+}
+
+impl Posting {
+    pub fn debit(account: &Account, amount: i64) -> Self {
+        Self {
+            account_code: account.code.clone(),
+            account_type: account.account_type,
+            currency: account.currency.clone(),
+            amount,
+        }
+    }
+
+    pub fn credit(account: &Account, amount: i64) -> Self {
+        Self { amount: -amount, ..Self::debit(account, amount) }
+    }
+}
+
+/// Business event a journal entry records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "journal_entry_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum JournalKind {
+    /// Purchase completed - `reference` is the transaction ID
+    Purchase,
+    /// Completed purchase refunded - `reference` is the transaction ID
+    Refund,
+    /// Completed purchase charged back - `reference` is the transaction ID
+    Chargeback,
+    /// Wallet credited - `reference` is the wallet ledger entry ID
+    WalletTopUp,
+    /// Wallet debited - `reference` is the wallet ledger entry ID
+    WalletSpend,
+    /// Wallet credit taken back - `reference` is the wallet ledger entry ID
+    WalletReversal,
+}
+
+impl JournalKind {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Purchase => "purchase",
+            Self::Refund => "refund",
+            Self::Chargeback => "chargeback",
+            Self::WalletTopUp => "wallet_top_up",
+            Self::WalletSpend => "wallet_spend",
+            Self::WalletReversal => "wallet_reversal",
+        }
+    }
+}
+
+/// Stored journal entry with its postings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub journal_id: Uuid,
+    pub kind: JournalKind,
+    pub reference: String,
+    pub player_id: Option<Uuid>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[sqlx(skip)]
+    pub postings: Vec<Posting>,
+}
+
+/// Journal entry to record
+///
+/// `(kind, reference)` is unique, so each business event is journaled once
+/// no matter how often the write that caused it is retried.
+#[derive(Debug, Clone)]
+pub struct NewJournalEntry {
+    pub journal_id: Uuid,
+    pub kind: JournalKind,
+    pub reference: String,
+    pub player_id: Option<Uuid>,
+    pub request_id: Option<String>,
+    pub postings: Vec<Posting>,
+}
+
+impl NewJournalEntry {
+    /// Build an entry, rejecting postings that do not balance
+    ///
+    /// ADVANTAGE: An unbalanced entry cannot be constructed, so no backend
+    /// can write one
+    pub fn new(kind: JournalKind, reference: impl Into<String>, postings: Vec<Posting>) -> AppResult<Self> {
+        check_balanced(&postings)?;
+
+        Ok(Self {
+            journal_id: Uuid::new_v4(),
+            kind,
+            reference: reference.into(),
+            player_id: None,
+            request_id: None,
+            postings,
+        })
+    }
+
+    /// Attach the player whose ledger view shows this entry
+    pub fn with_player(mut self, player_id: Uuid) -> Self {
+        self.player_id = Some(player_id);
+        self
+    }
+
+    /// Record the correlation ID of the causing request
+    pub fn with_request_id(mut self, request_id: Option<&str>) -> Self {
+        self.request_id = request_id.map(str::to_string);
+        self
+    }
+
+    /// Entry for `transaction` having moved from `previous` to its current
+    /// status, or `None` when the change moves no money
+    pub fn for_status_change(transaction: &Transaction, previous: TransactionStatus) -> AppResult<Option<Self>> {
+        // Gift code redemptions are recorded at zero and move no money
+        if previous == transaction.status || transaction.price_cents == 0 {
+            return Ok(None);
+        }
+
+        let currency = transaction.currency.as_str();
+        let amount = transaction.price_cents;
+        let clearing = Account::processor_clearing(currency);
+
+        let (kind, postings) = match transaction.status {
+            TransactionStatus::Completed => (JournalKind::Purchase, vec![
+                Posting::debit(&clearing, amount),
+                Posting::credit(&Account::sales_revenue(currency), amount),
+            ]),
+            TransactionStatus::Refunded if previous == TransactionStatus::Completed => (JournalKind::Refund, vec![
+                Posting::debit(&Account::refunds(currency), amount),
+                Posting::credit(&clearing, amount),
+            ]),
+            TransactionStatus::ChargedBack if previous == TransactionStatus::Completed => (JournalKind::Chargeback, vec![
+                Posting::debit(&Account::chargebacks(currency), amount),
+                Posting::credit(&clearing, amount),
+            ]),
+            _ => return Ok(None),
+        };
+
+        Self::new(kind, transaction.transaction_id.to_string(), postings).map(|entry| {
+            Some(entry
+                .with_player(transaction.player_id)
+                .with_request_id(transaction.request_id.as_deref()))
+        })
+    }
+
+    /// Entry mirroring a wallet ledger append
+    pub fn for_ledger_entry(entry: &LedgerEntry) -> AppResult<Self> {
+        let wallet = Account::player_wallet(entry.player_id, entry.currency);
+        let amount = entry.amount.abs();
+
+        let (kind, postings) = match entry.kind {
+            LedgerEntryKind::TopUp | LedgerEntryKind::CodeGrant => (JournalKind::WalletTopUp, vec![
+                Posting::debit(&Account::currency_issued(entry.currency), amount),
+                Posting::credit(&wallet, amount),
+            ]),
+            LedgerEntryKind::Spend => (JournalKind::WalletSpend, vec![
+                Posting::debit(&wallet, amount),
+                Posting::credit(&Account::currency_redeemed(entry.currency), amount),
+            ]),
+            LedgerEntryKind::Reversal => (JournalKind::WalletReversal, vec![
+                Posting::debit(&wallet, amount),
+                Posting::credit(&Account::currency_issued(entry.currency), amount),
+            ]),
+        };
+
+        Ok(Self::new(kind, entry.entry_id.to_string(), postings)?
+            .with_player(entry.player_id)
+            .with_request_id(entry.request_id.as_deref()))
+    }
+
+    /// The stored form of this entry
+    pub fn into_entry(self, created_at: DateTime<Utc>) -> JournalEntry {
+        JournalEntry {
+            journal_id: self.journal_id,
+            kind: self.kind,
+            reference: self.reference,
+            player_id: self.player_id,
+            request_id: self.request_id,
+            created_at,
+            postings: self.postings,
+        }
+    }
+}
+
+/// Check that `postings` has two sides and sums to zero in every currency
+pub fn check_balanced(postings: &[Posting]) -> AppResult<()> {
+    if postings.len() < 2 {
+        return Err(AppError::Internal("Journal entry needs at least two postings".into()));
+    }
+    if postings.iter().any(|p| p.amount == 0) {
+        return Err(AppError::Internal("Journal entry has a zero posting".into()));
+    }
+
+    let mut totals: BTreeMap<&str, i64> = BTreeMap::new();
+    for posting in postings {
+        let total = totals.entry(posting.currency.as_str()).or_default();
+        *total = total
+            .checked_add(posting.amount)
+            .ok_or_else(|| AppError::Internal("Journal entry amount out of range".into()))?;
+    }
+
+    match totals.into_iter().find(|(_, total)| *total != 0) {
+        Some((currency, total)) => Err(AppError::Internal(format!(
+            "Journal entry is unbalanced by {} {}",
+            total, currency
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Debit and credit totals of one account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TrialBalanceLine {
+    pub account_code: String,
+    pub account_type: AccountType,
+    pub currency: String,
+    pub debits: i64,
+    pub credits: i64,
+}
+
+/// Every account's totals - debits equal credits per currency when the
+/// journal is intact
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrialBalance {
+    pub lines: Vec<TrialBalanceLine>,
+}
+
+impl TrialBalance {
+    /// Totals from individual postings, one line per account
+    pub fn from_postings<'a>(postings: impl IntoIterator<Item = &'a Posting>) -> Self {
+        let mut lines: BTreeMap<&str, TrialBalanceLine> = BTreeMap::new();
+
+        for posting in postings {
+            let line = lines.entry(posting.account_code.as_str()).or_insert_with(|| TrialBalanceLine {
+                account_code: posting.account_code.clone(),
+                account_type: posting.account_type,
+                currency: posting.currency.clone(),
+                debits: 0,
+                credits: 0,
+            });
+            if posting.amount > 0 {
+                line.debits += posting.amount;
+            } else {
+                line.credits -= posting.amount;
+            }
+        }
+
+        Self { lines: lines.into_values().collect() }
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        let mut totals: BTreeMap<&str, i128> = BTreeMap::new();
+        for line in &self.lines {
+            *totals.entry(line.currency.as_str()).or_default() += i128::from(line.debits) - i128::from(line.credits);
+        }
+        totals.values().all(|total| *total == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::models::NewTransaction;
+
+    #[test]
+    fn test_status_changes_journal_balanced_entries() {
+        let new_tx = NewTransaction::new(Uuid::new_v4(), "sword".into(), "Sword".into(), 999, "USD".into(), 1, json!({}));
+        let mut tx = Transaction {
+            transaction_id: new_tx.transaction_id,
+            player_id: new_tx.player_id,
+            item_id: new_tx.item_id,
+            item_name: new_tx.item_name,
+            price_cents: new_tx.price_cents,
+            currency: new_tx.currency,
+            quantity: new_tx.quantity,
+            status: TransactionStatus::Completed,
+            metadata: new_tx.metadata,
+            processor_id: None,
+            request_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            original_price_cents: None,
+            applied_promotions: Vec::new(),
+            recipient_id: None,
+            gift_status: None,
+        };
+
+        let purchase = NewJournalEntry::for_status_change(&tx, TransactionStatus::Pending).unwrap().unwrap();
+        assert_eq!(purchase.kind, JournalKind::Purchase);
+        assert!(NewJournalEntry::for_status_change(&tx, TransactionStatus::Completed).unwrap().is_none());
+
+        tx.status = TransactionStatus::Refunded;
+        let refund = NewJournalEntry::for_status_change(&tx, TransactionStatus::Completed).unwrap().unwrap();
+
+        let trial = TrialBalance::from_postings(purchase.postings.iter().chain(&refund.postings));
+        assert!(trial.is_balanced());
+        let clearing = trial.lines.iter().find(|l| l.account_code == "processor_clearing:USD").unwrap();
+        assert_eq!((clearing.debits, clearing.credits), (999, 999));
+
+        let lopsided = vec![Posting::debit(&Account::sales_revenue("USD"), 5), Posting::credit(&Account::refunds("USD"), 4)];
+        assert!(matches!(check_balanced(&lopsided), Err(AppError::Internal(_))));
+    }
+}
@@ -8,8 +8,36 @@ pub mod config;
 pub mod transaction;
 pub mod request;
 pub mod response;
+pub mod wallet;
+pub mod journal;
+pub mod entitlement;
+pub mod transition;
+pub mod event;
+pub mod webhook;
+pub mod catalog;
+pub mod promotion;
+pub mod code;
+pub mod gift;
+pub mod subscription;
 
 pub use config::Config;
 pub use transaction::{Transaction, TransactionStatus, NewTransaction};
-pub use request::PurchaseRequest;
-pub use response::{PurchaseResponse, TransactionListResponse, ErrorResponse};
+pub use request::{PurchaseRequest, PurchaseRequestV2, SpendRequest, TopUpRequest};
+pub use catalog::{CatalogItem, CatalogItemRequest, ItemKind, StockSettlement};
+pub use promotion::{AppliedPromotion, BundleItem, CreatePromotionRequest, NewPromotion, Pricing, Promotion, PromotionKind};
+pub use promotion::{PlayerProfile, PlayerProfileRequest};
+pub use code::{CodeCampaign, CodeReward, CreateCodeCampaignRequest, NewCodeCampaign, RedeemCodeRequest};
+pub use gift::{GiftDecisionRequest, GiftStatus};
+pub use subscription::{ChangePlanRequest, SubscribeRequest, Subscription, SubscriptionActionRequest};
+pub use subscription::{SubscriptionPlan, SubscriptionPlanRequest, SubscriptionStatus};
+pub use entitlement::{Entitlement, EntitlementKind, EntitlementStatus, NewEntitlement};
+pub use transition::TransitionEffects;
+pub use event::{EventType, NewOutboxEvent, OutboxEvent, OutboxStatus, PurchaseEvent};
+pub use webhook::{CreateWebhookRequest, NewWebhookSubscription, WebhookDelivery, WebhookDeliveryStatus};
+pub use webhook::{WebhookDispatch, WebhookFanOut, WebhookSubscription};
+pub use journal::{JournalEntry, JournalKind, NewJournalEntry, Posting, TrialBalance};
+pub use wallet::{LedgerEntry, LedgerEntryKind, NewLedgerEntry, VirtualCurrency, WalletBalance, WalletDebit};
+pub use response::{PurchaseResponse, PurchaseResponseV2, TransactionListResponse, ErrorResponse, FieldError, ProblemDetails};
+pub use response::{EntitlementListResponse, PlayerLedgerResponse, SpendResponse, TopUpResponse, WalletResponse};
+pub use response::{CodeCampaignCreatedResponse, CodeRedemptionResponse, SubscriptionListResponse};
+pub use response::{CatalogItemResponse, PromotionListResponse, WebhookCreatedResponse, WebhookDeliveryListResponse, WebhookListResponse};
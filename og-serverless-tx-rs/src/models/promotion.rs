@@ -0,0 +1,436 @@
+//! Promotion models - sales and bundles evaluated at pricing time
+//!
+//! A promotion applies to a purchase when all of these hold:
+//!
+//! - the purchase falls inside `[starts_at, ends_at)`
+//! - `item_ids` is empty or contains the item
+//! - `segments` is empty or shares a segment with the player's profile
+//! - `currency` is unset or matches the purchase
+//!
+//! Stacking: a bundle replaces the price outright and never combines with
+//! anything. Otherwise the player gets whichever is cheaper - the best
+//! exclusive promotion alone, or every stackable promotion together,
+//! applied in priority order. A purchase always charges at least one minor
+//! unit; free grants are not purchases.
+//!
+//! Promotions price from the catalog list price, and a player's segments
+//! come from the profile operators maintain - nothing the client sends
+//! decides a discount.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::errors::AppError;
+use super::transaction::{Currency, NewTransaction};
+
+/// What a promotion does to the price
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "promotion_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PromotionKind {
+    /// `value` percent off the running price
+    PercentOff,
+    /// `value` minor units off each item
+    AmountOff,
+    /// The item is a bundle SKU sold for `value` minor units, granting
+    /// `bundle_items` instead of itself
+    Bundle,
+}
+
+impl PromotionKind {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::PercentOff => "percent_off",
+            Self::AmountOff => "amount_off",
+            Self::Bundle => "bundle",
+        }
+    }
+}
+
+/// One item granted by a bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Validate)]
+pub struct BundleItem {
+    #[validate(length(min = 1, max = 255))]
+    pub item_id: String,
+
+    /// Units granted per bundle bought
+    #[validate(range(min = 1, max = 10_000))]
+    pub quantity: i32,
+
+    #[serde(default)]
+    pub consumable: bool,
+}
+
+/// A scheduled discount or bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Promotion {
+    pub promotion_id: Uuid,
+    pub name: String,
+    pub kind: PromotionKind,
+    /// Percent off, minor units off per item, or the bundle price
+    pub value: i64,
+    /// Purchase currency the promotion is limited to
+    pub currency: Option<String>,
+    /// Items the promotion applies to, empty for every item
+    #[sqlx(json)]
+    pub item_ids: Vec<String>,
+    /// Player segments the promotion applies to, empty for every player
+    #[sqlx(json)]
+    pub segments: Vec<String>,
+    #[sqlx(json)]
+    pub bundle_items: Vec<BundleItem>,
+    pub stackable: bool,
+    /// Higher applies first when stacking and wins ties
+    pub priority: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Promotion {
+    /// Whether the promotion applies to `purchase` by a player in `segments` at `now`
+    pub fn applies_to(&self, purchase: &NewTransaction, segments: &[String], now: DateTime<Utc>) -> bool {
+        (self.starts_at..self.ends_at).contains(&now)
+            && (self.item_ids.is_empty() || self.item_ids.contains(&purchase.item_id))
+            && (self.segments.is_empty() || self.segments.iter().any(|s| segments.contains(s)))
+            && self.currency.as_ref().is_none_or(|c| *c == purchase.currency)
+    }
+
+    /// Discount taken off `price` for `quantity` items, never below one
+    /// minor unit and never negative
+    ///
+    /// ADVANTAGE: A bundle priced above the list price cannot raise the charge
+    fn discount(&self, price: i64, quantity: i32) -> i64 {
+        let discount = match self.kind {
+            PromotionKind::PercentOff => price.saturating_mul(self.value) / 100,
+            PromotionKind::AmountOff => self.value.saturating_mul(i64::from(quantity)),
+            PromotionKind::Bundle => price.saturating_sub(self.value.saturating_mul(i64::from(quantity))),
+        };
+        discount.clamp(0, (price - 1).max(0))
+    }
+
+    fn applied(&self, discount_cents: i64) -> AppliedPromotion {
+        AppliedPromotion {
+            promotion_id: self.promotion_id,
+            name: self.name.clone(),
+            kind: self.kind,
+            discount_cents,
+            bundle_items: self.bundle_items.clone(),
+            code: None,
+        }
+    }
+}
+
+/// Body of `POST /promotions`
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreatePromotionRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+
+    pub kind: PromotionKind,
+
+    #[validate(range(min = 1, max = 99_999_999))]
+    pub value: i64,
+
+    /// Required for amount-off and bundle promotions
+    #[serde(default)]
+    pub currency: Option<Currency>,
+
+    #[validate(length(max = 1000))]
+    #[serde(default)]
+    pub item_ids: Vec<String>,
+
+    #[validate(length(max = 50))]
+    #[serde(default)]
+    pub segments: Vec<String>,
+
+    #[validate(length(max = 50), nested)]
+    #[serde(default)]
+    pub bundle_items: Vec<BundleItem>,
+
+    #[serde(default)]
+    pub stackable: bool,
+
+    #[serde(default)]
+    pub priority: i32,
+
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+impl CreatePromotionRequest {
+    /// Rules spanning several fields, which `Validate` cannot express
+    pub fn check(&self) -> Result<(), AppError> {
+        if self.ends_at <= self.starts_at {
+            return Err(AppError::Validation("ends_at must be after starts_at".into()));
+        }
+        match self.kind {
+            PromotionKind::PercentOff if self.value > 100 => {
+                Err(AppError::Validation("percent_off value must be at most 100".into()))
+            }
+            PromotionKind::AmountOff | PromotionKind::Bundle if self.currency.is_none() => {
+                Err(AppError::Validation(format!("{} promotions need a currency", self.kind.as_str())))
+            }
+            PromotionKind::Bundle if self.item_ids.len() != 1 || self.bundle_items.is_empty() => {
+                Err(AppError::Validation(
+                    "bundles need exactly one item_id, the bundle SKU, and at least one bundle item".into(),
+                ))
+            }
+            PromotionKind::Bundle if self.stackable => {
+                Err(AppError::Validation("bundles cannot be stackable".into()))
+            }
+            PromotionKind::Bundle => {
+                let mut item_ids: Vec<_> = self.bundle_items.iter().map(|i| &i.item_id).collect();
+                item_ids.sort();
+                item_ids.dedup();
+                if item_ids.len() == self.bundle_items.len() {
+                    Ok(())
+                } else {
+                    Err(AppError::Validation("bundle items must be distinct".into()))
+                }
+            }
+            _ if !self.bundle_items.is_empty() => {
+                Err(AppError::Validation("only bundles have bundle items".into()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Promotion to insert
+#[derive(Debug, Clone)]
+pub struct NewPromotion {
+    pub promotion_id: Uuid,
+    pub request: CreatePromotionRequest,
+}
+
+impl NewPromotion {
+    pub fn new(request: CreatePromotionRequest) -> Self {
+        Self { promotion_id: Uuid::new_v4(), request }
+    }
+
+    /// The stored form of this promotion
+    pub fn into_promotion(self, created_at: DateTime<Utc>) -> Promotion {
+        let request = self.request;
+        Promotion {
+            promotion_id: self.promotion_id,
+            name: request.name,
+            kind: request.kind,
+            value: request.value,
+            currency: request.currency.map(|c| c.as_str().to_string()),
+            item_ids: request.item_ids,
+            segments: request.segments,
+            bundle_items: request.bundle_items,
+            stackable: request.stackable,
+            priority: request.priority,
+            starts_at: request.starts_at,
+            ends_at: request.ends_at,
+            created_at,
+        }
+    }
+}
+
+/// Segments an operator has placed a player in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerProfile {
+    pub player_id: Uuid,
+    #[sqlx(json)]
+    pub segments: Vec<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body of `PUT /players/{playerId}/profile`
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct PlayerProfileRequest {
+    /// Replaces the player's segments; empty removes them from every segment
+    #[validate(length(max = 50))]
+    pub segments: Vec<String>,
+}
+
+/// A promotion as applied to one transaction
+///
+/// Copied onto the transaction, so later edits to the promotion do not
+/// rewrite what a player was charged or granted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedPromotion {
+    pub promotion_id: Uuid,
+    pub name: String,
+    pub kind: PromotionKind,
+    /// Minor units this promotion took off the price
+    pub discount_cents: i64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bundle_items: Vec<BundleItem>,
+    /// Code the player redeemed for this, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// Outcome of evaluating promotions against a purchase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pricing {
+    /// Price before promotions
+    pub original_price_cents: i64,
+    /// Price to charge
+    pub price_cents: i64,
+    /// Applied in order, empty when nothing applied
+    pub applied: Vec<AppliedPromotion>,
+}
+
+impl Pricing {
+    /// Price `purchase` for a player in `segments` at `now`
+    ///
+    /// `purchase.price_cents` is the list price for the whole quantity.
+    pub fn evaluate(
+        purchase: &NewTransaction,
+        segments: &[String],
+        promotions: &[Promotion],
+        now: DateTime<Utc>,
+    ) -> Self {
+        let original = purchase.price_cents;
+        let mut eligible: Vec<&Promotion> = promotions
+            .iter()
+            .filter(|p| p.applies_to(purchase, segments, now))
+            .collect();
+        // Highest priority first; the sort is stable, so ties keep their given order
+        eligible.sort_by_key(|p| std::cmp::Reverse(p.priority));
+
+        let apply = |stack: &[&Promotion]| {
+            let mut price = original;
+            let mut applied = Vec::new();
+            for promotion in stack {
+                let discount = promotion.discount(price, purchase.quantity);
+                price -= discount;
+                applied.push(promotion.applied(discount));
+            }
+            Self { original_price_cents: original, price_cents: price, applied }
+        };
+
+        if let Some(bundle) = eligible.iter().find(|p| p.kind == PromotionKind::Bundle) {
+            return apply(&[bundle]);
+        }
+
+        let stackable: Vec<&Promotion> = eligible.iter().copied().filter(|p| p.stackable).collect();
+        eligible
+            .iter()
+            .filter(|p| !p.stackable)
+            .map(|p| apply(&[p]))
+            .chain((!stackable.is_empty()).then(|| apply(&stackable)))
+            // min_by_key keeps the first of equals - the higher priority
+            .min_by_key(|pricing| pricing.price_cents)
+            .unwrap_or_else(|| apply(&[]))
+    }
+
+    /// Take a redeemed `code`'s discount off the evaluated price
+    ///
+    /// Codes always stack: the player typed one in, so it applies on top of
+    /// whatever the automatic promotions gave.
+    pub fn with_code(mut self, discount: &Promotion, quantity: i32, code: &str) -> Self {
+        let discount_cents = discount.discount(self.price_cents, quantity);
+        self.price_cents -= discount_cents;
+        self.applied.push(AppliedPromotion { code: Some(code.to_string()), ..discount.applied(discount_cents) });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::Value;
+
+    fn promotion(kind: PromotionKind, value: i64, stackable: bool, priority: i32) -> Promotion {
+        let now = Utc::now();
+        Promotion {
+            promotion_id: Uuid::new_v4(),
+            name: format!("{} {}", kind.as_str(), value),
+            kind,
+            value,
+            currency: None,
+            item_ids: Vec::new(),
+            segments: Vec::new(),
+            bundle_items: Vec::new(),
+            stackable,
+            priority,
+            starts_at: now - Duration::hours(1),
+            ends_at: now + Duration::hours(1),
+            created_at: now,
+        }
+    }
+
+    fn purchase(item_id: &str, price_cents: i64, quantity: i32) -> NewTransaction {
+        NewTransaction::new(
+            Uuid::new_v4(), item_id.into(), "Item".into(), price_cents, "USD".into(), quantity, Value::Null,
+        )
+    }
+
+    #[test]
+    fn test_best_of_exclusive_or_stacked() {
+        let now = Utc::now();
+        let sale = promotion(PromotionKind::PercentOff, 30, false, 0);
+        let weekend = promotion(PromotionKind::PercentOff, 20, true, 10);
+        let coupon = promotion(PromotionKind::AmountOff, 100, true, 0);
+
+        // Stacked: 2000 - 20% = 1600, then 100 x 2 off = 1400 - the same as 30% off alone
+        let pricing = Pricing::evaluate(&purchase("sword", 2000, 2), &[], &[sale.clone(), weekend.clone(), coupon.clone()], now);
+        assert_eq!(pricing.price_cents, 1400);
+        assert_eq!(pricing.applied.len(), 1, "the exclusive sale wins the tie - it came first");
+
+        let pricing = Pricing::evaluate(&purchase("sword", 2000, 4), &[], &[sale, weekend.clone(), coupon], now);
+        assert_eq!((pricing.original_price_cents, pricing.price_cents), (2000, 1200));
+        assert_eq!(
+            pricing.applied.iter().map(|a| a.discount_cents).collect::<Vec<_>>(),
+            vec![400, 400],
+            "higher priority applies first"
+        );
+
+        let mut vip_only = promotion(PromotionKind::AmountOff, 5000, false, 0);
+        vip_only.segments = vec!["vip".into()];
+        let mut expired = weekend;
+        expired.ends_at = now;
+        let promotions = [vip_only, expired];
+
+        let pricing = Pricing::evaluate(&purchase("sword", 2000, 1), &[], &promotions, now);
+        assert_eq!((pricing.price_cents, pricing.applied.len()), (2000, 0));
+        let pricing = Pricing::evaluate(&purchase("sword", 2000, 1), &["vip".into()], &promotions, now);
+        assert_eq!(pricing.price_cents, 1, "never below one minor unit");
+    }
+
+    #[test]
+    fn test_bundle_replaces_price_and_rejects_bad_shapes() {
+        let mut bundle = promotion(PromotionKind::Bundle, 999, false, 0);
+        bundle.item_ids = vec!["starter_pack".into()];
+        bundle.bundle_items = vec![BundleItem { item_id: "sword".into(), quantity: 1, consumable: false }];
+        let sale = promotion(PromotionKind::PercentOff, 90, false, 100);
+
+        let pricing = Pricing::evaluate(&purchase("starter_pack", 2500, 2), &[], &[sale, bundle.clone()], Utc::now());
+        assert_eq!(pricing.price_cents, 1998);
+        assert_eq!(pricing.applied, vec![bundle.applied(502)]);
+
+        let request = |kind: &str, extra: serde_json::Value| {
+            let mut body = serde_json::json!({
+                "name": "Starter pack",
+                "kind": kind,
+                "value": 999,
+                "currency": "USD",
+                "item_ids": ["starter_pack"],
+                "starts_at": "2026-01-01T00:00:00Z",
+                "ends_at": "2026-01-03T00:00:00Z"
+            });
+            body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            serde_json::from_value::<CreatePromotionRequest>(body).unwrap().check()
+        };
+        let items = serde_json::json!({"bundle_items": [{"item_id": "sword", "quantity": 1}]});
+        assert!(request("bundle", items.clone()).is_ok());
+        assert!(request("bundle", serde_json::json!({})).is_err());
+        assert!(request("amount_off", items).is_err());
+        assert!(request("percent_off", serde_json::json!({})).is_err(), "999 percent");
+        assert!(request("amount_off", serde_json::json!({"ends_at": "2025-12-31T00:00:00Z"})).is_err());
+    }
+}
@@ -12,7 +12,6 @@ use validator::Validate;
 #[derive(Debug, Clone, Deserialize, Validate)]
 pub struct PurchaseRequest {
     /// Player's unique identifier
-    #[validate(required)]
     pub player_id: Uuid,  // ADVANTAGE: UUID type - invalid UUIDs rejected at parse
     
     /// Item identifier in the game catalog
@@ -23,7 +22,8 @@ pub struct PurchaseRequest {
     #[validate(length(min = 1, max = 255))]
     pub item_name: String,
     
-    /// Price in cents (smallest currency unit)
+    /// Price in cents (smallest currency unit); a catalog price, where the
+    /// item has one, is charged instead
     /// ADVANTAGE: i64 is explicit - no floating point precision issues
     #[validate(range(min = 1, max = 99_999_999))]
     pub price_cents: i64,
@@ -40,6 +40,15 @@ pub struct PurchaseRequest {
     /// Optional metadata (item stats, etc.)
     #[serde(default)]
     pub metadata: Option<serde_json::Value>,
+    
+    /// Discount code the player entered
+    #[validate(length(min = 1, max = 64))]
+    #[serde(default)]
+    pub promo_code: Option<String>,
+    
+    /// Friend the purchase is a gift for; `player_id` pays
+    #[serde(default)]
+    pub recipient_id: Option<Uuid>,
 }
 
 /// Default quantity for purchases
@@ -67,6 +76,67 @@ impl PurchaseRequest {
     }
 }
 
+/// Purchase request payload - API v2
+/// 
+/// Clients send the item and quantity; the unit price and currency come
+/// from the catalog and the total is computed server-side, so a client
+/// cannot name the amount it is charged.
+/// 
+/// ADVANTAGE: Unknown fields such as a stale `unit_price_cents` are rejected,
+/// not silently ignored
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct PurchaseRequestV2 {
+    pub player_id: Uuid,
+    
+    #[validate(length(min = 1, max = 255))]
+    pub item_id: String,
+    
+    #[validate(length(min = 1, max = 255))]
+    pub item_name: String,
+    
+    #[validate(range(min = 1, max = 100))]
+    #[serde(default = "default_quantity")]
+    pub quantity: i32,
+    
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    
+    #[validate(length(min = 1, max = 64))]
+    #[serde(default)]
+    pub promo_code: Option<String>,
+    
+    #[serde(default)]
+    pub recipient_id: Option<Uuid>,
+}
+
+/// Virtual currency top-up - a real-money purchase of a catalog currency pack
+/// 
+/// ADVANTAGE: Price and credit come from the catalog; a body naming either is rejected
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct TopUpRequest {
+    pub player_id: Uuid,
+    
+    /// Catalog item ID of the currency pack
+    #[validate(length(min = 1, max = 255))]
+    pub pack_id: String,
+}
+
+/// Item purchase paid with virtual currency at its catalog wallet price
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct SpendRequest {
+    pub player_id: Uuid,
+    
+    #[validate(length(min = 1, max = 255))]
+    pub item_id: String,
+    
+    /// Client-chosen key - retrying with the same key never debits twice
+    #[validate(length(min = 1, max = 128))]
+    pub idempotency_key: String,
+}
+
 /// Get player transactions request
 /// 
 /// ADVANTAGE: Query parameters are typed and validated
@@ -105,6 +175,8 @@ mod tests {
             currency: "USD".to_string(),
             quantity: 1,
             metadata: None,
+            promo_code: None,
+            recipient_id: None,
         };
         
         assert!(valid_request.validate().is_ok());
@@ -120,8 +192,32 @@ mod tests {
             currency: "USD".to_string(),
             quantity: 1,
             metadata: None,
+            promo_code: None,
+            recipient_id: None,
         };
         
         assert!(invalid_request.validate().is_err());
     }
+
+    #[test]
+    fn test_v2_rejects_client_price() {
+        let request: PurchaseRequestV2 = serde_json::from_value(serde_json::json!({
+            "player_id": Uuid::new_v4(),
+            "item_id": "gem_pack",
+            "item_name": "Gem Pack",
+            "quantity": 3
+        }))
+        .unwrap();
+        assert!(request.validate().is_ok());
+        
+        // ADVANTAGE: The price is the catalog's - a client-sent one fails loudly
+        assert!(serde_json::from_value::<PurchaseRequestV2>(serde_json::json!({
+            "player_id": Uuid::new_v4(),
+            "item_id": "gem_pack",
+            "item_name": "Gem Pack",
+            "unit_price_cents": 1,
+            "currency": "USD"
+        }))
+        .is_err());
+    }
 }
@@ -4,12 +4,23 @@
 //! ADVANTAGE: No accidental missing fields or wrong types
 
 use serde::Serialize;
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
+use crate::strategies::payment::PaymentResult;
 use super::{Transaction, TransactionStatus};
+use super::entitlement::Entitlement;
+use super::journal::JournalEntry;
+use super::wallet::{LedgerEntry, VirtualCurrency, WalletBalance, WalletCredit};
+use super::webhook::{WebhookDelivery, WebhookSubscription};
+use super::catalog::CatalogItem;
+use super::promotion::{AppliedPromotion, BundleItem, Promotion};
+use super::code::{format_code, CodeCampaign};
+use super::gift::GiftStatus;
+use super::subscription::Subscription;
 
 /// Successful purchase response
-/// 
+///
 /// ADVANTAGE: All fields required - no partial responses
 /// ADVANTAGE: Serialize derive generates optimal JSON output
 #[derive(Debug, Clone, Serialize)]
@@ -19,9 +30,33 @@ pub struct PurchaseResponse {
     pub status: TransactionStatus,
     pub item: ItemInfo,
     pub payment: PaymentInfo,
+    /// Promotions behind `payment.amountCents`, omitted when none applied
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub promotions: Vec<AppliedPromotion>,
+    /// Recipient and their answer, omitted unless the purchase is a gift
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gift: Option<GiftInfo>,
     pub created_at: String,
 }
 
+/// Gift details in response
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GiftInfo {
+    pub recipient_id: Uuid,
+    pub status: GiftStatus,
+}
+
+impl GiftInfo {
+    /// Gift details of `tx`, `None` for an ordinary purchase
+    pub fn of(tx: &Transaction) -> Option<Self> {
+        Some(Self {
+            recipient_id: tx.recipient_id?,
+            status: tx.gift_status?,
+        })
+    }
+}
+
 /// Item information in response
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -36,6 +71,9 @@ pub struct ItemInfo {
 #[serde(rename_all = "camelCase")]
 pub struct PaymentInfo {
     pub amount_cents: i64,
+    /// Price before promotions, omitted when none applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_amount_cents: Option<i64>,
     pub currency: String,
     pub processor_id: Option<String>,
 }
@@ -55,9 +93,95 @@ impl PurchaseResponse {
             },
             payment: PaymentInfo {
                 amount_cents: tx.price_cents,
+                original_amount_cents: tx.original_price_cents,
                 currency: tx.currency.clone(),
                 processor_id,
             },
+            promotions: tx.applied_promotions.clone(),
+            gift: GiftInfo::of(tx),
+            created_at: tx.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Successful purchase response - API v2
+///
+/// ADVANTAGE: Pricing and payment outcome are explicit, not inferred from `status`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurchaseResponseV2 {
+    pub transaction_id: Uuid,
+    pub status: TransactionStatus,
+    pub item: ItemInfo,
+    pub pricing: PricingInfo,
+    pub payment: PaymentOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gift: Option<GiftInfo>,
+    pub created_at: String,
+}
+
+/// Server-computed pricing
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingInfo {
+    pub unit_price_cents: i64,
+    pub total_cents: i64,
+    /// Total before promotions, omitted when none applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_total_cents: Option<i64>,
+    pub currency: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub promotions: Vec<AppliedPromotion>,
+}
+
+/// Outcome reported by the payment processor
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentOutcome {
+    pub result: PaymentResultKind,
+    pub processor_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decline_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decline_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentResultKind {
+    Approved,
+    Declined,
+}
+
+impl PurchaseResponseV2 {
+    /// Create response from transaction and processor result
+    pub fn from_transaction(tx: &Transaction, unit_price_cents: i64, payment: &PaymentResult) -> Self {
+        Self {
+            transaction_id: tx.transaction_id,
+            status: tx.status,
+            item: ItemInfo {
+                id: tx.item_id.clone(),
+                name: tx.item_name.clone(),
+                quantity: tx.quantity,
+            },
+            pricing: PricingInfo {
+                unit_price_cents,
+                total_cents: tx.price_cents,
+                original_total_cents: tx.original_price_cents,
+                currency: tx.currency.clone(),
+                promotions: tx.applied_promotions.clone(),
+            },
+            payment: PaymentOutcome {
+                result: if payment.success {
+                    PaymentResultKind::Approved
+                } else {
+                    PaymentResultKind::Declined
+                },
+                processor_id: Some(payment.processor_id.clone()),
+                decline_code: payment.error_code.clone(),
+                decline_message: payment.error_message.clone(),
+            },
+            gift: GiftInfo::of(tx),
             created_at: tx.created_at.to_rfc3339(),
         }
     }
@@ -84,8 +208,229 @@ impl TransactionListResponse {
     }
 }
 
-/// Error response
+/// Player's journal entries, newest first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerLedgerResponse {
+    pub player_id: Uuid,
+    pub entries: Vec<JournalEntry>,
+    pub count: usize,
+}
+
+impl PlayerLedgerResponse {
+    pub fn new(player_id: Uuid, entries: Vec<JournalEntry>) -> Self {
+        Self { player_id, count: entries.len(), entries }
+    }
+}
+
+/// Player's entitlements, most recently granted first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntitlementListResponse {
+    pub player_id: Uuid,
+    pub entitlements: Vec<Entitlement>,
+    pub count: usize,
+}
+
+impl EntitlementListResponse {
+    pub fn new(player_id: Uuid, entitlements: Vec<Entitlement>) -> Self {
+        Self { player_id, count: entitlements.len(), entitlements }
+    }
+}
+
+/// Player's subscriptions, newest first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionListResponse {
+    pub player_id: Uuid,
+    pub subscriptions: Vec<Subscription>,
+    pub count: usize,
+}
+
+impl SubscriptionListResponse {
+    pub fn new(player_id: Uuid, subscriptions: Vec<Subscription>) -> Self {
+        Self { player_id, count: subscriptions.len(), subscriptions }
+    }
+}
+
+/// Catalog entry with its remaining stock
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogItemResponse {
+    #[serde(flatten)]
+    pub item: CatalogItem,
+    /// `None` for unlimited items
+    pub stock_remaining: Option<i32>,
+}
+
+impl From<CatalogItem> for CatalogItemResponse {
+    fn from(item: CatalogItem) -> Self {
+        Self { stock_remaining: item.stock_remaining(), item }
+    }
+}
+
+/// Promotions, latest starting first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromotionListResponse {
+    pub promotions: Vec<Promotion>,
+    pub count: usize,
+}
+
+impl PromotionListResponse {
+    pub fn new(promotions: Vec<Promotion>) -> Self {
+        Self { count: promotions.len(), promotions }
+    }
+}
+
+/// Newly created code campaign - the only response listing its codes
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeCampaignCreatedResponse {
+    #[serde(flatten)]
+    pub campaign: CodeCampaign,
+    /// Display form, `XXXX-XXXX-XXXX-XXXX`
+    pub codes: Vec<String>,
+}
+
+impl CodeCampaignCreatedResponse {
+    pub fn new(campaign: CodeCampaign, codes: &[String]) -> Self {
+        Self { campaign, codes: codes.iter().map(|code| format_code(code)).collect() }
+    }
+}
+
+/// Outcome of redeeming a gift code
+///
+/// `ledgerEntry` is present when the code credits the wallet.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeRedemptionResponse {
+    pub transaction_id: Uuid,
+    pub status: TransactionStatus,
+    pub campaign_id: Uuid,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<BundleItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet_credit: Option<WalletCredit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ledger_entry: Option<LedgerEntry>,
+}
+
+/// Newly created webhook subscription - the only response showing its secret
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookCreatedResponse {
+    #[serde(flatten)]
+    pub subscription: WebhookSubscription,
+    pub secret: String,
+}
+
+impl WebhookCreatedResponse {
+    pub fn new(subscription: WebhookSubscription) -> Self {
+        let secret = subscription.secret.expose().clone();
+        Self { subscription, secret }
+    }
+}
+
+/// A title's webhook subscriptions, oldest first, without secrets
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookListResponse {
+    pub title_id: String,
+    pub subscriptions: Vec<WebhookSubscription>,
+    pub count: usize,
+}
+
+impl WebhookListResponse {
+    pub fn new(title_id: String, subscriptions: Vec<WebhookSubscription>) -> Self {
+        Self { title_id, count: subscriptions.len(), subscriptions }
+    }
+}
+
+/// Delivery log of one subscription, newest first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeliveryListResponse {
+    pub subscription_id: Uuid,
+    pub deliveries: Vec<WebhookDelivery>,
+    pub count: usize,
+}
+
+impl WebhookDeliveryListResponse {
+    pub fn new(subscription_id: Uuid, deliveries: Vec<WebhookDelivery>) -> Self {
+        Self { subscription_id, count: deliveries.len(), deliveries }
+    }
+}
+
+/// Wallet balances - every currency, zero if never held
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletResponse {
+    pub player_id: Uuid,
+    pub balances: Vec<WalletBalance>,
+}
+
+impl WalletResponse {
+    pub fn new(player_id: Uuid, held: &[WalletBalance]) -> Self {
+        let balances = VirtualCurrency::ALL
+            .iter()
+            .map(|&currency| {
+                held.iter()
+                    .find(|b| b.currency == currency)
+                    .copied()
+                    .unwrap_or(WalletBalance { currency, balance: 0 })
+            })
+            .collect();
+        
+        Self { player_id, balances }
+    }
+}
+
+/// Outcome of a top-up purchase
 /// 
+/// `ledgerEntry` is absent when the payment did not complete.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopUpResponse {
+    pub transaction_id: Uuid,
+    pub status: TransactionStatus,
+    pub processor_id: Option<String>,
+    pub currency: VirtualCurrency,
+    pub amount: i64,
+    pub balance: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ledger_entry: Option<LedgerEntry>,
+}
+
+/// Outcome of a virtual currency spend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendResponse {
+    /// The wallet-funded purchase that granted the item
+    pub transaction_id: Uuid,
+    pub entry_id: Uuid,
+    pub currency: VirtualCurrency,
+    pub amount: i64,
+    pub balance: i64,
+    pub item_id: Option<String>,
+}
+
+impl SpendResponse {
+    /// Response for the purchase `transaction` and its debit `entry`
+    pub fn new(transaction: &Transaction, entry: LedgerEntry) -> Self {
+        Self {
+            transaction_id: transaction.transaction_id,
+            entry_id: entry.entry_id,
+            currency: entry.currency,
+            amount: -entry.amount,
+            balance: entry.balance_after,
+            item_id: entry.item_id,
+        }
+    }
+}
+
+/// Error response
+///
 /// ADVANTAGE: Error structure is consistent and typed
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -94,7 +439,13 @@ pub struct ErrorResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub details: Vec<String>,
+    pub details: Vec<FieldError>,
+    /// Set when the requested API version is not served
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub supported_versions: Vec<String>,
+    /// Correlation ID to quote in support tickets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl ErrorResponse {
@@ -103,6 +454,8 @@ impl ErrorResponse {
             error: error.into(),
             code: None,
             details: Vec::new(),
+            supported_versions: Vec::new(),
+            request_id: None,
         }
     }
     
@@ -111,22 +464,97 @@ impl ErrorResponse {
         self
     }
     
-    pub fn with_details(mut self, details: Vec<String>) -> Self {
+    pub fn with_details(mut self, details: Vec<FieldError>) -> Self {
         self.details = details;
         self
     }
+    
+    pub fn with_supported_versions(mut self, versions: Vec<String>) -> Self {
+        self.supported_versions = versions;
+        self
+    }
+    
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+}
+
+/// RFC 7807 problem details
+///
+/// ADVANTAGE: Standard shape that generic HTTP clients already understand
+/// ADVANTAGE: Only public detail is carried - internal causes stay in logs
+#[derive(Debug, Clone, Serialize)]
+pub struct ProblemDetails {
+    /// URI reference identifying the problem type
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Short summary, the same for every occurrence of the type
+    pub title: String,
+    pub status: u16,
+    /// Client-safe explanation of this occurrence
+    pub detail: String,
+    /// URI reference identifying this occurrence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Extension: the `code` from the plain JSON format
+    pub error_code: String,
+    /// Extension: invalid fields, for validation problems
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    /// Extension: served versions, for unsupported API version problems
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub supported_versions: Vec<String>,
+    /// Extension: correlation ID to quote in support tickets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// One invalid request field
+///
+/// ADVANTAGE: Clients can highlight the exact field instead of parsing messages
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    /// Path into the request body, e.g. `item_id` or `metadata.tags[2]`
+    pub field: String,
+    /// Stable machine-readable code, e.g. `length`, `range`, `required`
+    pub code: String,
+    pub message: String,
+    /// Validator parameters such as `min` and `max`
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    pub params: serde_json::Map<String, serde_json::Value>,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+            params: serde_json::Map::new(),
+        }
+    }
 }
 
 /// Health check response
+/// 
+/// Liveness responses carry only `status`, `timestamp` and `build`;
+/// readiness adds one entry per component check.
 #[derive(Debug, Clone, Serialize)]
 pub struct HealthResponse {
     pub status: HealthStatus,
     pub timestamp: String,
+    pub build: BuildInfo,
+    /// Same as `checks.database` - kept for existing monitors
     #[serde(skip_serializing_if = "Option::is_none")]
     pub database: Option<ComponentHealth>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub checks: BTreeMap<String, ComponentHealth>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Component or overall health, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum HealthStatus {
     Healthy,
@@ -139,4 +567,38 @@ pub struct ComponentHealth {
     pub status: HealthStatus,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub latency_ms: Option<u64>,
+    /// Why the component is not healthy - never carries internal error text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ComponentHealth {
+    pub fn healthy() -> Self {
+        Self { status: HealthStatus::Healthy, latency_ms: None, detail: None }
+    }
+    
+    pub fn degraded(detail: impl Into<String>) -> Self {
+        Self { status: HealthStatus::Degraded, latency_ms: None, detail: Some(detail.into()) }
+    }
+    
+    pub fn unhealthy(detail: impl Into<String>) -> Self {
+        Self { status: HealthStatus::Unhealthy, latency_ms: None, detail: Some(detail.into()) }
+    }
+    
+    pub fn with_latency(mut self, latency: std::time::Duration) -> Self {
+        self.latency_ms = Some(latency.as_millis() as u64);
+        self
+    }
+}
+
+/// What is deployed - lets a health probe tell two builds apart
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    /// `GIT_COMMIT` at compile time, when the build sets it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<&'static str>,
+    /// Latest migration this build ships
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<i64>,
 }
@@ -0,0 +1,549 @@
+//! Subscription models - recurring plans and season passes
+//!
+//! A plan either renews every `period_days` (VIP) or runs until a fixed
+//! `season_ends_at` (battle pass). Every charge is an ordinary transaction
+//! whose entitlement expires with the period it paid for, so access ends
+//! on its own when billing stops.
+//!
+//! The billing rules live here as pure transitions; the renewal job and
+//! the endpoints only load, charge and save:
+//!
+//! - a renewal that fails puts the subscription `past_due` for the plan's
+//!   grace period, keeping access, and is retried after each
+//!   `DUNNING_RETRY_DELAYS` step
+//! - when the grace period runs out the subscription expires
+//! - a canceled subscription runs to the end of its paid period
+//! - a plan change keeps the billing date and prorates the rest of the
+//!   period: upgrades are charged the difference now, downgrades leave a
+//!   credit that comes off the next renewal
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::errors::AppError;
+use super::entitlement::ACCESS_EXPIRES_METADATA_KEY;
+use super::transaction::{Currency, NewTransaction, Transaction};
+
+/// Metadata key linking a transaction to the subscription it paid for
+pub const SUBSCRIPTION_METADATA_KEY: &str = "subscription_id";
+
+/// Delay before each retry of a failed renewal; after the last one the
+/// subscription waits out its grace period and expires
+pub const DUNNING_RETRY_DELAYS: [Duration; 3] = [Duration::days(1), Duration::days(3), Duration::days(5)];
+
+/// Where a subscription stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "subscription_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    /// First payment declined - the subscription never started
+    Incomplete,
+    Active,
+    /// Renewal failed - access continues through the grace period
+    PastDue,
+    /// Ended by the player at the end of a paid period
+    Canceled,
+    /// Lapsed after failed renewals, or its season ended
+    Expired,
+}
+
+impl SubscriptionStatus {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Incomplete => "incomplete",
+            Self::Active => "active",
+            Self::PastDue => "past_due",
+            Self::Canceled => "canceled",
+            Self::Expired => "expired",
+        }
+    }
+
+    /// Still billed and granting access
+    pub const fn is_live(&self) -> bool {
+        matches!(self, Self::Active | Self::PastDue)
+    }
+}
+
+/// What a subscription sells and how it is billed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionPlan {
+    pub plan_id: String,
+    pub name: String,
+    /// Item granted for each paid period
+    pub item_id: String,
+    pub price_cents: i64,
+    pub currency: String,
+    /// Length of a recurring period, `None` for a season pass
+    pub period_days: Option<i32>,
+    /// End of a season pass, `None` for a recurring plan
+    pub season_ends_at: Option<DateTime<Utc>>,
+    /// How long a failed renewal keeps access while it is retried
+    pub grace_period_days: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SubscriptionPlan {
+    /// End of a period starting at `start`
+    pub fn period_end(&self, start: DateTime<Utc>) -> Result<DateTime<Utc>, AppError> {
+        match (self.period_days, self.season_ends_at) {
+            (Some(days), _) => Ok(start + Duration::days(i64::from(days))),
+            (None, Some(ends_at)) if ends_at > start => Ok(ends_at),
+            (None, Some(_)) => Err(AppError::Conflict(format!("Season {} has ended", self.plan_id))),
+            (None, None) => Err(AppError::Internal(format!("Plan {} has no billing period", self.plan_id))),
+        }
+    }
+
+    /// Purchase of `amount_cents` paying for `subscription` until `paid_until`
+    pub fn charge(&self, subscription: &Subscription, amount_cents: i64, paid_until: DateTime<Utc>) -> NewTransaction {
+        NewTransaction::new(
+            subscription.player_id,
+            self.item_id.clone(),
+            self.name.clone(),
+            amount_cents,
+            self.currency.clone(),
+            1,
+            json!({
+                SUBSCRIPTION_METADATA_KEY: subscription.subscription_id,
+                ACCESS_EXPIRES_METADATA_KEY: paid_until,
+            }),
+        )
+    }
+}
+
+/// Body of `PUT /subscriptions/plans/{planId}`
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct SubscriptionPlanRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+
+    #[validate(length(min = 1, max = 255))]
+    pub item_id: String,
+
+    #[validate(range(min = 1, max = 99_999_999))]
+    pub price_cents: i64,
+
+    pub currency: Currency,
+
+    /// Set for a recurring plan
+    #[validate(range(min = 1, max = 366))]
+    #[serde(default)]
+    pub period_days: Option<i32>,
+
+    /// Set for a season pass
+    #[serde(default)]
+    pub season_ends_at: Option<DateTime<Utc>>,
+
+    #[validate(range(min = 0, max = 30))]
+    #[serde(default)]
+    pub grace_period_days: i32,
+}
+
+impl SubscriptionPlanRequest {
+    /// Rules the derive cannot express
+    pub fn check(&self) -> Result<(), AppError> {
+        if self.period_days.is_some() == self.season_ends_at.is_some() {
+            return Err(AppError::Validation("Set exactly one of period_days and season_ends_at".into()));
+        }
+        Ok(())
+    }
+
+    /// The plan as stored under `plan_id`
+    pub fn into_plan(self, plan_id: &str) -> SubscriptionPlan {
+        SubscriptionPlan {
+            plan_id: plan_id.to_string(),
+            name: self.name,
+            item_id: self.item_id,
+            price_cents: self.price_cents,
+            currency: self.currency.as_str().to_string(),
+            period_days: self.period_days,
+            season_ends_at: self.season_ends_at,
+            grace_period_days: self.grace_period_days,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// A player's subscription to one plan
+///
+/// ADVANTAGE: `version` makes every save a compare-and-set, so the renewal
+/// job and a player's cancel can never overwrite each other
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscription {
+    pub subscription_id: Uuid,
+    pub player_id: Uuid,
+    pub plan_id: String,
+    pub status: SubscriptionStatus,
+    /// Processor reference of the saved payment method renewals charge
+    #[serde(skip_serializing, default)]
+    pub payment_method: String,
+    pub current_period_start: DateTime<Utc>,
+    pub current_period_end: DateTime<Utc>,
+    /// End instead of renewing when the period is over
+    pub cancel_at_period_end: bool,
+    /// Failed renewal charges since the last successful one
+    pub renewal_attempts: i32,
+    /// Set while `past_due` - access and retries stop here
+    pub grace_ends_at: Option<DateTime<Utc>>,
+    /// When the renewal job next acts, `None` once ended
+    pub next_billing_at: Option<DateTime<Utc>>,
+    /// Downgrade credit taken off the next renewal
+    pub credit_cents: i64,
+    /// Purchase whose entitlement grants the current period
+    pub entitlement_transaction_id: Uuid,
+    /// Renewal charge in flight - resumed, never repeated, after a crash
+    pub renewal_transaction_id: Option<Uuid>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub version: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What the renewal job does with a due subscription
+#[derive(Debug, Clone, PartialEq)]
+pub enum DueStep {
+    /// Charge `amount_cents` for the next period
+    Charge { amount_cents: i64 },
+    /// Save the ended subscription
+    End(Box<Subscription>),
+}
+
+/// Plan change pricing for the rest of the current period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Proration {
+    /// Charged now
+    pub charge_cents: i64,
+    /// Added to the subscription's credit
+    pub credit_cents: i64,
+}
+
+impl Subscription {
+    /// Subscription to `plan` starting at `now`, paid by the purchase
+    /// `transaction_id`
+    pub fn start(
+        player_id: Uuid,
+        plan: &SubscriptionPlan,
+        payment_method: String,
+        transaction_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<Self, AppError> {
+        let period_end = plan.period_end(now)?;
+        Ok(Self {
+            subscription_id: Uuid::new_v4(),
+            player_id,
+            plan_id: plan.plan_id.clone(),
+            status: SubscriptionStatus::Active,
+            payment_method,
+            current_period_start: now,
+            current_period_end: period_end,
+            cancel_at_period_end: false,
+            renewal_attempts: 0,
+            grace_ends_at: None,
+            next_billing_at: Some(period_end),
+            credit_cents: 0,
+            entitlement_transaction_id: transaction_id,
+            renewal_transaction_id: None,
+            ended_at: None,
+            version: 0,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// End of the player's access in the current state
+    pub fn access_ends_at(&self) -> DateTime<Utc> {
+        match self.status {
+            SubscriptionStatus::Active => self.current_period_end,
+            SubscriptionStatus::PastDue => self.grace_ends_at.unwrap_or(self.current_period_end),
+            SubscriptionStatus::Incomplete | SubscriptionStatus::Canceled | SubscriptionStatus::Expired => {
+                self.ended_at.unwrap_or(self.current_period_end)
+            }
+        }
+    }
+
+    /// Entitlement expiries owed when this replaces `stored`, keyed by the
+    /// granting transaction: the current grant follows `access_ends_at`,
+    /// and a grant this one superseded ends at `now` at the latest
+    pub fn access_changes(&self, stored: &Self, now: DateTime<Utc>) -> Vec<(Uuid, DateTime<Utc>)> {
+        let mut changes = vec![(self.entitlement_transaction_id, self.access_ends_at())];
+        if stored.entitlement_transaction_id != self.entitlement_transaction_id {
+            changes.push((stored.entitlement_transaction_id, stored.access_ends_at().min(now)));
+        }
+        changes
+    }
+
+    /// Reject a change by anyone but the subscriber
+    pub fn check_owner(&self, player_id: Uuid) -> Result<(), AppError> {
+        if self.player_id == player_id {
+            Ok(())
+        } else {
+            Err(AppError::NotFound(format!("Subscription {} not found", self.subscription_id)))
+        }
+    }
+
+    /// First payment failed - the subscription never starts
+    pub fn incomplete(self, now: DateTime<Utc>) -> Self {
+        self.ended(SubscriptionStatus::Incomplete, now)
+    }
+
+    /// What the renewal job should do now that `next_billing_at` has passed
+    pub fn due_step(&self, plan: &SubscriptionPlan, now: DateTime<Utc>) -> DueStep {
+        let charge = DueStep::Charge { amount_cents: (plan.price_cents - self.credit_cents).max(0) };
+        // Checked before a claimed renewal is re-charged: a cancel that lands
+        // between the claim and the charge is still honoured
+        if self.cancel_at_period_end {
+            return DueStep::End(Box::new(self.clone().ended(SubscriptionStatus::Canceled, self.current_period_end)));
+        }
+        if self.renewal_transaction_id.is_some() {
+            return charge;
+        }
+        if plan.period_days.is_none() {
+            return DueStep::End(Box::new(self.clone().ended(SubscriptionStatus::Expired, self.current_period_end)));
+        }
+        match self.grace_ends_at {
+            Some(grace_ends_at) if now >= grace_ends_at => {
+                DueStep::End(Box::new(self.clone().ended(SubscriptionStatus::Expired, grace_ends_at)))
+            }
+            _ => charge,
+        }
+    }
+
+    /// Period the next renewal pays for - the billing date never moves
+    pub fn next_period(&self, plan: &SubscriptionPlan) -> Result<(DateTime<Utc>, DateTime<Utc>), AppError> {
+        Ok((self.current_period_end, plan.period_end(self.current_period_end)?))
+    }
+
+    /// Claim the renewal charged by `transaction_id`
+    pub fn claim_renewal(self, transaction_id: Uuid) -> Self {
+        Self { renewal_transaction_id: Some(transaction_id), ..self }
+    }
+
+    /// Renewal `transaction` completed - start the next period
+    pub fn renewed(self, plan: &SubscriptionPlan, transaction: &Transaction) -> Result<Self, AppError> {
+        let (start, end) = self.next_period(plan)?;
+        let credit_used = plan.price_cents - transaction.price_cents;
+        Ok(Self {
+            status: SubscriptionStatus::Active,
+            current_period_start: start,
+            current_period_end: end,
+            renewal_attempts: 0,
+            grace_ends_at: None,
+            next_billing_at: Some(end),
+            credit_cents: (self.credit_cents - credit_used).max(0),
+            entitlement_transaction_id: transaction.transaction_id,
+            renewal_transaction_id: None,
+            ..self
+        })
+    }
+
+    /// Renewal charge declined - retry during the grace period, or expire
+    /// once it is over
+    pub fn renewal_failed(self, plan: &SubscriptionPlan, now: DateTime<Utc>) -> Self {
+        let grace_ends_at = self
+            .grace_ends_at
+            .unwrap_or(self.current_period_end + Duration::days(i64::from(plan.grace_period_days)));
+        if now >= grace_ends_at {
+            return self.ended(SubscriptionStatus::Expired, grace_ends_at);
+        }
+
+        let renewal_attempts = self.renewal_attempts + 1;
+        let retry_at = DUNNING_RETRY_DELAYS
+            .get(renewal_attempts as usize - 1)
+            .map_or(grace_ends_at, |delay| (now + *delay).min(grace_ends_at));
+        Self {
+            status: SubscriptionStatus::PastDue,
+            renewal_attempts,
+            grace_ends_at: Some(grace_ends_at),
+            next_billing_at: Some(retry_at),
+            renewal_transaction_id: None,
+            ..self
+        }
+    }
+
+    /// Stop renewing; a past-due subscription has no paid time left and
+    /// ends at once
+    pub fn cancel(self, now: DateTime<Utc>) -> Self {
+        match self.status {
+            SubscriptionStatus::Active => Self { cancel_at_period_end: true, ..self },
+            SubscriptionStatus::PastDue => self.ended(SubscriptionStatus::Canceled, now),
+            _ => self,
+        }
+    }
+
+    /// Undo a cancellation before the period ends
+    pub fn resume(self) -> Result<Self, AppError> {
+        if !self.status.is_live() {
+            return Err(AppError::Conflict(format!(
+                "Subscription {} is {}; subscribe again instead",
+                self.subscription_id,
+                self.status.as_str()
+            )));
+        }
+        Ok(Self { cancel_at_period_end: false, ..self })
+    }
+
+    /// Price of moving from `current` to `next` for the rest of the period
+    pub fn prorate(
+        &self,
+        current: &SubscriptionPlan,
+        next: &SubscriptionPlan,
+        now: DateTime<Utc>,
+    ) -> Result<Proration, AppError> {
+        if self.status != SubscriptionStatus::Active || self.renewal_transaction_id.is_some() {
+            return Err(AppError::Conflict(format!(
+                "Subscription {} cannot change plan while {}",
+                self.subscription_id,
+                if self.status == SubscriptionStatus::Active { "renewing" } else { self.status.as_str() }
+            )));
+        }
+        if current.plan_id == next.plan_id {
+            return Err(AppError::Validation(format!("Already subscribed to {}", next.plan_id)));
+        }
+        if current.period_days.is_none() || current.period_days != next.period_days || current.currency != next.currency {
+            return Err(AppError::Validation(
+                "Plans can only change to another recurring plan with the same period and currency".into(),
+            ));
+        }
+
+        let total = (self.current_period_end - self.current_period_start).num_seconds().max(1);
+        let remaining = (self.current_period_end - now).num_seconds().clamp(0, total);
+        let prorated = |price_cents: i64| (i128::from(price_cents) * i128::from(remaining) / i128::from(total)) as i64;
+        let difference = prorated(next.price_cents) - prorated(current.price_cents);
+
+        Ok(Proration {
+            charge_cents: difference.max(0),
+            credit_cents: (-difference).max(0),
+        })
+    }
+
+    /// Move to `plan`, whose entitlement `transaction` now grants
+    pub fn changed_plan(self, plan: &SubscriptionPlan, transaction: &Transaction, proration: Proration) -> Self {
+        Self {
+            plan_id: plan.plan_id.clone(),
+            credit_cents: self.credit_cents + proration.credit_cents,
+            entitlement_transaction_id: transaction.transaction_id,
+            ..self
+        }
+    }
+
+    fn ended(self, status: SubscriptionStatus, at: DateTime<Utc>) -> Self {
+        Self {
+            status,
+            ended_at: Some(at),
+            next_billing_at: None,
+            renewal_transaction_id: None,
+            ..self
+        }
+    }
+}
+
+/// Body of `POST /subscriptions`
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct SubscribeRequest {
+    pub player_id: Uuid,
+
+    #[validate(length(min = 1, max = 255))]
+    pub plan_id: String,
+
+    /// Saved payment method renewals are charged to
+    #[validate(length(min = 1, max = 255))]
+    pub payment_method: String,
+}
+
+/// Body of `POST /subscriptions/{subscriptionId}/cancel` and `/resume`
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct SubscriptionActionRequest {
+    /// The subscriber - only they may change it
+    pub player_id: Uuid,
+}
+
+/// Body of `POST /subscriptions/{subscriptionId}/change-plan`
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct ChangePlanRequest {
+    pub player_id: Uuid,
+
+    #[validate(length(min = 1, max = 255))]
+    pub plan_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(plan_id: &str, price_cents: i64) -> SubscriptionPlan {
+        SubscriptionPlan {
+            plan_id: plan_id.into(),
+            name: "VIP".into(),
+            item_id: "vip".into(),
+            price_cents,
+            currency: "USD".into(),
+            period_days: Some(30),
+            season_ends_at: None,
+            grace_period_days: 7,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_failed_renewals_retry_through_grace_then_expire() {
+        let vip = plan("vip", 1000);
+        let start = Utc::now();
+        let subscription = Subscription::start(Uuid::new_v4(), &vip, "pm_card".into(), Uuid::new_v4(), start).unwrap();
+        let due = subscription.current_period_end;
+        assert_eq!(subscription.due_step(&vip, due), DueStep::Charge { amount_cents: 1000 });
+
+        let past_due = subscription.renewal_failed(&vip, due);
+        assert_eq!(past_due.status, SubscriptionStatus::PastDue);
+        assert_eq!(past_due.grace_ends_at, Some(due + Duration::days(7)));
+        assert_eq!(past_due.next_billing_at, Some(due + Duration::days(1)));
+        assert_eq!(past_due.access_ends_at(), due + Duration::days(7));
+
+        let past_due = past_due.renewal_failed(&vip, due + Duration::days(1));
+        assert_eq!(past_due.next_billing_at, Some(due + Duration::days(4)));
+        let past_due = past_due.renewal_failed(&vip, due + Duration::days(4));
+        assert_eq!(past_due.next_billing_at, Some(due + Duration::days(7)), "capped at the end of grace");
+
+        let DueStep::End(expired) = past_due.due_step(&vip, due + Duration::days(7)) else {
+            panic!("grace is over");
+        };
+        assert_eq!(expired.status, SubscriptionStatus::Expired);
+        assert_eq!(expired.access_ends_at(), due + Duration::days(7));
+    }
+
+    #[test]
+    fn test_cancel_after_the_renewal_is_claimed_ends_instead_of_charging() {
+        let vip = plan("vip", 1000);
+        let subscription = Subscription::start(Uuid::new_v4(), &vip, "pm_card".into(), Uuid::new_v4(), Utc::now()).unwrap();
+        let due = subscription.current_period_end;
+        let claimed = subscription.claim_renewal(Uuid::new_v4());
+        assert_eq!(claimed.due_step(&vip, due), DueStep::Charge { amount_cents: 1000 });
+
+        let canceled = Subscription { cancel_at_period_end: true, ..claimed };
+        let DueStep::End(ended) = canceled.due_step(&vip, due) else {
+            panic!("a canceled subscription is not re-charged");
+        };
+        assert_eq!(ended.status, SubscriptionStatus::Canceled);
+        assert_eq!((ended.renewal_transaction_id, ended.access_ends_at()), (None, due));
+    }
+
+    #[test]
+    fn test_plan_change_prorates_the_rest_of_the_period() {
+        let (basic, premium) = (plan("basic", 1000), plan("premium", 3000));
+        let start = Utc::now();
+        let subscription = Subscription::start(Uuid::new_v4(), &basic, "pm_card".into(), Uuid::new_v4(), start).unwrap();
+        let halfway = start + Duration::days(15);
+
+        let upgrade = subscription.prorate(&basic, &premium, halfway).unwrap();
+        assert_eq!(upgrade, Proration { charge_cents: 1000, credit_cents: 0 });
+        let downgrade = subscription.prorate(&premium, &basic, halfway).unwrap();
+        assert_eq!(downgrade, Proration { charge_cents: 0, credit_cents: 1000 });
+
+        let season = SubscriptionPlan { period_days: None, season_ends_at: Some(start + Duration::days(90)), ..plan("s1", 999) };
+        assert!(matches!(subscription.prorate(&basic, &season, halfway), Err(AppError::Validation(_))));
+    }
+}
@@ -5,6 +5,26 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use super::catalog::ITEM_KIND_METADATA_KEY;
+use super::code::CODE_GRANT_METADATA_KEY;
+use super::entitlement::{ACCESS_EXPIRES_METADATA_KEY, WALLET_TOP_UP_METADATA_KEY};
+use super::gift::GiftStatus;
+use super::promotion::{AppliedPromotion, Pricing};
+use super::wallet::WALLET_SPEND_METADATA_KEY;
+use super::webhook::TITLE_METADATA_KEY;
+
+/// Metadata keys only the server writes - they credit or debit wallets,
+/// grant access, pick webhook receivers or record the catalog's terms, so
+/// a client may not send them
+pub const SERVER_METADATA_KEYS: [&str; 6] = [
+    WALLET_TOP_UP_METADATA_KEY,
+    WALLET_SPEND_METADATA_KEY,
+    CODE_GRANT_METADATA_KEY,
+    ACCESS_EXPIRES_METADATA_KEY,
+    ITEM_KIND_METADATA_KEY,
+    TITLE_METADATA_KEY,
+];
+
 /// Transaction status enum
 /// 
 /// ADVANTAGE: Exhaustive pattern matching - compiler ensures all cases handled
@@ -18,6 +38,10 @@ pub enum TransactionStatus {
     Completed,
     Failed,
     Refunded,
+    /// Reversed by the card issuer after completing
+    #[sqlx(rename = "charged_back")]
+    #[serde(rename = "charged_back")]
+    ChargedBack,
 }
 
 impl TransactionStatus {
@@ -25,13 +49,42 @@ impl TransactionStatus {
     /// 
     /// ADVANTAGE: Method on enum - behavior attached to data
     pub const fn is_terminal(&self) -> bool {
-        matches!(self, Self::Completed | Self::Failed | Self::Refunded)
+        matches!(self, Self::Completed | Self::Failed | Self::Refunded | Self::ChargedBack)
     }
     
     /// Check if transaction can be refunded
     pub const fn can_refund(&self) -> bool {
         matches!(self, Self::Completed)
     }
+    
+    /// Check if a transaction in this status may move to `next`
+    /// 
+    /// Repeating the current status is allowed so retried updates succeed.
+    /// Money only moves on the remaining transitions, which keeps the
+    /// journal to one entry per change.
+    pub const fn can_transition_to(&self, next: Self) -> bool {
+        matches!(
+            (self, next),
+            (Self::Pending, Self::Pending | Self::Completed | Self::Failed)
+                | (Self::Completed, Self::Completed | Self::Refunded | Self::ChargedBack)
+                | (Self::Failed, Self::Failed)
+                | (Self::Refunded, Self::Refunded)
+                | (Self::ChargedBack, Self::ChargedBack)
+        )
+    }
+    
+    /// Database enum label
+    /// 
+    /// ADVANTAGE: Exhaustive match - a new status cannot be forgotten here
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Refunded => "refunded",
+            Self::ChargedBack => "charged_back",
+        }
+    }
 }
 
 /// Complete transaction record from database
@@ -50,8 +103,42 @@ pub struct Transaction {
     pub status: TransactionStatus,
     pub metadata: serde_json::Value,
     pub processor_id: Option<String>,
+    /// Correlation ID of the request that created the transaction
+    #[serde(default)]
+    pub request_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Price before promotions, `None` when none applied
+    #[serde(default)]
+    pub original_price_cents: Option<i64>,
+    /// Promotions that set `price_cents`, in the order they applied
+    #[serde(default)]
+    #[sqlx(json)]
+    pub applied_promotions: Vec<AppliedPromotion>,
+    /// Player a gift was bought for; `player_id` is always the payer
+    #[serde(default)]
+    pub recipient_id: Option<Uuid>,
+    /// Recipient's answer, set only on gifts
+    #[serde(default)]
+    pub gift_status: Option<GiftStatus>,
+}
+
+impl Transaction {
+    /// Player the purchased items belong to
+    pub fn owner_id(&self) -> Uuid {
+        self.recipient_id.unwrap_or(self.player_id)
+    }
+
+    /// This transaction as listed in `player_id`'s history
+    ///
+    /// A received gift hides the payer's payment reference and metadata.
+    pub fn seen_by(mut self, player_id: Uuid) -> Self {
+        if self.player_id != player_id {
+            self.processor_id = None;
+            self.metadata = serde_json::Value::Null;
+        }
+        self
+    }
 }
 
 /// New transaction for insertion
@@ -68,6 +155,14 @@ pub struct NewTransaction {
     pub currency: String,
     pub quantity: i32,
     pub metadata: serde_json::Value,
+    pub request_id: Option<String>,
+    pub original_price_cents: Option<i64>,
+    pub applied_promotions: Vec<AppliedPromotion>,
+    /// Code redeemed by this transaction; its limits are checked and the
+    /// redemption recorded in the same database transaction as the insert
+    pub code: Option<String>,
+    /// Player the purchase is a gift for
+    pub recipient_id: Option<Uuid>,
 }
 
 impl NewTransaction {
@@ -92,8 +187,42 @@ impl NewTransaction {
             currency,
             quantity,
             metadata,
+            request_id: None,
+            original_price_cents: None,
+            applied_promotions: Vec::new(),
+            code: None,
+            recipient_id: None,
         }
     }
+    
+    /// Record the correlation ID of the creating request
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// Charge the promotional price, recording the list price and the
+    /// promotions behind it; a pricing with nothing applied changes nothing
+    pub fn with_pricing(mut self, pricing: Pricing) -> Self {
+        if !pricing.applied.is_empty() {
+            self.price_cents = pricing.price_cents;
+            self.original_price_cents = Some(pricing.original_price_cents);
+            self.applied_promotions = pricing.applied;
+        }
+        self
+    }
+
+    /// Make the purchase a gift for `recipient_id`, pending their acceptance
+    pub fn with_recipient(mut self, recipient_id: Uuid) -> Self {
+        self.recipient_id = Some(recipient_id);
+        self
+    }
+
+    /// Redeem the normalized `code` with this transaction
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
 }
 
 /// Currency enum for compile-time currency validation
@@ -0,0 +1,85 @@
+//! Side effects of a transaction status change
+//!
+//! Backends compute `TransitionEffects` once, after the status update, and
+//! apply every effect in the same database transaction as the update.
+//!
+//! ADVANTAGE: What a transition does is decided here, not three times over
+//! in each storage backend
+
+use crate::errors::AppResult;
+use super::catalog::StockSettlement;
+use super::entitlement::NewEntitlement;
+use super::event::NewOutboxEvent;
+use super::gift::GiftStatus;
+use super::journal::NewJournalEntry;
+use super::transaction::{Transaction, TransactionStatus};
+use super::wallet::NewLedgerEntry;
+use super::webhook::WebhookFanOut;
+
+/// Writes owed by one status change
+#[derive(Debug, Clone, Default)]
+pub struct TransitionEffects {
+    /// Balanced journal entry, when money moved
+    pub journal: Option<NewJournalEntry>,
+    /// Items to grant, when the purchase completed or a gift was accepted
+    pub grants: Vec<NewEntitlement>,
+    /// Revoke the purchase's entitlements - it was refunded or charged back
+    pub revoke: bool,
+    /// Outbox event announcing the change to other systems
+    pub event: Option<NewOutboxEvent>,
+    /// Webhook deliveries of `event` to the purchase's title
+    pub webhooks: Option<WebhookFanOut>,
+    /// Settle the purchase's stock reservation, if it holds one
+    pub stock: Option<StockSettlement>,
+    /// Wallet credit of a completed top-up or code, or its reversal, or the
+    /// debit of a completed wallet-funded purchase
+    pub wallet: Option<NewLedgerEntry>,
+}
+
+impl TransitionEffects {
+    /// Effects of the recipient accepting the gift `transaction`
+    pub fn of_gift_accepted(transaction: &Transaction) -> Self {
+        Self { grants: NewEntitlement::for_transaction(transaction), ..Self::default() }
+    }
+
+    /// Effects of `transaction` having moved from `previous` to its current status
+    pub fn of(transaction: &Transaction, previous: TransactionStatus) -> AppResult<Self> {
+        if previous == transaction.status {
+            return Ok(Self::default());
+        }
+
+        let event = NewOutboxEvent::for_transaction(transaction);
+        let webhooks = event.as_ref().and_then(|e| WebhookFanOut::for_event(transaction, e));
+
+        Ok(Self {
+            journal: NewJournalEntry::for_status_change(transaction, previous)?,
+            // A gift's items wait for the recipient to accept
+            grants: match (transaction.status, transaction.gift_status) {
+                (TransactionStatus::Completed, None | Some(GiftStatus::Accepted)) => {
+                    NewEntitlement::for_transaction(transaction)
+                }
+                _ => Vec::new(),
+            },
+            revoke: matches!(
+                transaction.status,
+                TransactionStatus::Refunded | TransactionStatus::ChargedBack
+            ),
+            event,
+            webhooks,
+            stock: match transaction.status {
+                TransactionStatus::Completed => Some(StockSettlement::Commit),
+                TransactionStatus::Failed => Some(StockSettlement::Release),
+                _ => None,
+            },
+            wallet: match (previous, transaction.status) {
+                (_, TransactionStatus::Completed) => {
+                    NewLedgerEntry::credit_for(transaction).or_else(|| NewLedgerEntry::debit_for(transaction))
+                }
+                (TransactionStatus::Completed, TransactionStatus::Refunded | TransactionStatus::ChargedBack) => {
+                    NewLedgerEntry::reversal_for(transaction)
+                }
+                _ => None,
+            },
+        })
+    }
+}
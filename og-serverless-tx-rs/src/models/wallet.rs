@@ -0,0 +1,340 @@
+//! Wallet models - virtual currency balances kept as an append-only ledger
+//!
+//! A balance is never stored on its own: it is the `balance_after` of the
+//! player's latest ledger entry in that currency, which always equals the
+//! sum of every entry's `amount`.
+//!
+//! Top-ups and gift codes credit the wallet as an effect of their
+//! transaction completing, and a refund or chargeback of one posts the
+//! matching reversal, so a balance never drifts from the money behind it.
+//! Spends are purchases too: the debit is written as their transaction
+//! completes, together with the grant and the stock sale.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+use super::code::CODE_GRANT_METADATA_KEY;
+use super::entitlement::WALLET_TOP_UP_METADATA_KEY;
+use super::transaction::Transaction;
+
+/// Premium and soft currency held in wallets
+///
+/// ADVANTAGE: Unknown currencies are rejected during deserialization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "virtual_currency", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum VirtualCurrency {
+    Gems,
+    Gold,
+}
+
+impl VirtualCurrency {
+    pub const ALL: [Self; 2] = [Self::Gems, Self::Gold];
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gems => "gems",
+            Self::Gold => "gold",
+        }
+    }
+}
+
+impl std::str::FromStr for VirtualCurrency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gems" => Ok(Self::Gems),
+            "gold" => Ok(Self::Gold),
+            _ => Err(format!("Invalid virtual currency: {}", s)),
+        }
+    }
+}
+
+/// Why a ledger entry was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "ledger_entry_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerEntryKind {
+    /// Credit paid for with real money - `reference` is the transaction ID
+    TopUp,
+    /// Debit for an item - `reference` is the client's idempotency key
+    Spend,
+    /// Credit from a redeemed gift code - `reference` is the transaction ID
+    CodeGrant,
+    /// Debit taking back a refunded or charged back credit - `reference`
+    /// is the transaction ID
+    Reversal,
+}
+
+impl LedgerEntryKind {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::TopUp => "top_up",
+            Self::Spend => "spend",
+            Self::CodeGrant => "code_grant",
+            Self::Reversal => "reversal",
+        }
+    }
+}
+
+/// Virtual currency a transaction credits, stored in its metadata under
+/// `wallet_top_up`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletCredit {
+    pub currency: VirtualCurrency,
+    pub amount: i64,
+}
+
+impl WalletCredit {
+    /// Credit recorded on `transaction`, if it is a top-up or credits a code
+    pub fn of(transaction: &Transaction) -> Option<Self> {
+        transaction
+            .metadata
+            .get(WALLET_TOP_UP_METADATA_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+}
+
+/// Metadata key recording the virtual currency a wallet-funded purchase
+/// debits
+pub const WALLET_SPEND_METADATA_KEY: &str = "wallet_spend";
+
+/// Virtual currency a wallet-funded purchase debits, stored in its
+/// metadata under `wallet_spend`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletDebit {
+    pub currency: VirtualCurrency,
+    pub amount: i64,
+    /// The client's key - also the debit's ledger reference
+    pub idempotency_key: String,
+}
+
+impl WalletDebit {
+    /// Debit recorded on `transaction`, if it was paid from the wallet
+    pub fn of(transaction: &Transaction) -> Option<Self> {
+        transaction
+            .metadata
+            .get(WALLET_SPEND_METADATA_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// ID of the purchase `player_id` makes under `idempotency_key`
+    ///
+    /// ADVANTAGE: A retried spend finds its first purchase instead of
+    /// starting a second one
+    pub fn transaction_id(player_id: Uuid, idempotency_key: &str) -> Uuid {
+        let digest = Sha256::new()
+            .chain_update(b"wallet_spend:")
+            .chain_update(player_id.as_bytes())
+            .chain_update(b":")
+            .chain_update(idempotency_key.as_bytes())
+            .finalize();
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        uuid::Builder::from_custom_bytes(bytes).into_uuid()
+    }
+}
+
+/// One immutable ledger row
+///
+/// ADVANTAGE: `sequence` is gapless per player and currency, so a missing
+/// or reordered entry is detectable
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct LedgerEntry {
+    pub entry_id: Uuid,
+    pub player_id: Uuid,
+    pub currency: VirtualCurrency,
+    pub sequence: i64,
+    /// Positive for credits, negative for debits
+    pub amount: i64,
+    pub balance_after: i64,
+    pub kind: LedgerEntryKind,
+    pub reference: String,
+    pub item_id: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Ledger entry to append
+///
+/// `(player_id, kind, reference)` is unique: appending the same entry again
+/// returns the original instead of moving the balance twice.
+#[derive(Debug, Clone)]
+pub struct NewLedgerEntry {
+    pub entry_id: Uuid,
+    pub player_id: Uuid,
+    pub currency: VirtualCurrency,
+    pub amount: i64,
+    pub kind: LedgerEntryKind,
+    pub reference: String,
+    pub item_id: Option<String>,
+    pub request_id: Option<String>,
+}
+
+impl NewLedgerEntry {
+    /// Credit `amount` for the completed purchase `transaction_id`
+    pub fn top_up(player_id: Uuid, currency: VirtualCurrency, amount: i64, transaction_id: Uuid) -> Self {
+        Self {
+            entry_id: Uuid::new_v4(),
+            player_id,
+            currency,
+            amount,
+            kind: LedgerEntryKind::TopUp,
+            reference: transaction_id.to_string(),
+            item_id: None,
+            request_id: None,
+        }
+    }
+
+    /// Credit `amount` for the gift code redemption `transaction_id`
+    pub fn code_grant(player_id: Uuid, currency: VirtualCurrency, amount: i64, transaction_id: Uuid) -> Self {
+        Self {
+            kind: LedgerEntryKind::CodeGrant,
+            ..Self::top_up(player_id, currency, amount, transaction_id)
+        }
+    }
+
+    /// Credit owed by the completed `transaction`, `None` if it credits nothing
+    pub fn credit_for(transaction: &Transaction) -> Option<Self> {
+        let credit = WalletCredit::of(transaction)?;
+        let entry = if transaction.metadata.get(CODE_GRANT_METADATA_KEY).is_some() {
+            Self::code_grant(transaction.player_id, credit.currency, credit.amount, transaction.transaction_id)
+        } else {
+            Self::top_up(transaction.player_id, credit.currency, credit.amount, transaction.transaction_id)
+        };
+        Some(Self { request_id: transaction.request_id.clone(), ..entry })
+    }
+
+    /// Debit owed by the completed wallet-funded `transaction`, `None` if it
+    /// was paid for with money
+    pub fn debit_for(transaction: &Transaction) -> Option<Self> {
+        let debit = WalletDebit::of(transaction)?;
+        let entry = Self::spend(
+            transaction.player_id,
+            debit.currency,
+            debit.amount,
+            debit.idempotency_key,
+            transaction.item_id.clone(),
+        );
+        Some(Self { request_id: transaction.request_id.clone(), ..entry })
+    }
+
+    /// Debit taking back `transaction`'s credit after a refund or chargeback
+    pub fn reversal_for(transaction: &Transaction) -> Option<Self> {
+        Self::credit_for(transaction).map(|credit| Self {
+            entry_id: Uuid::new_v4(),
+            amount: -credit.amount,
+            kind: LedgerEntryKind::Reversal,
+            ..credit
+        })
+    }
+
+    /// Debit `amount` for `item_id`
+    pub fn spend(
+        player_id: Uuid,
+        currency: VirtualCurrency,
+        amount: i64,
+        idempotency_key: impl Into<String>,
+        item_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            entry_id: Uuid::new_v4(),
+            player_id,
+            currency,
+            amount: -amount,
+            kind: LedgerEntryKind::Spend,
+            reference: idempotency_key.into(),
+            item_id: Some(item_id.into()),
+            request_id: None,
+        }
+    }
+
+    /// Record the correlation ID of the creating request
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// Sequence and balance after appending to a ledger at `sequence`/`balance`
+    ///
+    /// A reversal is never refused: the money is already gone, so it may take
+    /// the balance below zero, and spends fail until top-ups cover the debt.
+    ///
+    /// ADVANTAGE: Every backend enforces the no-negative rule through this one function
+    pub fn position_after(&self, sequence: i64, balance: i64) -> AppResult<(i64, i64)> {
+        let balance_after = balance
+            .checked_add(self.amount)
+            .ok_or_else(|| AppError::Validation("Wallet balance out of range".into()))?;
+
+        if balance_after < 0 && self.kind == LedgerEntryKind::Spend {
+            return Err(AppError::InsufficientFunds(format!(
+                "{} balance is {}, {} required",
+                self.currency.as_str(),
+                balance,
+                -self.amount
+            )));
+        }
+
+        Ok((sequence + 1, balance_after))
+    }
+
+    /// Resolve a retry that found `existing` under the same reference
+    ///
+    /// ADVANTAGE: A reused idempotency key with a different amount is caught
+    pub fn replay(&self, existing: LedgerEntry) -> AppResult<LedgerEntry> {
+        if existing.currency == self.currency && existing.amount == self.amount {
+            Ok(existing)
+        } else {
+            Err(AppError::Conflict(format!(
+                "Reference {} was already used for a different {} entry",
+                self.reference,
+                existing.kind.as_str()
+            )))
+        }
+    }
+}
+
+/// Balance of one currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletBalance {
+    pub currency: VirtualCurrency,
+    pub balance: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debits_never_go_negative() {
+        let player_id = Uuid::new_v4();
+        let top_up = NewLedgerEntry::top_up(player_id, VirtualCurrency::Gems, 500, Uuid::new_v4());
+        assert_eq!(top_up.position_after(0, 0).unwrap(), (1, 500));
+
+        let spend = NewLedgerEntry::spend(player_id, VirtualCurrency::Gems, 200, "key-1", "sword");
+        assert_eq!(spend.position_after(1, 500).unwrap(), (2, 300));
+        assert!(matches!(spend.position_after(1, 199), Err(AppError::InsufficientFunds(_))));
+    }
+
+    #[test]
+    fn test_spend_keys_name_one_purchase_per_player() {
+        let (player_id, other_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let id = WalletDebit::transaction_id(player_id, "key-1");
+        assert_eq!(id, WalletDebit::transaction_id(player_id, "key-1"));
+        assert_ne!(id, WalletDebit::transaction_id(player_id, "key-2"));
+        assert_ne!(id, WalletDebit::transaction_id(other_id, "key-1"));
+    }
+
+    #[test]
+    fn test_reversals_may_overdraw() {
+        let player_id = Uuid::new_v4();
+        let credit = NewLedgerEntry::top_up(player_id, VirtualCurrency::Gems, 500, Uuid::new_v4());
+        let reversal = NewLedgerEntry { amount: -500, kind: LedgerEntryKind::Reversal, ..credit };
+        assert_eq!(reversal.position_after(4, 200).unwrap(), (5, -300));
+    }
+}
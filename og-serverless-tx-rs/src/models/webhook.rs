@@ -0,0 +1,280 @@
+//! Webhook models - per-title subscriptions and their delivery log
+//!
+//! A purchase belongs to a title when its catalog item names one, which is
+//! stamped on its metadata as `title_id` - clients cannot set it. When
+//! its status changes, one delivery per matching subscription is written in
+//! the same database transaction, carrying the outbox event's payload.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::net::IpAddr;
+use url::Host;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::privacy::Secret;
+use super::event::{EventType, NewOutboxEvent};
+use super::transaction::Transaction;
+
+/// Metadata key naming the game title a purchase belongs to
+pub const TITLE_METADATA_KEY: &str = "title_id";
+
+/// A title's request to be told about purchase events
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookSubscription {
+    pub subscription_id: Uuid,
+    pub title_id: String,
+    pub url: String,
+    /// HMAC key for signing deliveries - only shown when created
+    #[serde(skip)]
+    #[sqlx(try_from = "String")]
+    pub secret: Secret<String>,
+    #[sqlx(json)]
+    pub event_types: Vec<EventType>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body of `POST /titles/{titleId}/webhooks`
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateWebhookRequest {
+    /// HTTPS endpoint on a public address
+    #[validate(length(min = 1, max = 2048))]
+    pub url: String,
+
+    #[validate(length(min = 1, max = 3))]
+    pub event_types: Vec<EventType>,
+}
+
+impl CreateWebhookRequest {
+    /// Reject URLs deliveries should not go to
+    pub async fn check_url(&self) -> Result<(), AppError> {
+        check_destination(&self.url).await
+    }
+}
+
+/// Reject `url` unless it is HTTPS and every address its host resolves to
+/// is public
+///
+/// Checked when a subscription is created and again before each delivery,
+/// since the host's DNS can change in between.
+///
+/// ADVANTAGE: A subscription cannot point deliveries at localhost, the VPC
+/// or the instance metadata service
+pub async fn check_destination(url: &str) -> Result<(), AppError> {
+    let url = url::Url::parse(url)
+        .map_err(|_| AppError::Validation("url is not a valid URL".into()))?;
+    if url.scheme() != "https" {
+        return Err(AppError::Validation("url must use https".into()));
+    }
+
+    let addresses: Vec<IpAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+        Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+        Some(Host::Domain(domain)) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            tokio::net::lookup_host((domain, port))
+                .await
+                .map_err(|_| AppError::Validation(format!("url host {} does not resolve", domain)))?
+                .map(|address| address.ip())
+                .collect()
+        }
+        None => return Err(AppError::Validation("url has no host".into())),
+    };
+
+    if addresses.is_empty() || !addresses.iter().all(|ip| is_public_address(*ip)) {
+        return Err(AppError::Validation("url must resolve to public addresses only".into()));
+    }
+    Ok(())
+}
+
+/// Whether `ip` is reachable on the public internet - not loopback,
+/// private, link-local, shared (CGNAT), multicast or unspecified
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            let shared = a == 100 && (64..128).contains(&b);
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || shared
+                || a == 0
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_address(IpAddr::V4(mapped)),
+            None => {
+                let first = ip.segments()[0];
+                let unique_local = first & 0xfe00 == 0xfc00;
+                let link_local = first & 0xffc0 == 0xfe80;
+                !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+            }
+        },
+    }
+}
+
+/// Subscription to store, with a freshly generated signing secret
+#[derive(Debug, Clone)]
+pub struct NewWebhookSubscription {
+    pub subscription_id: Uuid,
+    pub title_id: String,
+    pub url: String,
+    pub secret: Secret<String>,
+    pub event_types: Vec<EventType>,
+}
+
+impl NewWebhookSubscription {
+    pub fn new(title_id: String, request: CreateWebhookRequest) -> Self {
+        let mut event_types = request.event_types;
+        event_types.sort_by_key(EventType::as_str);
+        event_types.dedup();
+
+        Self {
+            subscription_id: Uuid::new_v4(),
+            title_id,
+            url: request.url,
+            secret: Secret::new(generate_secret()),
+            event_types,
+        }
+    }
+
+    /// The stored form of this subscription
+    pub fn into_subscription(self, created_at: DateTime<Utc>) -> WebhookSubscription {
+        WebhookSubscription {
+            subscription_id: self.subscription_id,
+            title_id: self.title_id,
+            url: self.url,
+            secret: self.secret,
+            event_types: self.event_types,
+            created_at,
+        }
+    }
+}
+
+/// `whsec_` plus 244 random bits from two v4 UUIDs
+fn generate_secret() -> String {
+    format!("whsec_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Delivery state of one webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookDeliveryStatus {
+    /// Waiting for its first or next attempt
+    Pending,
+    Delivered,
+    /// Out of attempts - redeliver by hand once the receiver is fixed
+    Dead,
+}
+
+impl WebhookDeliveryStatus {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Dead => "dead",
+        }
+    }
+}
+
+/// One event owed to one subscription, with its retry state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    pub delivery_id: Uuid,
+    pub subscription_id: Uuid,
+    /// Outbox event ID - the same across redeliveries, for receiver deduplication
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    /// HTTP status of the last attempt, `None` if no response arrived
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Claimed delivery with the subscription details needed to send it
+#[derive(Debug, Clone)]
+pub struct WebhookDispatch {
+    pub delivery: WebhookDelivery,
+    pub url: String,
+    pub secret: Secret<String>,
+}
+
+/// Deliveries to create for a purchase event: one per subscription of
+/// `title_id` that wants `event_type`
+#[derive(Debug, Clone)]
+pub struct WebhookFanOut {
+    pub title_id: String,
+    pub event_id: Uuid,
+    pub event_type: EventType,
+    pub payload: serde_json::Value,
+}
+
+impl WebhookFanOut {
+    /// Fan-out of `event`, `None` unless `transaction` names its title
+    pub fn for_event(transaction: &Transaction, event: &NewOutboxEvent) -> Option<Self> {
+        let title_id = transaction.metadata.get(TITLE_METADATA_KEY)?.as_str()?;
+
+        Some(Self {
+            title_id: title_id.to_string(),
+            event_id: event.event_id,
+            event_type: event.event_type,
+            payload: event.payload.clone(),
+        })
+    }
+
+    /// Deliveries owed to `subscriptions`, due immediately
+    pub fn deliveries<'a>(
+        &'a self,
+        subscriptions: impl IntoIterator<Item = &'a WebhookSubscription> + 'a,
+        created_at: DateTime<Utc>,
+    ) -> impl Iterator<Item = WebhookDelivery> + 'a {
+        subscriptions
+            .into_iter()
+            .filter(|s| s.title_id == self.title_id && s.event_types.contains(&self.event_type))
+            .map(move |s| WebhookDelivery {
+                delivery_id: Uuid::new_v4(),
+                subscription_id: s.subscription_id,
+                event_id: self.event_id,
+                event_type: self.event_type.as_str().to_string(),
+                payload: self.payload.clone(),
+                status: WebhookDeliveryStatus::Pending,
+                attempts: 0,
+                next_attempt_at: created_at,
+                last_status_code: None,
+                last_error: None,
+                created_at,
+                delivered_at: None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_destinations_must_be_public_https() {
+        for ip in ["10.1.2.3", "172.16.0.1", "192.168.1.1", "127.0.0.1", "169.254.169.254", "100.64.0.1", "::1", "fd00::1", "fe80::1", "::ffff:10.0.0.1"] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{} is not public", ip);
+        }
+        assert!(is_public_address("203.0.113.10".parse().unwrap()));
+
+        assert!(check_destination("https://203.0.113.10/hooks").await.is_ok());
+        for url in ["http://203.0.113.10/hooks", "https://169.254.169.254/latest/meta-data", "https://[::1]:8443/", "https://localhost/hooks"] {
+            assert!(matches!(check_destination(url).await, Err(AppError::Validation(_))), "{} is refused", url);
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! # Redacting Log Formatter
+//!
+//! JSON formatter for `tracing_subscriber::fmt` that applies a
+//! `RedactionPolicy` to event and span fields before anything is written:
+//!
+//! ```text
+//! {"timestamp":"...","level":"INFO","fields":{"message":"...","player_id":"anon_1f2e..."},"spans":[{"name":"request","request_id":"..."}]}
+//! ```
+//!
+//! `RedactingFields` formats span fields, `RedactingJson` formats events;
+//! install both so `#[instrument]` arguments are covered as well as `info!`.
+
+use serde_json::{Map, Value};
+use std::fmt;
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+use super::{FieldTreatment, RedactionPolicy, REDACTED};
+
+/// Formats span fields as a redacted JSON object
+#[derive(Debug, Clone)]
+pub struct RedactingFields {
+    policy: Arc<RedactionPolicy>,
+}
+
+impl RedactingFields {
+    pub fn new(policy: Arc<RedactionPolicy>) -> Self {
+        Self { policy }
+    }
+}
+
+impl<'writer> FormatFields<'writer> for RedactingFields {
+    fn format_fields<R: RecordFields>(&self, mut writer: Writer<'writer>, fields: R) -> fmt::Result {
+        let mut map = Map::new();
+        fields.record(&mut RedactingVisitor::new(&self.policy, &mut map));
+        write!(writer, "{}", Value::Object(map))
+    }
+
+    // The default appends text; merge into the existing object instead
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> fmt::Result {
+        let mut map = match serde_json::from_str(&current.fields) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        fields.record(&mut RedactingVisitor::new(&self.policy, &mut map));
+        current.fields = Value::Object(map).to_string();
+        Ok(())
+    }
+}
+
+/// Formats events as redacted JSON lines
+///
+/// ADVANTAGE: Same line shape as the stock JSON formatter, so log queries keep working
+#[derive(Debug, Clone)]
+pub struct RedactingJson {
+    policy: Arc<RedactionPolicy>,
+}
+
+impl RedactingJson {
+    pub fn new(policy: Arc<RedactionPolicy>) -> Self {
+        Self { policy }
+    }
+}
+
+impl<S> FormatEvent<S, RedactingFields> for RedactingJson
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, RedactingFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut fields = Map::new();
+        event.record(&mut RedactingVisitor::new(&self.policy, &mut fields));
+
+        let mut line = Map::new();
+        line.insert(
+            "timestamp".into(),
+            chrono::Utc::now()
+                .to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+                .into(),
+        );
+        line.insert("level".into(), event.metadata().level().as_str().into());
+        line.insert("fields".into(), Value::Object(fields));
+
+        if let Some(scope) = ctx.event_scope() {
+            let spans: Vec<Value> = scope
+                .from_root()
+                .map(|span| {
+                    // Span fields were redacted by `RedactingFields` when recorded
+                    let mut object = span
+                        .extensions()
+                        .get::<FormattedFields<RedactingFields>>()
+                        .and_then(|f| serde_json::from_str::<Map<String, Value>>(&f.fields).ok())
+                        .unwrap_or_default();
+                    object.insert("name".into(), span.name().into());
+                    Value::Object(object)
+                })
+                .collect();
+            line.insert("spans".into(), Value::Array(spans));
+        }
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+/// Records fields into a JSON map, masking or hashing as the policy says
+struct RedactingVisitor<'a> {
+    policy: &'a RedactionPolicy,
+    map: &'a mut Map<String, Value>,
+}
+
+impl<'a> RedactingVisitor<'a> {
+    fn new(policy: &'a RedactionPolicy, map: &'a mut Map<String, Value>) -> Self {
+        Self { policy, map }
+    }
+
+    fn insert(&mut self, field: &Field, value: Value) {
+        let value = match self.policy.treatment(field.name()) {
+            FieldTreatment::Keep => value,
+            FieldTreatment::Mask => REDACTED.into(),
+            FieldTreatment::Hash => match value {
+                Value::String(s) => self.policy.hash(&s).into(),
+                other => self.policy.hash(&other.to_string()).into(),
+            },
+        };
+        self.map.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for RedactingVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{:?}", value).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::Mutex;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::privacy::{DEFAULT_HASHED_FIELDS, DEFAULT_REDACTED_FIELDS};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn subscriber(policy: &Arc<RedactionPolicy>, buffer: &Buffer) -> impl Subscriber + Send + Sync {
+        let writer = buffer.clone();
+        tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .fmt_fields(RedactingFields::new(policy.clone()))
+                .event_format(RedactingJson::new(policy.clone()))
+                .with_writer(move || writer.clone()),
+        )
+    }
+
+    #[test]
+    fn test_configured_fields_are_masked_and_hashed() {
+        let policy = Arc::new(RedactionPolicy::new(DEFAULT_REDACTED_FIELDS, DEFAULT_HASHED_FIELDS, "test-salt"));
+        let buffer = Buffer::default();
+        let subscriber = subscriber(&policy, &buffer);
+
+        let player_id = "550e8400-e29b-41d4-a716-446655440000";
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("handle_purchase", player_id = %player_id);
+            let _entered = span.enter();
+            tracing::info!(
+                player_id = %player_id,
+                processor_id = "pi_123",
+                metadata = ?serde_json::json!({"email": "a@b.c"}),
+                item_id = "sword",
+                "Purchase settled"
+            );
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(!output.contains(player_id));
+        assert!(!output.contains("pi_123"));
+        assert!(!output.contains("a@b.c"));
+
+        let line: Value = serde_json::from_str(output.trim()).unwrap();
+        let hashed = policy.hash(player_id);
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["fields"]["message"], "Purchase settled");
+        assert_eq!(line["fields"]["player_id"], hashed.as_str());
+        assert_eq!(line["fields"]["metadata"], REDACTED);
+        assert_eq!(line["fields"]["item_id"], "sword");
+        assert_eq!(line["spans"][0]["name"], "handle_purchase");
+        assert_eq!(line["spans"][0]["player_id"], hashed.as_str());
+    }
+
+    /// Handlers take the raw path segment and record it as `player_id`
+    #[tracing::instrument(skip(player_id_str), fields(player_id = %player_id_str))]
+    fn get_wallet(player_id_str: &str, currency: &str) {
+        tracing::info!("Wallet read");
+    }
+
+    #[test]
+    fn test_instrumented_arguments_are_hashed() {
+        let policy = Arc::new(RedactionPolicy::new(DEFAULT_REDACTED_FIELDS, DEFAULT_HASHED_FIELDS, "test-salt"));
+        let buffer = Buffer::default();
+
+        let player_id = "550e8400-e29b-41d4-a716-446655440000";
+        tracing::subscriber::with_default(subscriber(&policy, &buffer), || get_wallet(player_id, "gems"));
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(!output.contains(player_id));
+        let line: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["spans"][0]["name"], "get_wallet");
+        assert_eq!(line["spans"][0]["player_id"], policy.hash(player_id).as_str());
+        assert_eq!(line["spans"][0]["currency"], "gems");
+        assert!(line["spans"][0].get("player_id_str").is_none());
+    }
+}
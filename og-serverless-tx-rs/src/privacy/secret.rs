@@ -0,0 +1,41 @@
+//! # Secret Values
+
+use std::fmt;
+
+/// Credential that never prints
+///
+/// `Debug` and `Display` both write `[REDACTED]`; the value is only reachable
+/// through `expose`, which makes every use of it easy to find.
+///
+/// ADVANTAGE: `#[derive(Debug)]` on a struct holding a `Secret` is safe to log
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// The wrapped value - do not log it
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(super::REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(super::REDACTED)
+    }
+}
@@ -0,0 +1,289 @@
+//! # Minimal SigV4 AWS Client
+//!
+//! Secrets Manager, SSM, SQS and EventBridge speak the AWS JSON protocol
+//! and SNS the form-encoded Query protocol. One small signed client covers
+//! all of them without an SDK crate per service.
+//!
+//! ADVANTAGE: Credentials and region come from the `aws-config` chain
+//! ADVANTAGE: Clients are created lazily - unused services cost nothing at init
+//! ADVANTAGE: Calls time out and retry throttling and 5xx responses, so a
+//! slow endpoint never holds the Lambda until its own timeout
+
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use aws_sigv4::http_request::{sign, SignableBody, SignableRequest, SigningSettings};
+use aws_sigv4::sign::v4;
+use serde_json::Value;
+use std::time::{Duration, SystemTime};
+use tokio::sync::OnceCell;
+use tracing::warn;
+
+use crate::errors::{AppError, AppResult};
+
+/// Content type of AWS JSON 1.0 services (SQS)
+pub(crate) const JSON_1_0: &str = "application/x-amz-json-1.0";
+
+/// Content type of AWS JSON 1.1 services (Secrets Manager, SSM, EventBridge)
+pub(crate) const JSON_1_1: &str = "application/x-amz-json-1.1";
+
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// Time allowed to open a connection to an endpoint
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Time allowed for one attempt, response body included
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Attempts per call before the last response or error is returned
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; doubles per attempt
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Error codes AWS sends with a 400 when a call is throttled
+const THROTTLING_CODES: [&str; 4] = [
+    "ThrottlingException",
+    "Throttling",
+    "TooManyRequestsException",
+    "RequestLimitExceeded",
+];
+
+/// Region and credentials used to sign requests
+struct AwsContext {
+    region: String,
+    credentials: SharedCredentialsProvider,
+}
+
+/// SigV4-signed client for one AWS service
+pub(crate) struct AwsClient {
+    /// SigV4 signing name and endpoint prefix
+    service: &'static str,
+    /// `X-Amz-Target` prefix for JSON calls
+    target_prefix: &'static str,
+    content_type: &'static str,
+    endpoint_url: Option<String>,
+    http: reqwest::Client,
+    context: OnceCell<AwsContext>,
+}
+
+impl AwsClient {
+    /// AWS JSON 1.1 client
+    pub(crate) fn new(service: &'static str, target_prefix: &'static str, endpoint_url: Option<String>) -> Self {
+        Self {
+            service,
+            target_prefix,
+            content_type: JSON_1_1,
+            endpoint_url,
+            http: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            context: OnceCell::new(),
+        }
+    }
+
+    /// Use another JSON protocol version's content type
+    pub(crate) fn with_content_type(mut self, content_type: &'static str) -> Self {
+        self.content_type = content_type;
+        self
+    }
+
+    async fn context(&self) -> AppResult<&AwsContext> {
+        self.context
+            .get_or_try_init(|| async {
+                let sdk_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+                let region = sdk_config.region()
+                    .ok_or_else(|| AppError::Configuration(format!("AWS region required for {}", self.service)))?
+                    .to_string();
+                let credentials = sdk_config.credentials_provider()
+                    .ok_or_else(|| AppError::Configuration(format!("AWS credentials required for {}", self.service)))?;
+                Ok(AwsContext { region, credentials })
+            })
+            .await
+    }
+
+    /// Call JSON `operation`, returning the HTTP status and JSON body
+    pub(crate) async fn call(&self, operation: &str, body: &Value) -> AppResult<(u16, Value)> {
+        let payload = serde_json::to_vec(body)?;
+        let target = format!("{}.{}", self.target_prefix, operation);
+
+        let (status, body) = self
+            .send(self.content_type, Some(&target), payload)
+            .await?;
+
+        Ok((status, serde_json::from_slice(&body).unwrap_or(Value::Null)))
+    }
+
+    /// Call Query-protocol `action` with form `params`, returning the HTTP
+    /// status and raw (XML) body
+    pub(crate) async fn call_query(
+        &self,
+        action: &str,
+        version: &str,
+        params: &[(&str, &str)],
+    ) -> AppResult<(u16, String)> {
+        let payload = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("Action", action)
+            .append_pair("Version", version)
+            .extend_pairs(params)
+            .finish()
+            .into_bytes();
+
+        let (status, body) = self.send(FORM_CONTENT_TYPE, None, payload).await?;
+
+        Ok((status, String::from_utf8_lossy(&body).into_owned()))
+    }
+
+    /// POST `payload`, retrying transport errors, throttling and 5xx
+    /// responses with exponential backoff
+    async fn send(
+        &self,
+        content_type: &str,
+        target: Option<&str>,
+        payload: Vec<u8>,
+    ) -> AppResult<(u16, Vec<u8>)> {
+        let mut attempt = 1;
+        loop {
+            let result = self.send_once(content_type, target, &payload).await;
+            let retryable = match &result {
+                Ok((status, body)) => is_retryable(*status, body),
+                Err(_) => true,
+            };
+            if !retryable || attempt >= MAX_ATTEMPTS {
+                return result;
+            }
+
+            let delay = RETRY_BACKOFF * 2u32.pow(attempt - 1);
+            warn!(service = self.service, attempt, retry_in = ?delay, "AWS call failed, retrying");
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Sign and POST `payload` to the service endpoint once
+    async fn send_once(
+        &self,
+        content_type: &str,
+        target: Option<&str>,
+        payload: &[u8],
+    ) -> AppResult<(u16, Vec<u8>)> {
+        let context = self.context().await?;
+        let url = match &self.endpoint_url {
+            Some(endpoint) => format!("{}/", endpoint.trim_end_matches('/')),
+            None => format!("https://{}.{}.amazonaws.com/", self.service, context.region),
+        };
+
+        let mut headers = vec![("content-type", content_type)];
+        if let Some(target) = target {
+            headers.push(("x-amz-target", target));
+        }
+
+        let credentials = context.credentials
+            .provide_credentials()
+            .await
+            .map_err(|e| AppError::Configuration(format!("AWS credentials unavailable: {}", e)))?;
+        let identity = credentials.into();
+
+        let signing_params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&context.region)
+            .name(self.service)
+            .time(SystemTime::now())
+            .settings(SigningSettings::default())
+            .build()
+            .map_err(|e| AppError::Internal(format!("{} signing parameters: {}", self.service, e)))?;
+
+        let signable = SignableRequest::new(
+            "POST",
+            &url,
+            headers.iter().copied(),
+            SignableBody::Bytes(payload),
+        )
+        .map_err(|e| AppError::Internal(format!("{} request: {}", self.service, e)))?;
+
+        let (instructions, _signature) = sign(signable, &signing_params.into())
+            .map_err(|e| AppError::Internal(format!("{} signing: {}", self.service, e)))?
+            .into_parts();
+
+        let mut request = self.http.post(&url);
+        for (name, value) in headers.iter().copied().chain(instructions.headers()) {
+            request = request.header(name, value);
+        }
+
+        let failed = |e: reqwest::Error| AppError::Configuration(format!("{} request failed: {}", self.service, e));
+        let response = request.body(payload.to_vec()).send().await.map_err(failed)?;
+        let status = response.status().as_u16();
+        let body = response.bytes().await.map_err(failed)?;
+        Ok((status, body.to_vec()))
+    }
+
+    /// Sign with fixed test credentials instead of the `aws-config` chain
+    #[cfg(test)]
+    pub(crate) fn with_test_context(self) -> Self {
+        use aws_credential_types::Credentials;
+
+        let _ = self.context.set(AwsContext {
+            region: "us-east-1".into(),
+            credentials: SharedCredentialsProvider::new(
+                Credentials::new("AKIDSTANDIN", "secret", None, None, "stand-in"),
+            ),
+        });
+        self
+    }
+}
+
+/// Whether a response is worth another attempt: 429, 5xx, or a 400
+/// carrying a throttling error code (JSON `__type` or Query `<Code>`)
+fn is_retryable(status: u16, body: &[u8]) -> bool {
+    match status {
+        429 | 500..=599 => true,
+        400 => match serde_json::from_slice::<Value>(body) {
+            Ok(json) => THROTTLING_CODES.contains(&error_type(&json)),
+            Err(_) => {
+                let body = String::from_utf8_lossy(body);
+                THROTTLING_CODES.iter().any(|code| body.contains(&format!("<Code>{}</Code>", code)))
+            }
+        },
+        _ => false,
+    }
+}
+
+/// Error type name from an AWS JSON error body
+pub(crate) fn error_type(body: &Value) -> &str {
+    body["__type"]
+        .as_str()
+        .map(|t| t.rsplit('#').next().unwrap_or(t))
+        .unwrap_or("UnknownError")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use crate::test_support::HttpStandIn;
+
+    #[tokio::test]
+    async fn test_throttling_and_server_errors_are_retried() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let stand_in = HttpStandIn::start(move |_| match counter.fetch_add(1, Ordering::SeqCst) {
+            0 => (400, json!({"__type": "com.amazonaws#ThrottlingException"})),
+            1 => (503, json!({})),
+            _ => (200, json!({"ok": true})),
+        })
+        .await;
+        let client = AwsClient::new("ssm", "AmazonSSM", Some(stand_in.endpoint.clone())).with_test_context();
+
+        let (status, body) = client.call("GetParameter", &json!({})).await.unwrap();
+        assert_eq!((status, body["ok"].as_bool()), (200, Some(true)));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Client errors are answers, not retried
+        let stand_in = HttpStandIn::start(|_| (400, json!({"__type": "ParameterNotFound"}))).await;
+        let client = AwsClient::new("ssm", "AmazonSSM", Some(stand_in.endpoint.clone())).with_test_context();
+        assert_eq!(client.call("GetParameter", &json!({})).await.unwrap().0, 400);
+        assert_eq!(stand_in.requests().len(), 1);
+    }
+}
@@ -0,0 +1,120 @@
+//! # Circuit Breaker
+//!
+//! After `failure_threshold` consecutive errors the breaker opens and calls
+//! fail fast for `cooldown`. The first call after the cooldown is a trial:
+//! success closes the breaker, failure re-opens it.
+//!
+//! ADVANTAGE: A processor outage costs one fast 503 per request, not a timeout
+//! ADVANTAGE: State is readable, so health checks can report it
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Consecutive errors before the breaker opens
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// How long an open breaker rejects calls
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Breaker position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    /// Cooldown elapsed - the next call is let through as a trial
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+/// Consecutive-failure circuit breaker
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(DEFAULT_FAILURE_THRESHOLD, DEFAULT_COOLDOWN)
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let inner = self.lock();
+        match inner.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Whether a call may proceed
+    pub fn allow(&self) -> bool {
+        self.state() != CircuitState::Open
+    }
+
+    pub fn record_success(&self) {
+        *self.lock() = Inner::default();
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_failures += 1;
+
+        // A failed trial restarts the cooldown
+        if inner.consecutive_failures >= self.failure_threshold || inner.opened_at.is_some() {
+            if inner.opened_at.is_none() {
+                warn!(failures = inner.consecutive_failures, "Circuit breaker opened");
+            }
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_and_recovers() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(20));
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow());
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(25));
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
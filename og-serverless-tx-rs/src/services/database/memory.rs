@@ -0,0 +1,903 @@
+//! # In-Memory Database Backend
+//!
+//! ADVANTAGE: Local development and tests need no PostgreSQL instance
+//! ADVANTAGE: Same `Database` trait - handlers cannot tell the difference
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::{Transaction, TransactionStatus, NewTransaction};
+use crate::models::{Entitlement, EntitlementStatus, JournalEntry, LedgerEntry, NewJournalEntry, NewLedgerEntry};
+use crate::models::{OutboxEvent, OutboxStatus, TransitionEffects, TrialBalance, WalletBalance};
+use crate::models::{CatalogItem, CatalogItemRequest, NewPromotion, PlayerProfile, Promotion, StockSettlement};
+use crate::models::{CodeCampaign, NewCodeCampaign};
+use crate::models::GiftStatus;
+use crate::models::{Subscription, SubscriptionPlan};
+use crate::models::gift::{self, check_gift_limits, GIFT_LIMIT_WINDOW};
+use crate::models::{NewWebhookSubscription, WebhookDelivery, WebhookDeliveryStatus, WebhookDispatch, WebhookSubscription};
+use super::{check_subscription_version, check_transition, live_subscription_conflict, Database, MigrationReport, MIGRATIONS};
+
+/// Transactions held in process memory
+///
+/// Data lives only as long as the process. Pagination mirrors the
+/// PostgreSQL backend: newest first, with `cursor` excluding IDs at or
+/// above it.
+#[derive(Default)]
+pub struct InMemoryDatabase {
+    transactions: RwLock<Vec<Transaction>>,
+    /// Locked after `transactions` and before `catalog`
+    codes: RwLock<Codes>,
+    /// Locked after `transactions` and before `entitlements`
+    catalog: RwLock<Catalog>,
+    promotions: RwLock<Vec<Promotion>>,
+    profiles: RwLock<HashMap<Uuid, PlayerProfile>>,
+    ledger: RwLock<Vec<LedgerEntry>>,
+    entitlements: RwLock<Vec<Entitlement>>,
+    outbox: RwLock<Vec<OutboxEvent>>,
+    /// Always locked before `webhook_deliveries` when both are needed
+    webhook_subscriptions: RwLock<Vec<WebhookSubscription>>,
+    webhook_deliveries: RwLock<Vec<WebhookDelivery>>,
+    journal: RwLock<Vec<JournalEntry>>,
+    /// Locked before `entitlements`
+    subscriptions: RwLock<Subscriptions>,
+}
+
+/// Subscription plans and subscribers
+#[derive(Default)]
+struct Subscriptions {
+    plans: Vec<SubscriptionPlan>,
+    subscriptions: Vec<Subscription>,
+}
+
+impl Subscriptions {
+    /// Whether another live subscription holds `subscription`'s plan
+    fn has_live_duplicate(&self, subscription: &Subscription) -> bool {
+        subscription.status.is_live() && self.subscriptions.iter().any(|s| {
+            s.subscription_id != subscription.subscription_id
+                && s.player_id == subscription.player_id
+                && s.plan_id == subscription.plan_id
+                && s.status.is_live()
+        })
+    }
+}
+
+/// Catalog entries and the stock reservations against them
+#[derive(Default)]
+struct Catalog {
+    items: Vec<CatalogItem>,
+    reservations: Vec<StockReservation>,
+}
+
+/// Code campaigns, their codes, redemptions and failed attempts
+#[derive(Default)]
+struct Codes {
+    campaigns: Vec<CodeCampaign>,
+    /// Normalized code to campaign ID
+    codes: HashMap<String, Uuid>,
+    redemptions: Vec<CodeRedemption>,
+    failures: Vec<(String, DateTime<Utc>)>,
+}
+
+struct CodeRedemption {
+    code: String,
+    player_id: Uuid,
+    transaction_id: Uuid,
+}
+
+struct StockReservation {
+    transaction_id: Uuid,
+    item_id: String,
+    quantity: i32,
+}
+
+impl InMemoryDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a status change's effects while the caller holds the transactions lock
+    ///
+    /// The wallet entry is the only effect that can fail, so it goes first:
+    /// on error nothing has been written and the caller keeps the old status.
+    async fn apply(&self, effects: TransitionEffects, transaction_id: Uuid) -> AppResult<()> {
+        let now = Utc::now();
+        if let Some(entry) = &effects.wallet {
+            self.append(entry).await?;
+        }
+        if let Some(settlement) = effects.stock {
+            let mut catalog = self.catalog.write().await;
+            let Catalog { items, reservations } = &mut *catalog;
+            if let Some(index) = reservations.iter().position(|r| r.transaction_id == transaction_id) {
+                let reservation = reservations.remove(index);
+                if let Some(item) = items.iter_mut().find(|i| i.item_id == reservation.item_id) {
+                    item.stock_reserved -= reservation.quantity;
+                    if settlement == StockSettlement::Commit {
+                        item.stock_sold += reservation.quantity;
+                    }
+                }
+            }
+        }
+        {
+            let mut entitlements = self.entitlements.write().await;
+            for grant in effects.grants {
+                if !entitlements.iter().any(|e| e.source_transaction_id == transaction_id && e.item_id == grant.item_id) {
+                    entitlements.push(grant.into_entitlement(now));
+                }
+            }
+            if effects.revoke {
+                for entitlement in entitlements
+                    .iter_mut()
+                    .filter(|e| e.source_transaction_id == transaction_id && e.status == EntitlementStatus::Active)
+                {
+                    entitlement.status = EntitlementStatus::Revoked;
+                    entitlement.revoked_at = Some(now);
+                }
+            }
+        }
+        if let Some(event) = effects.event {
+            self.outbox.write().await.push(event.into_event(now));
+        }
+        if let Some(fan_out) = effects.webhooks {
+            let subscriptions = self.webhook_subscriptions.read().await;
+            self.webhook_deliveries.write().await.extend(fan_out.deliveries(subscriptions.iter(), now));
+        }
+        if let Some(entry) = effects.journal {
+            self.journal(entry).await;
+        }
+        Ok(())
+    }
+
+    /// Append `entry` and its journal entry, or replay an earlier append
+    async fn append(&self, entry: &NewLedgerEntry) -> AppResult<LedgerEntry> {
+        // One write lock for check and append - concurrent debits serialize here
+        let mut ledger = self.ledger.write().await;
+
+        if let Some(existing) = ledger
+            .iter()
+            .find(|e| e.player_id == entry.player_id && e.kind == entry.kind && e.reference == entry.reference)
+        {
+            return entry.replay(existing.clone());
+        }
+
+        let (sequence, balance) = ledger
+            .iter()
+            .rev()
+            .find(|e| e.player_id == entry.player_id && e.currency == entry.currency)
+            .map(|e| (e.sequence, e.balance_after))
+            .unwrap_or((0, 0));
+        let (sequence, balance_after) = entry.position_after(sequence, balance)?;
+
+        let appended = LedgerEntry {
+            entry_id: entry.entry_id,
+            player_id: entry.player_id,
+            currency: entry.currency,
+            sequence,
+            amount: entry.amount,
+            balance_after,
+            kind: entry.kind,
+            reference: entry.reference.clone(),
+            item_id: entry.item_id.clone(),
+            request_id: entry.request_id.clone(),
+            created_at: Utc::now(),
+        };
+        let journal_entry = NewJournalEntry::for_ledger_entry(&appended)?;
+
+        ledger.push(appended.clone());
+        self.journal(journal_entry).await;
+        Ok(appended)
+    }
+
+    /// Update one outbox event, failing if it does not exist
+    async fn update_outbox(&self, event_id: Uuid, update: impl FnOnce(&mut OutboxEvent)) -> AppResult<()> {
+        let mut outbox = self.outbox.write().await;
+        let event = outbox
+            .iter_mut()
+            .find(|e| e.event_id == event_id)
+            .ok_or_else(|| AppError::NotFound(format!("Outbox event {} not found", event_id)))?;
+        update(event);
+        Ok(())
+    }
+
+    /// Update one webhook delivery, failing if it does not exist
+    async fn update_delivery(
+        &self,
+        delivery_id: Uuid,
+        update: impl FnOnce(&mut WebhookDelivery),
+    ) -> AppResult<WebhookDelivery> {
+        let mut deliveries = self.webhook_deliveries.write().await;
+        let delivery = deliveries
+            .iter_mut()
+            .find(|d| d.delivery_id == delivery_id)
+            .ok_or_else(|| AppError::NotFound(format!("Webhook delivery {} not found", delivery_id)))?;
+        update(delivery);
+        Ok(delivery.clone())
+    }
+
+    /// Record `entry` unless its kind and reference are already journaled
+    ///
+    /// Callers hold their own write lock, and the journal lock is always
+    /// taken last, so the two locks cannot deadlock.
+    async fn journal(&self, entry: NewJournalEntry) {
+        let mut journal = self.journal.write().await;
+        if !journal.iter().any(|e| e.kind == entry.kind && e.reference == entry.reference) {
+            journal.push(entry.into_entry(Utc::now()));
+        }
+    }
+}
+
+#[async_trait]
+impl Database for InMemoryDatabase {
+    async fn health_check(&self) -> AppResult<Duration> {
+        Ok(Duration::ZERO)
+    }
+
+    async fn insert_transaction(&self, tx: &NewTransaction) -> AppResult<Transaction> {
+        let mut transactions = self.transactions.write().await;
+
+        if transactions.iter().any(|t| t.transaction_id == tx.transaction_id) {
+            return Err(AppError::Conflict(format!(
+                "Transaction {} already exists",
+                tx.transaction_id
+            )));
+        }
+
+        // Checked before the catalog so a refused gift or code reserves no stock
+        if let Some(recipient_id) = tx.recipient_id {
+            let since = Utc::now() - GIFT_LIMIT_WINDOW;
+            let gifts = || transactions
+                .iter()
+                .filter(|t| t.recipient_id.is_some() && t.status != TransactionStatus::Failed && t.created_at > since);
+            check_gift_limits(
+                gifts().filter(|t| t.player_id == tx.player_id).count() as i64,
+                gifts().filter(|t| t.recipient_id == Some(recipient_id)).count() as i64,
+            )?;
+        }
+
+        let mut codes = self.codes.write().await;
+        if let Some(code) = &tx.code {
+            let campaign_id = codes.codes.get(code).ok_or_else(|| AppError::NotFound("Code not found".into()))?;
+            let campaign = codes
+                .campaigns
+                .iter()
+                .find(|c| c.campaign_id == *campaign_id)
+                .ok_or_else(|| AppError::Internal("Code campaign missing".into()))?;
+            let counted: Vec<&CodeRedemption> = codes
+                .redemptions
+                .iter()
+                .filter(|r| r.code == *code)
+                .filter(|r| {
+                    transactions
+                        .iter()
+                        .any(|t| t.transaction_id == r.transaction_id && t.status != TransactionStatus::Failed)
+                })
+                .collect();
+            let player_uses = counted.iter().filter(|r| r.player_id == tx.player_id).count();
+            campaign.check_redemption(counted.len() as i64, player_uses as i64, Utc::now())?;
+        }
+
+        let mut catalog = self.catalog.write().await;
+        let Catalog { items, reservations } = &mut *catalog;
+        let mut metadata = tx.metadata.clone();
+        let owner_id = tx.recipient_id.unwrap_or(tx.player_id);
+        if let Some(item) = items.iter_mut().find(|i| i.item_id == tx.item_id) {
+            let owned = item.kind.is_unique() && (
+                transactions.iter().any(|t| {
+                    t.owner_id() == owner_id && t.item_id == tx.item_id && t.status == TransactionStatus::Pending
+                }) || self.entitlements.read().await.iter().any(|e| {
+                    e.player_id == owner_id
+                        && e.item_id == tx.item_id
+                        && e.status == EntitlementStatus::Active
+                        && e.expires_at.is_none()
+                })
+            );
+            let reserve = item.check_purchase(tx.quantity, owned)?;
+            if reserve > 0 {
+                item.stock_reserved += reserve;
+                reservations.push(StockReservation {
+                    transaction_id: tx.transaction_id,
+                    item_id: item.item_id.clone(),
+                    quantity: reserve,
+                });
+            }
+            metadata = item.stamp(&metadata);
+        }
+
+        let now = Utc::now();
+        let transaction = Transaction {
+            transaction_id: tx.transaction_id,
+            player_id: tx.player_id,
+            item_id: tx.item_id.clone(),
+            item_name: tx.item_name.clone(),
+            price_cents: tx.price_cents,
+            currency: tx.currency.clone(),
+            quantity: tx.quantity,
+            status: TransactionStatus::Pending,
+            metadata,
+            processor_id: None,
+            request_id: tx.request_id.clone(),
+            created_at: now,
+            updated_at: now,
+            original_price_cents: tx.original_price_cents,
+            applied_promotions: tx.applied_promotions.clone(),
+            recipient_id: tx.recipient_id,
+            gift_status: tx.recipient_id.map(|_| GiftStatus::Pending),
+        };
+
+        if let Some(code) = &tx.code {
+            codes.redemptions.push(CodeRedemption {
+                code: code.clone(),
+                player_id: tx.player_id,
+                transaction_id: tx.transaction_id,
+            });
+        }
+        transactions.push(transaction.clone());
+        Ok(transaction)
+    }
+
+    async fn update_transaction_status(
+        &self,
+        transaction_id: Uuid,
+        status: TransactionStatus,
+        processor_id: Option<&str>,
+    ) -> AppResult<Transaction> {
+        let mut transactions = self.transactions.write().await;
+
+        let transaction = transactions
+            .iter_mut()
+            .find(|t| t.transaction_id == transaction_id)
+            .ok_or_else(|| AppError::NotFound(format!("Transaction {} not found", transaction_id)))?;
+        check_transition(transaction, status)?;
+
+        let previous = transaction.status;
+        let mut updated = transaction.clone();
+        updated.status = status;
+        updated.gift_status = gift::status_after(transaction, status);
+        updated.processor_id = processor_id.map(str::to_string);
+        updated.updated_at = Utc::now();
+
+        self.apply(TransitionEffects::of(&updated, previous)?, transaction_id).await?;
+
+        *transaction = updated.clone();
+        Ok(updated)
+    }
+
+    async fn accept_gift(&self, transaction_id: Uuid, recipient_id: Uuid) -> AppResult<Transaction> {
+        let mut transactions = self.transactions.write().await;
+
+        let gift = transactions
+            .iter_mut()
+            .find(|t| t.transaction_id == transaction_id)
+            .ok_or_else(|| AppError::NotFound(format!("Gift {} not found", transaction_id)))?;
+        if let Some(status) = gift::answer(gift, recipient_id, true)? {
+            gift.gift_status = Some(status);
+            gift.updated_at = Utc::now();
+            self.apply(TransitionEffects::of_gift_accepted(gift), transaction_id).await?;
+        }
+
+        Ok(gift.clone())
+    }
+
+    async fn get_transaction(&self, transaction_id: Uuid) -> AppResult<Option<Transaction>> {
+        Ok(self.transactions
+            .read()
+            .await
+            .iter()
+            .find(|t| t.transaction_id == transaction_id)
+            .cloned())
+    }
+
+    async fn get_stale_pending_transactions(&self, before: DateTime<Utc>, limit: i32) -> AppResult<Vec<Transaction>> {
+        // Insertion order is creation order
+        Ok(self.transactions
+            .read()
+            .await
+            .iter()
+            .filter(|t| t.status == TransactionStatus::Pending && t.created_at < before)
+            .take(limit.clamp(1, 1000) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_unanswered_gifts(&self, before: DateTime<Utc>, limit: i32) -> AppResult<Vec<Transaction>> {
+        Ok(self.transactions
+            .read()
+            .await
+            .iter()
+            .filter(|t| t.status == TransactionStatus::Completed && t.gift_status == Some(GiftStatus::Pending))
+            .filter(|t| t.created_at < before)
+            .take(limit.clamp(1, 1000) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_player_transactions(
+        &self,
+        player_id: Uuid,
+        limit: i32,
+        cursor: Option<Uuid>,
+    ) -> AppResult<Vec<Transaction>> {
+        let safe_limit = limit.clamp(1, 1000) as usize;
+
+        // Insertion order is creation order, so reversing gives newest first
+        Ok(self.transactions
+            .read()
+            .await
+            .iter()
+            .rev()
+            .filter(|t| t.player_id == player_id || t.recipient_id == Some(player_id))
+            .filter(|t| cursor.is_none_or(|c| t.transaction_id < c))
+            .take(safe_limit)
+            .cloned()
+            .collect())
+    }
+
+    /// Nothing to migrate - there is no schema
+    async fn run_migrations(&self, dry_run: bool) -> AppResult<MigrationReport> {
+        Ok(MigrationReport {
+            dry_run,
+            current_version: None,
+            applied: Vec::new(),
+            pending: Vec::new(),
+        })
+    }
+
+    async fn append_ledger_entry(&self, entry: &NewLedgerEntry) -> AppResult<LedgerEntry> {
+        self.append(entry).await
+    }
+
+    async fn get_wallet(&self, player_id: Uuid) -> AppResult<Vec<WalletBalance>> {
+        let ledger = self.ledger.read().await;
+        let mut balances: Vec<WalletBalance> = Vec::new();
+
+        for entry in ledger.iter().filter(|e| e.player_id == player_id) {
+            match balances.iter_mut().find(|b| b.currency == entry.currency) {
+                Some(balance) => balance.balance = entry.balance_after,
+                None => balances.push(WalletBalance { currency: entry.currency, balance: entry.balance_after }),
+            }
+        }
+
+        balances.sort_by_key(|b| b.currency);
+        Ok(balances)
+    }
+
+    async fn get_player_journal(&self, player_id: Uuid, limit: i32) -> AppResult<Vec<JournalEntry>> {
+        let safe_limit = limit.clamp(1, 1000) as usize;
+
+        Ok(self.journal
+            .read()
+            .await
+            .iter()
+            .rev()
+            .filter(|e| e.player_id == Some(player_id))
+            .take(safe_limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn trial_balance(&self) -> AppResult<TrialBalance> {
+        let journal = self.journal.read().await;
+        Ok(TrialBalance::from_postings(journal.iter().flat_map(|e| &e.postings)))
+    }
+
+    async fn get_player_entitlements(&self, player_id: Uuid, include_revoked: bool) -> AppResult<Vec<Entitlement>> {
+        let now = Utc::now();
+        Ok(self.entitlements
+            .read()
+            .await
+            .iter()
+            .rev()
+            .filter(|e| e.player_id == player_id)
+            .filter(|e| include_revoked || e.is_current(now))
+            .cloned()
+            .collect())
+    }
+
+    async fn claim_outbox_events(&self, limit: i32, lease: Duration) -> AppResult<Vec<OutboxEvent>> {
+        let now = Utc::now();
+        let lease_until = now + chrono::Duration::from_std(lease).unwrap_or(chrono::Duration::MAX);
+        let mut outbox = self.outbox.write().await;
+
+        // Insertion order is creation order, so the first due events are the oldest
+        Ok(outbox
+            .iter_mut()
+            .filter(|e| e.status == OutboxStatus::Pending && e.next_attempt_at <= now)
+            .take(limit.clamp(1, 1000) as usize)
+            .map(|event| {
+                event.attempts += 1;
+                event.next_attempt_at = lease_until;
+                event.clone()
+            })
+            .collect())
+    }
+
+    async fn mark_outbox_delivered(&self, event_id: Uuid) -> AppResult<()> {
+        self.update_outbox(event_id, |event| {
+            event.status = OutboxStatus::Delivered;
+            event.delivered_at = Some(Utc::now());
+        })
+        .await
+    }
+
+    async fn mark_outbox_failed(&self, event_id: Uuid, error: &str, retry_in: Option<Duration>) -> AppResult<()> {
+        self.update_outbox(event_id, |event| {
+            event.last_error = Some(error.to_string());
+            match retry_in.and_then(|delay| chrono::Duration::from_std(delay).ok()) {
+                Some(delay) => event.next_attempt_at = Utc::now() + delay,
+                None => event.status = OutboxStatus::Dead,
+            }
+        })
+        .await
+    }
+
+    async fn create_webhook_subscription(&self, subscription: &NewWebhookSubscription) -> AppResult<WebhookSubscription> {
+        let stored = subscription.clone().into_subscription(Utc::now());
+        self.webhook_subscriptions.write().await.push(stored.clone());
+        Ok(stored)
+    }
+
+    async fn get_webhook_subscriptions(&self, title_id: &str) -> AppResult<Vec<WebhookSubscription>> {
+        Ok(self.webhook_subscriptions
+            .read()
+            .await
+            .iter()
+            .filter(|s| s.title_id == title_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_webhook_subscription(&self, subscription_id: Uuid) -> AppResult<Option<WebhookSubscription>> {
+        Ok(self.webhook_subscriptions
+            .read()
+            .await
+            .iter()
+            .find(|s| s.subscription_id == subscription_id)
+            .cloned())
+    }
+
+    async fn get_webhook_deliveries(&self, subscription_id: Uuid, limit: i32) -> AppResult<Vec<WebhookDelivery>> {
+        Ok(self.webhook_deliveries
+            .read()
+            .await
+            .iter()
+            .rev()
+            .filter(|d| d.subscription_id == subscription_id)
+            .take(limit.clamp(1, 1000) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn claim_webhook_deliveries(&self, limit: i32, lease: Duration) -> AppResult<Vec<WebhookDispatch>> {
+        let now = Utc::now();
+        let lease_until = now + chrono::Duration::from_std(lease).unwrap_or(chrono::Duration::MAX);
+        let subscriptions = self.webhook_subscriptions.read().await;
+        let mut deliveries = self.webhook_deliveries.write().await;
+
+        // Redelivered rows keep their place, so order by due time like Postgres
+        let mut due: Vec<&mut WebhookDelivery> = deliveries
+            .iter_mut()
+            .filter(|d| d.status == WebhookDeliveryStatus::Pending && d.next_attempt_at <= now)
+            .collect();
+        due.sort_by_key(|d| (d.next_attempt_at, d.created_at));
+
+        Ok(due
+            .into_iter()
+            .take(limit.clamp(1, 1000) as usize)
+            .filter_map(|delivery| {
+                let subscription = subscriptions.iter().find(|s| s.subscription_id == delivery.subscription_id)?;
+                delivery.attempts += 1;
+                delivery.next_attempt_at = lease_until;
+                Some(WebhookDispatch {
+                    delivery: delivery.clone(),
+                    url: subscription.url.clone(),
+                    secret: subscription.secret.clone(),
+                })
+            })
+            .collect())
+    }
+
+    async fn mark_webhook_delivered(&self, delivery_id: Uuid, status_code: i32) -> AppResult<()> {
+        self.update_delivery(delivery_id, |delivery| {
+            delivery.status = WebhookDeliveryStatus::Delivered;
+            delivery.last_status_code = Some(status_code);
+            delivery.last_error = None;
+            delivery.delivered_at = Some(Utc::now());
+        })
+        .await
+        .map(|_| ())
+    }
+
+    async fn mark_webhook_failed(
+        &self,
+        delivery_id: Uuid,
+        status_code: Option<i32>,
+        error: &str,
+        retry_in: Option<Duration>,
+    ) -> AppResult<()> {
+        self.update_delivery(delivery_id, |delivery| {
+            delivery.last_status_code = status_code;
+            delivery.last_error = Some(error.to_string());
+            match retry_in.and_then(|delay| chrono::Duration::from_std(delay).ok()) {
+                Some(delay) => delivery.next_attempt_at = Utc::now() + delay,
+                None => delivery.status = WebhookDeliveryStatus::Dead,
+            }
+        })
+        .await
+        .map(|_| ())
+    }
+
+    async fn create_promotion(&self, promotion: &NewPromotion) -> AppResult<Promotion> {
+        let mut promotions = self.promotions.write().await;
+        if promotions.iter().any(|p| p.promotion_id == promotion.promotion_id) {
+            return Err(AppError::Conflict(format!("Promotion {} already exists", promotion.promotion_id)));
+        }
+
+        let promotion = promotion.clone().into_promotion(Utc::now());
+        promotions.push(promotion.clone());
+        Ok(promotion)
+    }
+
+    async fn get_promotions(&self, limit: i32) -> AppResult<Vec<Promotion>> {
+        let mut promotions = self.promotions.read().await.clone();
+        promotions.sort_by_key(|p| std::cmp::Reverse(p.starts_at));
+        promotions.truncate(limit.clamp(1, 1000) as usize);
+        Ok(promotions)
+    }
+
+    async fn get_active_promotions(&self, item_id: &str, at: DateTime<Utc>) -> AppResult<Vec<Promotion>> {
+        let mut promotions: Vec<Promotion> = self
+            .promotions
+            .read()
+            .await
+            .iter()
+            .filter(|p| p.starts_at <= at && at < p.ends_at)
+            .filter(|p| p.item_ids.is_empty() || p.item_ids.iter().any(|i| i == item_id))
+            .cloned()
+            .collect();
+        promotions.sort_by_key(|p| std::cmp::Reverse(p.priority));
+        Ok(promotions)
+    }
+
+    async fn put_player_profile(&self, player_id: Uuid, segments: &[String]) -> AppResult<PlayerProfile> {
+        let profile = PlayerProfile { player_id, segments: segments.to_vec(), updated_at: Utc::now() };
+        self.profiles.write().await.insert(player_id, profile.clone());
+        Ok(profile)
+    }
+
+    async fn get_player_profile(&self, player_id: Uuid) -> AppResult<Option<PlayerProfile>> {
+        Ok(self.profiles.read().await.get(&player_id).cloned())
+    }
+
+    async fn create_code_campaign(&self, campaign: &NewCodeCampaign) -> AppResult<CodeCampaign> {
+        let mut codes = self.codes.write().await;
+        if campaign.codes.iter().any(|code| codes.codes.contains_key(code)) {
+            return Err(AppError::Conflict("Code already exists".into()));
+        }
+
+        let created = campaign.clone().into_campaign(Utc::now());
+        codes.codes.extend(campaign.codes.iter().map(|code| (code.clone(), created.campaign_id)));
+        codes.campaigns.push(created.clone());
+        Ok(created)
+    }
+
+    async fn get_code_campaign(&self, code: &str) -> AppResult<Option<CodeCampaign>> {
+        let codes = self.codes.read().await;
+        Ok(codes
+            .codes
+            .get(code)
+            .and_then(|campaign_id| codes.campaigns.iter().find(|c| c.campaign_id == *campaign_id))
+            .cloned())
+    }
+
+    async fn record_code_failure(&self, subject: &str) -> AppResult<()> {
+        self.codes.write().await.failures.push((subject.to_string(), Utc::now()));
+        Ok(())
+    }
+
+    async fn count_code_failures(&self, subject: &str, since: DateTime<Utc>) -> AppResult<i64> {
+        Ok(self
+            .codes
+            .read()
+            .await
+            .failures
+            .iter()
+            .filter(|(s, failed_at)| s == subject && *failed_at > since)
+            .count() as i64)
+    }
+
+    async fn upsert_subscription_plan(&self, plan: &SubscriptionPlan) -> AppResult<SubscriptionPlan> {
+        let mut subscriptions = self.subscriptions.write().await;
+        subscriptions.plans.retain(|p| p.plan_id != plan.plan_id);
+        subscriptions.plans.push(plan.clone());
+        Ok(plan.clone())
+    }
+
+    async fn get_subscription_plan(&self, plan_id: &str) -> AppResult<Option<SubscriptionPlan>> {
+        Ok(self.subscriptions.read().await.plans.iter().find(|p| p.plan_id == plan_id).cloned())
+    }
+
+    async fn insert_subscription(&self, subscription: &Subscription) -> AppResult<Subscription> {
+        let mut subscriptions = self.subscriptions.write().await;
+        if subscriptions.has_live_duplicate(subscription) {
+            return Err(live_subscription_conflict(&subscription.plan_id));
+        }
+        subscriptions.subscriptions.push(subscription.clone());
+        Ok(subscription.clone())
+    }
+
+    async fn get_subscription(&self, subscription_id: Uuid) -> AppResult<Option<Subscription>> {
+        Ok(self.subscriptions
+            .read()
+            .await
+            .subscriptions
+            .iter()
+            .find(|s| s.subscription_id == subscription_id)
+            .cloned())
+    }
+
+    async fn get_player_subscriptions(&self, player_id: Uuid) -> AppResult<Vec<Subscription>> {
+        Ok(self.subscriptions
+            .read()
+            .await
+            .subscriptions
+            .iter()
+            .rev()
+            .filter(|s| s.player_id == player_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_due_subscriptions(&self, now: DateTime<Utc>, limit: i32) -> AppResult<Vec<Subscription>> {
+        let mut due: Vec<Subscription> = self.subscriptions
+            .read()
+            .await
+            .subscriptions
+            .iter()
+            .filter(|s| s.status.is_live() && s.next_billing_at.is_some_and(|at| at <= now))
+            .cloned()
+            .collect();
+        due.sort_by_key(|s| s.next_billing_at);
+        due.truncate(limit.max(0) as usize);
+        Ok(due)
+    }
+
+    async fn update_subscription(&self, subscription: &Subscription) -> AppResult<Subscription> {
+        let now = Utc::now();
+        let mut subscriptions = self.subscriptions.write().await;
+        let index = subscriptions
+            .subscriptions
+            .iter()
+            .position(|s| s.subscription_id == subscription.subscription_id)
+            .ok_or_else(|| AppError::NotFound(format!("Subscription {} not found", subscription.subscription_id)))?;
+        let stored = subscriptions.subscriptions[index].clone();
+        check_subscription_version(subscription, &stored)?;
+        if subscriptions.has_live_duplicate(subscription) {
+            return Err(live_subscription_conflict(&subscription.plan_id));
+        }
+
+        let saved = Subscription {
+            version: stored.version + 1,
+            updated_at: now,
+            ..subscription.clone()
+        };
+        subscriptions.subscriptions[index] = saved.clone();
+
+        let mut entitlements = self.entitlements.write().await;
+        for (transaction_id, expires_at) in saved.access_changes(&stored, now) {
+            for entitlement in entitlements.iter_mut().filter(|e| e.source_transaction_id == transaction_id) {
+                entitlement.expires_at = Some(expires_at);
+            }
+        }
+        Ok(saved)
+    }
+
+    async fn redeliver_webhook(&self, delivery_id: Uuid) -> AppResult<WebhookDelivery> {
+        self.update_delivery(delivery_id, |delivery| {
+            delivery.status = WebhookDeliveryStatus::Pending;
+            delivery.attempts = 0;
+            delivery.next_attempt_at = Utc::now();
+            delivery.delivered_at = None;
+        })
+        .await
+    }
+
+    async fn upsert_catalog_item(&self, item_id: &str, request: &CatalogItemRequest) -> AppResult<CatalogItem> {
+        let mut catalog = self.catalog.write().await;
+        let now = Utc::now();
+
+        match catalog.items.iter_mut().find(|i| i.item_id == item_id) {
+            Some(item) => {
+                if request.stock_limit.is_some_and(|limit| limit < item.stock_reserved + item.stock_sold) {
+                    return Err(AppError::Conflict(format!(
+                        "Item {} already has {} units reserved or sold",
+                        item_id,
+                        item.stock_reserved + item.stock_sold
+                    )));
+                }
+                item.kind = request.kind;
+                item.stock_limit = request.stock_limit;
+                item.price_cents = request.price_cents;
+                item.currency = request.currency.map(|c| c.as_str().to_string());
+                item.credit_currency = request.credit_currency;
+                item.credit_amount = request.credit_amount;
+                item.wallet_price = request.wallet_price;
+                item.wallet_currency = request.wallet_currency;
+                item.title_id = request.title_id.clone();
+                item.updated_at = now;
+                Ok(item.clone())
+            }
+            None => {
+                let item = CatalogItem {
+                    item_id: item_id.to_string(),
+                    kind: request.kind,
+                    stock_limit: request.stock_limit,
+                    stock_reserved: 0,
+                    stock_sold: 0,
+                    price_cents: request.price_cents,
+                    currency: request.currency.map(|c| c.as_str().to_string()),
+                    credit_currency: request.credit_currency,
+                    credit_amount: request.credit_amount,
+                    wallet_price: request.wallet_price,
+                    wallet_currency: request.wallet_currency,
+                    title_id: request.title_id.clone(),
+                    updated_at: now,
+                };
+                catalog.items.push(item.clone());
+                Ok(item)
+            }
+        }
+    }
+
+    async fn get_catalog_item(&self, item_id: &str) -> AppResult<Option<CatalogItem>> {
+        Ok(self.catalog.read().await.items.iter().find(|i| i.item_id == item_id).cloned())
+    }
+
+    /// Process memory has no schema - it always matches this build
+    async fn schema_version(&self) -> AppResult<Option<i64>> {
+        Ok(MIGRATIONS.last().map(|m| m.version))
+    }
+
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_insert_update_and_list() {
+        let db = InMemoryDatabase::new();
+        let player_id = Uuid::new_v4();
+
+        let first = db.insert_transaction(&NewTransaction::new(
+            player_id, "sword".into(), "Sword".into(), 999, "USD".into(), 1, json!({}),
+        )).await.unwrap();
+        let second = db.insert_transaction(&NewTransaction::new(
+            player_id, "shield".into(), "Shield".into(), 499, "USD".into(), 1, json!({}),
+        )).await.unwrap();
+        assert_eq!(first.status, TransactionStatus::Pending);
+
+        let updated = db
+            .update_transaction_status(first.transaction_id, TransactionStatus::Completed, Some("pi_123"))
+            .await
+            .unwrap();
+        assert_eq!(updated.processor_id.as_deref(), Some("pi_123"));
+
+        let listed = db.get_player_transactions(player_id, 10, None).await.unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].transaction_id, second.transaction_id);
+
+        assert!(db.get_player_transactions(Uuid::new_v4(), 10, None).await.unwrap().is_empty());
+        assert!(matches!(
+            db.update_transaction_status(Uuid::new_v4(), TransactionStatus::Failed, None).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
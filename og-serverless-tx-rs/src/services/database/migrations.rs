@@ -0,0 +1,431 @@
+//! # Embedded Schema Migrations
+//!
+//! ADVANTAGE: SQL is compiled into the binary - no files to ship or forget
+//! ADVANTAGE: Applied versions and checksums are tracked in `schema_migrations`
+//! ADVANTAGE: A Postgres advisory lock serializes concurrent cold starts
+//! ADVANTAGE: Edited migrations are detected by checksum, not silently skipped
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::pool::PoolConnection;
+use sqlx::{Acquire, Executor, PgConnection, PgPool, Postgres, Row};
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::errors::{AppError, AppResult};
+
+/// Advisory lock key shared by every migrator ("mmogmigr" as ASCII)
+pub(crate) const MIGRATION_LOCK_KEY: i64 = 0x6d6d_6f67_6d69_6772;
+
+/// A versioned schema change
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+impl Migration {
+    /// SHA-256 of the migration SQL, hex encoded
+    pub fn checksum(&self) -> String {
+        hex::encode(Sha256::digest(self.sql.as_bytes()))
+    }
+}
+
+/// Every migration, in version order
+///
+/// ADVANTAGE: `include_str!` fails the build if a migration file goes missing
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_transactions",
+        sql: include_str!("../../../migrations/001_create_transactions.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "add_request_id",
+        sql: include_str!("../../../migrations/002_add_request_id.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "create_wallet_ledger",
+        sql: include_str!("../../../migrations/003_create_wallet_ledger.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "create_journal",
+        sql: include_str!("../../../migrations/004_create_journal.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "create_entitlements",
+        sql: include_str!("../../../migrations/005_create_entitlements.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "create_outbox",
+        sql: include_str!("../../../migrations/006_create_outbox.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "create_webhooks",
+        sql: include_str!("../../../migrations/007_create_webhooks.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "create_catalog",
+        sql: include_str!("../../../migrations/008_create_catalog.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "create_promotions",
+        sql: include_str!("../../../migrations/009_create_promotions.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "create_codes",
+        sql: include_str!("../../../migrations/010_create_codes.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "add_gifting",
+        sql: include_str!("../../../migrations/011_add_gifting.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "create_subscriptions",
+        sql: include_str!("../../../migrations/012_create_subscriptions.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "add_catalog_prices",
+        sql: include_str!("../../../migrations/013_add_catalog_prices.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "add_wallet_pricing",
+        sql: include_str!("../../../migrations/014_add_wallet_pricing.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "add_catalog_titles",
+        sql: include_str!("../../../migrations/015_add_catalog_titles.sql"),
+    },
+    Migration {
+        version: 16,
+        name: "create_player_profiles",
+        sql: include_str!("../../../migrations/016_create_player_profiles.sql"),
+    },
+];
+
+/// Row of `schema_migrations`
+#[derive(Debug, Clone)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub checksum: String,
+}
+
+/// Migration not yet applied
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingMigration {
+    pub version: i64,
+    pub name: &'static str,
+    pub checksum: String,
+    /// SQL to run - only populated for dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sql: Option<&'static str>,
+}
+
+/// Outcome of a migration run
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    pub dry_run: bool,
+    pub current_version: Option<i64>,
+    pub applied: Vec<i64>,
+    pub pending: Vec<PendingMigration>,
+}
+
+/// Determine which migrations still need to run
+///
+/// Fails if an applied migration's checksum no longer matches the embedded
+/// SQL. Applied versions unknown to this binary (a newer deploy ran them)
+/// are logged and ignored.
+pub fn plan<'a>(migrations: &'a [Migration], applied: &[AppliedMigration]) -> AppResult<Vec<&'a Migration>> {
+    for row in applied {
+        match migrations.iter().find(|m| m.version == row.version) {
+            Some(migration) if migration.checksum() != row.checksum => {
+                return Err(AppError::Configuration(format!(
+                    "Migration {} ({}) was modified after being applied",
+                    migration.version, migration.name
+                )));
+            }
+            Some(_) => {}
+            None => warn!(version = row.version, "Database has a migration unknown to this build"),
+        }
+    }
+
+    Ok(migrations
+        .iter()
+        .filter(|m| !applied.iter().any(|row| row.version == m.version))
+        .collect())
+}
+
+/// DDL for the version table, run before the migration lock is taken
+pub(crate) const CREATE_SCHEMA_MIGRATIONS: &str = r#"
+    CREATE TABLE IF NOT EXISTS schema_migrations (
+        version BIGINT PRIMARY KEY,
+        name TEXT NOT NULL,
+        checksum CHAR(64) NOT NULL,
+        execution_ms BIGINT NOT NULL,
+        applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    )
+"#;
+
+/// Split migration SQL into single statements
+///
+/// Needed where only one statement may be sent per call (the Data API).
+/// Semicolons inside quoted strings, quoted identifiers, `$tag$` bodies and
+/// comments do not end a statement; empty statements are dropped.
+pub fn split_statements(sql: &str) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+            }
+            quote @ (b'\'' | b'"') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'$' => {
+                // `$tag$` opens a dollar-quoted body closed by the same tag
+                let tag_len = sql[i + 1..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .filter(|&len| sql[i + 1 + len..].starts_with('$'));
+                match tag_len {
+                    Some(len) => {
+                        let tag = &sql[i..i + len + 2];
+                        let body = i + tag.len();
+                        i = sql[body..].find(tag).map_or(bytes.len(), |end| body + end + tag.len());
+                    }
+                    None => i += 1,
+                }
+            }
+            b';' => {
+                statements.push(&sql[start..i]);
+                i += 1;
+                start = i;
+            }
+            _ => i += 1,
+        }
+    }
+    statements.push(&sql[start..]);
+
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|statement| !statement.lines().all(|line| line.trim().is_empty() || line.trim().starts_with("--")))
+        .collect()
+}
+
+/// Summarise a run for the CLI and the migrate function
+pub(crate) fn report(
+    dry_run: bool,
+    previously_applied: &[AppliedMigration],
+    pending: &[&Migration],
+    newly_applied: Vec<i64>,
+) -> MigrationReport {
+    let current_version = previously_applied
+        .iter()
+        .map(|row| row.version)
+        .chain(newly_applied.iter().copied())
+        .max();
+
+    MigrationReport {
+        dry_run,
+        current_version,
+        applied: newly_applied,
+        pending: pending
+            .iter()
+            .map(|m| PendingMigration {
+                version: m.version,
+                name: m.name,
+                checksum: m.checksum(),
+                sql: dry_run.then_some(m.sql),
+            })
+            .collect(),
+    }
+}
+
+/// Applies embedded migrations to a PostgreSQL pool
+pub struct Migrator {
+    migrations: &'static [Migration],
+}
+
+impl Migrator {
+    pub fn new(migrations: &'static [Migration]) -> Self {
+        Self { migrations }
+    }
+
+    /// Apply pending migrations, or only report them when `dry_run`
+    ///
+    /// Each migration runs in its own transaction together with its
+    /// `schema_migrations` row, under a session advisory lock held for the
+    /// whole run so concurrent callers wait and then find nothing pending.
+    pub async fn run(&self, pool: &PgPool, dry_run: bool) -> AppResult<MigrationReport> {
+        if dry_run {
+            let applied = self.applied_versions(pool).await?;
+            let pending = plan(self.migrations, &applied)?;
+            return Ok(report(true, &applied, &pending, Vec::new()));
+        }
+
+        let mut conn = pool.acquire().await?;
+
+        sqlx::query("SELECT pg_advisory_lock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *conn)
+            .await?;
+
+        let result = self.apply_locked(&mut conn).await;
+
+        if let Err(e) = sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *conn)
+            .await
+        {
+            warn!(error = %e, "Failed to release migration lock");
+        }
+
+        result
+    }
+
+    async fn apply_locked(&self, conn: &mut PoolConnection<Postgres>) -> AppResult<MigrationReport> {
+        sqlx::query(CREATE_SCHEMA_MIGRATIONS)
+            .execute(&mut **conn)
+            .await?;
+
+        // Read under the lock - another cold start may have just finished
+        let applied = fetch_applied(conn).await?;
+        let pending = plan(self.migrations, &applied)?;
+        let mut newly_applied = Vec::with_capacity(pending.len());
+
+        for migration in &pending {
+            let start = Instant::now();
+            let mut tx = conn.begin().await?;
+
+            // Plain `&str` runs unprepared, so multi-statement files (DO blocks,
+            // functions) execute as written
+            tx.execute(migration.sql).await?;
+
+            sqlx::query(
+                "INSERT INTO schema_migrations (version, name, checksum, execution_ms) VALUES ($1, $2, $3, $4)"
+            )
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(migration.checksum())
+            .bind(start.elapsed().as_millis() as i64)
+            .execute(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+
+            info!(version = migration.version, name = migration.name, "Migration applied");
+            newly_applied.push(migration.version);
+        }
+
+        Ok(report(false, &applied, &[], newly_applied))
+    }
+
+    /// Highest applied version, `None` if nothing has been applied
+    pub async fn current_version(&self, pool: &PgPool) -> AppResult<Option<i64>> {
+        Ok(self.applied_versions(pool).await?.iter().map(|row| row.version).max())
+    }
+
+    /// Applied migrations, empty if the version table does not exist yet
+    async fn applied_versions(&self, pool: &PgPool) -> AppResult<Vec<AppliedMigration>> {
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass('schema_migrations') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+
+        if !exists {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = pool.acquire().await?;
+        fetch_applied(&mut conn).await
+    }
+}
+
+async fn fetch_applied(conn: &mut PgConnection) -> AppResult<Vec<AppliedMigration>> {
+    let rows = sqlx::query("SELECT version, checksum FROM schema_migrations ORDER BY version")
+        .fetch_all(conn)
+        .await?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(AppliedMigration {
+                version: row.try_get("version")?,
+                checksum: row.try_get::<String, _>("checksum")?.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_ordered_and_unique() {
+        // ADVANTAGE: A duplicated or out-of-order version fails CI, not prod
+        assert!(MIGRATIONS.windows(2).all(|pair| pair[0].version < pair[1].version));
+        assert!(MIGRATIONS.iter().all(|m| m.checksum().len() == 64));
+    }
+
+    #[test]
+    fn test_plan_skips_applied_and_detects_edits() {
+        let applied = vec![AppliedMigration {
+            version: 1,
+            checksum: MIGRATIONS[0].checksum(),
+        }];
+        let pending = plan(MIGRATIONS, &applied).unwrap();
+        assert!(pending.iter().all(|m| m.version > 1));
+
+        let pending = plan(MIGRATIONS, &[]).unwrap();
+        assert_eq!(pending.len(), MIGRATIONS.len());
+
+        let tampered = vec![AppliedMigration {
+            version: 1,
+            checksum: "0".repeat(64),
+        }];
+        assert!(matches!(plan(MIGRATIONS, &tampered), Err(AppError::Configuration(_))));
+    }
+
+    #[test]
+    fn test_split_statements_keeps_bodies_and_quotes_whole() {
+        // ADVANTAGE: The Data API runner sends DO blocks and functions intact
+        let sql = "-- header; not a statement\nCREATE TABLE t (note TEXT DEFAULT 'a;b');\n\
+            DO $$ BEGIN PERFORM 1; END $$;\n\
+            CREATE FUNCTION f() RETURNS TRIGGER AS $body$ BEGIN RETURN NEW; END; $body$ language 'plpgsql';\n";
+        let statements = split_statements(sql);
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].ends_with("DEFAULT 'a;b')"));
+        assert_eq!(statements[1], "DO $$ BEGIN PERFORM 1; END $$");
+        assert!(statements[2].ends_with("$body$ language 'plpgsql'"));
+
+        // Every embedded migration splits into something runnable
+        assert!(MIGRATIONS.iter().all(|m| !split_statements(m.sql).is_empty()));
+    }
+}
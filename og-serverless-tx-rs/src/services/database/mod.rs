@@ -0,0 +1,301 @@
+//! # Database Service
+//!
+//! ADVANTAGE: Storage backends implement one trait - handlers never know which
+//! ADVANTAGE: Backend is selected once at startup from `Config`
+//! ADVANTAGE: Same Strategy-pattern shape as `PaymentStrategy`
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::{Transaction, TransactionStatus, NewTransaction};
+use crate::models::{Entitlement, JournalEntry, LedgerEntry, NewLedgerEntry, Posting, TrialBalance, WalletBalance};
+use crate::models::{CatalogItem, CatalogItemRequest, CodeCampaign, NewCodeCampaign, NewPromotion, PlayerProfile, Promotion};
+use crate::models::{NewWebhookSubscription, OutboxEvent, WebhookDelivery, WebhookDispatch, WebhookSubscription};
+use crate::models::{Subscription, SubscriptionPlan};
+use crate::models::config::DatabaseConfig;
+
+pub mod iam_auth;
+pub mod memory;
+pub mod migrations;
+pub mod postgres;
+pub mod rds_data;
+
+pub use memory::InMemoryDatabase;
+pub use migrations::{MigrationReport, MIGRATIONS};
+pub use postgres::PostgresDatabase;
+pub use rds_data::RdsDataDatabase;
+
+/// Transaction storage backend
+///
+/// ADVANTAGE: Send + Sync bounds allow sharing via Arc across invocations
+/// ADVANTAGE: Every backend returns the same typed `Transaction`
+#[async_trait]
+pub trait Database: Send + Sync {
+    /// Check backend health, returning round-trip latency
+    async fn health_check(&self) -> AppResult<Duration>;
+
+    /// Insert new transaction in `Pending` state, with its pricing as set
+    /// by `NewTransaction::with_pricing`
+    ///
+    /// A catalogued item is checked under its catalog row lock: one-per-player
+    /// items the player owns or has pending fail with `AlreadyOwned`, and
+    /// limited items reserve stock or fail with `OutOfStock`. The stored
+    /// metadata records the catalog kind.
+    ///
+    /// A transaction redeeming a code checks the campaign's limits under the
+    /// code row lock, failing with `NotFound` or `Conflict`, and records the
+    /// redemption alongside the insert.
+    ///
+    /// A gift checks ownership against the recipient and fails with
+    /// `GiftLimitReached` once the payer or recipient hits a daily limit.
+    async fn insert_transaction(&self, tx: &NewTransaction) -> AppResult<Transaction>;
+
+    /// Update transaction status and processor reference
+    ///
+    /// Transitions `TransactionStatus::can_transition_to` rejects fail with
+    /// `Conflict`. The change's `TransitionEffects` - journal entry,
+    /// entitlement grant or revocation, outbox event, stock settlement - are
+    /// written in the same database transaction, as is the gift status
+    /// `gift::status_after` gives.
+    async fn update_transaction_status(
+        &self,
+        transaction_id: Uuid,
+        status: TransactionStatus,
+        processor_id: Option<&str>,
+    ) -> AppResult<Transaction>;
+
+    /// Record `recipient_id`'s acceptance of a gift, granting its items in
+    /// the same database transaction
+    ///
+    /// Accepting again returns the gift unchanged; see `gift::answer` for
+    /// what fails. A gift is declined by refunding it.
+    async fn accept_gift(&self, transaction_id: Uuid, recipient_id: Uuid) -> AppResult<Transaction>;
+
+    /// Get transaction by ID
+    async fn get_transaction(&self, transaction_id: Uuid) -> AppResult<Option<Transaction>>;
+
+    /// Get player's transactions with pagination - purchases they paid for
+    /// and gifts they received
+    async fn get_player_transactions(
+        &self,
+        player_id: Uuid,
+        limit: i32,
+        cursor: Option<Uuid>,
+    ) -> AppResult<Vec<Transaction>>;
+
+    /// Transactions still `Pending` that were created before `before`,
+    /// oldest first
+    async fn get_stale_pending_transactions(&self, before: DateTime<Utc>, limit: i32) -> AppResult<Vec<Transaction>>;
+
+    /// Paid gifts still waiting for an answer that were bought before
+    /// `before`, oldest first
+    async fn get_unanswered_gifts(&self, before: DateTime<Utc>, limit: i32) -> AppResult<Vec<Transaction>>;
+
+    /// Apply embedded schema migrations, or only report them when `dry_run`
+    async fn run_migrations(&self, dry_run: bool) -> AppResult<MigrationReport>;
+
+    /// Highest applied migration version, `None` before the first migration
+    async fn schema_version(&self) -> AppResult<Option<i64>>;
+
+    /// Append a wallet ledger entry, keeping the balance non-negative
+    ///
+    /// Entries for one player and currency are serialized, so concurrent
+    /// debits cannot both spend the same balance. A debit below zero fails
+    /// with `InsufficientFunds`; an entry whose kind and reference already
+    /// exist returns the stored entry instead of appending again. A new
+    /// entry is journaled in the same database transaction.
+    async fn append_ledger_entry(&self, entry: &NewLedgerEntry) -> AppResult<LedgerEntry>;
+
+    /// Balance of every currency the player has held
+    async fn get_wallet(&self, player_id: Uuid) -> AppResult<Vec<WalletBalance>>;
+
+    /// Player's journal entries with postings, newest first
+    async fn get_player_journal(&self, player_id: Uuid, limit: i32) -> AppResult<Vec<JournalEntry>>;
+
+    /// Debit and credit totals of every account
+    async fn trial_balance(&self) -> AppResult<TrialBalance>;
+
+    /// Player's entitlements, most recently granted first
+    ///
+    /// Revoked and expired entitlements are only listed with `include_revoked`.
+    async fn get_player_entitlements(&self, player_id: Uuid, include_revoked: bool) -> AppResult<Vec<Entitlement>>;
+
+    /// Claim up to `limit` due pending outbox events, oldest first
+    ///
+    /// A claim counts as an attempt and leases the event for `lease`:
+    /// concurrent dispatchers skip it, and if this one dies before marking
+    /// the outcome the event comes due again when the lease runs out.
+    async fn claim_outbox_events(&self, limit: i32, lease: Duration) -> AppResult<Vec<OutboxEvent>>;
+
+    /// Record that the event was published
+    async fn mark_outbox_delivered(&self, event_id: Uuid) -> AppResult<()>;
+
+    /// Record a failed publish, retrying after `retry_in` or, when `None`,
+    /// giving up and marking the event dead
+    async fn mark_outbox_failed(&self, event_id: Uuid, error: &str, retry_in: Option<Duration>) -> AppResult<()>;
+
+    /// Store a webhook subscription
+    async fn create_webhook_subscription(&self, subscription: &NewWebhookSubscription) -> AppResult<WebhookSubscription>;
+
+    /// Title's webhook subscriptions, oldest first
+    async fn get_webhook_subscriptions(&self, title_id: &str) -> AppResult<Vec<WebhookSubscription>>;
+
+    async fn get_webhook_subscription(&self, subscription_id: Uuid) -> AppResult<Option<WebhookSubscription>>;
+
+    /// Subscription's deliveries, newest first
+    async fn get_webhook_deliveries(&self, subscription_id: Uuid, limit: i32) -> AppResult<Vec<WebhookDelivery>>;
+
+    /// Claim up to `limit` due pending webhook deliveries, oldest first,
+    /// with the same attempt counting and lease as `claim_outbox_events`
+    async fn claim_webhook_deliveries(&self, limit: i32, lease: Duration) -> AppResult<Vec<WebhookDispatch>>;
+
+    /// Record a delivery the receiver acknowledged with `status_code`
+    async fn mark_webhook_delivered(&self, delivery_id: Uuid, status_code: i32) -> AppResult<()>;
+
+    /// Record a failed attempt, retrying after `retry_in` or, when `None`,
+    /// marking the delivery dead
+    async fn mark_webhook_failed(
+        &self,
+        delivery_id: Uuid,
+        status_code: Option<i32>,
+        error: &str,
+        retry_in: Option<Duration>,
+    ) -> AppResult<()>;
+
+    /// Create or replace an item's catalog entry, keeping its stock counters
+    ///
+    /// A `stock_limit` below the units already reserved or sold fails with `Conflict`.
+    async fn upsert_catalog_item(&self, item_id: &str, item: &CatalogItemRequest) -> AppResult<CatalogItem>;
+
+    async fn get_catalog_item(&self, item_id: &str) -> AppResult<Option<CatalogItem>>;
+
+    /// Store a promotion
+    async fn create_promotion(&self, promotion: &NewPromotion) -> AppResult<Promotion>;
+
+    /// Promotions, latest starting first
+    async fn get_promotions(&self, limit: i32) -> AppResult<Vec<Promotion>>;
+
+    /// Promotions running at `at` that target `item_id` or every item,
+    /// highest priority first
+    async fn get_active_promotions(&self, item_id: &str, at: DateTime<Utc>) -> AppResult<Vec<Promotion>>;
+
+    /// Replace the player's segments, creating their profile on first use
+    async fn put_player_profile(&self, player_id: Uuid, segments: &[String]) -> AppResult<PlayerProfile>;
+
+    /// Player's profile, `None` until an operator sets one
+    async fn get_player_profile(&self, player_id: Uuid) -> AppResult<Option<PlayerProfile>>;
+
+    /// Store a code campaign and all of its codes
+    async fn create_code_campaign(&self, campaign: &NewCodeCampaign) -> AppResult<CodeCampaign>;
+
+    /// Campaign of the normalized `code`, `None` for an unknown code
+    async fn get_code_campaign(&self, code: &str) -> AppResult<Option<CodeCampaign>>;
+
+    /// Record a failed redemption attempt by `subject`
+    async fn record_code_failure(&self, subject: &str) -> AppResult<()>;
+
+    /// Failed redemption attempts by `subject` since `since`
+    async fn count_code_failures(&self, subject: &str, since: DateTime<Utc>) -> AppResult<i64>;
+
+    /// Create or replace a subscription plan; existing subscribers move to
+    /// its new terms at their next renewal
+    async fn upsert_subscription_plan(&self, plan: &SubscriptionPlan) -> AppResult<SubscriptionPlan>;
+
+    async fn get_subscription_plan(&self, plan_id: &str) -> AppResult<Option<SubscriptionPlan>>;
+
+    /// Store a new subscription
+    ///
+    /// A player holds at most one live subscription per plan; another fails
+    /// with `Conflict`.
+    async fn insert_subscription(&self, subscription: &Subscription) -> AppResult<Subscription>;
+
+    async fn get_subscription(&self, subscription_id: Uuid) -> AppResult<Option<Subscription>>;
+
+    /// Player's subscriptions, newest first
+    async fn get_player_subscriptions(&self, player_id: Uuid) -> AppResult<Vec<Subscription>>;
+
+    /// Up to `limit` live subscriptions whose `next_billing_at` is at or
+    /// before `now`, most overdue first
+    async fn get_due_subscriptions(&self, now: DateTime<Utc>, limit: i32) -> AppResult<Vec<Subscription>>;
+
+    /// Save `subscription` over the stored row and bump its `version`
+    ///
+    /// Fails with `Conflict` when the stored version differs - someone else
+    /// saved first - or when the save would give the player a second live
+    /// subscription to a plan. The entitlement expiries from
+    /// `Subscription::access_changes` are written in the same database
+    /// transaction.
+    async fn update_subscription(&self, subscription: &Subscription) -> AppResult<Subscription>;
+
+    /// Queue a delivery to be sent again now, whatever its status, with a
+    /// fresh attempt budget
+    async fn redeliver_webhook(&self, delivery_id: Uuid) -> AppResult<WebhookDelivery>;
+
+    /// Get backend name for logging
+    fn name(&self) -> &'static str;
+}
+
+/// Create the backend selected by `config`
+///
+/// ADVANTAGE: Lambda and the local dev server build storage the same way
+pub async fn connect(config: &DatabaseConfig) -> AppResult<Arc<dyn Database>> {
+    Ok(match config {
+        DatabaseConfig::Postgres { url, iam_auth } => {
+            info!(iam_auth = *iam_auth, "Using PostgreSQL connection pool");
+            Arc::new(PostgresDatabase::new(url.expose(), *iam_auth).await?)
+        }
+        DatabaseConfig::DataApi(data_api) => {
+            info!("Using Aurora Data API");
+            Arc::new(RdsDataDatabase::new(data_api).await?)
+        }
+        DatabaseConfig::Memory => {
+            info!("Using in-memory storage");
+            Arc::new(InMemoryDatabase::new())
+        }
+    })
+}
+
+/// Reject a status change `TransactionStatus::can_transition_to` forbids
+fn check_transition(transaction: &Transaction, next: TransactionStatus) -> AppResult<()> {
+    if transaction.status.can_transition_to(next) {
+        Ok(())
+    } else {
+        Err(AppError::Conflict(format!(
+            "Transaction {} cannot move from {} to {}",
+            transaction.transaction_id,
+            transaction.status.as_str(),
+            next.as_str()
+        )))
+    }
+}
+
+/// Reject saving `subscription` over `stored` unless it was read at the stored version
+fn check_subscription_version(subscription: &Subscription, stored: &Subscription) -> AppResult<()> {
+    if subscription.version == stored.version {
+        Ok(())
+    } else {
+        Err(AppError::Conflict(format!(
+            "Subscription {} was changed concurrently; reload and retry",
+            subscription.subscription_id
+        )))
+    }
+}
+
+/// Reject a second live subscription to `plan_id`
+fn live_subscription_conflict(plan_id: &str) -> AppError {
+    AppError::Conflict(format!("Player already has a live subscription to {}", plan_id))
+}
+
+/// Distribute `(journal_id, posting)` rows onto their entries, keeping row order
+fn attach_postings(entries: &mut [JournalEntry], postings: Vec<(Uuid, Posting)>) {
+    for (journal_id, posting) in postings {
+        if let Some(entry) = entries.iter_mut().find(|e| e.journal_id == journal_id) {
+            entry.postings.push(posting);
+        }
+    }
+}
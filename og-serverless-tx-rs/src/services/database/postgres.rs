@@ -0,0 +1,1596 @@
+//! # PostgreSQL Database Backend
+//! 
+//! ADVANTAGE: sqlx provides compile-time SQL query validation
+//! ADVANTAGE: Connection pooling is built-in and efficient
+//! ADVANTAGE: Async queries don't block the runtime
+//! ADVANTAGE: Transactions are type-safe with RAII
+
+use async_trait::async_trait;
+use sqlx::{PgConnection, PgPool, postgres::{PgConnectOptions, PgPoolOptions, PgSslMode}};
+use std::str::FromStr;
+use std::time::SystemTime;
+use tokio::sync::Mutex;
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::{Transaction, TransactionStatus, NewTransaction};
+use crate::models::{Entitlement, JournalEntry, LedgerEntry, NewJournalEntry, NewLedgerEntry};
+use crate::models::{EntitlementStatus, OutboxEvent, OutboxStatus, TransitionEffects, TrialBalance, WalletBalance};
+use crate::models::{CatalogItem, CatalogItemRequest, NewPromotion, PlayerProfile, Promotion, StockSettlement};
+use crate::models::{CodeCampaign, GiftStatus, NewCodeCampaign};
+use crate::models::gift::{self, GIFT_LIMIT_WINDOW};
+use crate::models::{Subscription, SubscriptionPlan};
+use crate::models::{NewWebhookSubscription, WebhookDelivery, WebhookDeliveryStatus, WebhookDispatch, WebhookSubscription};
+use crate::models::journal::{AccountType, Posting, TrialBalanceLine};
+use super::{attach_postings, check_subscription_version, check_transition, live_subscription_conflict, Database, MigrationReport};
+use super::migrations::{Migrator, MIGRATIONS};
+use super::iam_auth::{IamAuthTarget, IamTokenProvider};
+
+/// Columns of `wallet_ledger`, in `LedgerEntry` field order
+const LEDGER_COLUMNS: &str = "entry_id, player_id, currency, sequence, amount, balance_after, \
+    kind, reference, item_id, request_id, created_at";
+
+/// Columns of `outbox_events`, in `OutboxEvent` field order
+const OUTBOX_COLUMNS: &str = "event_id, event_type, aggregate_id, payload, status, attempts, \
+    next_attempt_at, last_error, created_at, delivered_at";
+
+/// Columns of `catalog_items`, in `CatalogItem` field order
+const CATALOG_COLUMNS: &str = "item_id, kind, stock_limit, stock_reserved, stock_sold, price_cents, currency, \
+    credit_currency, credit_amount, wallet_price, wallet_currency, title_id, updated_at";
+
+/// Columns of `promotions`, in `Promotion` field order
+const PROMOTION_COLUMNS: &str = "promotion_id, name, kind, value, currency, item_ids, segments, bundle_items, \
+    stackable, priority, starts_at, ends_at, created_at";
+
+/// Columns of `player_profiles`, in `PlayerProfile` field order
+const PLAYER_PROFILE_COLUMNS: &str = "player_id, segments, updated_at";
+
+/// Columns of `code_campaigns`, in `CodeCampaign` field order
+const CODE_CAMPAIGN_COLUMNS: &str = "campaign_id, name, reward, max_uses, per_player_limit, expires_at, \
+    code_count, created_at";
+
+/// Columns of `subscription_plans`, in `SubscriptionPlan` field order
+const SUBSCRIPTION_PLAN_COLUMNS: &str = "plan_id, name, item_id, price_cents, currency, period_days, \
+    season_ends_at, grace_period_days, updated_at";
+
+/// Columns of `subscriptions`, in `Subscription` field order
+const SUBSCRIPTION_COLUMNS: &str = "subscription_id, player_id, plan_id, status, payment_method, \
+    current_period_start, current_period_end, cancel_at_period_end, renewal_attempts, grace_ends_at, \
+    next_billing_at, credit_cents, entitlement_transaction_id, renewal_transaction_id, ended_at, \
+    version, created_at, updated_at";
+
+/// Columns of `webhook_subscriptions`, in `WebhookSubscription` field order
+const WEBHOOK_SUBSCRIPTION_COLUMNS: &str = "subscription_id, title_id, url, secret, event_types, created_at";
+
+/// Columns of `webhook_deliveries`, in `WebhookDelivery` field order
+const WEBHOOK_DELIVERY_COLUMNS: &str = "delivery_id, subscription_id, event_id, event_type, payload, status, \
+    attempts, next_attempt_at, last_status_code, last_error, created_at, delivered_at";
+
+/// Claimed delivery joined with its subscription's target
+#[derive(sqlx::FromRow)]
+struct ClaimedWebhookRow {
+    #[sqlx(flatten)]
+    delivery: WebhookDelivery,
+    url: String,
+    secret: String,
+}
+
+/// PostgreSQL database service
+/// 
+/// ADVANTAGE: Pool is managed internally - no global mutable state
+/// ADVANTAGE: Connection reuse across Lambda warm starts
+pub struct PostgresDatabase {
+    pool: PgPool,
+    iam_auth: Option<IamAuth>,
+}
+
+/// IAM auth token state for new pool connections
+/// 
+/// ADVANTAGE: Only connect needs the token - open connections stay valid
+struct IamAuth {
+    provider: IamTokenProvider,
+    issued_at: Mutex<SystemTime>,
+}
+
+impl PostgresDatabase {
+    /// Create new database connection pool
+    /// 
+    /// With `iam_auth`, the password in `database_url` is replaced by an RDS
+    /// IAM auth token signed with credentials from the `aws-config` chain.
+    /// 
+    /// ADVANTAGE: Pool configuration is explicit and type-checked
+    pub async fn new(database_url: &str, iam_auth: bool) -> AppResult<Self> {
+        let mut options = PgConnectOptions::from_str(database_url)?;
+        
+        let iam_auth = if iam_auth {
+            let sdk_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+            let region = sdk_config.region()
+                .ok_or_else(|| AppError::Configuration("AWS region required for IAM database auth".into()))?
+                .to_string();
+            let credentials = sdk_config.credentials_provider()
+                .ok_or_else(|| AppError::Configuration("AWS credentials required for IAM database auth".into()))?;
+            
+            let provider = IamTokenProvider::new(
+                IamAuthTarget {
+                    hostname: options.get_host().to_string(),
+                    port: options.get_port(),
+                    username: options.get_username().to_string(),
+                    region,
+                },
+                credentials,
+            );
+            
+            // ADVANTAGE: RDS only accepts IAM tokens over TLS
+            options = options
+                .password(&provider.token().await?)
+                .ssl_mode(PgSslMode::Require);
+            
+            info!("IAM database authentication enabled");
+            Some(IamAuth {
+                provider,
+                issued_at: Mutex::new(SystemTime::now()),
+            })
+        } else {
+            None
+        };
+        
+        let pool = PgPoolOptions::new()
+            // ADVANTAGE: Lambda-optimized pool size
+            .max_connections(5)
+            // ADVANTAGE: Fast connection timeout for Lambda
+            .acquire_timeout(std::time::Duration::from_secs(3))
+            // ADVANTAGE: Connections are tested before use
+            .test_before_acquire(true)
+            .connect_with(options)
+            .await
+            .map_err(AppError::Database)?;
+        
+        info!("Database pool initialized");
+        Ok(Self { pool, iam_auth })
+    }
+    
+    /// Get the pool, refreshing the IAM auth token first if it is near expiry
+    /// 
+    /// ADVANTAGE: Checked lazily per call - works across Lambda freeze/thaw,
+    /// where a background refresh timer would not run
+    async fn pool(&self) -> AppResult<&PgPool> {
+        if let Some(iam_auth) = &self.iam_auth {
+            let mut issued_at = iam_auth.issued_at.lock().await;
+            let now = SystemTime::now();
+            
+            if IamTokenProvider::needs_refresh(*issued_at, now) {
+                let token = iam_auth.provider.token().await?;
+                let options = (*self.pool.connect_options()).clone().password(&token);
+                self.pool.set_connect_options(options);
+                *issued_at = now;
+                info!("IAM database auth token refreshed");
+            }
+        }
+        
+        Ok(&self.pool)
+    }
+    
+    /// Execute a transactional operation
+    /// 
+    /// ADVANTAGE: Transaction is automatically rolled back on error
+    /// ADVANTAGE: RAII ensures transaction is committed or rolled back
+    pub async fn with_transaction<F, T>(&self, f: F) -> AppResult<T>
+    where
+        F: for<'c> FnOnce(&'c mut sqlx::Transaction<'static, sqlx::Postgres>) -> std::pin::Pin<Box<dyn std::future::Future<Output = AppResult<T>> + Send + 'c>> + Send,
+        T: Send,
+    {
+        let mut tx = self.pool().await?.begin().await?;
+        
+        match f(&mut tx).await {
+            Ok(result) => {
+                tx.commit().await?;
+                Ok(result)
+            }
+            Err(e) => {
+                // ADVANTAGE: Rollback is automatic if commit not called
+                // but we can be explicit
+                let _ = tx.rollback().await;
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Write a status change's effects inside the caller's transaction
+async fn apply_effects(conn: &mut PgConnection, effects: &TransitionEffects, transaction_id: Uuid) -> AppResult<()> {
+    if let Some(entry) = &effects.wallet {
+        append_ledger(conn, entry).await?;
+    }
+    
+    if let Some(settlement) = effects.stock {
+        sqlx::query(
+            r#"
+            WITH settled AS (
+                DELETE FROM stock_reservations WHERE transaction_id = $1
+                RETURNING item_id, quantity
+            )
+            UPDATE catalog_items c
+            SET stock_reserved = c.stock_reserved - s.quantity,
+                stock_sold = c.stock_sold + CASE WHEN $2 THEN s.quantity ELSE 0 END,
+                updated_at = NOW()
+            FROM settled s
+            WHERE c.item_id = s.item_id
+            "#
+        )
+            .bind(transaction_id)
+            .bind(settlement == StockSettlement::Commit)
+            .execute(&mut *conn)
+            .await?;
+    }
+    
+    for grant in &effects.grants {
+        sqlx::query(
+            r#"
+            INSERT INTO entitlements (
+                entitlement_id, player_id, item_id, quantity, kind, source_transaction_id, expires_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (source_transaction_id, item_id) DO NOTHING
+            "#
+        )
+            .bind(grant.entitlement_id)
+            .bind(grant.player_id)
+            .bind(&grant.item_id)
+            .bind(grant.quantity)
+            .bind(grant.kind)
+            .bind(grant.source_transaction_id)
+            .bind(grant.expires_at)
+            .execute(&mut *conn)
+            .await?;
+    }
+    
+    if effects.revoke {
+        sqlx::query(
+            r#"
+            UPDATE entitlements
+            SET status = $1, revoked_at = NOW()
+            WHERE source_transaction_id = $2 AND status = $3
+            "#
+        )
+            .bind(EntitlementStatus::Revoked)
+            .bind(transaction_id)
+            .bind(EntitlementStatus::Active)
+            .execute(&mut *conn)
+            .await?;
+    }
+    
+    if let Some(event) = &effects.event {
+        sqlx::query(
+            r#"
+            INSERT INTO outbox_events (event_id, event_type, aggregate_id, payload)
+            VALUES ($1, $2, $3, $4)
+            "#
+        )
+            .bind(event.event_id)
+            .bind(event.event_type.as_str())
+            .bind(event.aggregate_id)
+            .bind(&event.payload)
+            .execute(&mut *conn)
+            .await?;
+    }
+    
+    if let Some(fan_out) = &effects.webhooks {
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (delivery_id, subscription_id, event_id, event_type, payload)
+            SELECT gen_random_uuid(), subscription_id, $1, $2, $3
+            FROM webhook_subscriptions
+            WHERE title_id = $4 AND event_types ? $2
+            ON CONFLICT (subscription_id, event_id) DO NOTHING
+            "#
+        )
+            .bind(fan_out.event_id)
+            .bind(fan_out.event_type.as_str())
+            .bind(&fan_out.payload)
+            .bind(&fan_out.title_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    
+    if let Some(entry) = &effects.journal {
+        insert_journal_entry(conn, entry).await?;
+    }
+    
+    Ok(())
+}
+
+/// Append `entry` and its journal entry under the wallet's advisory lock,
+/// inside the caller's transaction
+async fn append_ledger(conn: &mut PgConnection, entry: &NewLedgerEntry) -> AppResult<LedgerEntry> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+        .bind(format!("wallet:{}:{}", entry.player_id, entry.currency.as_str()))
+        .execute(&mut *conn)
+        .await?;
+    
+    let existing = sqlx::query_as::<_, LedgerEntry>(&format!(
+        "SELECT {} FROM wallet_ledger WHERE player_id = $1 AND kind = $2 AND reference = $3",
+        LEDGER_COLUMNS
+    ))
+        .bind(entry.player_id)
+        .bind(entry.kind)
+        .bind(&entry.reference)
+        .fetch_optional(&mut *conn)
+        .await?;
+    
+    if let Some(existing) = existing {
+        return entry.replay(existing);
+    }
+    
+    let (sequence, balance): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT sequence, balance_after FROM wallet_ledger
+        WHERE player_id = $1 AND currency = $2
+        ORDER BY sequence DESC
+        LIMIT 1
+        "#,
+    )
+        .bind(entry.player_id)
+        .bind(entry.currency)
+        .fetch_optional(&mut *conn)
+        .await?
+        .unwrap_or((0, 0));
+    let (sequence, balance_after) = entry.position_after(sequence, balance)?;
+    
+    let appended = sqlx::query_as::<_, LedgerEntry>(&format!(
+        r#"
+        INSERT INTO wallet_ledger (
+            entry_id, player_id, currency, sequence, amount, balance_after,
+            kind, reference, item_id, request_id
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING {}
+        "#,
+        LEDGER_COLUMNS
+    ))
+        .bind(entry.entry_id)
+        .bind(entry.player_id)
+        .bind(entry.currency)
+        .bind(sequence)
+        .bind(entry.amount)
+        .bind(balance_after)
+        .bind(entry.kind)
+        .bind(&entry.reference)
+        .bind(&entry.item_id)
+        .bind(&entry.request_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    
+    insert_journal_entry(conn, &NewJournalEntry::for_ledger_entry(&appended)?).await?;
+    Ok(appended)
+}
+
+/// Fail with `NotFound` when an outbox update matched no row
+fn outbox_updated(result: sqlx::postgres::PgQueryResult, event_id: Uuid) -> AppResult<()> {
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Outbox event {} not found", event_id)));
+    }
+    Ok(())
+}
+
+/// Fail with `NotFound` when a webhook delivery update matched no row
+fn delivery_updated(result: sqlx::postgres::PgQueryResult, delivery_id: Uuid) -> AppResult<()> {
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Webhook delivery {} not found", delivery_id)));
+    }
+    Ok(())
+}
+
+/// Lock `code` and check one more redemption by `player_id` against its campaign
+/// 
+/// ADVANTAGE: The code row lock serializes redemptions of the same code
+async fn check_code_redemption(
+    conn: &mut PgConnection,
+    code: &str,
+    player_id: Uuid,
+    now: chrono::DateTime<chrono::Utc>,
+) -> AppResult<()> {
+    let campaign_id = sqlx::query_scalar::<_, Uuid>("SELECT campaign_id FROM redeem_codes WHERE code = $1 FOR UPDATE")
+        .bind(code)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Code not found".into()))?;
+    
+    let campaign = sqlx::query_as::<_, CodeCampaign>(&format!(
+        "SELECT {} FROM code_campaigns WHERE campaign_id = $1",
+        CODE_CAMPAIGN_COLUMNS
+    ))
+        .bind(campaign_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    
+    let (uses, player_uses) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT COUNT(*), COUNT(*) FILTER (WHERE r.player_id = $2)
+        FROM code_redemptions r
+        JOIN microtransactions t ON t.transaction_id = r.transaction_id
+        WHERE r.code = $1 AND t.status <> 'failed'
+        "#
+    )
+        .bind(code)
+        .bind(player_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    
+    campaign.check_redemption(uses, player_uses, now)
+}
+
+/// Reject a live `subscription` when the player holds another live one to its plan
+/// 
+/// ADVANTAGE: The advisory lock serializes subscribes to the same plan, so
+/// two concurrent requests cannot both pass the check
+async fn check_live_subscription(conn: &mut PgConnection, subscription: &Subscription) -> AppResult<()> {
+    if !subscription.status.is_live() {
+        return Ok(());
+    }
+    
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+        .bind(format!("subscription:{}:{}", subscription.player_id, subscription.plan_id))
+        .execute(&mut *conn)
+        .await?;
+    
+    let (exists,) = sqlx::query_as::<_, (bool,)>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM subscriptions
+            WHERE player_id = $1 AND plan_id = $2 AND subscription_id <> $3
+              AND status IN ('active', 'past_due')
+        )
+        "#
+    )
+        .bind(subscription.player_id)
+        .bind(&subscription.plan_id)
+        .bind(subscription.subscription_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    
+    if exists {
+        return Err(live_subscription_conflict(&subscription.plan_id));
+    }
+    Ok(())
+}
+
+/// Check a gift from `payer_id` to `recipient_id` against the daily limits
+/// 
+/// ADVANTAGE: Advisory locks on both players, taken in a fixed order,
+/// serialize gifts that share a payer or recipient
+async fn check_gift_limits(
+    conn: &mut PgConnection,
+    payer_id: Uuid,
+    recipient_id: Uuid,
+    now: chrono::DateTime<chrono::Utc>,
+) -> AppResult<()> {
+    let mut players = [payer_id, recipient_id];
+    players.sort();
+    for player_id in players {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+            .bind(format!("gift:{}", player_id))
+            .execute(&mut *conn)
+            .await?;
+    }
+    
+    let (sent, received) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT COUNT(*) FILTER (WHERE player_id = $1), COUNT(*) FILTER (WHERE recipient_id = $2)
+        FROM microtransactions
+        WHERE (player_id = $1 OR recipient_id = $2)
+          AND recipient_id IS NOT NULL AND status <> 'failed' AND created_at > $3
+        "#
+    )
+        .bind(payer_id)
+        .bind(recipient_id)
+        .bind(now - GIFT_LIMIT_WINDOW)
+        .fetch_one(&mut *conn)
+        .await?;
+    
+    gift::check_gift_limits(sent, received)
+}
+
+/// Write `entry` and its postings unless `(kind, reference)` is already journaled
+/// 
+/// ADVANTAGE: The deferred balance trigger re-checks the postings at commit
+async fn insert_journal_entry(conn: &mut PgConnection, entry: &NewJournalEntry) -> AppResult<()> {
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO journal_entries (journal_id, kind, reference, player_id, request_id)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (kind, reference) DO NOTHING
+        "#
+    )
+        .bind(entry.journal_id)
+        .bind(entry.kind)
+        .bind(&entry.reference)
+        .bind(entry.player_id)
+        .bind(&entry.request_id)
+        .execute(&mut *conn)
+        .await?;
+    
+    if inserted.rows_affected() == 0 {
+        return Ok(());
+    }
+    
+    for posting in &entry.postings {
+        sqlx::query(
+            r#"
+            INSERT INTO ledger_accounts (account_code, account_type, currency)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (account_code) DO NOTHING
+            "#
+        )
+            .bind(&posting.account_code)
+            .bind(posting.account_type)
+            .bind(&posting.currency)
+            .execute(&mut *conn)
+            .await?;
+        
+        sqlx::query(
+            "INSERT INTO journal_postings (journal_id, account_code, currency, amount) VALUES ($1, $2, $3, $4)"
+        )
+            .bind(entry.journal_id)
+            .bind(&posting.account_code)
+            .bind(&posting.currency)
+            .bind(posting.amount)
+            .execute(&mut *conn)
+            .await?;
+    }
+    
+    Ok(())
+}
+
+#[async_trait]
+impl Database for PostgresDatabase {
+    /// Check database health
+    async fn health_check(&self) -> AppResult<std::time::Duration> {
+        let start = std::time::Instant::now();
+        
+        sqlx::query("SELECT 1")
+            .execute(self.pool().await?)
+            .await?;
+        
+        Ok(start.elapsed())
+    }
+    
+    /// Insert new transaction
+    /// 
+    /// ADVANTAGE: SQL is validated at compile time (with sqlx::query!)
+    /// ADVANTAGE: Parameters are typed - no injection possible
+    /// ADVANTAGE: Return type matches actual database schema
+    #[instrument(skip(self, tx), fields(transaction_id = %tx.transaction_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn insert_transaction(&self, tx: &NewTransaction) -> AppResult<Transaction> {
+        let tx = tx.clone();
+        
+        // ADVANTAGE: The catalog row lock serializes ownership and stock checks per item
+        let result = self.with_transaction(move |conn| Box::pin(async move {
+            let item = sqlx::query_as::<_, CatalogItem>(&format!(
+                "SELECT {} FROM catalog_items WHERE item_id = $1 FOR UPDATE",
+                CATALOG_COLUMNS
+            ))
+                .bind(&tx.item_id)
+                .fetch_optional(&mut **conn)
+                .await?;
+            
+            let mut metadata = tx.metadata.clone();
+            let mut reserve = 0;
+            if let Some(item) = &item {
+                let owned = item.kind.is_unique() && sqlx::query_scalar::<_, bool>(
+                    r#"
+                    SELECT EXISTS (
+                        SELECT 1 FROM entitlements
+                        WHERE player_id = $1 AND item_id = $2 AND status = 'active' AND expires_at IS NULL
+                    ) OR EXISTS (
+                        SELECT 1 FROM microtransactions
+                        WHERE COALESCE(recipient_id, player_id) = $1 AND item_id = $2 AND status = 'pending'
+                    )
+                    "#
+                )
+                    .bind(tx.recipient_id.unwrap_or(tx.player_id))
+                    .bind(&tx.item_id)
+                    .fetch_one(&mut **conn)
+                    .await?;
+                
+                reserve = item.check_purchase(tx.quantity, owned)?;
+                metadata = item.stamp(&metadata);
+            }
+            
+            let now = chrono::Utc::now();
+            
+            if let Some(code) = &tx.code {
+                check_code_redemption(conn, code, tx.player_id, now).await?;
+            }
+            if let Some(recipient_id) = tx.recipient_id {
+                check_gift_limits(conn, tx.player_id, recipient_id, now).await?;
+            }
+            
+            // Note: In production with sqlx prepare, this would be compile-time checked
+            let inserted = sqlx::query_as::<_, Transaction>(
+                r#"
+                INSERT INTO microtransactions (
+                    transaction_id,
+                    player_id,
+                    item_id,
+                    item_name,
+                    price_cents,
+                    currency,
+                    quantity,
+                    status,
+                    metadata,
+                    request_id,
+                    created_at,
+                    updated_at,
+                    original_price_cents,
+                    applied_promotions,
+                    recipient_id,
+                    gift_status
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+                RETURNING *
+                "#
+            )
+            .bind(tx.transaction_id)
+            .bind(tx.player_id)
+            .bind(&tx.item_id)
+            .bind(&tx.item_name)
+            .bind(tx.price_cents)
+            .bind(&tx.currency)
+            .bind(tx.quantity)
+            .bind(TransactionStatus::Pending)
+            .bind(&metadata)
+            .bind(&tx.request_id)
+            .bind(now)
+            .bind(now)
+            .bind(tx.original_price_cents)
+            .bind(sqlx::types::Json(&tx.applied_promotions))
+            .bind(tx.recipient_id)
+            .bind(tx.recipient_id.map(|_| GiftStatus::Pending))
+            .fetch_one(&mut **conn)
+            .await?;
+            
+            if reserve > 0 {
+                sqlx::query("UPDATE catalog_items SET stock_reserved = stock_reserved + $1, updated_at = NOW() WHERE item_id = $2")
+                    .bind(reserve)
+                    .bind(&tx.item_id)
+                    .execute(&mut **conn)
+                    .await?;
+                sqlx::query("INSERT INTO stock_reservations (transaction_id, item_id, quantity) VALUES ($1, $2, $3)")
+                    .bind(tx.transaction_id)
+                    .bind(&tx.item_id)
+                    .bind(reserve)
+                    .execute(&mut **conn)
+                    .await?;
+            }
+            
+            if let Some(code) = &tx.code {
+                sqlx::query("INSERT INTO code_redemptions (redemption_id, code, player_id, transaction_id) VALUES ($1, $2, $3, $4)")
+                    .bind(Uuid::new_v4())
+                    .bind(code)
+                    .bind(tx.player_id)
+                    .bind(tx.transaction_id)
+                    .execute(&mut **conn)
+                    .await?;
+            }
+            
+            Ok(inserted)
+        })).await?;
+        
+        info!("Transaction inserted");
+        Ok(result)
+    }
+    
+    /// Update transaction status
+    /// 
+    /// ADVANTAGE: Status is enum - invalid status impossible
+    #[instrument(skip(self), fields(transaction_id = %transaction_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn update_transaction_status(
+        &self,
+        transaction_id: Uuid,
+        status: TransactionStatus,
+        processor_id: Option<&str>,
+    ) -> AppResult<Transaction> {
+        let processor_id = processor_id.map(str::to_string);
+        
+        // ADVANTAGE: Row lock makes the transition check and journal entry race-free
+        let result = self.with_transaction(move |tx| Box::pin(async move {
+            let current = sqlx::query_as::<_, Transaction>(
+                "SELECT * FROM microtransactions WHERE transaction_id = $1 FOR UPDATE"
+            )
+                .bind(transaction_id)
+                .fetch_optional(&mut **tx)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Transaction {} not found", transaction_id)))?;
+            check_transition(&current, status)?;
+            
+            let updated = sqlx::query_as::<_, Transaction>(
+                r#"
+                UPDATE microtransactions
+                SET status = $1, processor_id = $2, updated_at = $3, gift_status = $5
+                WHERE transaction_id = $4
+                RETURNING *
+                "#
+            )
+                .bind(status)
+                .bind(&processor_id)
+                .bind(chrono::Utc::now())
+                .bind(transaction_id)
+                .bind(gift::status_after(&current, status))
+                .fetch_one(&mut **tx)
+                .await?;
+            
+            apply_effects(tx, &TransitionEffects::of(&updated, current.status)?, transaction_id).await?;
+            
+            Ok(updated)
+        })).await?;
+        
+        info!(status = ?status, "Transaction status updated");
+        Ok(result)
+    }
+    
+    async fn accept_gift(&self, transaction_id: Uuid, recipient_id: Uuid) -> AppResult<Transaction> {
+        self.with_transaction(move |conn| Box::pin(async move {
+            let current = sqlx::query_as::<_, Transaction>(
+                "SELECT * FROM microtransactions WHERE transaction_id = $1 FOR UPDATE"
+            )
+                .bind(transaction_id)
+                .fetch_optional(&mut **conn)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Gift {} not found", transaction_id)))?;
+            let Some(status) = gift::answer(&current, recipient_id, true)? else {
+                return Ok(current);
+            };
+            
+            let updated = sqlx::query_as::<_, Transaction>(
+                "UPDATE microtransactions SET gift_status = $1, updated_at = NOW() WHERE transaction_id = $2 RETURNING *"
+            )
+                .bind(status)
+                .bind(transaction_id)
+                .fetch_one(&mut **conn)
+                .await?;
+            
+            apply_effects(conn, &TransitionEffects::of_gift_accepted(&updated), transaction_id).await?;
+            
+            Ok(updated)
+        })).await
+    }
+    
+    /// Get transaction by ID
+    async fn get_transaction(&self, transaction_id: Uuid) -> AppResult<Option<Transaction>> {
+        let result = sqlx::query_as::<_, Transaction>(
+            "SELECT * FROM microtransactions WHERE transaction_id = $1"
+        )
+        .bind(transaction_id)
+        .fetch_optional(self.pool().await?)
+        .await?;
+        
+        Ok(result)
+    }
+    
+    #[instrument(skip(self), fields(otel.kind = "client", db.system = "postgresql"))]
+    async fn get_stale_pending_transactions(&self, before: chrono::DateTime<chrono::Utc>, limit: i32) -> AppResult<Vec<Transaction>> {
+        let results = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT * FROM microtransactions
+            WHERE status = 'pending' AND created_at < $1
+            ORDER BY created_at
+            LIMIT $2
+            "#
+        )
+        .bind(before)
+        .bind(limit.clamp(1, 1000))
+        .fetch_all(self.pool().await?)
+        .await?;
+        
+        Ok(results)
+    }
+    
+    #[instrument(skip(self), fields(otel.kind = "client", db.system = "postgresql"))]
+    async fn get_unanswered_gifts(&self, before: chrono::DateTime<chrono::Utc>, limit: i32) -> AppResult<Vec<Transaction>> {
+        let results = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT * FROM microtransactions
+            WHERE gift_status = 'pending' AND status = 'completed' AND created_at < $1
+            ORDER BY created_at
+            LIMIT $2
+            "#
+        )
+        .bind(before)
+        .bind(limit.clamp(1, 1000))
+        .fetch_all(self.pool().await?)
+        .await?;
+        
+        Ok(results)
+    }
+    
+    /// Get player's transactions with pagination
+    /// 
+    /// ADVANTAGE: Pagination is type-safe with proper bounds
+    #[instrument(skip(self), fields(player_id = %player_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn get_player_transactions(
+        &self,
+        player_id: Uuid,
+        limit: i32,
+        cursor: Option<Uuid>,
+    ) -> AppResult<Vec<Transaction>> {
+        // ADVANTAGE: Limit is i32, not any - can't pass "DROP TABLE"
+        let safe_limit = limit.clamp(1, 1000);
+        
+        let results = match cursor {
+            Some(cursor_id) => {
+                sqlx::query_as::<_, Transaction>(
+                    r#"
+                    SELECT * FROM microtransactions
+                    WHERE (player_id = $1 OR recipient_id = $1) AND transaction_id < $2
+                    ORDER BY created_at DESC
+                    LIMIT $3
+                    "#
+                )
+                .bind(player_id)
+                .bind(cursor_id)
+                .bind(safe_limit)
+                .fetch_all(self.pool().await?)
+                .await?
+            }
+            None => {
+                sqlx::query_as::<_, Transaction>(
+                    r#"
+                    SELECT * FROM microtransactions
+                    WHERE player_id = $1 OR recipient_id = $1
+                    ORDER BY created_at DESC
+                    LIMIT $2
+                    "#
+                )
+                .bind(player_id)
+                .bind(safe_limit)
+                .fetch_all(self.pool().await?)
+                .await?
+            }
+        };
+        
+        info!(count = results.len(), "Retrieved player transactions");
+        Ok(results)
+    }
+    
+    /// Apply embedded migrations under an advisory lock
+    async fn run_migrations(&self, dry_run: bool) -> AppResult<MigrationReport> {
+        Migrator::new(MIGRATIONS).run(self.pool().await?, dry_run).await
+    }
+    
+    async fn schema_version(&self) -> AppResult<Option<i64>> {
+        Migrator::new(MIGRATIONS).current_version(self.pool().await?).await
+    }
+
+    /// Append under a transaction-scoped advisory lock on player and currency
+    /// 
+    /// ADVANTAGE: The unique `(player_id, currency, sequence)` constraint backs
+    /// the lock - a racing writer fails instead of double-spending
+    #[instrument(skip(self, entry), fields(entry_id = %entry.entry_id, kind = entry.kind.as_str(), otel.kind = "client", db.system = "postgresql"))]
+    async fn append_ledger_entry(&self, entry: &NewLedgerEntry) -> AppResult<LedgerEntry> {
+        let entry = entry.clone();
+        
+        let appended = self.with_transaction(move |tx| Box::pin(async move {
+            append_ledger(tx, &entry).await
+        })).await?;
+        
+        info!(sequence = appended.sequence, "Ledger entry appended");
+        Ok(appended)
+    }
+    
+    async fn get_wallet(&self, player_id: Uuid) -> AppResult<Vec<WalletBalance>> {
+        let rows: Vec<(crate::models::VirtualCurrency, i64)> = sqlx::query_as(
+            r#"
+            SELECT DISTINCT ON (currency) currency, balance_after
+            FROM wallet_ledger
+            WHERE player_id = $1
+            ORDER BY currency, sequence DESC
+            "#,
+        )
+            .bind(player_id)
+            .fetch_all(self.pool().await?)
+            .await?;
+        
+        Ok(rows
+            .into_iter()
+            .map(|(currency, balance)| WalletBalance { currency, balance })
+            .collect())
+    }
+    
+    #[instrument(skip(self), fields(player_id = %player_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn get_player_journal(&self, player_id: Uuid, limit: i32) -> AppResult<Vec<JournalEntry>> {
+        let pool = self.pool().await?;
+        
+        let mut entries = sqlx::query_as::<_, JournalEntry>(
+            r#"
+            SELECT journal_id, kind, reference, player_id, request_id, created_at
+            FROM journal_entries
+            WHERE player_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#
+        )
+            .bind(player_id)
+            .bind(limit.clamp(1, 1000))
+            .fetch_all(pool)
+            .await?;
+        
+        let journal_ids: Vec<Uuid> = entries.iter().map(|e| e.journal_id).collect();
+        let rows: Vec<(Uuid, String, AccountType, String, i64)> = sqlx::query_as(
+            r#"
+            SELECT p.journal_id, p.account_code, a.account_type, p.currency, p.amount
+            FROM journal_postings p
+            JOIN ledger_accounts a USING (account_code)
+            WHERE p.journal_id = ANY($1)
+            ORDER BY p.posting_id
+            "#
+        )
+            .bind(&journal_ids)
+            .fetch_all(pool)
+            .await?;
+        
+        attach_postings(&mut entries, rows
+            .into_iter()
+            .map(|(journal_id, account_code, account_type, currency, amount)| {
+                (journal_id, Posting { account_code, account_type, currency, amount })
+            })
+            .collect());
+        
+        Ok(entries)
+    }
+    
+    async fn trial_balance(&self) -> AppResult<TrialBalance> {
+        let lines = sqlx::query_as::<_, TrialBalanceLine>(
+            "SELECT account_code, account_type, currency, debits, credits FROM trial_balance ORDER BY account_code"
+        )
+            .fetch_all(self.pool().await?)
+            .await?;
+        
+        Ok(TrialBalance { lines })
+    }
+    
+    #[instrument(skip(self), fields(player_id = %player_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn get_player_entitlements(&self, player_id: Uuid, include_revoked: bool) -> AppResult<Vec<Entitlement>> {
+        let results = sqlx::query_as::<_, Entitlement>(
+            r#"
+            SELECT entitlement_id, player_id, item_id, quantity, kind, status,
+                   source_transaction_id, granted_at, revoked_at, expires_at
+            FROM entitlements
+            WHERE player_id = $1 AND ($2 OR (status = 'active' AND (expires_at IS NULL OR expires_at > NOW())))
+            ORDER BY granted_at DESC
+            "#
+        )
+            .bind(player_id)
+            .bind(include_revoked)
+            .fetch_all(self.pool().await?)
+            .await?;
+        
+        Ok(results)
+    }
+    
+    /// Claim with `FOR UPDATE SKIP LOCKED` - concurrent dispatchers never
+    /// block on or double-claim each other's rows
+    #[instrument(skip(self), fields(otel.kind = "client", db.system = "postgresql"))]
+    async fn claim_outbox_events(&self, limit: i32, lease: std::time::Duration) -> AppResult<Vec<OutboxEvent>> {
+        let mut events = sqlx::query_as::<_, OutboxEvent>(&format!(
+            r#"
+            UPDATE outbox_events
+            SET attempts = attempts + 1, next_attempt_at = NOW() + make_interval(secs => $2)
+            WHERE event_id IN (
+                SELECT event_id FROM outbox_events
+                WHERE status = $3 AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at, created_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {}
+            "#,
+            OUTBOX_COLUMNS
+        ))
+            .bind(limit.clamp(1, 1000))
+            .bind(lease.as_secs_f64())
+            .bind(OutboxStatus::Pending)
+            .fetch_all(self.pool().await?)
+            .await?;
+        
+        // RETURNING order is unspecified
+        events.sort_by_key(|e| e.created_at);
+        Ok(events)
+    }
+    
+    async fn mark_outbox_delivered(&self, event_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query(
+            "UPDATE outbox_events SET status = $1, delivered_at = NOW(), last_error = NULL WHERE event_id = $2"
+        )
+            .bind(OutboxStatus::Delivered)
+            .bind(event_id)
+            .execute(self.pool().await?)
+            .await?;
+        
+        outbox_updated(result, event_id)
+    }
+    
+    async fn mark_outbox_failed(&self, event_id: Uuid, error: &str, retry_in: Option<std::time::Duration>) -> AppResult<()> {
+        let query = match retry_in {
+            Some(delay) => sqlx::query(
+                "UPDATE outbox_events SET last_error = $1, next_attempt_at = NOW() + make_interval(secs => $3) WHERE event_id = $2"
+            )
+                .bind(error)
+                .bind(event_id)
+                .bind(delay.as_secs_f64()),
+            None => sqlx::query(
+                "UPDATE outbox_events SET last_error = $1, status = $3 WHERE event_id = $2"
+            )
+                .bind(error)
+                .bind(event_id)
+                .bind(OutboxStatus::Dead),
+        };
+        
+        outbox_updated(query.execute(self.pool().await?).await?, event_id)
+    }
+    
+    async fn upsert_catalog_item(&self, item_id: &str, item: &CatalogItemRequest) -> AppResult<CatalogItem> {
+        // The WHERE leaves an existing row alone when the new limit is below its counters
+        sqlx::query_as::<_, CatalogItem>(&format!(
+            r#"
+            INSERT INTO catalog_items (
+                item_id, kind, stock_limit, price_cents, currency,
+                credit_currency, credit_amount, wallet_price, wallet_currency, title_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (item_id) DO UPDATE
+            SET kind = EXCLUDED.kind, stock_limit = EXCLUDED.stock_limit,
+                price_cents = EXCLUDED.price_cents, currency = EXCLUDED.currency,
+                credit_currency = EXCLUDED.credit_currency, credit_amount = EXCLUDED.credit_amount,
+                wallet_price = EXCLUDED.wallet_price, wallet_currency = EXCLUDED.wallet_currency,
+                title_id = EXCLUDED.title_id, updated_at = NOW()
+            WHERE EXCLUDED.stock_limit IS NULL
+                OR EXCLUDED.stock_limit >= catalog_items.stock_reserved + catalog_items.stock_sold
+            RETURNING {}
+            "#,
+            CATALOG_COLUMNS
+        ))
+            .bind(item_id)
+            .bind(item.kind)
+            .bind(item.stock_limit)
+            .bind(item.price_cents)
+            .bind(item.currency.map(|c| c.as_str()))
+            .bind(item.credit_currency)
+            .bind(item.credit_amount)
+            .bind(item.wallet_price)
+            .bind(item.wallet_currency)
+            .bind(item.title_id.as_deref())
+            .fetch_optional(self.pool().await?)
+            .await?
+            .ok_or_else(|| AppError::Conflict(format!(
+                "Item {} already has more units reserved or sold than the new stock limit",
+                item_id
+            )))
+    }
+    
+    async fn get_catalog_item(&self, item_id: &str) -> AppResult<Option<CatalogItem>> {
+        let result = sqlx::query_as::<_, CatalogItem>(&format!(
+            "SELECT {} FROM catalog_items WHERE item_id = $1",
+            CATALOG_COLUMNS
+        ))
+            .bind(item_id)
+            .fetch_optional(self.pool().await?)
+            .await?;
+        
+        Ok(result)
+    }
+    
+    async fn create_webhook_subscription(&self, subscription: &NewWebhookSubscription) -> AppResult<WebhookSubscription> {
+        let result = sqlx::query_as::<_, WebhookSubscription>(&format!(
+            r#"
+            INSERT INTO webhook_subscriptions (subscription_id, title_id, url, secret, event_types)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING {}
+            "#,
+            WEBHOOK_SUBSCRIPTION_COLUMNS
+        ))
+            .bind(subscription.subscription_id)
+            .bind(&subscription.title_id)
+            .bind(&subscription.url)
+            .bind(subscription.secret.expose())
+            .bind(sqlx::types::Json(&subscription.event_types))
+            .fetch_one(self.pool().await?)
+            .await?;
+        
+        info!(subscription_id = %result.subscription_id, "Webhook subscription created");
+        Ok(result)
+    }
+    
+    async fn get_webhook_subscriptions(&self, title_id: &str) -> AppResult<Vec<WebhookSubscription>> {
+        let results = sqlx::query_as::<_, WebhookSubscription>(&format!(
+            "SELECT {} FROM webhook_subscriptions WHERE title_id = $1 ORDER BY created_at",
+            WEBHOOK_SUBSCRIPTION_COLUMNS
+        ))
+            .bind(title_id)
+            .fetch_all(self.pool().await?)
+            .await?;
+        
+        Ok(results)
+    }
+    
+    async fn get_webhook_subscription(&self, subscription_id: Uuid) -> AppResult<Option<WebhookSubscription>> {
+        let result = sqlx::query_as::<_, WebhookSubscription>(&format!(
+            "SELECT {} FROM webhook_subscriptions WHERE subscription_id = $1",
+            WEBHOOK_SUBSCRIPTION_COLUMNS
+        ))
+            .bind(subscription_id)
+            .fetch_optional(self.pool().await?)
+            .await?;
+        
+        Ok(result)
+    }
+    
+    async fn get_webhook_deliveries(&self, subscription_id: Uuid, limit: i32) -> AppResult<Vec<WebhookDelivery>> {
+        let results = sqlx::query_as::<_, WebhookDelivery>(&format!(
+            r#"
+            SELECT {} FROM webhook_deliveries
+            WHERE subscription_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            WEBHOOK_DELIVERY_COLUMNS
+        ))
+            .bind(subscription_id)
+            .bind(limit.clamp(1, 1000))
+            .fetch_all(self.pool().await?)
+            .await?;
+        
+        Ok(results)
+    }
+    
+    /// Same `FOR UPDATE SKIP LOCKED` claim as the outbox
+    #[instrument(skip(self), fields(otel.kind = "client", db.system = "postgresql"))]
+    async fn claim_webhook_deliveries(&self, limit: i32, lease: std::time::Duration) -> AppResult<Vec<WebhookDispatch>> {
+        let rows = sqlx::query_as::<_, ClaimedWebhookRow>(&format!(
+            r#"
+            WITH claimed AS (
+                UPDATE webhook_deliveries
+                SET attempts = attempts + 1, next_attempt_at = NOW() + make_interval(secs => $2)
+                WHERE delivery_id IN (
+                    SELECT delivery_id FROM webhook_deliveries
+                    WHERE status = $3 AND next_attempt_at <= NOW()
+                    ORDER BY next_attempt_at, created_at
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING {}
+            )
+            SELECT claimed.*, s.url, s.secret
+            FROM claimed JOIN webhook_subscriptions s USING (subscription_id)
+            ORDER BY claimed.created_at
+            "#,
+            WEBHOOK_DELIVERY_COLUMNS
+        ))
+            .bind(limit.clamp(1, 1000))
+            .bind(lease.as_secs_f64())
+            .bind(WebhookDeliveryStatus::Pending)
+            .fetch_all(self.pool().await?)
+            .await?;
+        
+        Ok(rows
+            .into_iter()
+            .map(|row| WebhookDispatch { delivery: row.delivery, url: row.url, secret: row.secret.into() })
+            .collect())
+    }
+    
+    async fn mark_webhook_delivered(&self, delivery_id: Uuid, status_code: i32) -> AppResult<()> {
+        let result = sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $1, delivered_at = NOW(), last_status_code = $2, last_error = NULL
+            WHERE delivery_id = $3
+            "#
+        )
+            .bind(WebhookDeliveryStatus::Delivered)
+            .bind(status_code)
+            .bind(delivery_id)
+            .execute(self.pool().await?)
+            .await?;
+        
+        delivery_updated(result, delivery_id)
+    }
+    
+    async fn mark_webhook_failed(
+        &self,
+        delivery_id: Uuid,
+        status_code: Option<i32>,
+        error: &str,
+        retry_in: Option<std::time::Duration>,
+    ) -> AppResult<()> {
+        let query = match retry_in {
+            Some(delay) => sqlx::query(
+                r#"
+                UPDATE webhook_deliveries
+                SET last_status_code = $1, last_error = $2, next_attempt_at = NOW() + make_interval(secs => $4)
+                WHERE delivery_id = $3
+                "#
+            )
+                .bind(status_code)
+                .bind(error)
+                .bind(delivery_id)
+                .bind(delay.as_secs_f64()),
+            None => sqlx::query(
+                r#"
+                UPDATE webhook_deliveries
+                SET last_status_code = $1, last_error = $2, status = $4
+                WHERE delivery_id = $3
+                "#
+            )
+                .bind(status_code)
+                .bind(error)
+                .bind(delivery_id)
+                .bind(WebhookDeliveryStatus::Dead),
+        };
+        
+        delivery_updated(query.execute(self.pool().await?).await?, delivery_id)
+    }
+    
+    async fn create_promotion(&self, promotion: &NewPromotion) -> AppResult<Promotion> {
+        let promotion = promotion.clone().into_promotion(chrono::Utc::now());
+        
+        Ok(sqlx::query_as::<_, Promotion>(&format!(
+            r#"
+            INSERT INTO promotions ({0})
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            RETURNING {0}
+            "#,
+            PROMOTION_COLUMNS
+        ))
+            .bind(promotion.promotion_id)
+            .bind(&promotion.name)
+            .bind(promotion.kind)
+            .bind(promotion.value)
+            .bind(&promotion.currency)
+            .bind(sqlx::types::Json(&promotion.item_ids))
+            .bind(sqlx::types::Json(&promotion.segments))
+            .bind(sqlx::types::Json(&promotion.bundle_items))
+            .bind(promotion.stackable)
+            .bind(promotion.priority)
+            .bind(promotion.starts_at)
+            .bind(promotion.ends_at)
+            .bind(promotion.created_at)
+            .fetch_one(self.pool().await?)
+            .await?)
+    }
+    
+    async fn get_promotions(&self, limit: i32) -> AppResult<Vec<Promotion>> {
+        Ok(sqlx::query_as::<_, Promotion>(&format!(
+            "SELECT {} FROM promotions ORDER BY starts_at DESC LIMIT $1",
+            PROMOTION_COLUMNS
+        ))
+            .bind(i64::from(limit.clamp(1, 1000)))
+            .fetch_all(self.pool().await?)
+            .await?)
+    }
+    
+    async fn get_active_promotions(&self, item_id: &str, at: chrono::DateTime<chrono::Utc>) -> AppResult<Vec<Promotion>> {
+        Ok(sqlx::query_as::<_, Promotion>(&format!(
+            r#"
+            SELECT {} FROM promotions
+            WHERE ends_at > $2 AND starts_at <= $2
+              AND (item_ids = '[]'::jsonb OR item_ids ? $1)
+            ORDER BY priority DESC, created_at
+            "#,
+            PROMOTION_COLUMNS
+        ))
+            .bind(item_id)
+            .bind(at)
+            .fetch_all(self.pool().await?)
+            .await?)
+    }
+    
+    async fn put_player_profile(&self, player_id: Uuid, segments: &[String]) -> AppResult<PlayerProfile> {
+        Ok(sqlx::query_as::<_, PlayerProfile>(&format!(
+            r#"
+            INSERT INTO player_profiles (player_id, segments)
+            VALUES ($1, $2)
+            ON CONFLICT (player_id) DO UPDATE
+            SET segments = EXCLUDED.segments, updated_at = NOW()
+            RETURNING {}
+            "#,
+            PLAYER_PROFILE_COLUMNS
+        ))
+            .bind(player_id)
+            .bind(sqlx::types::Json(segments))
+            .fetch_one(self.pool().await?)
+            .await?)
+    }
+    
+    async fn get_player_profile(&self, player_id: Uuid) -> AppResult<Option<PlayerProfile>> {
+        Ok(sqlx::query_as::<_, PlayerProfile>(&format!(
+            "SELECT {} FROM player_profiles WHERE player_id = $1",
+            PLAYER_PROFILE_COLUMNS
+        ))
+            .bind(player_id)
+            .fetch_optional(self.pool().await?)
+            .await?)
+    }
+    
+    async fn create_code_campaign(&self, campaign: &NewCodeCampaign) -> AppResult<CodeCampaign> {
+        let codes = campaign.codes.clone();
+        let campaign = campaign.clone().into_campaign(chrono::Utc::now());
+        
+        self.with_transaction(move |conn| Box::pin(async move {
+            let created = sqlx::query_as::<_, CodeCampaign>(&format!(
+                r#"
+                INSERT INTO code_campaigns ({0})
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING {0}
+                "#,
+                CODE_CAMPAIGN_COLUMNS
+            ))
+                .bind(campaign.campaign_id)
+                .bind(&campaign.name)
+                .bind(sqlx::types::Json(&campaign.reward))
+                .bind(campaign.max_uses)
+                .bind(campaign.per_player_limit)
+                .bind(campaign.expires_at)
+                .bind(campaign.code_count)
+                .bind(campaign.created_at)
+                .fetch_one(&mut **conn)
+                .await?;
+            
+            sqlx::query("INSERT INTO redeem_codes (code, campaign_id) SELECT UNNEST($1::text[]), $2")
+                .bind(&codes)
+                .bind(campaign.campaign_id)
+                .execute(&mut **conn)
+                .await?;
+            
+            Ok(created)
+        })).await
+    }
+    
+    async fn get_code_campaign(&self, code: &str) -> AppResult<Option<CodeCampaign>> {
+        Ok(sqlx::query_as::<_, CodeCampaign>(&format!(
+            "SELECT {} FROM code_campaigns WHERE campaign_id = (SELECT campaign_id FROM redeem_codes WHERE code = $1)",
+            CODE_CAMPAIGN_COLUMNS
+        ))
+            .bind(code)
+            .fetch_optional(self.pool().await?)
+            .await?)
+    }
+    
+    async fn record_code_failure(&self, subject: &str) -> AppResult<()> {
+        sqlx::query("INSERT INTO code_failures (subject) VALUES ($1)")
+            .bind(subject)
+            .execute(self.pool().await?)
+            .await?;
+        Ok(())
+    }
+    
+    async fn count_code_failures(&self, subject: &str, since: chrono::DateTime<chrono::Utc>) -> AppResult<i64> {
+        Ok(sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM code_failures WHERE subject = $1 AND failed_at > $2")
+            .bind(subject)
+            .bind(since)
+            .fetch_one(self.pool().await?)
+            .await?)
+    }
+    
+    async fn upsert_subscription_plan(&self, plan: &SubscriptionPlan) -> AppResult<SubscriptionPlan> {
+        let result = sqlx::query_as::<_, SubscriptionPlan>(&format!(
+            r#"
+            INSERT INTO subscription_plans (
+                plan_id, name, item_id, price_cents, currency, period_days, season_ends_at, grace_period_days
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (plan_id) DO UPDATE
+            SET name = EXCLUDED.name,
+                item_id = EXCLUDED.item_id,
+                price_cents = EXCLUDED.price_cents,
+                currency = EXCLUDED.currency,
+                period_days = EXCLUDED.period_days,
+                season_ends_at = EXCLUDED.season_ends_at,
+                grace_period_days = EXCLUDED.grace_period_days,
+                updated_at = NOW()
+            RETURNING {}
+            "#,
+            SUBSCRIPTION_PLAN_COLUMNS
+        ))
+            .bind(&plan.plan_id)
+            .bind(&plan.name)
+            .bind(&plan.item_id)
+            .bind(plan.price_cents)
+            .bind(&plan.currency)
+            .bind(plan.period_days)
+            .bind(plan.season_ends_at)
+            .bind(plan.grace_period_days)
+            .fetch_one(self.pool().await?)
+            .await?;
+        
+        Ok(result)
+    }
+    
+    async fn get_subscription_plan(&self, plan_id: &str) -> AppResult<Option<SubscriptionPlan>> {
+        let result = sqlx::query_as::<_, SubscriptionPlan>(&format!(
+            "SELECT {} FROM subscription_plans WHERE plan_id = $1",
+            SUBSCRIPTION_PLAN_COLUMNS
+        ))
+            .bind(plan_id)
+            .fetch_optional(self.pool().await?)
+            .await?;
+        
+        Ok(result)
+    }
+    
+    #[instrument(skip(self, subscription), fields(subscription_id = %subscription.subscription_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn insert_subscription(&self, subscription: &Subscription) -> AppResult<Subscription> {
+        let subscription = subscription.clone();
+        self.with_transaction(move |conn| Box::pin(async move {
+            check_live_subscription(conn, &subscription).await?;
+            
+            let result = sqlx::query_as::<_, Subscription>(&format!(
+                r#"
+                INSERT INTO subscriptions (
+                    subscription_id, player_id, plan_id, status, payment_method,
+                    current_period_start, current_period_end, cancel_at_period_end, renewal_attempts,
+                    grace_ends_at, next_billing_at, credit_cents, entitlement_transaction_id,
+                    renewal_transaction_id, ended_at, version, created_at, updated_at
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+                RETURNING {}
+                "#,
+                SUBSCRIPTION_COLUMNS
+            ))
+                .bind(subscription.subscription_id)
+                .bind(subscription.player_id)
+                .bind(&subscription.plan_id)
+                .bind(subscription.status)
+                .bind(&subscription.payment_method)
+                .bind(subscription.current_period_start)
+                .bind(subscription.current_period_end)
+                .bind(subscription.cancel_at_period_end)
+                .bind(subscription.renewal_attempts)
+                .bind(subscription.grace_ends_at)
+                .bind(subscription.next_billing_at)
+                .bind(subscription.credit_cents)
+                .bind(subscription.entitlement_transaction_id)
+                .bind(subscription.renewal_transaction_id)
+                .bind(subscription.ended_at)
+                .bind(subscription.version)
+                .bind(subscription.created_at)
+                .bind(subscription.updated_at)
+                .fetch_one(&mut **conn)
+                .await?;
+            
+            Ok(result)
+        })).await
+    }
+    
+    async fn get_subscription(&self, subscription_id: Uuid) -> AppResult<Option<Subscription>> {
+        let result = sqlx::query_as::<_, Subscription>(&format!(
+            "SELECT {} FROM subscriptions WHERE subscription_id = $1",
+            SUBSCRIPTION_COLUMNS
+        ))
+            .bind(subscription_id)
+            .fetch_optional(self.pool().await?)
+            .await?;
+        
+        Ok(result)
+    }
+    
+    async fn get_player_subscriptions(&self, player_id: Uuid) -> AppResult<Vec<Subscription>> {
+        let results = sqlx::query_as::<_, Subscription>(&format!(
+            "SELECT {} FROM subscriptions WHERE player_id = $1 ORDER BY created_at DESC",
+            SUBSCRIPTION_COLUMNS
+        ))
+            .bind(player_id)
+            .fetch_all(self.pool().await?)
+            .await?;
+        
+        Ok(results)
+    }
+    
+    async fn get_due_subscriptions(&self, now: chrono::DateTime<chrono::Utc>, limit: i32) -> AppResult<Vec<Subscription>> {
+        let results = sqlx::query_as::<_, Subscription>(&format!(
+            r#"
+            SELECT {} FROM subscriptions
+            WHERE status IN ('active', 'past_due') AND next_billing_at <= $1
+            ORDER BY next_billing_at
+            LIMIT $2
+            "#,
+            SUBSCRIPTION_COLUMNS
+        ))
+            .bind(now)
+            .bind(limit as i64)
+            .fetch_all(self.pool().await?)
+            .await?;
+        
+        Ok(results)
+    }
+    
+    #[instrument(skip(self, subscription), fields(subscription_id = %subscription.subscription_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn update_subscription(&self, subscription: &Subscription) -> AppResult<Subscription> {
+        let subscription = subscription.clone();
+        self.with_transaction(move |conn| Box::pin(async move {
+            let stored = sqlx::query_as::<_, Subscription>(&format!(
+                "SELECT {} FROM subscriptions WHERE subscription_id = $1 FOR UPDATE",
+                SUBSCRIPTION_COLUMNS
+            ))
+                .bind(subscription.subscription_id)
+                .fetch_optional(&mut **conn)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Subscription {} not found", subscription.subscription_id)))?;
+            check_subscription_version(&subscription, &stored)?;
+            if subscription.plan_id != stored.plan_id || subscription.status.is_live() != stored.status.is_live() {
+                check_live_subscription(conn, &subscription).await?;
+            }
+            
+            let saved = sqlx::query_as::<_, Subscription>(&format!(
+                r#"
+                UPDATE subscriptions
+                SET plan_id = $2,
+                    status = $3,
+                    payment_method = $4,
+                    current_period_start = $5,
+                    current_period_end = $6,
+                    cancel_at_period_end = $7,
+                    renewal_attempts = $8,
+                    grace_ends_at = $9,
+                    next_billing_at = $10,
+                    credit_cents = $11,
+                    entitlement_transaction_id = $12,
+                    renewal_transaction_id = $13,
+                    ended_at = $14,
+                    version = version + 1,
+                    updated_at = NOW()
+                WHERE subscription_id = $1
+                RETURNING {}
+                "#,
+                SUBSCRIPTION_COLUMNS
+            ))
+                .bind(subscription.subscription_id)
+                .bind(&subscription.plan_id)
+                .bind(subscription.status)
+                .bind(&subscription.payment_method)
+                .bind(subscription.current_period_start)
+                .bind(subscription.current_period_end)
+                .bind(subscription.cancel_at_period_end)
+                .bind(subscription.renewal_attempts)
+                .bind(subscription.grace_ends_at)
+                .bind(subscription.next_billing_at)
+                .bind(subscription.credit_cents)
+                .bind(subscription.entitlement_transaction_id)
+                .bind(subscription.renewal_transaction_id)
+                .bind(subscription.ended_at)
+                .fetch_one(&mut **conn)
+                .await?;
+            
+            for (transaction_id, expires_at) in saved.access_changes(&stored, chrono::Utc::now()) {
+                sqlx::query("UPDATE entitlements SET expires_at = $2 WHERE source_transaction_id = $1")
+                    .bind(transaction_id)
+                    .bind(expires_at)
+                    .execute(&mut **conn)
+                    .await?;
+            }
+            
+            Ok(saved)
+        })).await
+    }
+    
+    async fn redeliver_webhook(&self, delivery_id: Uuid) -> AppResult<WebhookDelivery> {
+        sqlx::query_as::<_, WebhookDelivery>(&format!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $1, attempts = 0, next_attempt_at = NOW(), delivered_at = NULL
+            WHERE delivery_id = $2
+            RETURNING {}
+            "#,
+            WEBHOOK_DELIVERY_COLUMNS
+        ))
+            .bind(WebhookDeliveryStatus::Pending)
+            .bind(delivery_id)
+            .fetch_optional(self.pool().await?)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Webhook delivery {} not found", delivery_id)))
+    }
+    
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // ADVANTAGE: Tests use same types as production code
+    // Invalid queries would fail to compile
+}
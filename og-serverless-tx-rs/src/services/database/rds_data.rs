@@ -0,0 +1,2739 @@
+//! # Aurora Data API Database Backend
+//!
+//! ADVANTAGE: HTTPS-only access - the Lambda can run outside the VPC
+//! ADVANTAGE: No connection pool to open on cold start
+//! ADVANTAGE: Records are mapped to the same typed `Transaction` as sqlx
+//! ADVANTAGE: Begin/Commit/Rollback map onto the Data API transaction calls
+
+use async_trait::async_trait;
+use aws_sdk_rdsdata::Client;
+use aws_sdk_rdsdata::error::DisplayErrorContext;
+use aws_sdk_rdsdata::operation::execute_statement::ExecuteStatementOutput;
+use aws_sdk_rdsdata::types::{Field, SqlParameter, TypeHint};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::{Transaction, TransactionStatus, NewTransaction};
+use crate::models::{Entitlement, JournalEntry, LedgerEntry, NewJournalEntry, NewLedgerEntry};
+use crate::models::{OutboxEvent, OutboxStatus, TransitionEffects, TrialBalance, WalletBalance};
+use crate::models::{CatalogItem, CatalogItemRequest, NewPromotion, PlayerProfile, Promotion, StockSettlement};
+use crate::models::{CodeCampaign, NewCodeCampaign};
+use crate::models::GiftStatus;
+use crate::models::{Subscription, SubscriptionPlan};
+use crate::models::gift::{self, check_gift_limits, GIFT_LIMIT_WINDOW};
+use crate::models::{NewWebhookSubscription, WebhookDelivery, WebhookDeliveryStatus, WebhookDispatch, WebhookSubscription};
+use crate::models::config::DataApiConfig;
+use crate::models::journal::{Posting, TrialBalanceLine};
+use super::{attach_postings, check_subscription_version, check_transition, live_subscription_conflict};
+use super::{Database, MigrationReport, MIGRATIONS};
+use super::migrations::{self, AppliedMigration};
+
+/// Column list shared by every query that maps rows to `Transaction`
+///
+/// ADVANTAGE: Column order is fixed here and mirrored by `transaction_from_record`
+const TRANSACTION_COLUMNS: &str = "transaction_id, player_id, item_id, item_name, \
+    price_cents, currency, quantity, status, metadata, processor_id, created_at, updated_at, \
+    request_id, original_price_cents, applied_promotions, recipient_id, gift_status";
+
+/// Columns of `wallet_ledger`, in `ledger_entry_from_record` order
+const LEDGER_COLUMNS: &str = "entry_id, player_id, currency, sequence, amount, balance_after, \
+    kind, reference, item_id, request_id, created_at";
+
+/// Columns of `entitlements`, in `entitlement_from_record` order
+const ENTITLEMENT_COLUMNS: &str = "entitlement_id, player_id, item_id, quantity, kind, status, \
+    source_transaction_id, granted_at, revoked_at, expires_at";
+
+/// Columns of `subscription_plans`, in `subscription_plan_from_record` order
+const SUBSCRIPTION_PLAN_COLUMNS: &str = "plan_id, name, item_id, price_cents, currency, period_days, \
+    season_ends_at, grace_period_days, updated_at";
+
+/// Columns of `subscriptions`, in `subscription_from_record` order
+const SUBSCRIPTION_COLUMNS: &str = "subscription_id, player_id, plan_id, status, payment_method, \
+    current_period_start, current_period_end, cancel_at_period_end, renewal_attempts, grace_ends_at, \
+    next_billing_at, credit_cents, entitlement_transaction_id, renewal_transaction_id, ended_at, \
+    version, created_at, updated_at";
+
+/// Columns of `journal_entries`, in `journal_entry_from_record` order
+const JOURNAL_COLUMNS: &str = "journal_id, kind, reference, player_id, request_id, created_at";
+
+/// Columns of `outbox_events`, in `outbox_event_from_record` order
+const OUTBOX_COLUMNS: &str = "event_id, event_type, aggregate_id, payload, status, attempts, \
+    next_attempt_at, last_error, created_at, delivered_at";
+
+/// Columns of `catalog_items`, in `catalog_item_from_record` order
+const CATALOG_COLUMNS: &str = "item_id, kind, stock_limit, stock_reserved, stock_sold, price_cents, currency, \
+    credit_currency, credit_amount, wallet_price, wallet_currency, title_id, updated_at";
+
+/// Columns of `promotions`, in `promotion_from_record` order
+const PROMOTION_COLUMNS: &str = "promotion_id, name, kind, value, currency, item_ids, segments, bundle_items, \
+    stackable, priority, starts_at, ends_at, created_at";
+
+/// Columns of `player_profiles`, in `player_profile_from_record` order
+const PLAYER_PROFILE_COLUMNS: &str = "player_id, segments, updated_at";
+
+/// Columns of `code_campaigns`, in `code_campaign_from_record` order
+const CODE_CAMPAIGN_COLUMNS: &str = "campaign_id, name, reward, max_uses, per_player_limit, expires_at, \
+    code_count, created_at";
+
+/// Columns of `webhook_subscriptions`, in `webhook_subscription_from_record` order
+const WEBHOOK_SUBSCRIPTION_COLUMNS: &str = "subscription_id, title_id, url, secret, event_types, created_at";
+
+/// Columns of `webhook_deliveries`, in `webhook_delivery_from_record` order
+const WEBHOOK_DELIVERY_COLUMNS: &str = "delivery_id, subscription_id, event_id, event_type, payload, status, \
+    attempts, next_attempt_at, last_status_code, last_error, created_at, delivered_at";
+
+/// Aurora Data API database service
+///
+/// ADVANTAGE: SDK client is cheap to clone and reused across warm starts
+/// ADVANTAGE: Cluster and secret ARNs are fixed at construction
+pub struct RdsDataDatabase {
+    client: Client,
+    config: DataApiConfig,
+}
+
+impl RdsDataDatabase {
+    /// Create Data API client from the default AWS credential chain
+    ///
+    /// ADVANTAGE: `endpoint_url` override allows pointing at a local stand-in
+    pub async fn new(config: &DataApiConfig) -> AppResult<Self> {
+        let sdk_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let mut builder = aws_sdk_rdsdata::config::Builder::from(&sdk_config);
+
+        if let Some(endpoint_url) = &config.endpoint_url {
+            builder = builder.endpoint_url(endpoint_url);
+        }
+
+        let client = Client::from_conf(builder.build());
+
+        info!(database = %config.database, "Data API client initialized");
+        Ok(Self::from_client(client, config.clone()))
+    }
+
+    /// Create service from an already-configured SDK client
+    pub fn from_client(client: Client, config: DataApiConfig) -> Self {
+        Self { client, config }
+    }
+
+    /// Run a single statement via ExecuteStatement
+    ///
+    /// ADVANTAGE: Named parameters are typed `SqlParameter`s - no string splicing
+    pub async fn execute(
+        &self,
+        sql: &str,
+        parameters: Vec<SqlParameter>,
+        transaction_id: Option<&str>,
+    ) -> AppResult<ExecuteStatementOutput> {
+        self.client
+            .execute_statement()
+            .resource_arn(&self.config.resource_arn)
+            .secret_arn(&self.config.secret_arn)
+            .database(&self.config.database)
+            .sql(sql)
+            .set_parameters(Some(parameters))
+            .set_transaction_id(transaction_id.map(str::to_string))
+            .send()
+            .await
+            .map_err(data_api_error)
+    }
+
+    /// Run one statement for many parameter sets via BatchExecuteStatement
+    ///
+    /// Returns the number of parameter sets the Data API acknowledged.
+    pub async fn batch_execute(
+        &self,
+        sql: &str,
+        parameter_sets: Vec<Vec<SqlParameter>>,
+        transaction_id: Option<&str>,
+    ) -> AppResult<usize> {
+        let output = self.client
+            .batch_execute_statement()
+            .resource_arn(&self.config.resource_arn)
+            .secret_arn(&self.config.secret_arn)
+            .database(&self.config.database)
+            .sql(sql)
+            .set_parameter_sets(Some(parameter_sets))
+            .set_transaction_id(transaction_id.map(str::to_string))
+            .send()
+            .await
+            .map_err(data_api_error)?;
+
+        Ok(output.update_results().len())
+    }
+
+    /// Begin a Data API transaction, returning its ID
+    pub async fn begin_transaction(&self) -> AppResult<String> {
+        let output = self.client
+            .begin_transaction()
+            .resource_arn(&self.config.resource_arn)
+            .secret_arn(&self.config.secret_arn)
+            .database(&self.config.database)
+            .send()
+            .await
+            .map_err(data_api_error)?;
+
+        output
+            .transaction_id()
+            .map(str::to_string)
+            .ok_or_else(|| AppError::DataApi("BeginTransaction returned no transaction ID".into()))
+    }
+
+    /// Commit a Data API transaction
+    pub async fn commit_transaction(&self, transaction_id: &str) -> AppResult<()> {
+        self.client
+            .commit_transaction()
+            .resource_arn(&self.config.resource_arn)
+            .secret_arn(&self.config.secret_arn)
+            .transaction_id(transaction_id)
+            .send()
+            .await
+            .map_err(data_api_error)?;
+
+        Ok(())
+    }
+
+    /// Roll back a Data API transaction
+    pub async fn rollback_transaction(&self, transaction_id: &str) -> AppResult<()> {
+        self.client
+            .rollback_transaction()
+            .resource_arn(&self.config.resource_arn)
+            .secret_arn(&self.config.secret_arn)
+            .transaction_id(transaction_id)
+            .send()
+            .await
+            .map_err(data_api_error)?;
+
+        Ok(())
+    }
+
+    /// Execute a transactional operation
+    ///
+    /// ADVANTAGE: Closure receives the Data API transaction ID to pass along
+    /// ADVANTAGE: Rolled back on error, committed on success
+    pub async fn with_transaction<F, Fut, T>(&self, f: F) -> AppResult<T>
+    where
+        F: FnOnce(String) -> Fut + Send,
+        Fut: Future<Output = AppResult<T>> + Send,
+        T: Send,
+    {
+        let transaction_id = self.begin_transaction().await?;
+
+        match f(transaction_id.clone()).await {
+            Ok(result) => {
+                self.commit_transaction(&transaction_id).await?;
+                Ok(result)
+            }
+            Err(e) => {
+                if let Err(rollback_err) = self.rollback_transaction(&transaction_id).await {
+                    warn!(error = %rollback_err, "Data API rollback failed");
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Rows of `schema_migrations`, empty if the table does not exist yet
+    async fn applied_migrations(&self, transaction_id: Option<&str>) -> AppResult<Vec<AppliedMigration>> {
+        let exists = self
+            .execute("SELECT to_regclass('schema_migrations') IS NOT NULL", Vec::new(), transaction_id)
+            .await?;
+        let exists = match exists.records().first() {
+            Some(record) => RecordReader::new(record).boolean("exists")?,
+            None => false,
+        };
+        if !exists {
+            return Ok(Vec::new());
+        }
+
+        let output = self
+            .execute("SELECT version, checksum FROM schema_migrations ORDER BY version", Vec::new(), transaction_id)
+            .await?;
+
+        output
+            .records()
+            .iter()
+            .map(|record| {
+                let mut reader = RecordReader::new(record);
+                Ok(AppliedMigration {
+                    version: reader.long("version")?,
+                    checksum: reader.string("checksum")?.trim().to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Run a statement and map every returned record to `Transaction`
+    async fn query_transactions(
+        &self,
+        sql: &str,
+        parameters: Vec<SqlParameter>,
+    ) -> AppResult<Vec<Transaction>> {
+        let output = self.execute(sql, parameters, None).await?;
+
+        output
+            .records()
+            .iter()
+            .map(|record| transaction_from_record(record))
+            .collect()
+    }
+
+    /// Lock the catalog row of `tx.item_id` and check the purchase against it,
+    /// returning the stock to reserve and the stamped metadata
+    async fn check_catalog_purchase(
+        &self,
+        tx: &NewTransaction,
+        data_api_tx: &str,
+    ) -> AppResult<(i32, serde_json::Value)> {
+        let tid = Some(data_api_tx);
+
+        let item = self.execute(
+            &format!("SELECT {} FROM catalog_items WHERE item_id = :item_id FOR UPDATE", CATALOG_COLUMNS),
+            vec![string_param("item_id", &tx.item_id)],
+            tid,
+        )
+        .await?;
+        let item = item
+            .records()
+            .first()
+            .ok_or_else(|| AppError::DataApi("Catalog item disappeared".into()))
+            .and_then(|record| catalog_item_from_record(record))?;
+
+        let owned = item.kind.is_unique() && {
+            let output = self.execute(
+                "SELECT EXISTS (\
+                     SELECT 1 FROM entitlements \
+                     WHERE player_id = :owner_id AND item_id = :item_id AND status = 'active' \
+                       AND expires_at IS NULL\
+                 ) OR EXISTS (\
+                     SELECT 1 FROM microtransactions \
+                     WHERE COALESCE(recipient_id, player_id) = :owner_id \
+                       AND item_id = :item_id AND status = 'pending'\
+                 )",
+                vec![
+                    uuid_param("owner_id", tx.recipient_id.unwrap_or(tx.player_id)),
+                    string_param("item_id", &tx.item_id),
+                ],
+                tid,
+            )
+            .await?;
+            matches!(output.records().first().and_then(|r| r.first()), Some(Field::BooleanValue(true)))
+        };
+
+        let reserve = item.check_purchase(tx.quantity, owned)?;
+        Ok((reserve, item.stamp(&tx.metadata)))
+    }
+
+    /// Check a gift from `payer_id` to `recipient_id` against the daily limits
+    ///
+    /// Advisory locks on both players, taken in a fixed order, serialize
+    /// gifts that share a payer or recipient.
+    async fn check_gift_limits(&self, payer_id: Uuid, recipient_id: Uuid, data_api_tx: &str) -> AppResult<()> {
+        let tid = Some(data_api_tx);
+
+        let mut players = [payer_id, recipient_id];
+        players.sort();
+        for player_id in players {
+            self.execute(
+                "SELECT pg_advisory_xact_lock(hashtextextended(:key, 0))",
+                vec![string_param("key", &format!("gift:{}", player_id))],
+                tid,
+            )
+            .await?;
+        }
+
+        let counts = self
+            .execute(
+                "SELECT COUNT(*) FILTER (WHERE player_id = :payer_id), \
+                        COUNT(*) FILTER (WHERE recipient_id = :recipient_id) \
+                 FROM microtransactions \
+                 WHERE (player_id = :payer_id OR recipient_id = :recipient_id) \
+                   AND recipient_id IS NOT NULL AND status <> 'failed' AND created_at > :since",
+                vec![
+                    uuid_param("payer_id", payer_id),
+                    uuid_param("recipient_id", recipient_id),
+                    timestamp_param("since", Utc::now() - GIFT_LIMIT_WINDOW),
+                ],
+                tid,
+            )
+            .await?;
+        let (sent, received) = counts
+            .records()
+            .first()
+            .ok_or_else(|| AppError::DataApi("COUNT returned no record".into()))
+            .and_then(|record| {
+                let mut reader = RecordReader::new(record);
+                Ok((reader.long("sent")?, reader.long("received")?))
+            })?;
+
+        check_gift_limits(sent, received)
+    }
+
+    /// Reject a live `subscription` when the player holds another live one
+    /// to its plan, under the same advisory lock as the Postgres backend
+    async fn check_live_subscription(&self, subscription: &Subscription, data_api_tx: &str) -> AppResult<()> {
+        if !subscription.status.is_live() {
+            return Ok(());
+        }
+        let tid = Some(data_api_tx);
+
+        self.execute(
+            "SELECT pg_advisory_xact_lock(hashtextextended(:key, 0))",
+            vec![string_param(
+                "key",
+                &format!("subscription:{}:{}", subscription.player_id, subscription.plan_id),
+            )],
+            tid,
+        )
+        .await?;
+
+        let output = self
+            .execute(
+                "SELECT EXISTS (\
+                     SELECT 1 FROM subscriptions \
+                     WHERE player_id = :player_id AND plan_id = :plan_id \
+                       AND subscription_id <> :subscription_id AND status IN ('active', 'past_due')\
+                 )",
+                vec![
+                    uuid_param("player_id", subscription.player_id),
+                    string_param("plan_id", &subscription.plan_id),
+                    uuid_param("subscription_id", subscription.subscription_id),
+                ],
+                tid,
+            )
+            .await?;
+        if matches!(output.records().first().and_then(|r| r.first()), Some(Field::BooleanValue(true))) {
+            return Err(live_subscription_conflict(&subscription.plan_id));
+        }
+        Ok(())
+    }
+
+    /// Lock `code` and check one more redemption by `player_id` against its campaign
+    async fn check_code_redemption(&self, code: &str, player_id: Uuid, data_api_tx: &str) -> AppResult<()> {
+        let tid = Some(data_api_tx);
+
+        let locked = self
+            .execute(
+                "SELECT campaign_id FROM redeem_codes WHERE code = :code FOR UPDATE",
+                vec![string_param("code", code)],
+                tid,
+            )
+            .await?;
+        let campaign_id = locked
+            .records()
+            .first()
+            .ok_or_else(|| AppError::NotFound("Code not found".into()))
+            .and_then(|record| RecordReader::new(record).uuid("campaign_id"))?;
+
+        let campaign = self
+            .execute(
+                &format!("SELECT {} FROM code_campaigns WHERE campaign_id = :campaign_id", CODE_CAMPAIGN_COLUMNS),
+                vec![uuid_param("campaign_id", campaign_id)],
+                tid,
+            )
+            .await?;
+        let campaign = campaign
+            .records()
+            .first()
+            .ok_or_else(|| AppError::DataApi("Code campaign disappeared".into()))
+            .and_then(|record| code_campaign_from_record(record))?;
+
+        let counts = self
+            .execute(
+                "SELECT COUNT(*), COUNT(*) FILTER (WHERE r.player_id = :player_id) \
+                 FROM code_redemptions r \
+                 JOIN microtransactions t ON t.transaction_id = r.transaction_id \
+                 WHERE r.code = :code AND t.status <> 'failed'",
+                vec![string_param("code", code), uuid_param("player_id", player_id)],
+                tid,
+            )
+            .await?;
+        let (uses, player_uses) = counts
+            .records()
+            .first()
+            .ok_or_else(|| AppError::DataApi("COUNT returned no record".into()))
+            .and_then(|record| {
+                let mut reader = RecordReader::new(record);
+                Ok((reader.long("count")?, reader.long("player_count")?))
+            })?;
+
+        campaign.check_redemption(uses, player_uses, Utc::now())
+    }
+
+    /// Same effects as the Postgres backend, inside `data_api_tx`
+    async fn apply_effects(
+        &self,
+        effects: &TransitionEffects,
+        transaction_id: Uuid,
+        data_api_tx: &str,
+    ) -> AppResult<()> {
+        if let Some(entry) = &effects.wallet {
+            self.append_ledger(entry, data_api_tx).await?;
+        }
+
+        if let Some(settlement) = effects.stock {
+            self.execute(
+                "WITH settled AS (\
+                     DELETE FROM stock_reservations WHERE transaction_id = :transaction_id \
+                     RETURNING item_id, quantity\
+                 ) \
+                 UPDATE catalog_items c \
+                 SET stock_reserved = c.stock_reserved - s.quantity, \
+                     stock_sold = c.stock_sold + CASE WHEN :sold THEN s.quantity ELSE 0 END, \
+                     updated_at = NOW() \
+                 FROM settled s \
+                 WHERE c.item_id = s.item_id",
+                vec![
+                    uuid_param("transaction_id", transaction_id),
+                    param("sold", Field::BooleanValue(settlement == StockSettlement::Commit)),
+                ],
+                Some(data_api_tx),
+            )
+            .await?;
+        }
+
+        for grant in &effects.grants {
+            self.execute(
+                "INSERT INTO entitlements (\
+                     entitlement_id, player_id, item_id, quantity, kind, source_transaction_id, expires_at\
+                 ) VALUES (\
+                     :entitlement_id, :player_id, :item_id, :quantity,\
+                     CAST(:kind AS entitlement_kind), :source_transaction_id, :expires_at\
+                 ) ON CONFLICT (source_transaction_id, item_id) DO NOTHING",
+                vec![
+                    uuid_param("entitlement_id", grant.entitlement_id),
+                    uuid_param("player_id", grant.player_id),
+                    string_param("item_id", &grant.item_id),
+                    long_param("quantity", i64::from(grant.quantity)),
+                    string_param("kind", grant.kind.as_str()),
+                    uuid_param("source_transaction_id", grant.source_transaction_id),
+                    optional_timestamp_param("expires_at", grant.expires_at),
+                ],
+                Some(data_api_tx),
+            )
+            .await?;
+        }
+
+        if effects.revoke {
+            self.execute(
+                "UPDATE entitlements SET status = 'revoked', revoked_at = NOW() \
+                 WHERE source_transaction_id = :transaction_id AND status = 'active'",
+                vec![uuid_param("transaction_id", transaction_id)],
+                Some(data_api_tx),
+            )
+            .await?;
+        }
+
+        if let Some(event) = &effects.event {
+            self.execute(
+                "INSERT INTO outbox_events (event_id, event_type, aggregate_id, payload) \
+                 VALUES (:event_id, :event_type, :aggregate_id, :payload)",
+                vec![
+                    uuid_param("event_id", event.event_id),
+                    string_param("event_type", event.event_type.as_str()),
+                    uuid_param("aggregate_id", event.aggregate_id),
+                    json_param("payload", &event.payload),
+                ],
+                Some(data_api_tx),
+            )
+            .await?;
+        }
+
+        if let Some(fan_out) = &effects.webhooks {
+            self.execute(
+                "INSERT INTO webhook_deliveries (delivery_id, subscription_id, event_id, event_type, payload) \
+                 SELECT gen_random_uuid(), subscription_id, :event_id, :event_type, :payload \
+                 FROM webhook_subscriptions \
+                 WHERE title_id = :title_id AND event_types ? :event_type \
+                 ON CONFLICT (subscription_id, event_id) DO NOTHING",
+                vec![
+                    uuid_param("event_id", fan_out.event_id),
+                    string_param("event_type", fan_out.event_type.as_str()),
+                    json_param("payload", &fan_out.payload),
+                    string_param("title_id", &fan_out.title_id),
+                ],
+                Some(data_api_tx),
+            )
+            .await?;
+        }
+
+        if let Some(entry) = &effects.journal {
+            self.insert_journal_entry(entry, data_api_tx).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Insert `tx` as pending with `metadata`, inside `transaction_id` if given
+    async fn insert_pending(
+        &self,
+        tx: &NewTransaction,
+        metadata: &serde_json::Value,
+        transaction_id: Option<&str>,
+    ) -> AppResult<Transaction> {
+        let sql = format!(
+            r#"
+            INSERT INTO microtransactions (
+                transaction_id,
+                player_id,
+                item_id,
+                item_name,
+                price_cents,
+                currency,
+                quantity,
+                status,
+                metadata,
+                request_id,
+                created_at,
+                updated_at,
+                original_price_cents,
+                applied_promotions,
+                recipient_id,
+                gift_status
+            ) VALUES (
+                :transaction_id, :player_id, :item_id, :item_name, :price_cents,
+                :currency, :quantity, CAST(:status AS transaction_status), :metadata,
+                :request_id, NOW(), NOW(), :original_price_cents, :applied_promotions,
+                CAST(:recipient_id AS uuid), CAST(:gift_status AS gift_status)
+            )
+            RETURNING {}
+            "#,
+            TRANSACTION_COLUMNS
+        );
+
+        let parameters = vec![
+            uuid_param("transaction_id", tx.transaction_id),
+            uuid_param("player_id", tx.player_id),
+            string_param("item_id", &tx.item_id),
+            string_param("item_name", &tx.item_name),
+            long_param("price_cents", tx.price_cents),
+            string_param("currency", &tx.currency),
+            long_param("quantity", i64::from(tx.quantity)),
+            string_param("status", TransactionStatus::Pending.as_str()),
+            json_param("metadata", metadata),
+            optional_string_param("request_id", tx.request_id.as_deref()),
+            optional_long_param("original_price_cents", tx.original_price_cents),
+            json_param("applied_promotions", &serde_json::to_value(&tx.applied_promotions)?),
+            optional_string_param("recipient_id", tx.recipient_id.map(|id| id.to_string()).as_deref()),
+            optional_string_param("gift_status", tx.recipient_id.map(|_| GiftStatus::Pending.as_str())),
+        ];
+
+        let output = self.execute(&sql, parameters, transaction_id).await?;
+
+        output
+            .records()
+            .first()
+            .ok_or_else(|| AppError::DataApi("INSERT returned no record".into()))
+            .and_then(|record| transaction_from_record(record))
+    }
+
+    /// Run an outbox update, failing with `NotFound` when it matched no row
+    async fn update_outbox(&self, sql: &str, parameters: Vec<SqlParameter>, event_id: Uuid) -> AppResult<()> {
+        let output = self.execute(sql, parameters, None).await?;
+
+        if output.number_of_records_updated() == 0 {
+            return Err(AppError::NotFound(format!("Outbox event {} not found", event_id)));
+        }
+        Ok(())
+    }
+
+    /// Run a webhook delivery update, failing with `NotFound` when it matched no row
+    async fn update_delivery(&self, sql: &str, parameters: Vec<SqlParameter>, delivery_id: Uuid) -> AppResult<()> {
+        let output = self.execute(sql, parameters, None).await?;
+
+        if output.number_of_records_updated() == 0 {
+            return Err(AppError::NotFound(format!("Webhook delivery {} not found", delivery_id)));
+        }
+        Ok(())
+    }
+
+    /// Same ledger append as the Postgres backend, inside `transaction_id`
+    async fn append_ledger(&self, entry: &NewLedgerEntry, transaction_id: &str) -> AppResult<LedgerEntry> {
+        let tid = Some(transaction_id);
+
+        // pg_advisory_xact_lock returns void, which the Data API cannot map
+        self.execute(
+            "SELECT 1 FROM (SELECT pg_advisory_xact_lock(hashtextextended(:lock_key, 0))) AS locked",
+            vec![string_param(
+                "lock_key",
+                &format!("wallet:{}:{}", entry.player_id, entry.currency.as_str()),
+            )],
+            tid,
+        )
+        .await?;
+
+        let existing = self.execute(
+            &format!(
+                "SELECT {} FROM wallet_ledger \
+                 WHERE player_id = :player_id AND kind = CAST(:kind AS ledger_entry_kind) AND reference = :reference",
+                LEDGER_COLUMNS
+            ),
+            vec![
+                uuid_param("player_id", entry.player_id),
+                string_param("kind", entry.kind.as_str()),
+                string_param("reference", &entry.reference),
+            ],
+            tid,
+        )
+        .await?;
+
+        if let Some(record) = existing.records().first() {
+            return entry.replay(ledger_entry_from_record(record)?);
+        }
+
+        let latest = self.execute(
+            "SELECT sequence, balance_after FROM wallet_ledger \
+             WHERE player_id = :player_id AND currency = CAST(:currency AS virtual_currency) \
+             ORDER BY sequence DESC LIMIT 1",
+            vec![
+                uuid_param("player_id", entry.player_id),
+                string_param("currency", entry.currency.as_str()),
+            ],
+            tid,
+        )
+        .await?;
+
+        let (sequence, balance) = match latest.records().first() {
+            Some(record) => {
+                let mut reader = RecordReader::new(record);
+                (reader.long("sequence")?, reader.long("balance_after")?)
+            }
+            None => (0, 0),
+        };
+        let (sequence, balance_after) = entry.position_after(sequence, balance)?;
+
+        let inserted = self.execute(
+            &format!(
+                r#"
+                INSERT INTO wallet_ledger (
+                    entry_id, player_id, currency, sequence, amount, balance_after,
+                    kind, reference, item_id, request_id
+                ) VALUES (
+                    :entry_id, :player_id, CAST(:currency AS virtual_currency), :sequence, :amount,
+                    :balance_after, CAST(:kind AS ledger_entry_kind), :reference, :item_id, :request_id
+                )
+                RETURNING {}
+                "#,
+                LEDGER_COLUMNS
+            ),
+            vec![
+                uuid_param("entry_id", entry.entry_id),
+                uuid_param("player_id", entry.player_id),
+                string_param("currency", entry.currency.as_str()),
+                long_param("sequence", sequence),
+                long_param("amount", entry.amount),
+                long_param("balance_after", balance_after),
+                string_param("kind", entry.kind.as_str()),
+                string_param("reference", &entry.reference),
+                optional_string_param("item_id", entry.item_id.as_deref()),
+                optional_string_param("request_id", entry.request_id.as_deref()),
+            ],
+            tid,
+        )
+        .await?;
+
+        let appended = inserted
+            .records()
+            .first()
+            .ok_or_else(|| AppError::DataApi("INSERT returned no record".into()))
+            .and_then(|record| ledger_entry_from_record(record))?;
+
+        self.insert_journal_entry(&NewJournalEntry::for_ledger_entry(&appended)?, transaction_id).await?;
+        Ok(appended)
+    }
+
+    /// Same journaling as the Postgres backend, inside `transaction_id`
+    async fn insert_journal_entry(&self, entry: &NewJournalEntry, transaction_id: &str) -> AppResult<()> {
+        let inserted = self.execute(
+            "INSERT INTO journal_entries (journal_id, kind, reference, player_id, request_id) \
+             VALUES (:journal_id, CAST(:kind AS journal_entry_kind), :reference, CAST(:player_id AS uuid), :request_id) \
+             ON CONFLICT (kind, reference) DO NOTHING \
+             RETURNING journal_id",
+            vec![
+                uuid_param("journal_id", entry.journal_id),
+                string_param("kind", entry.kind.as_str()),
+                string_param("reference", &entry.reference),
+                optional_string_param("player_id", entry.player_id.map(|id| id.to_string()).as_deref()),
+                optional_string_param("request_id", entry.request_id.as_deref()),
+            ],
+            Some(transaction_id),
+        )
+        .await?;
+
+        if inserted.records().is_empty() {
+            return Ok(());
+        }
+
+        self.batch_execute(
+            "INSERT INTO ledger_accounts (account_code, account_type, currency) \
+             VALUES (:account_code, CAST(:account_type AS account_type), :currency) \
+             ON CONFLICT (account_code) DO NOTHING",
+            entry.postings.iter().map(|posting| vec![
+                string_param("account_code", &posting.account_code),
+                string_param("account_type", posting.account_type.as_str()),
+                string_param("currency", &posting.currency),
+            ]).collect(),
+            Some(transaction_id),
+        )
+        .await?;
+
+        self.batch_execute(
+            "INSERT INTO journal_postings (journal_id, account_code, currency, amount) \
+             VALUES (:journal_id, :account_code, :currency, :amount)",
+            entry.postings.iter().map(|posting| vec![
+                uuid_param("journal_id", entry.journal_id),
+                string_param("account_code", &posting.account_code),
+                string_param("currency", &posting.currency),
+                long_param("amount", posting.amount),
+            ]).collect(),
+            Some(transaction_id),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Database for RdsDataDatabase {
+    /// Check Data API health
+    async fn health_check(&self) -> AppResult<Duration> {
+        let start = Instant::now();
+
+        self.execute("SELECT 1", Vec::new(), None).await?;
+
+        Ok(start.elapsed())
+    }
+
+    #[instrument(skip(self, tx), fields(transaction_id = %tx.transaction_id, otel.kind = "client", db.system = "postgresql"))]
+    /// Same checks as the Postgres backend; uncatalogued items skip the
+    /// Data API transaction and insert in one call
+    async fn insert_transaction(&self, tx: &NewTransaction) -> AppResult<Transaction> {
+        let catalogued = self.get_catalog_item(&tx.item_id).await?.is_some();
+        if !catalogued && tx.code.is_none() && tx.recipient_id.is_none() {
+            let result = self.insert_pending(tx, &tx.metadata, None).await?;
+            info!("Transaction inserted");
+            return Ok(result);
+        }
+
+        let result = self.with_transaction(|data_api_tx| async move {
+            let tid = Some(data_api_tx.as_str());
+
+            let (reserve, metadata) = if catalogued {
+                self.check_catalog_purchase(tx, &data_api_tx).await?
+            } else {
+                (0, tx.metadata.clone())
+            };
+            if let Some(code) = &tx.code {
+                self.check_code_redemption(code, tx.player_id, &data_api_tx).await?;
+            }
+            if let Some(recipient_id) = tx.recipient_id {
+                self.check_gift_limits(tx.player_id, recipient_id, &data_api_tx).await?;
+            }
+            let inserted = self.insert_pending(tx, &metadata, tid).await?;
+
+            if reserve > 0 {
+                self.execute(
+                    "UPDATE catalog_items SET stock_reserved = stock_reserved + :quantity, updated_at = NOW() \
+                     WHERE item_id = :item_id",
+                    vec![long_param("quantity", i64::from(reserve)), string_param("item_id", &tx.item_id)],
+                    tid,
+                )
+                .await?;
+                self.execute(
+                    "INSERT INTO stock_reservations (transaction_id, item_id, quantity) \
+                     VALUES (:transaction_id, :item_id, :quantity)",
+                    vec![
+                        uuid_param("transaction_id", tx.transaction_id),
+                        string_param("item_id", &tx.item_id),
+                        long_param("quantity", i64::from(reserve)),
+                    ],
+                    tid,
+                )
+                .await?;
+            }
+            if let Some(code) = &tx.code {
+                self.execute(
+                    "INSERT INTO code_redemptions (redemption_id, code, player_id, transaction_id) \
+                     VALUES (:redemption_id, :code, :player_id, :transaction_id)",
+                    vec![
+                        uuid_param("redemption_id", Uuid::new_v4()),
+                        string_param("code", code),
+                        uuid_param("player_id", tx.player_id),
+                        uuid_param("transaction_id", tx.transaction_id),
+                    ],
+                    tid,
+                )
+                .await?;
+            }
+
+            Ok(inserted)
+        })
+        .await?;
+
+        info!("Transaction inserted");
+        Ok(result)
+    }
+
+    #[instrument(skip(self), fields(transaction_id = %transaction_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn update_transaction_status(
+        &self,
+        transaction_id: Uuid,
+        status: TransactionStatus,
+        processor_id: Option<&str>,
+    ) -> AppResult<Transaction> {
+        let result = self.with_transaction(|data_api_tx| async move {
+            let tid = Some(data_api_tx.as_str());
+
+            let current = self.execute(
+                &format!(
+                    "SELECT {} FROM microtransactions WHERE transaction_id = :transaction_id FOR UPDATE",
+                    TRANSACTION_COLUMNS
+                ),
+                vec![uuid_param("transaction_id", transaction_id)],
+                tid,
+            )
+            .await?;
+            let current = current
+                .records()
+                .first()
+                .ok_or_else(|| AppError::NotFound(format!("Transaction {} not found", transaction_id)))
+                .and_then(|record| transaction_from_record(record))?;
+            check_transition(&current, status)?;
+
+            let updated = self.execute(
+                &format!(
+                    r#"
+                    UPDATE microtransactions
+                    SET status = CAST(:status AS transaction_status),
+                        processor_id = :processor_id,
+                        gift_status = CAST(:gift_status AS gift_status),
+                        updated_at = NOW()
+                    WHERE transaction_id = :transaction_id
+                    RETURNING {}
+                    "#,
+                    TRANSACTION_COLUMNS
+                ),
+                vec![
+                    string_param("status", status.as_str()),
+                    optional_string_param("processor_id", processor_id),
+                    optional_string_param("gift_status", gift::status_after(&current, status).map(|s| s.as_str())),
+                    uuid_param("transaction_id", transaction_id),
+                ],
+                tid,
+            )
+            .await?;
+            let updated = updated
+                .records()
+                .first()
+                .ok_or_else(|| AppError::DataApi("UPDATE returned no record".into()))
+                .and_then(|record| transaction_from_record(record))?;
+
+            let effects = TransitionEffects::of(&updated, current.status)?;
+            self.apply_effects(&effects, transaction_id, &data_api_tx).await?;
+
+            Ok(updated)
+        })
+        .await?;
+
+        info!(status = ?status, "Transaction status updated");
+        Ok(result)
+    }
+
+    async fn accept_gift(&self, transaction_id: Uuid, recipient_id: Uuid) -> AppResult<Transaction> {
+        self.with_transaction(|data_api_tx| async move {
+            let tid = Some(data_api_tx.as_str());
+
+            let current = self.execute(
+                &format!(
+                    "SELECT {} FROM microtransactions WHERE transaction_id = :transaction_id FOR UPDATE",
+                    TRANSACTION_COLUMNS
+                ),
+                vec![uuid_param("transaction_id", transaction_id)],
+                tid,
+            )
+            .await?;
+            let current = current
+                .records()
+                .first()
+                .ok_or_else(|| AppError::NotFound(format!("Gift {} not found", transaction_id)))
+                .and_then(|record| transaction_from_record(record))?;
+            let Some(status) = gift::answer(&current, recipient_id, true)? else {
+                return Ok(current);
+            };
+
+            let updated = self.execute(
+                &format!(
+                    "UPDATE microtransactions \
+                     SET gift_status = CAST(:gift_status AS gift_status), updated_at = NOW() \
+                     WHERE transaction_id = :transaction_id \
+                     RETURNING {}",
+                    TRANSACTION_COLUMNS
+                ),
+                vec![
+                    string_param("gift_status", status.as_str()),
+                    uuid_param("transaction_id", transaction_id),
+                ],
+                tid,
+            )
+            .await?;
+            let updated = updated
+                .records()
+                .first()
+                .ok_or_else(|| AppError::DataApi("UPDATE returned no record".into()))
+                .and_then(|record| transaction_from_record(record))?;
+
+            self.apply_effects(&TransitionEffects::of_gift_accepted(&updated), transaction_id, &data_api_tx)
+                .await?;
+
+            Ok(updated)
+        })
+        .await
+    }
+
+    async fn get_transaction(&self, transaction_id: Uuid) -> AppResult<Option<Transaction>> {
+        let sql = format!(
+            "SELECT {} FROM microtransactions WHERE transaction_id = :transaction_id",
+            TRANSACTION_COLUMNS
+        );
+
+        let result = self
+            .query_transactions(&sql, vec![uuid_param("transaction_id", transaction_id)])
+            .await?
+            .into_iter()
+            .next();
+
+        Ok(result)
+    }
+
+    #[instrument(skip(self), fields(otel.kind = "client", db.system = "postgresql"))]
+    async fn get_stale_pending_transactions(&self, before: DateTime<Utc>, limit: i32) -> AppResult<Vec<Transaction>> {
+        let sql = format!(
+            "SELECT {} FROM microtransactions \
+             WHERE status = 'pending' AND created_at < :before \
+             ORDER BY created_at LIMIT :limit",
+            TRANSACTION_COLUMNS
+        );
+
+        self.query_transactions(
+            &sql,
+            vec![timestamp_param("before", before), long_param("limit", i64::from(limit.clamp(1, 1000)))],
+        )
+        .await
+    }
+
+    async fn get_unanswered_gifts(&self, before: DateTime<Utc>, limit: i32) -> AppResult<Vec<Transaction>> {
+        let sql = format!(
+            "SELECT {} FROM microtransactions \
+             WHERE gift_status = 'pending' AND status = 'completed' AND created_at < :before \
+             ORDER BY created_at LIMIT :limit",
+            TRANSACTION_COLUMNS
+        );
+
+        self.query_transactions(
+            &sql,
+            vec![timestamp_param("before", before), long_param("limit", i64::from(limit.clamp(1, 1000)))],
+        )
+        .await
+    }
+
+    #[instrument(skip(self), fields(player_id = %player_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn get_player_transactions(
+        &self,
+        player_id: Uuid,
+        limit: i32,
+        cursor: Option<Uuid>,
+    ) -> AppResult<Vec<Transaction>> {
+        let safe_limit = limit.clamp(1, 1000);
+
+        let mut parameters = vec![
+            uuid_param("player_id", player_id),
+            long_param("limit", i64::from(safe_limit)),
+        ];
+
+        let cursor_clause = match cursor {
+            Some(cursor_id) => {
+                parameters.push(uuid_param("cursor", cursor_id));
+                "AND transaction_id < :cursor"
+            }
+            None => "",
+        };
+
+        let sql = format!(
+            r#"
+            SELECT {} FROM microtransactions
+            WHERE (player_id = :player_id OR recipient_id = :player_id) {}
+            ORDER BY created_at DESC
+            LIMIT :limit
+            "#,
+            TRANSACTION_COLUMNS, cursor_clause
+        );
+
+        let results = self.query_transactions(&sql, parameters).await?;
+
+        info!(count = results.len(), "Retrieved player transactions");
+        Ok(results)
+    }
+
+    /// Apply embedded migrations one statement per call
+    ///
+    /// Each migration runs in its own Data API transaction together with its
+    /// `schema_migrations` row, under a transaction-scoped advisory lock. The
+    /// applied set is re-read under the lock, so concurrent callers skip
+    /// what another already applied.
+    async fn run_migrations(&self, dry_run: bool) -> AppResult<MigrationReport> {
+        let applied = self.applied_migrations(None).await?;
+        let pending = migrations::plan(MIGRATIONS, &applied)?;
+        if dry_run || pending.is_empty() {
+            return Ok(migrations::report(dry_run, &applied, &pending, Vec::new()));
+        }
+
+        self.execute(migrations::CREATE_SCHEMA_MIGRATIONS, Vec::new(), None).await?;
+        let mut newly_applied = Vec::with_capacity(pending.len());
+
+        for migration in pending {
+            let ran = self.with_transaction(|transaction_id| async move {
+                let tid = Some(transaction_id.as_str());
+
+                // pg_advisory_xact_lock returns void, which the Data API cannot map
+                self.execute(
+                    "SELECT 1 FROM (SELECT pg_advisory_xact_lock(:lock_key)) AS locked",
+                    vec![long_param("lock_key", migrations::MIGRATION_LOCK_KEY)],
+                    tid,
+                )
+                .await?;
+
+                let applied = self.applied_migrations(tid).await?;
+                if applied.iter().any(|row| row.version == migration.version) {
+                    return Ok(false);
+                }
+
+                let start = Instant::now();
+                for statement in migrations::split_statements(migration.sql) {
+                    self.execute(statement, Vec::new(), tid).await?;
+                }
+
+                self.execute(
+                    "INSERT INTO schema_migrations (version, name, checksum, execution_ms) \
+                     VALUES (:version, :name, :checksum, :execution_ms)",
+                    vec![
+                        long_param("version", migration.version),
+                        string_param("name", migration.name),
+                        string_param("checksum", &migration.checksum()),
+                        long_param("execution_ms", start.elapsed().as_millis() as i64),
+                    ],
+                    tid,
+                )
+                .await?;
+
+                Ok(true)
+            }).await?;
+
+            if ran {
+                info!(version = migration.version, name = migration.name, "Migration applied");
+                newly_applied.push(migration.version);
+            }
+        }
+
+        Ok(migrations::report(false, &applied, &[], newly_applied))
+    }
+
+    /// Highest applied version, `None` on a database never migrated
+    async fn schema_version(&self) -> AppResult<Option<i64>> {
+        Ok(self.applied_migrations(None).await?.iter().map(|row| row.version).max())
+    }
+
+    /// Same locking as the Postgres backend, inside a Data API transaction
+    #[instrument(skip(self, entry), fields(entry_id = %entry.entry_id, kind = entry.kind.as_str(), otel.kind = "client", db.system = "postgresql"))]
+    async fn append_ledger_entry(&self, entry: &NewLedgerEntry) -> AppResult<LedgerEntry> {
+        let appended = self.with_transaction(|transaction_id| async move {
+            self.append_ledger(entry, &transaction_id).await
+        })
+        .await?;
+
+        info!(sequence = appended.sequence, "Ledger entry appended");
+        Ok(appended)
+    }
+
+    async fn get_wallet(&self, player_id: Uuid) -> AppResult<Vec<WalletBalance>> {
+        let output = self
+            .execute(
+                "SELECT DISTINCT ON (currency) currency, balance_after FROM wallet_ledger \
+                 WHERE player_id = :player_id ORDER BY currency, sequence DESC",
+                vec![uuid_param("player_id", player_id)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .iter()
+            .map(|record| {
+                let mut reader = RecordReader::new(record);
+                Ok(WalletBalance {
+                    currency: reader.enum_value("currency")?,
+                    balance: reader.long("balance_after")?,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip(self), fields(player_id = %player_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn get_player_journal(&self, player_id: Uuid, limit: i32) -> AppResult<Vec<JournalEntry>> {
+        let output = self
+            .execute(
+                &format!(
+                    "SELECT {} FROM journal_entries WHERE player_id = :player_id \
+                     ORDER BY created_at DESC LIMIT :limit",
+                    JOURNAL_COLUMNS
+                ),
+                vec![
+                    uuid_param("player_id", player_id),
+                    long_param("limit", i64::from(limit.clamp(1, 1000))),
+                ],
+                None,
+            )
+            .await?;
+        let mut entries = output
+            .records()
+            .iter()
+            .map(|record| journal_entry_from_record(record))
+            .collect::<AppResult<Vec<_>>>()?;
+
+        if entries.is_empty() {
+            return Ok(entries);
+        }
+
+        // The Data API has no array parameters - pass a Postgres array literal
+        let journal_ids = entries.iter().map(|e| e.journal_id.to_string()).collect::<Vec<_>>().join(",");
+        let postings = self
+            .execute(
+                "SELECT p.journal_id, p.account_code, a.account_type, p.currency, p.amount \
+                 FROM journal_postings p JOIN ledger_accounts a USING (account_code) \
+                 WHERE p.journal_id = ANY(CAST(:journal_ids AS uuid[])) ORDER BY p.posting_id",
+                vec![string_param("journal_ids", &format!("{{{}}}", journal_ids))],
+                None,
+            )
+            .await?;
+
+        let postings = postings
+            .records()
+            .iter()
+            .map(|record| {
+                let mut reader = RecordReader::new(record);
+                Ok((reader.uuid("journal_id")?, Posting {
+                    account_code: reader.string("account_code")?,
+                    account_type: reader.enum_value("account_type")?,
+                    currency: reader.string("currency")?,
+                    amount: reader.long("amount")?,
+                }))
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        attach_postings(&mut entries, postings);
+
+        Ok(entries)
+    }
+
+    async fn trial_balance(&self) -> AppResult<TrialBalance> {
+        let output = self
+            .execute(
+                "SELECT account_code, account_type, currency, debits, credits FROM trial_balance ORDER BY account_code",
+                Vec::new(),
+                None,
+            )
+            .await?;
+
+        let lines = output
+            .records()
+            .iter()
+            .map(|record| {
+                let mut reader = RecordReader::new(record);
+                Ok(TrialBalanceLine {
+                    account_code: reader.string("account_code")?,
+                    account_type: reader.enum_value("account_type")?,
+                    currency: reader.string("currency")?,
+                    debits: reader.long("debits")?,
+                    credits: reader.long("credits")?,
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+
+        Ok(TrialBalance { lines })
+    }
+
+    #[instrument(skip(self), fields(player_id = %player_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn get_player_entitlements(&self, player_id: Uuid, include_revoked: bool) -> AppResult<Vec<Entitlement>> {
+        let status_clause = if include_revoked {
+            ""
+        } else {
+            "AND status = 'active' AND (expires_at IS NULL OR expires_at > NOW())"
+        };
+        let output = self
+            .execute(
+                &format!(
+                    "SELECT {} FROM entitlements WHERE player_id = :player_id {} ORDER BY granted_at DESC",
+                    ENTITLEMENT_COLUMNS, status_clause
+                ),
+                vec![uuid_param("player_id", player_id)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .iter()
+            .map(|record| entitlement_from_record(record))
+            .collect()
+    }
+
+    /// Same `FOR UPDATE SKIP LOCKED` claim as the Postgres backend
+    #[instrument(skip(self), fields(otel.kind = "client", db.system = "postgresql"))]
+    async fn claim_outbox_events(&self, limit: i32, lease: Duration) -> AppResult<Vec<OutboxEvent>> {
+        let output = self
+            .execute(
+                &format!(
+                    "UPDATE outbox_events \
+                     SET attempts = attempts + 1, next_attempt_at = NOW() + make_interval(secs => :lease_seconds) \
+                     WHERE event_id IN (\
+                         SELECT event_id FROM outbox_events \
+                         WHERE status = 'pending' AND next_attempt_at <= NOW() \
+                         ORDER BY next_attempt_at, created_at \
+                         LIMIT :limit \
+                         FOR UPDATE SKIP LOCKED\
+                     ) \
+                     RETURNING {}",
+                    OUTBOX_COLUMNS
+                ),
+                vec![
+                    long_param("limit", i64::from(limit.clamp(1, 1000))),
+                    double_param("lease_seconds", lease.as_secs_f64()),
+                ],
+                None,
+            )
+            .await?;
+
+        let mut events = output
+            .records()
+            .iter()
+            .map(|record| outbox_event_from_record(record))
+            .collect::<AppResult<Vec<_>>>()?;
+        events.sort_by_key(|e| e.created_at);
+        Ok(events)
+    }
+
+    async fn mark_outbox_delivered(&self, event_id: Uuid) -> AppResult<()> {
+        self.update_outbox(
+            "UPDATE outbox_events SET status = 'delivered', delivered_at = NOW(), last_error = NULL \
+             WHERE event_id = :event_id",
+            vec![uuid_param("event_id", event_id)],
+            event_id,
+        )
+        .await
+    }
+
+    async fn mark_outbox_failed(&self, event_id: Uuid, error: &str, retry_in: Option<Duration>) -> AppResult<()> {
+        let mut parameters = vec![uuid_param("event_id", event_id), string_param("error", error)];
+        let sql = match retry_in {
+            Some(delay) => {
+                parameters.push(double_param("delay_seconds", delay.as_secs_f64()));
+                "UPDATE outbox_events \
+                 SET last_error = :error, next_attempt_at = NOW() + make_interval(secs => :delay_seconds) \
+                 WHERE event_id = :event_id"
+            }
+            None => {
+                parameters.push(string_param("status", OutboxStatus::Dead.as_str()));
+                "UPDATE outbox_events SET last_error = :error, status = CAST(:status AS outbox_status) \
+                 WHERE event_id = :event_id"
+            }
+        };
+
+        self.update_outbox(sql, parameters, event_id).await
+    }
+
+    async fn upsert_catalog_item(&self, item_id: &str, item: &CatalogItemRequest) -> AppResult<CatalogItem> {
+        let output = self
+            .execute(
+                &format!(
+                    "INSERT INTO catalog_items ( \
+                         item_id, kind, stock_limit, price_cents, currency, \
+                         credit_currency, credit_amount, wallet_price, wallet_currency, title_id\
+                     ) VALUES ( \
+                         :item_id, CAST(:kind AS item_kind), :stock_limit, :price_cents, :currency, \
+                         CAST(:credit_currency AS virtual_currency), :credit_amount, :wallet_price, \
+                         CAST(:wallet_currency AS virtual_currency), :title_id\
+                     ) \
+                     ON CONFLICT (item_id) DO UPDATE \
+                     SET kind = EXCLUDED.kind, stock_limit = EXCLUDED.stock_limit, \
+                         price_cents = EXCLUDED.price_cents, currency = EXCLUDED.currency, \
+                         credit_currency = EXCLUDED.credit_currency, credit_amount = EXCLUDED.credit_amount, \
+                         wallet_price = EXCLUDED.wallet_price, wallet_currency = EXCLUDED.wallet_currency, \
+                         title_id = EXCLUDED.title_id, updated_at = NOW() \
+                     WHERE EXCLUDED.stock_limit IS NULL \
+                         OR EXCLUDED.stock_limit >= catalog_items.stock_reserved + catalog_items.stock_sold \
+                     RETURNING {}",
+                    CATALOG_COLUMNS
+                ),
+                vec![
+                    string_param("item_id", item_id),
+                    string_param("kind", item.kind.as_str()),
+                    match item.stock_limit {
+                        Some(limit) => long_param("stock_limit", i64::from(limit)),
+                        None => param("stock_limit", Field::IsNull(true)),
+                    },
+                    optional_long_param("price_cents", item.price_cents),
+                    optional_string_param("currency", item.currency.map(|c| c.as_str())),
+                    optional_string_param("credit_currency", item.credit_currency.map(|c| c.as_str())),
+                    optional_long_param("credit_amount", item.credit_amount),
+                    optional_long_param("wallet_price", item.wallet_price),
+                    optional_string_param("wallet_currency", item.wallet_currency.map(|c| c.as_str())),
+                    optional_string_param("title_id", item.title_id.as_deref()),
+                ],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .map(|record| catalog_item_from_record(record))
+            .transpose()?
+            .ok_or_else(|| AppError::Conflict(format!(
+                "Item {} already has more units reserved or sold than the new stock limit",
+                item_id
+            )))
+    }
+
+    async fn get_catalog_item(&self, item_id: &str) -> AppResult<Option<CatalogItem>> {
+        let output = self
+            .execute(
+                &format!("SELECT {} FROM catalog_items WHERE item_id = :item_id", CATALOG_COLUMNS),
+                vec![string_param("item_id", item_id)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .map(|record| catalog_item_from_record(record))
+            .transpose()
+    }
+
+    async fn create_promotion(&self, promotion: &NewPromotion) -> AppResult<Promotion> {
+        let promotion = promotion.clone().into_promotion(Utc::now());
+        let output = self
+            .execute(
+                &format!(
+                    "INSERT INTO promotions ({0}) VALUES (\
+                         :promotion_id, :name, CAST(:kind AS promotion_kind), :value, :currency, \
+                         :item_ids, :segments, :bundle_items, :stackable, :priority, \
+                         :starts_at, :ends_at, :created_at\
+                     ) RETURNING {0}",
+                    PROMOTION_COLUMNS
+                ),
+                vec![
+                    uuid_param("promotion_id", promotion.promotion_id),
+                    string_param("name", &promotion.name),
+                    string_param("kind", promotion.kind.as_str()),
+                    long_param("value", promotion.value),
+                    optional_string_param("currency", promotion.currency.as_deref()),
+                    json_param("item_ids", &serde_json::to_value(&promotion.item_ids)?),
+                    json_param("segments", &serde_json::to_value(&promotion.segments)?),
+                    json_param("bundle_items", &serde_json::to_value(&promotion.bundle_items)?),
+                    param("stackable", Field::BooleanValue(promotion.stackable)),
+                    long_param("priority", i64::from(promotion.priority)),
+                    timestamp_param("starts_at", promotion.starts_at),
+                    timestamp_param("ends_at", promotion.ends_at),
+                    timestamp_param("created_at", promotion.created_at),
+                ],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .ok_or_else(|| AppError::DataApi("INSERT returned no record".into()))
+            .and_then(|record| promotion_from_record(record))
+    }
+
+    async fn get_promotions(&self, limit: i32) -> AppResult<Vec<Promotion>> {
+        let output = self
+            .execute(
+                &format!("SELECT {} FROM promotions ORDER BY starts_at DESC LIMIT :limit", PROMOTION_COLUMNS),
+                vec![long_param("limit", i64::from(limit.clamp(1, 1000)))],
+                None,
+            )
+            .await?;
+
+        output.records().iter().map(|record| promotion_from_record(record)).collect()
+    }
+
+    async fn get_active_promotions(&self, item_id: &str, at: DateTime<Utc>) -> AppResult<Vec<Promotion>> {
+        let output = self
+            .execute(
+                &format!(
+                    "SELECT {} FROM promotions \
+                     WHERE ends_at > :at AND starts_at <= :at \
+                       AND (item_ids = '[]'::jsonb OR item_ids ? :item_id) \
+                     ORDER BY priority DESC, created_at",
+                    PROMOTION_COLUMNS
+                ),
+                vec![string_param("item_id", item_id), timestamp_param("at", at)],
+                None,
+            )
+            .await?;
+
+        output.records().iter().map(|record| promotion_from_record(record)).collect()
+    }
+
+    async fn put_player_profile(&self, player_id: Uuid, segments: &[String]) -> AppResult<PlayerProfile> {
+        let output = self
+            .execute(
+                &format!(
+                    "INSERT INTO player_profiles (player_id, segments) VALUES (:player_id, :segments) \
+                     ON CONFLICT (player_id) DO UPDATE \
+                     SET segments = EXCLUDED.segments, updated_at = NOW() \
+                     RETURNING {}",
+                    PLAYER_PROFILE_COLUMNS
+                ),
+                vec![
+                    uuid_param("player_id", player_id),
+                    json_param("segments", &serde_json::to_value(segments)?),
+                ],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .ok_or_else(|| AppError::DataApi("INSERT returned no record".into()))
+            .and_then(|record| player_profile_from_record(record))
+    }
+
+    async fn get_player_profile(&self, player_id: Uuid) -> AppResult<Option<PlayerProfile>> {
+        let output = self
+            .execute(
+                &format!("SELECT {} FROM player_profiles WHERE player_id = :player_id", PLAYER_PROFILE_COLUMNS),
+                vec![uuid_param("player_id", player_id)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .map(|record| player_profile_from_record(record))
+            .transpose()
+    }
+
+    async fn create_code_campaign(&self, campaign: &NewCodeCampaign) -> AppResult<CodeCampaign> {
+        let codes = serde_json::to_value(&campaign.codes)?;
+        let campaign = campaign.clone().into_campaign(Utc::now());
+
+        self.with_transaction(|data_api_tx| async move {
+            let tid = Some(data_api_tx.as_str());
+
+            let created = self
+                .execute(
+                    &format!(
+                        "INSERT INTO code_campaigns ({0}) VALUES (\
+                             :campaign_id, :name, :reward, :max_uses, :per_player_limit, \
+                             :expires_at, :code_count, :created_at\
+                         ) RETURNING {0}",
+                        CODE_CAMPAIGN_COLUMNS
+                    ),
+                    vec![
+                        uuid_param("campaign_id", campaign.campaign_id),
+                        string_param("name", &campaign.name),
+                        json_param("reward", &serde_json::to_value(&campaign.reward)?),
+                        optional_long_param("max_uses", campaign.max_uses.map(i64::from)),
+                        long_param("per_player_limit", i64::from(campaign.per_player_limit)),
+                        match campaign.expires_at {
+                            Some(expires_at) => timestamp_param("expires_at", expires_at),
+                            None => param("expires_at", Field::IsNull(true)),
+                        },
+                        long_param("code_count", i64::from(campaign.code_count)),
+                        timestamp_param("created_at", campaign.created_at),
+                    ],
+                    tid,
+                )
+                .await?;
+            let created = created
+                .records()
+                .first()
+                .ok_or_else(|| AppError::DataApi("INSERT returned no record".into()))
+                .and_then(|record| code_campaign_from_record(record))?;
+
+            self.execute(
+                "INSERT INTO redeem_codes (code, campaign_id) \
+                 SELECT json_array_elements_text(:codes), :campaign_id",
+                vec![json_param("codes", &codes), uuid_param("campaign_id", campaign.campaign_id)],
+                tid,
+            )
+            .await?;
+
+            Ok(created)
+        })
+        .await
+    }
+
+    async fn get_code_campaign(&self, code: &str) -> AppResult<Option<CodeCampaign>> {
+        let output = self
+            .execute(
+                &format!(
+                    "SELECT {} FROM code_campaigns \
+                     WHERE campaign_id = (SELECT campaign_id FROM redeem_codes WHERE code = :code)",
+                    CODE_CAMPAIGN_COLUMNS
+                ),
+                vec![string_param("code", code)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .map(|record| code_campaign_from_record(record))
+            .transpose()
+    }
+
+    async fn record_code_failure(&self, subject: &str) -> AppResult<()> {
+        self.execute(
+            "INSERT INTO code_failures (subject) VALUES (:subject)",
+            vec![string_param("subject", subject)],
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn count_code_failures(&self, subject: &str, since: DateTime<Utc>) -> AppResult<i64> {
+        let output = self
+            .execute(
+                "SELECT COUNT(*) FROM code_failures WHERE subject = :subject AND failed_at > :since",
+                vec![string_param("subject", subject), timestamp_param("since", since)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .ok_or_else(|| AppError::DataApi("COUNT returned no record".into()))
+            .and_then(|record| RecordReader::new(record).long("count"))
+    }
+
+    async fn create_webhook_subscription(&self, subscription: &NewWebhookSubscription) -> AppResult<WebhookSubscription> {
+        let output = self
+            .execute(
+                &format!(
+                    "INSERT INTO webhook_subscriptions (subscription_id, title_id, url, secret, event_types) \
+                     VALUES (:subscription_id, :title_id, :url, :secret, CAST(:event_types AS jsonb)) \
+                     RETURNING {}",
+                    WEBHOOK_SUBSCRIPTION_COLUMNS
+                ),
+                vec![
+                    uuid_param("subscription_id", subscription.subscription_id),
+                    string_param("title_id", &subscription.title_id),
+                    string_param("url", &subscription.url),
+                    string_param("secret", subscription.secret.expose()),
+                    string_param("event_types", &serde_json::to_string(&subscription.event_types)?),
+                ],
+                None,
+            )
+            .await?;
+
+        let record = output
+            .records()
+            .first()
+            .ok_or_else(|| AppError::DataApi("INSERT returned no record".into()))?;
+        let result = webhook_subscription_from_record(record)?;
+
+        info!(subscription_id = %result.subscription_id, "Webhook subscription created");
+        Ok(result)
+    }
+
+    async fn get_webhook_subscriptions(&self, title_id: &str) -> AppResult<Vec<WebhookSubscription>> {
+        let output = self
+            .execute(
+                &format!(
+                    "SELECT {} FROM webhook_subscriptions WHERE title_id = :title_id ORDER BY created_at",
+                    WEBHOOK_SUBSCRIPTION_COLUMNS
+                ),
+                vec![string_param("title_id", title_id)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .iter()
+            .map(|record| webhook_subscription_from_record(record))
+            .collect()
+    }
+
+    async fn get_webhook_subscription(&self, subscription_id: Uuid) -> AppResult<Option<WebhookSubscription>> {
+        let output = self
+            .execute(
+                &format!(
+                    "SELECT {} FROM webhook_subscriptions WHERE subscription_id = :subscription_id",
+                    WEBHOOK_SUBSCRIPTION_COLUMNS
+                ),
+                vec![uuid_param("subscription_id", subscription_id)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .map(|record| webhook_subscription_from_record(record))
+            .transpose()
+    }
+
+    async fn get_webhook_deliveries(&self, subscription_id: Uuid, limit: i32) -> AppResult<Vec<WebhookDelivery>> {
+        let output = self
+            .execute(
+                &format!(
+                    "SELECT {} FROM webhook_deliveries \
+                     WHERE subscription_id = :subscription_id \
+                     ORDER BY created_at DESC \
+                     LIMIT :limit",
+                    WEBHOOK_DELIVERY_COLUMNS
+                ),
+                vec![
+                    uuid_param("subscription_id", subscription_id),
+                    long_param("limit", i64::from(limit.clamp(1, 1000))),
+                ],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .iter()
+            .map(|record| webhook_delivery_from_record(record))
+            .collect()
+    }
+
+    /// Same claim as the Postgres backend, joined with each subscription's target
+    #[instrument(skip(self), fields(otel.kind = "client", db.system = "postgresql"))]
+    async fn claim_webhook_deliveries(&self, limit: i32, lease: Duration) -> AppResult<Vec<WebhookDispatch>> {
+        let output = self
+            .execute(
+                &format!(
+                    "WITH claimed AS (\
+                         UPDATE webhook_deliveries \
+                         SET attempts = attempts + 1, next_attempt_at = NOW() + make_interval(secs => :lease_seconds) \
+                         WHERE delivery_id IN (\
+                             SELECT delivery_id FROM webhook_deliveries \
+                             WHERE status = 'pending' AND next_attempt_at <= NOW() \
+                             ORDER BY next_attempt_at, created_at \
+                             LIMIT :limit \
+                             FOR UPDATE SKIP LOCKED\
+                         ) \
+                         RETURNING {}\
+                     ) \
+                     SELECT claimed.*, s.url, s.secret \
+                     FROM claimed JOIN webhook_subscriptions s USING (subscription_id) \
+                     ORDER BY claimed.created_at",
+                    WEBHOOK_DELIVERY_COLUMNS
+                ),
+                vec![
+                    long_param("limit", i64::from(limit.clamp(1, 1000))),
+                    double_param("lease_seconds", lease.as_secs_f64()),
+                ],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .iter()
+            .map(|record| {
+                let (columns, target) = record.split_at(record.len().saturating_sub(2));
+                let mut reader = RecordReader::new(target);
+                Ok(WebhookDispatch {
+                    delivery: webhook_delivery_from_record(columns)?,
+                    url: reader.string("url")?,
+                    secret: reader.string("secret")?.into(),
+                })
+            })
+            .collect()
+    }
+
+    async fn mark_webhook_delivered(&self, delivery_id: Uuid, status_code: i32) -> AppResult<()> {
+        self.update_delivery(
+            "UPDATE webhook_deliveries \
+             SET status = 'delivered', delivered_at = NOW(), last_status_code = :status_code, last_error = NULL \
+             WHERE delivery_id = :delivery_id",
+            vec![
+                uuid_param("delivery_id", delivery_id),
+                long_param("status_code", i64::from(status_code)),
+            ],
+            delivery_id,
+        )
+        .await
+    }
+
+    async fn mark_webhook_failed(
+        &self,
+        delivery_id: Uuid,
+        status_code: Option<i32>,
+        error: &str,
+        retry_in: Option<Duration>,
+    ) -> AppResult<()> {
+        let mut parameters = vec![
+            uuid_param("delivery_id", delivery_id),
+            match status_code {
+                Some(code) => long_param("status_code", i64::from(code)),
+                None => param("status_code", Field::IsNull(true)),
+            },
+            string_param("error", error),
+        ];
+        let sql = match retry_in {
+            Some(delay) => {
+                parameters.push(double_param("delay_seconds", delay.as_secs_f64()));
+                "UPDATE webhook_deliveries \
+                 SET last_status_code = :status_code, last_error = :error, \
+                     next_attempt_at = NOW() + make_interval(secs => :delay_seconds) \
+                 WHERE delivery_id = :delivery_id"
+            }
+            None => {
+                parameters.push(string_param("status", WebhookDeliveryStatus::Dead.as_str()));
+                "UPDATE webhook_deliveries \
+                 SET last_status_code = :status_code, last_error = :error, \
+                     status = CAST(:status AS webhook_delivery_status) \
+                 WHERE delivery_id = :delivery_id"
+            }
+        };
+
+        self.update_delivery(sql, parameters, delivery_id).await
+    }
+
+    async fn upsert_subscription_plan(&self, plan: &SubscriptionPlan) -> AppResult<SubscriptionPlan> {
+        let output = self
+            .execute(
+                &format!(
+                    "INSERT INTO subscription_plans (\
+                         plan_id, name, item_id, price_cents, currency, period_days, season_ends_at, grace_period_days\
+                     ) VALUES (\
+                         :plan_id, :name, :item_id, :price_cents, :currency, :period_days, :season_ends_at, \
+                         :grace_period_days\
+                     ) ON CONFLICT (plan_id) DO UPDATE \
+                     SET name = EXCLUDED.name, item_id = EXCLUDED.item_id, price_cents = EXCLUDED.price_cents, \
+                         currency = EXCLUDED.currency, period_days = EXCLUDED.period_days, \
+                         season_ends_at = EXCLUDED.season_ends_at, \
+                         grace_period_days = EXCLUDED.grace_period_days, updated_at = NOW() \
+                     RETURNING {}",
+                    SUBSCRIPTION_PLAN_COLUMNS
+                ),
+                vec![
+                    string_param("plan_id", &plan.plan_id),
+                    string_param("name", &plan.name),
+                    string_param("item_id", &plan.item_id),
+                    long_param("price_cents", plan.price_cents),
+                    string_param("currency", &plan.currency),
+                    optional_long_param("period_days", plan.period_days.map(i64::from)),
+                    optional_timestamp_param("season_ends_at", plan.season_ends_at),
+                    long_param("grace_period_days", i64::from(plan.grace_period_days)),
+                ],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .ok_or_else(|| AppError::DataApi("INSERT returned no record".into()))
+            .and_then(|record| subscription_plan_from_record(record))
+    }
+
+    async fn get_subscription_plan(&self, plan_id: &str) -> AppResult<Option<SubscriptionPlan>> {
+        let output = self
+            .execute(
+                &format!("SELECT {} FROM subscription_plans WHERE plan_id = :plan_id", SUBSCRIPTION_PLAN_COLUMNS),
+                vec![string_param("plan_id", plan_id)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .map(|record| subscription_plan_from_record(record))
+            .transpose()
+    }
+
+    #[instrument(skip(self, subscription), fields(subscription_id = %subscription.subscription_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn insert_subscription(&self, subscription: &Subscription) -> AppResult<Subscription> {
+        self.with_transaction(|data_api_tx| async move {
+            self.check_live_subscription(subscription, &data_api_tx).await?;
+
+            let mut parameters = subscription_params(subscription);
+            parameters.push(long_param("version", subscription.version));
+            let output = self
+                .execute(
+                    &format!(
+                        "INSERT INTO subscriptions ({0}) VALUES (\
+                             :subscription_id, :player_id, :plan_id, CAST(:status AS subscription_status), \
+                             :payment_method, :current_period_start, :current_period_end, :cancel_at_period_end, \
+                             :renewal_attempts, :grace_ends_at, :next_billing_at, :credit_cents, \
+                             :entitlement_transaction_id, CAST(:renewal_transaction_id AS uuid), :ended_at, \
+                             :version, :created_at, :updated_at\
+                         ) RETURNING {0}",
+                        SUBSCRIPTION_COLUMNS
+                    ),
+                    parameters,
+                    Some(data_api_tx.as_str()),
+                )
+                .await?;
+
+            output
+                .records()
+                .first()
+                .ok_or_else(|| AppError::DataApi("INSERT returned no record".into()))
+                .and_then(|record| subscription_from_record(record))
+        })
+        .await
+    }
+
+    async fn get_subscription(&self, subscription_id: Uuid) -> AppResult<Option<Subscription>> {
+        let output = self
+            .execute(
+                &format!(
+                    "SELECT {} FROM subscriptions WHERE subscription_id = :subscription_id",
+                    SUBSCRIPTION_COLUMNS
+                ),
+                vec![uuid_param("subscription_id", subscription_id)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .map(|record| subscription_from_record(record))
+            .transpose()
+    }
+
+    async fn get_player_subscriptions(&self, player_id: Uuid) -> AppResult<Vec<Subscription>> {
+        let output = self
+            .execute(
+                &format!(
+                    "SELECT {} FROM subscriptions WHERE player_id = :player_id ORDER BY created_at DESC",
+                    SUBSCRIPTION_COLUMNS
+                ),
+                vec![uuid_param("player_id", player_id)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .iter()
+            .map(|record| subscription_from_record(record))
+            .collect()
+    }
+
+    async fn get_due_subscriptions(&self, now: DateTime<Utc>, limit: i32) -> AppResult<Vec<Subscription>> {
+        let output = self
+            .execute(
+                &format!(
+                    "SELECT {} FROM subscriptions \
+                     WHERE status IN ('active', 'past_due') AND next_billing_at <= :now \
+                     ORDER BY next_billing_at LIMIT :limit",
+                    SUBSCRIPTION_COLUMNS
+                ),
+                vec![timestamp_param("now", now), long_param("limit", i64::from(limit))],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .iter()
+            .map(|record| subscription_from_record(record))
+            .collect()
+    }
+
+    #[instrument(skip(self, subscription), fields(subscription_id = %subscription.subscription_id, otel.kind = "client", db.system = "postgresql"))]
+    async fn update_subscription(&self, subscription: &Subscription) -> AppResult<Subscription> {
+        self.with_transaction(|data_api_tx| async move {
+            let tid = Some(data_api_tx.as_str());
+
+            let stored = self
+                .execute(
+                    &format!(
+                        "SELECT {} FROM subscriptions WHERE subscription_id = :subscription_id FOR UPDATE",
+                        SUBSCRIPTION_COLUMNS
+                    ),
+                    vec![uuid_param("subscription_id", subscription.subscription_id)],
+                    tid,
+                )
+                .await?;
+            let stored = stored
+                .records()
+                .first()
+                .map(|record| subscription_from_record(record))
+                .transpose()?
+                .ok_or_else(|| AppError::NotFound(format!("Subscription {} not found", subscription.subscription_id)))?;
+            check_subscription_version(subscription, &stored)?;
+            if subscription.plan_id != stored.plan_id || subscription.status.is_live() != stored.status.is_live() {
+                self.check_live_subscription(subscription, &data_api_tx).await?;
+            }
+
+            let saved = self
+                .execute(
+                    &format!(
+                        "UPDATE subscriptions \
+                         SET plan_id = :plan_id, status = CAST(:status AS subscription_status), \
+                             payment_method = :payment_method, current_period_start = :current_period_start, \
+                             current_period_end = :current_period_end, cancel_at_period_end = :cancel_at_period_end, \
+                             renewal_attempts = :renewal_attempts, grace_ends_at = :grace_ends_at, \
+                             next_billing_at = :next_billing_at, credit_cents = :credit_cents, \
+                             entitlement_transaction_id = :entitlement_transaction_id, \
+                             renewal_transaction_id = CAST(:renewal_transaction_id AS uuid), ended_at = :ended_at, \
+                             version = version + 1, updated_at = NOW() \
+                         WHERE subscription_id = :subscription_id \
+                         RETURNING {}",
+                        SUBSCRIPTION_COLUMNS
+                    ),
+                    subscription_params(subscription)
+                        .into_iter()
+                        .filter(|p| !matches!(p.name(), Some("player_id" | "created_at" | "updated_at")))
+                        .collect(),
+                    tid,
+                )
+                .await?;
+            let saved = saved
+                .records()
+                .first()
+                .ok_or_else(|| AppError::DataApi("UPDATE returned no record".into()))
+                .and_then(|record| subscription_from_record(record))?;
+
+            for (transaction_id, expires_at) in saved.access_changes(&stored, Utc::now()) {
+                self.execute(
+                    "UPDATE entitlements SET expires_at = :expires_at WHERE source_transaction_id = :transaction_id",
+                    vec![uuid_param("transaction_id", transaction_id), timestamp_param("expires_at", expires_at)],
+                    tid,
+                )
+                .await?;
+            }
+
+            Ok(saved)
+        })
+        .await
+    }
+
+    async fn redeliver_webhook(&self, delivery_id: Uuid) -> AppResult<WebhookDelivery> {
+        let output = self
+            .execute(
+                &format!(
+                    "UPDATE webhook_deliveries \
+                     SET status = 'pending', attempts = 0, next_attempt_at = NOW(), delivered_at = NULL \
+                     WHERE delivery_id = :delivery_id \
+                     RETURNING {}",
+                    WEBHOOK_DELIVERY_COLUMNS
+                ),
+                vec![uuid_param("delivery_id", delivery_id)],
+                None,
+            )
+            .await?;
+
+        output
+            .records()
+            .first()
+            .map(|record| webhook_delivery_from_record(record))
+            .transpose()?
+            .ok_or_else(|| AppError::NotFound(format!("Webhook delivery {} not found", delivery_id)))
+    }
+
+    fn name(&self) -> &'static str {
+        "data-api"
+    }
+}
+
+// ============================================================================
+// PARAMETER BUILDERS
+// ============================================================================
+
+fn param(name: &str, value: Field) -> SqlParameter {
+    SqlParameter::builder().name(name).value(value).build()
+}
+
+fn hinted_param(name: &str, value: Field, hint: TypeHint) -> SqlParameter {
+    SqlParameter::builder().name(name).value(value).type_hint(hint).build()
+}
+
+fn uuid_param(name: &str, value: Uuid) -> SqlParameter {
+    hinted_param(name, Field::StringValue(value.to_string()), TypeHint::Uuid)
+}
+
+fn string_param(name: &str, value: &str) -> SqlParameter {
+    param(name, Field::StringValue(value.to_string()))
+}
+
+fn optional_string_param(name: &str, value: Option<&str>) -> SqlParameter {
+    match value {
+        Some(v) => string_param(name, v),
+        None => param(name, Field::IsNull(true)),
+    }
+}
+
+fn long_param(name: &str, value: i64) -> SqlParameter {
+    param(name, Field::LongValue(value))
+}
+
+fn optional_long_param(name: &str, value: Option<i64>) -> SqlParameter {
+    match value {
+        Some(v) => long_param(name, v),
+        None => param(name, Field::IsNull(true)),
+    }
+}
+
+fn timestamp_param(name: &str, value: DateTime<Utc>) -> SqlParameter {
+    hinted_param(
+        name,
+        Field::StringValue(value.format("%Y-%m-%d %H:%M:%S%.6f").to_string()),
+        TypeHint::Timestamp,
+    )
+}
+
+fn optional_timestamp_param(name: &str, value: Option<DateTime<Utc>>) -> SqlParameter {
+    match value {
+        Some(v) => timestamp_param(name, v),
+        None => param(name, Field::IsNull(true)),
+    }
+}
+
+fn double_param(name: &str, value: f64) -> SqlParameter {
+    param(name, Field::DoubleValue(value))
+}
+
+fn json_param(name: &str, value: &serde_json::Value) -> SqlParameter {
+    hinted_param(name, Field::StringValue(value.to_string()), TypeHint::Json)
+}
+
+// ============================================================================
+// RECORD MAPPING
+// ============================================================================
+
+fn data_api_error<E: std::error::Error>(err: E) -> AppError {
+    AppError::DataApi(DisplayErrorContext(err).to_string())
+}
+
+/// Sequential reader over one Data API record
+///
+/// ADVANTAGE: Every column access is typed and reports the column on mismatch
+struct RecordReader<'a> {
+    fields: std::slice::Iter<'a, Field>,
+}
+
+impl<'a> RecordReader<'a> {
+    fn new(record: &'a [Field]) -> Self {
+        Self { fields: record.iter() }
+    }
+
+    fn next(&mut self, column: &str) -> AppResult<&'a Field> {
+        self.fields
+            .next()
+            .ok_or_else(|| AppError::DataApi(format!("Missing column {}", column)))
+    }
+
+    fn optional_string(&mut self, column: &str) -> AppResult<Option<String>> {
+        match self.next(column)? {
+            Field::StringValue(s) => Ok(Some(s.clone())),
+            Field::IsNull(true) => Ok(None),
+            other => Err(unexpected_field(column, other)),
+        }
+    }
+
+    fn string(&mut self, column: &str) -> AppResult<String> {
+        self.optional_string(column)?
+            .ok_or_else(|| AppError::DataApi(format!("Column {} is null", column)))
+    }
+
+    fn long(&mut self, column: &str) -> AppResult<i64> {
+        self.optional_long(column)?
+            .ok_or_else(|| AppError::DataApi(format!("Column {} is null", column)))
+    }
+
+    fn optional_long(&mut self, column: &str) -> AppResult<Option<i64>> {
+        match self.next(column)? {
+            Field::LongValue(v) => Ok(Some(*v)),
+            Field::IsNull(true) => Ok(None),
+            other => Err(unexpected_field(column, other)),
+        }
+    }
+
+    fn boolean(&mut self, column: &str) -> AppResult<bool> {
+        match self.next(column)? {
+            Field::BooleanValue(v) => Ok(*v),
+            other => Err(unexpected_field(column, other)),
+        }
+    }
+
+    /// JSON column, read through its text form
+    fn json<T: serde::de::DeserializeOwned>(&mut self, column: &str) -> AppResult<T> {
+        serde_json::from_str(&self.string(column)?)
+            .map_err(|_| AppError::DataApi(format!("Column {} does not hold the expected JSON", column)))
+    }
+
+    /// Postgres enum label, read through its serde name
+    fn enum_value<T: serde::de::DeserializeOwned>(&mut self, column: &str) -> AppResult<T> {
+        serde_json::from_value(serde_json::Value::String(self.string(column)?))
+            .map_err(|_| AppError::DataApi(format!("Column {} has an unknown label", column)))
+    }
+
+    fn optional_enum_value<T: serde::de::DeserializeOwned>(&mut self, column: &str) -> AppResult<Option<T>> {
+        self.optional_string(column)?
+            .map(|label| serde_json::from_value(serde_json::Value::String(label)))
+            .transpose()
+            .map_err(|_| AppError::DataApi(format!("Column {} has an unknown label", column)))
+    }
+
+    fn uuid(&mut self, column: &str) -> AppResult<Uuid> {
+        self.string(column)?
+            .parse()
+            .map_err(|_| AppError::DataApi(format!("Column {} is not a UUID", column)))
+    }
+
+    fn timestamp(&mut self, column: &str) -> AppResult<DateTime<Utc>> {
+        parse_timestamp(&self.string(column)?)
+            .ok_or_else(|| AppError::DataApi(format!("Column {} is not a timestamp", column)))
+    }
+
+    fn optional_timestamp(&mut self, column: &str) -> AppResult<Option<DateTime<Utc>>> {
+        self.optional_string(column)?
+            .map(|value| {
+                parse_timestamp(&value)
+                    .ok_or_else(|| AppError::DataApi(format!("Column {} is not a timestamp", column)))
+            })
+            .transpose()
+    }
+
+    fn int(&mut self, column: &str) -> AppResult<i32> {
+        i32::try_from(self.long(column)?).map_err(|_| AppError::DataApi(format!("Column {} out of range", column)))
+    }
+}
+
+fn unexpected_field(column: &str, field: &Field) -> AppError {
+    AppError::DataApi(format!("Unexpected value for column {}: {:?}", column, field))
+}
+
+/// Parse Data API timestamp text (UTC, space separated) or RFC 3339
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+                .ok()
+                .map(|naive| naive.and_utc())
+        })
+}
+
+/// Map a record selected with `TRANSACTION_COLUMNS` to `Transaction`
+fn transaction_from_record(record: &[Field]) -> AppResult<Transaction> {
+    let mut reader = RecordReader::new(record);
+
+    let transaction_id = reader.uuid("transaction_id")?;
+    let player_id = reader.uuid("player_id")?;
+    let item_id = reader.string("item_id")?;
+    let item_name = reader.string("item_name")?;
+    let price_cents = reader.long("price_cents")?;
+    let currency = reader.string("currency")?;
+    let quantity = i32::try_from(reader.long("quantity")?)
+        .map_err(|_| AppError::DataApi("Column quantity out of range".into()))?;
+    let status = reader.enum_value("status")?;
+    let metadata = serde_json::from_str(&reader.string("metadata")?)
+        .map_err(|_| AppError::DataApi("Column metadata is not valid JSON".into()))?;
+    let processor_id = reader.optional_string("processor_id")?;
+    let created_at = reader.timestamp("created_at")?;
+    let updated_at = reader.timestamp("updated_at")?;
+    let request_id = reader.optional_string("request_id")?;
+    let original_price_cents = reader.optional_long("original_price_cents")?;
+    let applied_promotions = reader.json("applied_promotions")?;
+    let recipient_id = reader
+        .optional_string("recipient_id")?
+        .map(|id| id.parse())
+        .transpose()
+        .map_err(|_| AppError::DataApi("Column recipient_id is not a UUID".into()))?;
+    let gift_status = reader
+        .optional_string("gift_status")?
+        .map(|status| serde_json::from_value(serde_json::Value::String(status)))
+        .transpose()
+        .map_err(|_| AppError::DataApi("Column gift_status has an unknown label".into()))?;
+
+    Ok(Transaction {
+        transaction_id,
+        player_id,
+        item_id,
+        item_name,
+        price_cents,
+        currency,
+        quantity,
+        status,
+        metadata,
+        processor_id,
+        request_id,
+        created_at,
+        updated_at,
+        original_price_cents,
+        applied_promotions,
+        recipient_id,
+        gift_status,
+    })
+}
+
+/// Map a `LEDGER_COLUMNS` record to `LedgerEntry`
+fn ledger_entry_from_record(record: &[Field]) -> AppResult<LedgerEntry> {
+    let mut reader = RecordReader::new(record);
+
+    Ok(LedgerEntry {
+        entry_id: reader.uuid("entry_id")?,
+        player_id: reader.uuid("player_id")?,
+        currency: reader.enum_value("currency")?,
+        sequence: reader.long("sequence")?,
+        amount: reader.long("amount")?,
+        balance_after: reader.long("balance_after")?,
+        kind: reader.enum_value("kind")?,
+        reference: reader.string("reference")?,
+        item_id: reader.optional_string("item_id")?,
+        request_id: reader.optional_string("request_id")?,
+        created_at: reader.timestamp("created_at")?,
+    })
+}
+
+/// Map an `ENTITLEMENT_COLUMNS` record to `Entitlement`
+fn entitlement_from_record(record: &[Field]) -> AppResult<Entitlement> {
+    let mut reader = RecordReader::new(record);
+
+    Ok(Entitlement {
+        entitlement_id: reader.uuid("entitlement_id")?,
+        player_id: reader.uuid("player_id")?,
+        item_id: reader.string("item_id")?,
+        quantity: i32::try_from(reader.long("quantity")?)
+            .map_err(|_| AppError::DataApi("Column quantity out of range".into()))?,
+        kind: reader.enum_value("kind")?,
+        status: reader.enum_value("status")?,
+        source_transaction_id: reader.uuid("source_transaction_id")?,
+        granted_at: reader.timestamp("granted_at")?,
+        revoked_at: reader.optional_timestamp("revoked_at")?,
+        expires_at: reader.optional_timestamp("expires_at")?,
+    })
+}
+
+/// Map a `JOURNAL_COLUMNS` record to `JournalEntry`, without postings
+fn journal_entry_from_record(record: &[Field]) -> AppResult<JournalEntry> {
+    let mut reader = RecordReader::new(record);
+
+    Ok(JournalEntry {
+        journal_id: reader.uuid("journal_id")?,
+        kind: reader.enum_value("kind")?,
+        reference: reader.string("reference")?,
+        player_id: reader
+            .optional_string("player_id")?
+            .map(|id| id.parse())
+            .transpose()
+            .map_err(|_| AppError::DataApi("Column player_id is not a UUID".into()))?,
+        request_id: reader.optional_string("request_id")?,
+        created_at: reader.timestamp("created_at")?,
+        postings: Vec::new(),
+    })
+}
+
+/// Map an `OUTBOX_COLUMNS` record to `OutboxEvent`
+fn outbox_event_from_record(record: &[Field]) -> AppResult<OutboxEvent> {
+    let mut reader = RecordReader::new(record);
+
+    Ok(OutboxEvent {
+        event_id: reader.uuid("event_id")?,
+        event_type: reader.string("event_type")?,
+        aggregate_id: reader.uuid("aggregate_id")?,
+        payload: serde_json::from_str(&reader.string("payload")?)
+            .map_err(|_| AppError::DataApi("Column payload is not valid JSON".into()))?,
+        status: reader.enum_value("status")?,
+        attempts: i32::try_from(reader.long("attempts")?)
+            .map_err(|_| AppError::DataApi("Column attempts out of range".into()))?,
+        next_attempt_at: reader.timestamp("next_attempt_at")?,
+        last_error: reader.optional_string("last_error")?,
+        created_at: reader.timestamp("created_at")?,
+        delivered_at: reader
+            .optional_string("delivered_at")?
+            .map(|value| {
+                parse_timestamp(&value)
+                    .ok_or_else(|| AppError::DataApi("Column delivered_at is not a timestamp".into()))
+            })
+            .transpose()?,
+    })
+}
+
+/// Map a `CATALOG_COLUMNS` record to `CatalogItem`
+fn catalog_item_from_record(record: &[Field]) -> AppResult<CatalogItem> {
+    let mut reader = RecordReader::new(record);
+    let count = |column: &str, value: i64| {
+        i32::try_from(value).map_err(|_| AppError::DataApi(format!("Column {} out of range", column)))
+    };
+
+    Ok(CatalogItem {
+        item_id: reader.string("item_id")?,
+        kind: reader.enum_value("kind")?,
+        stock_limit: reader
+            .optional_long("stock_limit")?
+            .map(|limit| count("stock_limit", limit))
+            .transpose()?,
+        stock_reserved: count("stock_reserved", reader.long("stock_reserved")?)?,
+        stock_sold: count("stock_sold", reader.long("stock_sold")?)?,
+        price_cents: reader.optional_long("price_cents")?,
+        currency: reader.optional_string("currency")?,
+        credit_currency: reader.optional_enum_value("credit_currency")?,
+        credit_amount: reader.optional_long("credit_amount")?,
+        wallet_price: reader.optional_long("wallet_price")?,
+        wallet_currency: reader.optional_enum_value("wallet_currency")?,
+        title_id: reader.optional_string("title_id")?,
+        updated_at: reader.timestamp("updated_at")?,
+    })
+}
+
+/// Map a `PROMOTION_COLUMNS` record to `Promotion`
+fn promotion_from_record(record: &[Field]) -> AppResult<Promotion> {
+    let mut reader = RecordReader::new(record);
+
+    Ok(Promotion {
+        promotion_id: reader.uuid("promotion_id")?,
+        name: reader.string("name")?,
+        kind: reader.enum_value("kind")?,
+        value: reader.long("value")?,
+        currency: reader.optional_string("currency")?,
+        item_ids: reader.json("item_ids")?,
+        segments: reader.json("segments")?,
+        bundle_items: reader.json("bundle_items")?,
+        stackable: reader.boolean("stackable")?,
+        priority: i32::try_from(reader.long("priority")?)
+            .map_err(|_| AppError::DataApi("Column priority out of range".into()))?,
+        starts_at: reader.timestamp("starts_at")?,
+        ends_at: reader.timestamp("ends_at")?,
+        created_at: reader.timestamp("created_at")?,
+    })
+}
+
+/// Map a `PLAYER_PROFILE_COLUMNS` record to `PlayerProfile`
+fn player_profile_from_record(record: &[Field]) -> AppResult<PlayerProfile> {
+    let mut reader = RecordReader::new(record);
+
+    Ok(PlayerProfile {
+        player_id: reader.uuid("player_id")?,
+        segments: reader.json("segments")?,
+        updated_at: reader.timestamp("updated_at")?,
+    })
+}
+
+/// Map a `CODE_CAMPAIGN_COLUMNS` record to `CodeCampaign`
+fn code_campaign_from_record(record: &[Field]) -> AppResult<CodeCampaign> {
+    let mut reader = RecordReader::new(record);
+    let int = |column: &str, value: i64| {
+        i32::try_from(value).map_err(|_| AppError::DataApi(format!("Column {} out of range", column)))
+    };
+
+    Ok(CodeCampaign {
+        campaign_id: reader.uuid("campaign_id")?,
+        name: reader.string("name")?,
+        reward: reader.json("reward")?,
+        max_uses: reader.optional_long("max_uses")?.map(|v| int("max_uses", v)).transpose()?,
+        per_player_limit: int("per_player_limit", reader.long("per_player_limit")?)?,
+        expires_at: reader
+            .optional_string("expires_at")?
+            .map(|value| {
+                parse_timestamp(&value)
+                    .ok_or_else(|| AppError::DataApi("Column expires_at is not a timestamp".into()))
+            })
+            .transpose()?,
+        code_count: int("code_count", reader.long("code_count")?)?,
+        created_at: reader.timestamp("created_at")?,
+    })
+}
+
+/// Map a `SUBSCRIPTION_PLAN_COLUMNS` record to `SubscriptionPlan`
+fn subscription_plan_from_record(record: &[Field]) -> AppResult<SubscriptionPlan> {
+    let mut reader = RecordReader::new(record);
+
+    Ok(SubscriptionPlan {
+        plan_id: reader.string("plan_id")?,
+        name: reader.string("name")?,
+        item_id: reader.string("item_id")?,
+        price_cents: reader.long("price_cents")?,
+        currency: reader.string("currency")?,
+        period_days: reader
+            .optional_long("period_days")?
+            .map(|v| i32::try_from(v).map_err(|_| AppError::DataApi("Column period_days out of range".into())))
+            .transpose()?,
+        season_ends_at: reader.optional_timestamp("season_ends_at")?,
+        grace_period_days: reader.int("grace_period_days")?,
+        updated_at: reader.timestamp("updated_at")?,
+    })
+}
+
+/// Map a `SUBSCRIPTION_COLUMNS` record to `Subscription`
+fn subscription_from_record(record: &[Field]) -> AppResult<Subscription> {
+    let mut reader = RecordReader::new(record);
+
+    Ok(Subscription {
+        subscription_id: reader.uuid("subscription_id")?,
+        player_id: reader.uuid("player_id")?,
+        plan_id: reader.string("plan_id")?,
+        status: reader.enum_value("status")?,
+        payment_method: reader.string("payment_method")?,
+        current_period_start: reader.timestamp("current_period_start")?,
+        current_period_end: reader.timestamp("current_period_end")?,
+        cancel_at_period_end: reader.boolean("cancel_at_period_end")?,
+        renewal_attempts: reader.int("renewal_attempts")?,
+        grace_ends_at: reader.optional_timestamp("grace_ends_at")?,
+        next_billing_at: reader.optional_timestamp("next_billing_at")?,
+        credit_cents: reader.long("credit_cents")?,
+        entitlement_transaction_id: reader.uuid("entitlement_transaction_id")?,
+        renewal_transaction_id: reader
+            .optional_string("renewal_transaction_id")?
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| AppError::DataApi("Column renewal_transaction_id is not a UUID".into()))
+            })
+            .transpose()?,
+        ended_at: reader.optional_timestamp("ended_at")?,
+        version: reader.long("version")?,
+        created_at: reader.timestamp("created_at")?,
+        updated_at: reader.timestamp("updated_at")?,
+    })
+}
+
+/// Parameters naming every `subscriptions` column but `version`
+fn subscription_params(subscription: &Subscription) -> Vec<SqlParameter> {
+    let renewal_transaction_id = subscription.renewal_transaction_id.map(|id| id.to_string());
+    vec![
+        uuid_param("subscription_id", subscription.subscription_id),
+        uuid_param("player_id", subscription.player_id),
+        string_param("plan_id", &subscription.plan_id),
+        string_param("status", subscription.status.as_str()),
+        string_param("payment_method", &subscription.payment_method),
+        timestamp_param("current_period_start", subscription.current_period_start),
+        timestamp_param("current_period_end", subscription.current_period_end),
+        param("cancel_at_period_end", Field::BooleanValue(subscription.cancel_at_period_end)),
+        long_param("renewal_attempts", i64::from(subscription.renewal_attempts)),
+        optional_timestamp_param("grace_ends_at", subscription.grace_ends_at),
+        optional_timestamp_param("next_billing_at", subscription.next_billing_at),
+        long_param("credit_cents", subscription.credit_cents),
+        uuid_param("entitlement_transaction_id", subscription.entitlement_transaction_id),
+        optional_string_param("renewal_transaction_id", renewal_transaction_id.as_deref()),
+        optional_timestamp_param("ended_at", subscription.ended_at),
+        timestamp_param("created_at", subscription.created_at),
+        timestamp_param("updated_at", subscription.updated_at),
+    ]
+}
+
+/// Map a `WEBHOOK_SUBSCRIPTION_COLUMNS` record to `WebhookSubscription`
+fn webhook_subscription_from_record(record: &[Field]) -> AppResult<WebhookSubscription> {
+    let mut reader = RecordReader::new(record);
+
+    Ok(WebhookSubscription {
+        subscription_id: reader.uuid("subscription_id")?,
+        title_id: reader.string("title_id")?,
+        url: reader.string("url")?,
+        secret: reader.string("secret")?.into(),
+        event_types: serde_json::from_str(&reader.string("event_types")?)
+            .map_err(|_| AppError::DataApi("Column event_types is not a list of event types".into()))?,
+        created_at: reader.timestamp("created_at")?,
+    })
+}
+
+/// Map a `WEBHOOK_DELIVERY_COLUMNS` record to `WebhookDelivery`
+fn webhook_delivery_from_record(record: &[Field]) -> AppResult<WebhookDelivery> {
+    let mut reader = RecordReader::new(record);
+
+    Ok(WebhookDelivery {
+        delivery_id: reader.uuid("delivery_id")?,
+        subscription_id: reader.uuid("subscription_id")?,
+        event_id: reader.uuid("event_id")?,
+        event_type: reader.string("event_type")?,
+        payload: serde_json::from_str(&reader.string("payload")?)
+            .map_err(|_| AppError::DataApi("Column payload is not valid JSON".into()))?,
+        status: reader.enum_value("status")?,
+        attempts: i32::try_from(reader.long("attempts")?)
+            .map_err(|_| AppError::DataApi("Column attempts out of range".into()))?,
+        next_attempt_at: reader.timestamp("next_attempt_at")?,
+        last_status_code: reader
+            .optional_long("last_status_code")?
+            .map(i32::try_from)
+            .transpose()
+            .map_err(|_| AppError::DataApi("Column last_status_code out of range".into()))?,
+        last_error: reader.optional_string("last_error")?,
+        created_at: reader.timestamp("created_at")?,
+        delivered_at: reader
+            .optional_string("delivered_at")?
+            .map(|value| {
+                parse_timestamp(&value)
+                    .ok_or_else(|| AppError::DataApi("Column delivered_at is not a timestamp".into()))
+            })
+            .transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_rdsdata::config::{BehaviorVersion, Credentials, Region};
+    use aws_sdk_rdsdata::config::retry::RetryConfig;
+    use serde_json::{json, Value};
+    use crate::test_support::HttpStandIn;
+
+    fn stand_in_database(stand_in: &HttpStandIn) -> RdsDataDatabase {
+        let sdk_config = aws_sdk_rdsdata::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("AKIDSTANDIN", "secret", None, None, "stand-in"))
+            .endpoint_url(&stand_in.endpoint)
+            .retry_config(RetryConfig::disabled())
+            .build();
+
+        RdsDataDatabase::from_client(
+            Client::from_conf(sdk_config),
+            DataApiConfig {
+                resource_arn: "arn:aws:rds:us-east-1:123456789012:cluster:mmog".into(),
+                secret_arn: "arn:aws:secretsmanager:us-east-1:123456789012:secret:mmog".into(),
+                database: "mmog_transactions".into(),
+                endpoint_url: Some(stand_in.endpoint.clone()),
+            },
+        )
+    }
+
+    fn transaction_record_json(transaction_id: &str, status: &str) -> Value {
+        json!([
+            {"stringValue": transaction_id},
+            {"stringValue": "550e8400-e29b-41d4-a716-446655440000"},
+            {"stringValue": "sword_001"},
+            {"stringValue": "Iron Sword"},
+            {"longValue": 999},
+            {"stringValue": "USD"},
+            {"longValue": 1},
+            {"stringValue": status},
+            {"stringValue": "{\"rarity\":\"common\"}"},
+            {"isNull": true},
+            {"stringValue": "2025-01-15 10:30:00.123456"},
+            {"stringValue": "2025-01-15 10:30:00.123456"},
+            {"stringValue": "req-42"},
+            {"isNull": true},
+            {"stringValue": "[]"},
+            {"isNull": true},
+            {"isNull": true}
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_insert_maps_returned_record() {
+        let stand_in = HttpStandIn::start(|request| match request.path.as_str() {
+            // Not catalogued - inserted without a Data API transaction
+            "/Execute" if request.json()["sql"].as_str().is_some_and(|sql| sql.contains("FROM catalog_items")) => {
+                (200, json!({"records": []}))
+            }
+            "/Execute" => {
+                let body = request.json();
+                let id = body["parameters"][0]["value"]["stringValue"].as_str().unwrap_or_default();
+                (200, json!({
+                    "records": [transaction_record_json(id, "pending")],
+                    "numberOfRecordsUpdated": 1
+                }))
+            }
+            _ => (200, json!({})),
+        })
+        .await;
+        let db = stand_in_database(&stand_in);
+
+        let new_tx = NewTransaction::new(
+            "550e8400-e29b-41d4-a716-446655440000".parse().unwrap(),
+            "sword_001".into(),
+            "Iron Sword".into(),
+            999,
+            "USD".into(),
+            1,
+            json!({"rarity": "common"}),
+        );
+
+        let tx = db.insert_transaction(&new_tx).await.unwrap();
+
+        // ADVANTAGE: Data API records come back as the same typed Transaction
+        assert_eq!(tx.transaction_id, new_tx.transaction_id);
+        assert_eq!(tx.status, TransactionStatus::Pending);
+        assert_eq!(tx.metadata, json!({"rarity": "common"}));
+        assert!(tx.processor_id.is_none());
+        assert_eq!(tx.request_id.as_deref(), Some("req-42"));
+
+        let requests = stand_in.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].json()["database"], "mmog_transactions");
+        assert_eq!(requests[1].json()["parameters"][0]["typeHint"], "UUID");
+    }
+
+    #[tokio::test]
+    async fn test_with_transaction_commits_and_rolls_back() {
+        let stand_in = HttpStandIn::start(|request| match request.path.as_str() {
+            "/BeginTransaction" => (200, json!({"transactionId": "tx-123"})),
+            "/BatchExecute" => (200, json!({"updateResults": [{}, {}]})),
+            "/CommitTransaction" => (200, json!({"transactionStatus": "Transaction Committed"})),
+            "/RollbackTransaction" => (200, json!({"transactionStatus": "Rollback Complete"})),
+            _ => (200, json!({})),
+        })
+        .await;
+        let db = stand_in_database(&stand_in);
+
+        let updated = db
+            .with_transaction(|tx_id| {
+                let db = &db;
+                async move {
+                    db.batch_execute(
+                        "UPDATE microtransactions SET status = 'failed' WHERE transaction_id = :id",
+                        vec![
+                            vec![uuid_param("id", Uuid::new_v4())],
+                            vec![uuid_param("id", Uuid::new_v4())],
+                        ],
+                        Some(&tx_id),
+                    )
+                    .await
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(updated, 2);
+
+        let failed: AppResult<()> = db
+            .with_transaction(|_| async { Err(AppError::Conflict("boom".into())) })
+            .await;
+        assert!(matches!(failed, Err(AppError::Conflict(_))));
+
+        let paths: Vec<String> = stand_in.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/BeginTransaction",
+                "/BatchExecute",
+                "/CommitTransaction",
+                "/BeginTransaction",
+                "/RollbackTransaction",
+            ]
+        );
+        assert_eq!(stand_in.requests()[1].json()["transactionId"], "tx-123");
+    }
+
+    #[test]
+    fn test_record_type_mismatch_is_reported() {
+        let record = vec![Field::LongValue(42)];
+
+        let err = transaction_from_record(&record).unwrap_err();
+
+        assert!(matches!(err, AppError::DataApi(ref msg) if msg.contains("transaction_id")));
+    }
+}
@@ -0,0 +1,273 @@
+//! # Component Health Checks
+//!
+//! Readiness runs every registered `HealthCheck` concurrently, each under
+//! `CHECK_TIMEOUT`, and reports the worst status. Liveness runs none.
+//!
+//! ADVANTAGE: New components plug in by implementing one trait
+//! ADVANTAGE: A hung dependency times out instead of hanging the probe
+
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::warn;
+
+use crate::metrics::Metrics;
+use crate::models::response::{BuildInfo, ComponentHealth, HealthStatus};
+use crate::services::circuit_breaker::{CircuitBreaker, CircuitState};
+use crate::services::database::MIGRATIONS;
+use crate::services::{Database, PaymentService, SecretStore};
+
+/// Longest a single check may take before it is reported unhealthy
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Database round trip above which the database is reported degraded
+pub const DEFAULT_DB_DEGRADED_AFTER: Duration = Duration::from_millis(250);
+
+/// One component's health check
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    /// Key in the response's `checks` object
+    fn name(&self) -> &'static str;
+
+    async fn check(&self) -> ComponentHealth;
+}
+
+/// This build's version, commit and expected schema version
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        commit: option_env!("GIT_COMMIT"),
+        schema_version: MIGRATIONS.last().map(|m| m.version),
+    }
+}
+
+/// Run `checks` concurrently, keyed by check name
+pub async fn run_checks(checks: &[Arc<dyn HealthCheck>]) -> BTreeMap<String, ComponentHealth> {
+    let mut tasks = JoinSet::new();
+    for check in checks {
+        let check = Arc::clone(check);
+        tasks.spawn(async move {
+            let health = tokio::time::timeout(CHECK_TIMEOUT, check.check())
+                .await
+                .unwrap_or_else(|_| ComponentHealth::unhealthy("check timed out"));
+            (check.name(), health)
+        });
+    }
+
+    let mut results = BTreeMap::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok((name, health)) = joined {
+            results.insert(name.to_string(), health);
+        }
+    }
+    results
+}
+
+/// Worst status among `components`, healthy if there are none
+pub fn overall_status<'a>(components: impl IntoIterator<Item = &'a ComponentHealth>) -> HealthStatus {
+    components
+        .into_iter()
+        .map(|c| c.status)
+        .max()
+        .unwrap_or(HealthStatus::Healthy)
+}
+
+/// Round trip to the database, degraded above a latency threshold
+pub struct DatabaseCheck {
+    db: Arc<dyn Database>,
+    degraded_after: Duration,
+    metrics: Metrics,
+}
+
+impl DatabaseCheck {
+    pub fn new(db: Arc<dyn Database>, degraded_after: Duration, metrics: Metrics) -> Self {
+        Self { db, degraded_after, metrics }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for DatabaseCheck {
+    fn name(&self) -> &'static str {
+        "database"
+    }
+
+    async fn check(&self) -> ComponentHealth {
+        match self.db.health_check().await {
+            Ok(latency) => {
+                self.metrics.record_db_latency("health_check", latency);
+                let health = if latency > self.degraded_after {
+                    ComponentHealth::degraded(format!(
+                        "round trip above {}ms",
+                        self.degraded_after.as_millis()
+                    ))
+                } else {
+                    ComponentHealth::healthy()
+                };
+                health.with_latency(latency)
+            }
+            Err(e) => {
+                warn!(error = %e, "Database unhealthy");
+                ComponentHealth::unhealthy("database unreachable")
+            }
+        }
+    }
+}
+
+/// Applied schema version matches the migrations this build ships
+///
+/// A schema behind the build is unhealthy - queries may use missing
+/// columns. A schema ahead of it (a newer deploy migrated first) is
+/// degraded, since migrations are additive.
+pub struct MigrationCheck {
+    db: Arc<dyn Database>,
+}
+
+impl MigrationCheck {
+    pub fn new(db: Arc<dyn Database>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for MigrationCheck {
+    fn name(&self) -> &'static str {
+        "migrations"
+    }
+
+    async fn check(&self) -> ComponentHealth {
+        let expected = build_info().schema_version;
+
+        match self.db.schema_version().await {
+            Ok(applied) if applied == expected => ComponentHealth::healthy(),
+            Ok(applied) if applied > expected => ComponentHealth::degraded(format!(
+                "schema version {} is newer than this build ({})",
+                applied.unwrap_or_default(),
+                expected.unwrap_or_default()
+            )),
+            Ok(applied) => ComponentHealth::unhealthy(format!(
+                "schema version {} is behind this build ({})",
+                applied.unwrap_or_default(),
+                expected.unwrap_or_default()
+            )),
+            Err(e) => {
+                warn!(error = %e, "Schema version unavailable");
+                ComponentHealth::unhealthy("schema version unavailable")
+            }
+        }
+    }
+}
+
+/// Payment processor reachable with the current credentials
+pub struct PaymentProcessorCheck {
+    payment_service: Arc<PaymentService>,
+}
+
+impl PaymentProcessorCheck {
+    pub fn new(payment_service: Arc<PaymentService>) -> Self {
+        Self { payment_service }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for PaymentProcessorCheck {
+    fn name(&self) -> &'static str {
+        "payment_processor"
+    }
+
+    async fn check(&self) -> ComponentHealth {
+        let start = std::time::Instant::now();
+        match self.payment_service.health_check().await {
+            Ok(()) => ComponentHealth::healthy().with_latency(start.elapsed()),
+            Err(e) => {
+                warn!(strategy = self.payment_service.strategy_name(), error = %e, "Payment processor unhealthy");
+                ComponentHealth::unhealthy("payment processor unreachable")
+            }
+        }
+    }
+}
+
+/// Payment circuit breaker position - open means purchases fail fast
+pub struct CircuitBreakerCheck {
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl CircuitBreakerCheck {
+    pub fn new(breaker: Arc<CircuitBreaker>) -> Self {
+        Self { breaker }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for CircuitBreakerCheck {
+    fn name(&self) -> &'static str {
+        "circuit_breaker"
+    }
+
+    async fn check(&self) -> ComponentHealth {
+        match self.breaker.state() {
+            CircuitState::Closed => ComponentHealth::healthy(),
+            CircuitState::HalfOpen => ComponentHealth::degraded("half_open"),
+            CircuitState::Open => ComponentHealth::degraded("open"),
+        }
+    }
+}
+
+/// Secrets are being refreshed - degraded while any is served stale
+pub struct SecretsCheck {
+    store: Arc<SecretStore>,
+}
+
+impl SecretsCheck {
+    pub fn new(store: Arc<SecretStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for SecretsCheck {
+    fn name(&self) -> &'static str {
+        "secrets"
+    }
+
+    async fn check(&self) -> ComponentHealth {
+        let freshness = self.store.freshness().await;
+        if freshness.stale.is_empty() {
+            ComponentHealth::healthy()
+        } else {
+            ComponentHealth::degraded(format!("refresh failing for {}", freshness.stale.join(", ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::InMemoryDatabase;
+    use crate::strategies::payment::MockPaymentStrategy;
+
+    #[tokio::test]
+    async fn test_open_breaker_degrades_readiness() {
+        let db: Arc<dyn Database> = Arc::new(InMemoryDatabase::new());
+        let payment_service = Arc::new(PaymentService::new(Arc::new(MockPaymentStrategy::new())));
+        let breaker = payment_service.circuit_breaker();
+        let checks: Vec<Arc<dyn HealthCheck>> = vec![
+            Arc::new(DatabaseCheck::new(Arc::clone(&db), DEFAULT_DB_DEGRADED_AFTER, Metrics::default())),
+            Arc::new(MigrationCheck::new(db)),
+            Arc::new(PaymentProcessorCheck::new(payment_service)),
+            Arc::new(CircuitBreakerCheck::new(Arc::clone(&breaker))),
+        ];
+
+        let results = run_checks(&checks).await;
+        assert_eq!(results.len(), 4);
+        assert_eq!(overall_status(results.values()), HealthStatus::Healthy);
+
+        for _ in 0..crate::services::circuit_breaker::DEFAULT_FAILURE_THRESHOLD {
+            breaker.record_failure();
+        }
+        let results = run_checks(&checks).await;
+        assert_eq!(results["circuit_breaker"].detail.as_deref(), Some("open"));
+        assert_eq!(overall_status(results.values()), HealthStatus::Degraded);
+    }
+}
@@ -3,8 +3,18 @@
 //! ADVANTAGE: Clear separation of concerns
 //! ADVANTAGE: Services are typed and injectable
 
+pub(crate) mod aws_client;
+pub mod circuit_breaker;
 pub mod database;
+pub mod gifts;
+pub mod health;
+pub mod outbox;
 pub mod payment;
+pub mod reaper;
+pub mod secrets;
+pub mod subscriptions;
+pub mod webhooks;
 
-pub use database::PostgresDatabase;
+pub use database::{Database, InMemoryDatabase, PostgresDatabase, RdsDataDatabase};
 pub use payment::PaymentService;
+pub use secrets::{SecretHandle, SecretStore};
@@ -0,0 +1,238 @@
+//! # Outbox Dispatcher
+//!
+//! Status changes write purchase events to the `outbox_events` table in the
+//! same database transaction. The dispatcher claims due events, publishes
+//! each to an `EventSink` and records the outcome:
+//!
+//! - published: the event is marked delivered
+//! - failed: it is retried with exponential backoff
+//! - failed `max_attempts` times: it is marked dead for an operator
+//!
+//! Delivery is at-least-once - a dispatcher that dies after publishing but
+//! before marking re-publishes when the claim lease runs out. Consumers
+//! deduplicate on `eventId`.
+//!
+//! ADVANTAGE: A committed purchase always produces its event, whatever
+//! happens to the Lambda afterwards
+//! ADVANTAGE: Sinks are pluggable - same Strategy-pattern shape as `Database`
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::errors::AppResult;
+use crate::models::config::OutboxSinkConfig;
+use crate::models::OutboxEvent;
+use crate::services::Database;
+
+pub mod sinks;
+
+pub use sinks::{EventBridgeSink, InMemorySink, SnsSink, SqsSink};
+
+/// Default delay before the first retry; doubles per attempt
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Longest delay between retries
+pub const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(3600);
+
+/// How long a claim hides an event from other dispatchers
+pub const DEFAULT_CLAIM_LEASE: Duration = Duration::from_secs(60);
+
+/// Destination for purchase events
+///
+/// ADVANTAGE: Send + Sync bounds allow sharing via Arc across invocations
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    /// Get sink name for logging
+    fn name(&self) -> &'static str;
+
+    /// Publish one event; an error leaves it for retry
+    async fn publish(&self, event: &OutboxEvent) -> AppResult<()>;
+}
+
+/// Create the sink selected by `config`
+pub fn sink_for(config: &OutboxSinkConfig) -> Arc<dyn EventSink> {
+    match config {
+        OutboxSinkConfig::Sns { topic_arn, endpoint_url } => {
+            Arc::new(SnsSink::new(topic_arn.clone(), endpoint_url.clone()))
+        }
+        OutboxSinkConfig::Sqs { queue_url, endpoint_url } => {
+            Arc::new(SqsSink::new(queue_url.clone(), endpoint_url.clone()))
+        }
+        OutboxSinkConfig::EventBridge { bus_name, endpoint_url } => {
+            Arc::new(EventBridgeSink::new(bus_name.clone(), endpoint_url.clone()))
+        }
+        OutboxSinkConfig::Memory => Arc::new(InMemorySink::new()),
+    }
+}
+
+/// `base` doubled per failed attempt after the first, capped at `MAX_RETRY_BACKOFF`
+pub fn backoff_delay(base: Duration, attempts: i32) -> Duration {
+    let doublings = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    base.saturating_mul(1 << doublings).min(MAX_RETRY_BACKOFF)
+}
+
+/// Outcome counts of one dispatch run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DispatchReport {
+    pub claimed: usize,
+    pub delivered: usize,
+    /// Failed and scheduled for another attempt
+    pub retried: usize,
+    /// Failed for the last time
+    pub dead: usize,
+}
+
+impl DispatchReport {
+    pub(crate) fn add(&mut self, other: Self) {
+        self.claimed += other.claimed;
+        self.delivered += other.delivered;
+        self.retried += other.retried;
+        self.dead += other.dead;
+    }
+}
+
+/// Publishes claimed outbox events to one sink
+pub struct OutboxDispatcher {
+    db: Arc<dyn Database>,
+    sink: Arc<dyn EventSink>,
+    batch_size: i32,
+    max_attempts: i32,
+    retry_backoff: Duration,
+    claim_lease: Duration,
+}
+
+impl OutboxDispatcher {
+    pub fn new(db: Arc<dyn Database>, sink: Arc<dyn EventSink>) -> Self {
+        Self {
+            db,
+            sink,
+            batch_size: 50,
+            max_attempts: 10,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            claim_lease: DEFAULT_CLAIM_LEASE,
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: i32) -> Self {
+        self.batch_size = batch_size.clamp(1, 1000);
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: i32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn with_retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Delay before the attempt after `attempts` failures
+    pub fn retry_delay(&self, attempts: i32) -> Duration {
+        backoff_delay(self.retry_backoff, attempts)
+    }
+
+    /// Claim and publish one batch of due events
+    pub async fn dispatch_once(&self) -> AppResult<DispatchReport> {
+        let events = self.db.claim_outbox_events(self.batch_size, self.claim_lease).await?;
+        let mut report = DispatchReport { claimed: events.len(), ..Default::default() };
+
+        for event in &events {
+            match self.sink.publish(event).await {
+                Ok(()) => {
+                    self.db.mark_outbox_delivered(event.event_id).await?;
+                    report.delivered += 1;
+                }
+                Err(e) if event.attempts >= self.max_attempts => {
+                    error!(event_id = %event.event_id, attempts = event.attempts, error = %e, "Outbox event is dead");
+                    self.db.mark_outbox_failed(event.event_id, &e.to_string(), None).await?;
+                    report.dead += 1;
+                }
+                Err(e) => {
+                    let delay = self.retry_delay(event.attempts);
+                    warn!(event_id = %event.event_id, attempts = event.attempts, retry_in = ?delay, error = %e, "Outbox publish failed");
+                    self.db.mark_outbox_failed(event.event_id, &e.to_string(), Some(delay)).await?;
+                    report.retried += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Dispatch batches until one comes back short, at most `max_batches`
+    ///
+    /// ADVANTAGE: A backlog drains in one invocation instead of one batch per schedule tick
+    pub async fn drain(&self, max_batches: usize) -> AppResult<DispatchReport> {
+        let mut report = DispatchReport::default();
+
+        for _ in 0..max_batches {
+            let batch = self.dispatch_once().await?;
+            report.add(batch);
+            if batch.claimed < self.batch_size as usize {
+                break;
+            }
+        }
+
+        info!(sink = self.sink.name(), ?report, "Outbox dispatched");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::models::{NewTransaction, TransactionStatus};
+    use crate::services::InMemoryDatabase;
+
+    #[tokio::test]
+    async fn test_failed_events_retry_then_die() {
+        let db = Arc::new(InMemoryDatabase::new());
+        let sink = Arc::new(InMemorySink::new());
+        let dispatcher = OutboxDispatcher::new(db.clone(), sink.clone())
+            .with_max_attempts(2)
+            .with_retry_backoff(Duration::ZERO);
+
+        let tx = db.insert_transaction(&NewTransaction::new(
+            uuid::Uuid::new_v4(), "sword".into(), "Sword".into(), 999, "USD".into(), 1, json!({}),
+        )).await.unwrap();
+        db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, Some("pi_1")).await.unwrap();
+
+        sink.fail_next(1);
+        let first = dispatcher.dispatch_once().await.unwrap();
+        assert_eq!(first, DispatchReport { claimed: 1, retried: 1, ..Default::default() });
+
+        let second = dispatcher.dispatch_once().await.unwrap();
+        assert_eq!(second, DispatchReport { claimed: 1, delivered: 1, ..Default::default() });
+        let published = sink.published();
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].payload["eventType"], "purchase.completed");
+        assert_eq!(published[0].payload["transaction"]["transactionId"], tx.transaction_id.to_string());
+
+        // Refunds announce themselves too; this one runs out of attempts
+        db.update_transaction_status(tx.transaction_id, TransactionStatus::Refunded, Some("pi_1")).await.unwrap();
+        sink.fail_next(2);
+        dispatcher.dispatch_once().await.unwrap();
+        let last = dispatcher.dispatch_once().await.unwrap();
+        assert_eq!(last, DispatchReport { claimed: 1, dead: 1, ..Default::default() });
+        // Dead events are never claimed again
+        assert!(db.claim_outbox_events(10, DEFAULT_CLAIM_LEASE).await.unwrap().is_empty());
+        assert_eq!(sink.published().len(), 1);
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_cap() {
+        let db = Arc::new(InMemoryDatabase::new());
+        let dispatcher = OutboxDispatcher::new(db, Arc::new(InMemorySink::new()));
+
+        assert_eq!(dispatcher.retry_delay(1), Duration::from_secs(10));
+        assert_eq!(dispatcher.retry_delay(3), Duration::from_secs(40));
+        assert_eq!(dispatcher.retry_delay(30), MAX_RETRY_BACKOFF);
+    }
+}
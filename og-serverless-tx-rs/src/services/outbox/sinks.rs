@@ -0,0 +1,289 @@
+//! # Event Sinks
+//!
+//! SNS, SQS and EventBridge publishers on the shared `AwsClient`, plus an
+//! in-memory sink for tests and local runs.
+//!
+//! Every message carries the event type as an attribute, so subscribers can
+//! filter without parsing the body. FIFO topics and queues are grouped by
+//! transaction, keeping one purchase's events in order.
+
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Mutex;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::OutboxEvent;
+use crate::services::aws_client::{error_type, AwsClient, JSON_1_0};
+use super::EventSink;
+
+/// `Source` of the EventBridge events this service puts
+pub const EVENT_SOURCE: &str = "og.microtransactions";
+
+/// SNS topic publisher (Query protocol `Publish`)
+pub struct SnsSink {
+    client: AwsClient,
+    topic_arn: String,
+}
+
+impl SnsSink {
+    pub fn new(topic_arn: String, endpoint_url: Option<String>) -> Self {
+        Self {
+            client: AwsClient::new("sns", "", endpoint_url),
+            topic_arn,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for SnsSink {
+    fn name(&self) -> &'static str {
+        "sns"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> AppResult<()> {
+        let message = event.payload.to_string();
+        let event_id = event.event_id.to_string();
+        let aggregate_id = event.aggregate_id.to_string();
+
+        let mut params = vec![
+            ("TopicArn", self.topic_arn.as_str()),
+            ("Message", message.as_str()),
+            ("MessageAttributes.entry.1.Name", "eventType"),
+            ("MessageAttributes.entry.1.Value.DataType", "String"),
+            ("MessageAttributes.entry.1.Value.StringValue", event.event_type.as_str()),
+        ];
+        if self.topic_arn.ends_with(".fifo") {
+            params.push(("MessageGroupId", aggregate_id.as_str()));
+            params.push(("MessageDeduplicationId", event_id.as_str()));
+        }
+
+        let (status, body) = self.client.call_query("Publish", "2010-03-31", &params).await?;
+        match status {
+            200 => Ok(()),
+            _ => Err(AppError::Unavailable(format!(
+                "SNS Publish failed with {}: {}",
+                status,
+                query_error_code(&body)
+            ))),
+        }
+    }
+}
+
+/// SQS queue publisher (JSON 1.0 `SendMessage`)
+pub struct SqsSink {
+    client: AwsClient,
+    queue_url: String,
+}
+
+impl SqsSink {
+    pub fn new(queue_url: String, endpoint_url: Option<String>) -> Self {
+        Self {
+            client: AwsClient::new("sqs", "AmazonSQS", endpoint_url).with_content_type(JSON_1_0),
+            queue_url,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for SqsSink {
+    fn name(&self) -> &'static str {
+        "sqs"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> AppResult<()> {
+        let mut request = json!({
+            "QueueUrl": self.queue_url,
+            "MessageBody": event.payload.to_string(),
+            "MessageAttributes": {
+                "eventType": { "DataType": "String", "StringValue": event.event_type }
+            }
+        });
+        if self.queue_url.ends_with(".fifo") {
+            request["MessageGroupId"] = json!(event.aggregate_id);
+            request["MessageDeduplicationId"] = json!(event.event_id);
+        }
+
+        let (status, body) = self.client.call("SendMessage", &request).await?;
+        match status {
+            200 => Ok(()),
+            _ => Err(AppError::Unavailable(format!(
+                "SQS SendMessage failed with {}: {}",
+                status,
+                error_type(&body)
+            ))),
+        }
+    }
+}
+
+/// EventBridge bus publisher (JSON 1.1 `PutEvents`)
+pub struct EventBridgeSink {
+    client: AwsClient,
+    bus_name: String,
+}
+
+impl EventBridgeSink {
+    pub fn new(bus_name: String, endpoint_url: Option<String>) -> Self {
+        Self {
+            client: AwsClient::new("events", "AWSEvents", endpoint_url),
+            bus_name,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for EventBridgeSink {
+    fn name(&self) -> &'static str {
+        "eventbridge"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> AppResult<()> {
+        let request = json!({
+            "Entries": [{
+                "EventBusName": self.bus_name,
+                "Source": EVENT_SOURCE,
+                "DetailType": event.event_type,
+                "Detail": event.payload.to_string(),
+                "Resources": [],
+            }]
+        });
+
+        let (status, body) = self.client.call("PutEvents", &request).await?;
+        match status {
+            // PutEvents reports per-entry failures inside a 200 response
+            200 if body["FailedEntryCount"].as_i64().unwrap_or(0) == 0 => Ok(()),
+            200 => Err(AppError::Unavailable(format!(
+                "EventBridge rejected the event: {}",
+                body["Entries"][0]["ErrorCode"].as_str().unwrap_or("UnknownError")
+            ))),
+            _ => Err(AppError::Unavailable(format!(
+                "EventBridge PutEvents failed with {}: {}",
+                status,
+                error_type(&body)
+            ))),
+        }
+    }
+}
+
+/// Events kept in process memory
+///
+/// ADVANTAGE: Tests can inject failures to exercise retry and dead-lettering
+#[derive(Default)]
+pub struct InMemorySink {
+    published: Mutex<Vec<OutboxEvent>>,
+    failures: Mutex<u32>,
+}
+
+impl InMemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail the next `count` publishes
+    pub fn fail_next(&self, count: u32) {
+        *self.failures.lock().unwrap_or_else(|e| e.into_inner()) = count;
+    }
+
+    /// Events published so far, oldest first
+    pub fn published(&self) -> Vec<OutboxEvent> {
+        self.published.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+#[async_trait]
+impl EventSink for InMemorySink {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> AppResult<()> {
+        {
+            let mut failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(AppError::Unavailable("In-memory sink failure injected".into()));
+            }
+        }
+
+        self.published.lock().unwrap_or_else(|e| e.into_inner()).push(event.clone());
+        Ok(())
+    }
+}
+
+/// `<Code>` of a Query-protocol XML error body
+fn query_error_code(body: &str) -> &str {
+    body.split_once("<Code>")
+        .and_then(|(_, rest)| rest.split_once("</Code>"))
+        .map(|(code, _)| code)
+        .unwrap_or("UnknownError")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::Value;
+    use uuid::Uuid;
+    use crate::models::OutboxStatus;
+    use crate::test_support::HttpStandIn;
+
+    fn event() -> OutboxEvent {
+        OutboxEvent {
+            event_id: Uuid::new_v4(),
+            event_type: "purchase.completed".into(),
+            aggregate_id: Uuid::new_v4(),
+            payload: json!({"schemaVersion": 1, "eventType": "purchase.completed"}),
+            status: OutboxStatus::Pending,
+            attempts: 1,
+            next_attempt_at: Utc::now(),
+            last_error: None,
+            created_at: Utc::now(),
+            delivered_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_aws_sinks_send_signed_requests() {
+        let stand_in = HttpStandIn::start(|request| match request.header("x-amz-target") {
+            Some("AmazonSQS.SendMessage") => (200, json!({"MessageId": "m-1"})),
+            Some("AWSEvents.PutEvents") => (200, json!({"FailedEntryCount": 1, "Entries": [{"ErrorCode": "ThrottlingException"}]})),
+            _ => (200, Value::Null),
+        })
+        .await;
+        let event = event();
+
+        let sqs = SqsSink {
+            client: AwsClient::new("sqs", "AmazonSQS", Some(stand_in.endpoint.clone()))
+                .with_content_type(JSON_1_0)
+                .with_test_context(),
+            queue_url: "https://sqs.us-east-1.amazonaws.com/1/purchases.fifo".into(),
+        };
+        sqs.publish(&event).await.unwrap();
+
+        let sns = SnsSink {
+            client: AwsClient::new("sns", "", Some(stand_in.endpoint.clone())).with_test_context(),
+            topic_arn: "arn:aws:sns:us-east-1:1:purchases".into(),
+        };
+        sns.publish(&event).await.unwrap();
+
+        let events = EventBridgeSink {
+            client: AwsClient::new("events", "AWSEvents", Some(stand_in.endpoint.clone())).with_test_context(),
+            bus_name: "game".into(),
+        };
+        let err = events.publish(&event).await.unwrap_err();
+        assert!(err.to_string().contains("ThrottlingException"));
+
+        let requests = stand_in.requests();
+        assert!(requests.iter().all(|r| r.header("authorization").is_some_and(|a| a.starts_with("AWS4-HMAC-SHA256"))));
+
+        let sent = requests[0].json();
+        assert_eq!(requests[0].header("content-type"), Some(JSON_1_0));
+        assert_eq!(sent["MessageGroupId"], event.aggregate_id.to_string());
+        assert_eq!(sent["MessageAttributes"]["eventType"]["StringValue"], "purchase.completed");
+
+        let form = String::from_utf8(requests[1].body.clone()).unwrap();
+        assert!(form.starts_with("Action=Publish&Version=2010-03-31&TopicArn=arn%3Aaws%3Asns"));
+        assert!(!form.contains("MessageGroupId"));
+
+        assert_eq!(requests[2].json()["Entries"][0]["Source"], EVENT_SOURCE);
+    }
+}
@@ -4,19 +4,26 @@
 //! ADVANTAGE: Strategy can be swapped without changing service code
 //! ADVANTAGE: Testing is easy with mock strategy injection
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tracing::{info, instrument};
 use uuid::Uuid;
 
 use crate::errors::{AppError, AppResult};
+use crate::metrics::Metrics;
+use crate::services::circuit_breaker::CircuitBreaker;
 use crate::strategies::payment::{PaymentStrategy, PaymentRequest, PaymentResult};
 
 /// Payment service that delegates to a strategy
 /// 
 /// ADVANTAGE: Arc allows sharing across async tasks without copying
 /// ADVANTAGE: Strategy is determined at construction, not per-call
+/// ADVANTAGE: A circuit breaker stops piling requests onto a failing processor
 pub struct PaymentService {
     strategy: Arc<dyn PaymentStrategy>,
+    metrics: Metrics,
+    breaker: Arc<CircuitBreaker>,
 }
 
 impl PaymentService {
@@ -26,13 +33,36 @@ impl PaymentService {
     /// ADVANTAGE: dyn PaymentStrategy allows runtime polymorphism when needed
     pub fn new(strategy: Arc<dyn PaymentStrategy>) -> Self {
         info!(strategy = strategy.name(), "Payment service initialized");
-        Self { strategy }
+        Self {
+            strategy,
+            metrics: Metrics::default(),
+            breaker: Arc::new(CircuitBreaker::default()),
+        }
+    }
+    
+    /// Report strategy latency to `metrics`
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+    
+    /// Replace the default circuit breaker
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Arc::new(breaker);
+        self
+    }
+    
+    /// Breaker guarding the strategy, for health reporting
+    pub fn circuit_breaker(&self) -> Arc<CircuitBreaker> {
+        Arc::clone(&self.breaker)
     }
     
     /// Process a purchase
     /// 
     /// ADVANTAGE: Input and output types are fully specified
     /// ADVANTAGE: Errors are typed and must be handled
+    /// 
+    /// `request_id` is forwarded to the processor as charge metadata.
     #[instrument(skip(self), fields(
         strategy = self.strategy.name(),
         transaction_id = %transaction_id,
@@ -44,6 +74,7 @@ impl PaymentService {
         player_id: Uuid,
         amount_cents: i64,
         currency: &str,
+        request_id: Option<&str>,
     ) -> AppResult<PaymentResult> {
         // Validate inputs
         if amount_cents <= 0 {
@@ -51,7 +82,13 @@ impl PaymentService {
         }
         
         // Create idempotency key from transaction ID
-        let idempotency_key = format!("purchase_{}", transaction_id);
+        let idempotency_key = purchase_key(transaction_id);
+        
+        let mut metadata = BTreeMap::new();
+        metadata.insert("transaction_id".to_string(), transaction_id.to_string());
+        if let Some(request_id) = request_id {
+            metadata.insert("request_id".to_string(), request_id.to_string());
+        }
         
         let request = PaymentRequest {
             amount_cents,
@@ -59,12 +96,73 @@ impl PaymentService {
             player_id,
             transaction_id,
             idempotency_key,
+            metadata,
+        };
+        
+        self.charge(request, None).await
+    }
+    
+    /// Charge a saved payment method without the player present
+    /// 
+    /// ADVANTAGE: Renewals share the purchase path's breaker and latency metrics
+    #[instrument(skip(self, payment_method), fields(
+        strategy = self.strategy.name(),
+        transaction_id = %transaction_id,
+        amount = amount_cents
+    ))]
+    pub async fn charge_off_session(
+        &self,
+        transaction_id: Uuid,
+        player_id: Uuid,
+        payment_method: &str,
+        amount_cents: i64,
+        currency: &str,
+    ) -> AppResult<PaymentResult> {
+        if amount_cents <= 0 {
+            return Err(AppError::Validation("Amount must be positive".into()));
+        }
+        
+        let mut metadata = BTreeMap::new();
+        metadata.insert("transaction_id".to_string(), transaction_id.to_string());
+        
+        let request = PaymentRequest {
+            amount_cents,
+            currency: currency.to_string(),
+            player_id,
+            transaction_id,
+            idempotency_key: renewal_key(transaction_id),
+            metadata,
         };
         
+        self.charge(request, Some(payment_method)).await
+    }
+    
+    /// Send `request` to the strategy through the breaker, off-session when
+    /// a saved `payment_method` is given
+    async fn charge(&self, mut request: PaymentRequest, payment_method: Option<&str>) -> AppResult<PaymentResult> {
+        if !self.breaker.allow() {
+            return Err(AppError::Unavailable("Payment processor is temporarily unavailable".into()));
+        }
+        
+        // ADVANTAGE: The key is stored on the charge, so `find_charge` can search by it
+        request.metadata.insert("idempotency_key".to_string(), request.idempotency_key.clone());
+        
         info!("Delegating to payment strategy");
         
         // ADVANTAGE: Strategy call is just a method call - no reflection
-        let result = self.strategy.process_payment(request).await?;
+        let start = Instant::now();
+        let result = match payment_method {
+            Some(payment_method) => self.strategy.charge_off_session(payment_method, request).await,
+            None => self.strategy.process_payment(request).await,
+        };
+        self.metrics.record_payment_latency(self.strategy.name(), start.elapsed(), result.is_ok());
+        
+        // Declines are answers; only errors count against the processor
+        match &result {
+            Ok(_) => self.breaker.record_success(),
+            Err(_) => self.breaker.record_failure(),
+        }
+        let result = result?;
         
         if result.success {
             info!(processor_id = %result.processor_id, "Payment processed successfully");
@@ -78,10 +176,25 @@ impl PaymentService {
         Ok(result)
     }
     
-    /// Process a refund
+    /// Look up the charge sent under `idempotency_key`, `None` when the
+    /// processor never received one
+    /// 
+    /// A charge that errored may still have gone through; this settles it.
+    /// Lookups bypass the breaker: an open breaker sent nothing, which is
+    /// exactly what they confirm.
     #[instrument(skip(self), fields(strategy = self.strategy.name()))]
+    pub async fn find_charge(&self, idempotency_key: &str) -> AppResult<Option<PaymentResult>> {
+        self.strategy.find_payment(idempotency_key).await
+    }
+    
+    /// Refund transaction `transaction_id`'s charge
+    /// 
+    /// ADVANTAGE: The idempotency key comes from the transaction ID, so a
+    /// retried refund never pays out twice
+    #[instrument(skip(self), fields(strategy = self.strategy.name(), transaction_id = %transaction_id))]
     pub async fn process_refund(
         &self,
+        transaction_id: Uuid,
         processor_id: &str,
         amount_cents: i64,
     ) -> AppResult<PaymentResult> {
@@ -91,17 +204,33 @@ impl PaymentService {
         
         info!(processor_id = %processor_id, amount = amount_cents, "Processing refund");
         
-        let result = self.strategy.refund_payment(processor_id, amount_cents).await?;
+        let idempotency_key = format!("refund_{}", transaction_id);
+        let result = self.strategy.refund_payment(processor_id, amount_cents, &idempotency_key).await?;
         
         Ok(result)
     }
     
+    /// Check the processor through the strategy
+    pub async fn health_check(&self) -> AppResult<()> {
+        self.strategy.health_check().await
+    }
+    
     /// Get the name of the current strategy
     pub fn strategy_name(&self) -> &'static str {
         self.strategy.name()
     }
 }
 
+/// Idempotency key of transaction `transaction_id`'s purchase charge
+pub fn purchase_key(transaction_id: Uuid) -> String {
+    format!("purchase_{}", transaction_id)
+}
+
+/// Idempotency key of renewal transaction `transaction_id`'s off-session charge
+pub fn renewal_key(transaction_id: Uuid) -> String {
+    format!("renewal_{}", transaction_id)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,12 +247,29 @@ mod tests {
             Uuid::new_v4(),
             1000,
             "USD",
+            Some("req-1"),
         ).await.unwrap();
         
         // ADVANTAGE: Result type is known - all fields accessible
         assert!(result.success);
     }
 
+    #[tokio::test]
+    async fn test_strategy_latency_is_recorded() {
+        use crate::metrics::RecordingSink;
+        
+        let sink = Arc::new(RecordingSink::new());
+        let service = PaymentService::new(Arc::new(MockPaymentStrategy::new()))
+            .with_metrics(Metrics::new(sink.clone()));
+        
+        service.process_purchase(Uuid::new_v4(), Uuid::new_v4(), 1000, "USD", None).await.unwrap();
+        
+        let event = sink.find("PaymentLatency").unwrap();
+        assert_eq!(event.dimension_value("Strategy"), Some("mock"));
+        assert!(event.value("PaymentLatency").unwrap() > 0.0);
+        assert_eq!(event.value("PaymentErrors"), Some(0.0));
+    }
+
     #[tokio::test]
     async fn test_validation_error() {
         let mock_strategy = Arc::new(MockPaymentStrategy::new());
@@ -135,6 +281,7 @@ mod tests {
             Uuid::new_v4(),
             -100,  // Invalid amount
             "USD",
+            None,
         ).await;
         
         assert!(matches!(result, Err(AppError::Validation(_))));
@@ -0,0 +1,274 @@
+//! # Pending Transaction Reaper
+//!
+//! A purchase is inserted `Pending` and settled once the processor answers.
+//! A Lambda that times out or dies in between leaves the row `Pending`,
+//! holding its stock reservation and blocking a rebuy of unique items. A
+//! scheduled job settles such rows once they are older than the timeout:
+//! it asks the processor for the charge sent under the row's idempotency
+//! key and completes or fails the row to match. A row the processor never
+//! charged is failed, releasing its stock in the same transition; one whose
+//! charge cannot be looked up stays pending for the next run.
+//!
+//! Renewals a subscription has claimed are left alone: the biller re-charges
+//! them under the same idempotency key on its next run.
+//!
+//! ADVANTAGE: Failures are per transaction - one bad row never blocks the batch
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::errors::AppResult;
+use crate::models::subscription::SUBSCRIPTION_METADATA_KEY;
+use crate::models::{Transaction, TransactionStatus};
+use crate::services::payment::{purchase_key, renewal_key};
+use crate::services::{Database, PaymentService};
+
+/// Age after which a pending purchase is given up on - well past any
+/// Lambda timeout, so a live request is never reaped under itself
+pub const DEFAULT_PENDING_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Outcome counts of one reaper run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReapReport {
+    pub stale: usize,
+    /// Charged by the processor after all; marked completed
+    pub completed: usize,
+    /// Never charged or declined; marked failed, stock released
+    pub failed: usize,
+    /// Claimed renewals, left to the biller
+    pub skipped: usize,
+    /// Errored or charge outcome unknown; left pending for the next run
+    pub errors: usize,
+}
+
+impl ReapReport {
+    fn add(&mut self, other: Self) {
+        self.stale += other.stale;
+        self.completed += other.completed;
+        self.failed += other.failed;
+        self.skipped += other.skipped;
+        self.errors += other.errors;
+    }
+}
+
+/// Settles purchases left pending past the timeout
+pub struct PendingReaper {
+    db: Arc<dyn Database>,
+    payments: Arc<PaymentService>,
+    batch_size: i32,
+    timeout: Duration,
+}
+
+impl PendingReaper {
+    pub fn new(db: Arc<dyn Database>, payments: Arc<PaymentService>) -> Self {
+        Self { db, payments, batch_size: 100, timeout: DEFAULT_PENDING_TIMEOUT }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: i32) -> Self {
+        self.batch_size = batch_size.clamp(1, 1000);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Reap one batch of transactions pending since before `now - timeout`
+    pub async fn reap_once(&self, now: DateTime<Utc>) -> AppResult<ReapReport> {
+        let before = now - chrono::Duration::from_std(self.timeout).unwrap_or(chrono::Duration::MAX);
+        let stale = self.db.get_stale_pending_transactions(before, self.batch_size).await?;
+        let mut report = ReapReport { stale: stale.len(), ..Default::default() };
+
+        for tx in stale {
+            let transaction_id = tx.transaction_id;
+            match self.reap(tx).await {
+                Ok(Some(TransactionStatus::Completed)) => report.completed += 1,
+                Ok(Some(_)) => report.failed += 1,
+                Ok(None) => report.skipped += 1,
+                Err(e) => {
+                    error!(transaction_id = %transaction_id, error = %e, "Pending transaction not reaped");
+                    report.errors += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Reap batches until one comes back short or makes no progress, at
+    /// most `max_batches`
+    pub async fn drain(&self, max_batches: usize) -> AppResult<ReapReport> {
+        let mut report = ReapReport::default();
+        let now = Utc::now();
+
+        for _ in 0..max_batches {
+            let batch = self.reap_once(now).await?;
+            report.add(batch);
+            // Skipped and errored rows come back; stop rather than fetch them again
+            if batch.stale < self.batch_size as usize || batch.completed + batch.failed == 0 {
+                break;
+            }
+        }
+
+        info!(?report, "Pending transactions reaped");
+        Ok(report)
+    }
+
+    /// Settle `tx` to the processor's outcome unless a subscription has
+    /// claimed it as its renewal, returning the status it was given
+    async fn reap(&self, tx: Transaction) -> AppResult<Option<TransactionStatus>> {
+        if self.is_claimed_renewal(&tx).await? {
+            return Ok(None);
+        }
+
+        // Free rows, such as wallet spends, never reach the processor
+        let charge = if tx.price_cents > 0 {
+            self.payments.find_charge(&self.idempotency_key(&tx)).await?
+        } else {
+            None
+        };
+        let (status, processor_id) = match &charge {
+            Some(charge) if charge.success => (TransactionStatus::Completed, Some(charge.processor_id.as_str())),
+            Some(charge) => (TransactionStatus::Failed, Some(charge.processor_id.as_str())),
+            None => (TransactionStatus::Failed, None),
+        };
+
+        warn!(
+            transaction_id = %tx.transaction_id,
+            created_at = %tx.created_at,
+            status = ?status,
+            "Pending transaction timed out; settling to the processor's outcome"
+        );
+        self.db
+            .update_transaction_status(tx.transaction_id, status, processor_id)
+            .await?;
+        Ok(Some(status))
+    }
+
+    /// Key `tx` was charged under: renewals are charged off-session
+    fn idempotency_key(&self, tx: &Transaction) -> String {
+        if tx.metadata.get(SUBSCRIPTION_METADATA_KEY).is_some() {
+            renewal_key(tx.transaction_id)
+        } else {
+            purchase_key(tx.transaction_id)
+        }
+    }
+
+    async fn is_claimed_renewal(&self, tx: &Transaction) -> AppResult<bool> {
+        let subscription_id = tx
+            .metadata
+            .get(SUBSCRIPTION_METADATA_KEY)
+            .and_then(|id| id.as_str())
+            .and_then(|id| id.parse::<Uuid>().ok());
+        let Some(subscription_id) = subscription_id else {
+            return Ok(false);
+        };
+
+        Ok(self
+            .db
+            .get_subscription(subscription_id)
+            .await?
+            .is_some_and(|s| s.renewal_transaction_id == Some(tx.transaction_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::models::{CatalogItemRequest, NewTransaction};
+    use crate::services::InMemoryDatabase;
+    use crate::strategies::payment::{MockPaymentStrategy, PaymentRequest, PaymentResult, PaymentStrategy};
+
+    /// Processor whose charges cannot be looked up
+    struct BlindStrategy;
+
+    #[async_trait::async_trait]
+    impl PaymentStrategy for BlindStrategy {
+        async fn process_payment(&self, _: PaymentRequest) -> AppResult<PaymentResult> {
+            Err(crate::errors::AppError::Unavailable("processor timed out".into()))
+        }
+
+        async fn refund_payment(&self, _: &str, _: i64, _: &str) -> AppResult<PaymentResult> {
+            Err(crate::errors::AppError::Unavailable("processor timed out".into()))
+        }
+
+        fn name(&self) -> &'static str {
+            "blind"
+        }
+    }
+
+    async fn pending_cape(db: &InMemoryDatabase) -> Transaction {
+        let listing: CatalogItemRequest =
+            serde_json::from_value(json!({"kind": "consumable", "stock_limit": 10})).unwrap();
+        db.upsert_catalog_item("founders_cape", &listing).await.unwrap();
+        db.insert_transaction(&NewTransaction::new(
+            Uuid::new_v4(), "founders_cape".into(), "Founders Cape".into(), 999, "USD".into(), 2, serde_json::Value::Null,
+        )).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stale_purchases_fail_and_release_stock() {
+        let db = Arc::new(InMemoryDatabase::new());
+        let payments = Arc::new(PaymentService::new(Arc::new(MockPaymentStrategy::new())));
+        let tx = pending_cape(&db).await;
+        assert_eq!(db.get_catalog_item("founders_cape").await.unwrap().unwrap().stock_reserved, 2);
+
+        let reaper = PendingReaper::new(db.clone(), payments);
+        // Not stale yet
+        assert_eq!(reaper.reap_once(Utc::now()).await.unwrap(), ReapReport::default());
+
+        // The processor never received a charge for it
+        let later = Utc::now() + chrono::Duration::minutes(16);
+        let report = reaper.reap_once(later).await.unwrap();
+        assert_eq!(report, ReapReport { stale: 1, failed: 1, ..Default::default() });
+
+        let tx = db.get_transaction(tx.transaction_id).await.unwrap().unwrap();
+        assert_eq!(tx.status, TransactionStatus::Failed);
+        assert_eq!(db.get_catalog_item("founders_cape").await.unwrap().unwrap().stock_reserved, 0);
+    }
+
+    #[tokio::test]
+    async fn test_stale_purchases_settle_to_the_processors_charge() {
+        let db = Arc::new(InMemoryDatabase::new());
+        let strategy = Arc::new(MockPaymentStrategy::new());
+        let payments = Arc::new(PaymentService::new(strategy.clone()));
+        let tx = pending_cape(&db).await;
+        // Charged, but the request died before storing the outcome
+        payments
+            .process_purchase(tx.transaction_id, tx.player_id, tx.price_cents, &tx.currency, None)
+            .await
+            .unwrap();
+
+        let later = Utc::now() + chrono::Duration::minutes(16);
+        let report = PendingReaper::new(db.clone(), payments).reap_once(later).await.unwrap();
+        assert_eq!(report, ReapReport { stale: 1, completed: 1, ..Default::default() });
+
+        let charge = strategy.find_payment(&purchase_key(tx.transaction_id)).await.unwrap().unwrap();
+        let tx = db.get_transaction(tx.transaction_id).await.unwrap().unwrap();
+        assert_eq!(tx.status, TransactionStatus::Completed);
+        assert_eq!(tx.processor_id, Some(charge.processor_id));
+        assert_eq!(db.get_catalog_item("founders_cape").await.unwrap().unwrap().stock_sold, 2);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_charge_outcome_stays_pending() {
+        let db = Arc::new(InMemoryDatabase::new());
+        let tx = pending_cape(&db).await;
+
+        let later = Utc::now() + chrono::Duration::minutes(16);
+        let reaper = PendingReaper::new(db.clone(), Arc::new(PaymentService::new(Arc::new(BlindStrategy))));
+        let report = reaper.reap_once(later).await.unwrap();
+        assert_eq!(report, ReapReport { stale: 1, errors: 1, ..Default::default() });
+
+        let tx = db.get_transaction(tx.transaction_id).await.unwrap().unwrap();
+        assert_eq!(tx.status, TransactionStatus::Pending);
+        assert_eq!(db.get_catalog_item("founders_cape").await.unwrap().unwrap().stock_reserved, 2);
+    }
+}
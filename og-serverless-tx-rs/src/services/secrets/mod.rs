@@ -0,0 +1,412 @@
+//! # Secrets Service
+//!
+//! Configuration values may be secret references instead of plaintext:
+//!
+//! - `secretsmanager:<secret-id>` - AWS Secrets Manager (`SecretString`)
+//! - `ssm:/path/to/parameter` - SSM Parameter Store (decrypted)
+//! - `file:/path/to/file` - local file, for development runs
+//!
+//! Any reference may end in `#field` to select one field of a JSON secret.
+//! Values that are not references are used as-is.
+//!
+//! ADVANTAGE: Secret values never appear in the Lambda console environment
+//! ADVANTAGE: Resolved values are cached across warm invocations with a TTL
+//! ADVANTAGE: Errors name the configuration key, never the secret value
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::errors::{AppError, AppResult};
+
+pub mod aws;
+pub mod file;
+
+pub use aws::{SecretsManagerProvider, SsmParameterProvider};
+pub use file::FileSecretsProvider;
+
+/// Default time a resolved secret is served from cache
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Parsed secret reference
+///
+/// ADVANTAGE: Scheme, ID and JSON field are split once, up front
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretReference {
+    pub scheme: String,
+    pub id: String,
+    pub field: Option<String>,
+}
+
+impl SecretReference {
+    /// Schemes recognised as references
+    pub const SCHEMES: [&'static str; 3] = ["secretsmanager", "ssm", "file"];
+
+    /// Parse `raw` as a reference
+    ///
+    /// Returns `Ok(None)` for plain values, and a configuration error naming
+    /// `key` for references with an empty ID or field.
+    pub fn parse(key: &str, raw: &str) -> AppResult<Option<Self>> {
+        let Some((scheme, rest)) = raw.split_once(':') else {
+            return Ok(None);
+        };
+
+        if !Self::SCHEMES.contains(&scheme) {
+            return Ok(None);
+        }
+
+        let (id, field) = match rest.rsplit_once('#') {
+            Some((id, field)) => (id, Some(field)),
+            None => (rest, None),
+        };
+
+        if id.is_empty() || field.is_some_and(str::is_empty) {
+            return Err(AppError::Configuration(format!(
+                "{} is a malformed {} secret reference",
+                key, scheme
+            )));
+        }
+
+        Ok(Some(Self {
+            scheme: scheme.to_string(),
+            id: id.to_string(),
+            field: field.map(str::to_string),
+        }))
+    }
+}
+
+/// Secret source strategy
+///
+/// ADVANTAGE: Same Strategy shape as `PaymentStrategy` - stores are swappable
+#[async_trait]
+pub trait SecretsProvider: Send + Sync {
+    /// Reference scheme served by this provider
+    fn scheme(&self) -> &'static str;
+
+    /// Fetch the raw secret text for `id`, `Ok(None)` if it does not exist
+    ///
+    /// Errors must not include the secret value.
+    async fn fetch(&self, id: &str) -> AppResult<Option<String>>;
+}
+
+/// Cached secret value
+struct CachedSecret {
+    key: String,
+    value: String,
+    fetched_at: Instant,
+    /// Last refresh failed and the old value is being served
+    refresh_failed: bool,
+}
+
+/// Age of the cached secrets, for health reporting
+#[derive(Debug, Clone, Default)]
+pub struct SecretsFreshness {
+    pub cached: usize,
+    /// Keys served from a stale cache entry because their refresh failed
+    pub stale: Vec<String>,
+    pub oldest: Option<Duration>,
+}
+
+/// Resolves and caches secret references
+///
+/// ADVANTAGE: One store per Lambda container - warm invocations hit the cache
+pub struct SecretStore {
+    providers: Vec<Arc<dyn SecretsProvider>>,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, CachedSecret>>,
+}
+
+impl SecretStore {
+    pub fn new(providers: Vec<Arc<dyn SecretsProvider>>, ttl: Duration) -> Self {
+        Self {
+            providers,
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Build store with the AWS and file providers
+    ///
+    /// `SECRETS_CACHE_TTL_SECONDS` overrides the cache TTL; the standard
+    /// `AWS_ENDPOINT_URL_SECRETS_MANAGER` / `AWS_ENDPOINT_URL_SSM` variables
+    /// point the AWS providers at local stand-ins.
+    pub fn from_env() -> AppResult<Self> {
+        let ttl = match std::env::var("SECRETS_CACHE_TTL_SECONDS") {
+            Ok(v) => Duration::from_secs(v.parse::<u64>().map_err(|_| {
+                AppError::Configuration("SECRETS_CACHE_TTL_SECONDS must be a valid integer".into())
+            })?),
+            Err(_) => DEFAULT_CACHE_TTL,
+        };
+
+        Ok(Self::new(
+            vec![
+                Arc::new(SecretsManagerProvider::new(
+                    std::env::var("AWS_ENDPOINT_URL_SECRETS_MANAGER").ok(),
+                )),
+                Arc::new(SsmParameterProvider::new(std::env::var("AWS_ENDPOINT_URL_SSM").ok())),
+                Arc::new(FileSecretsProvider::new()),
+            ],
+            ttl,
+        ))
+    }
+
+    /// Resolve the configuration value `raw` for `key`
+    ///
+    /// Plain values are returned unchanged. References are fetched, cached
+    /// for the TTL, and re-fetched once stale; if a refresh fails the stale
+    /// value is served and a warning logged.
+    pub async fn resolve(&self, key: &str, raw: &str) -> AppResult<String> {
+        let Some(reference) = SecretReference::parse(key, raw)? else {
+            return Ok(raw.to_string());
+        };
+
+        // The lock is never held across the fetch - a slow provider must not
+        // stall every other key, or cached reads of this one
+        if let Some(cached) = self.cache.lock().await.get(raw) {
+            if cached.fetched_at.elapsed() < self.ttl {
+                return Ok(cached.value.clone());
+            }
+        }
+
+        let fetched = self.fetch(key, &reference).await;
+        let mut cache = self.cache.lock().await;
+        match fetched {
+            Ok(value) => {
+                info!(key = key, scheme = %reference.scheme, "Secret resolved");
+                cache.insert(raw.to_string(), CachedSecret {
+                    key: key.to_string(),
+                    value: value.clone(),
+                    fetched_at: Instant::now(),
+                    refresh_failed: false,
+                });
+                Ok(value)
+            }
+            Err(e) => match cache.get_mut(raw) {
+                Some(stale) => {
+                    warn!(key = key, error = %e, "Secret refresh failed, serving cached value");
+                    stale.refresh_failed = true;
+                    Ok(stale.value.clone())
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// How many secrets are cached and which are being served stale
+    pub async fn freshness(&self) -> SecretsFreshness {
+        let cache = self.cache.lock().await;
+        let mut stale: Vec<String> = cache
+            .values()
+            .filter(|c| c.refresh_failed)
+            .map(|c| c.key.clone())
+            .collect();
+        stale.sort();
+
+        SecretsFreshness {
+            cached: cache.len(),
+            stale,
+            oldest: cache.values().map(|c| c.fetched_at.elapsed()).max(),
+        }
+    }
+
+    async fn fetch(&self, key: &str, reference: &SecretReference) -> AppResult<String> {
+        let provider = self.providers
+            .iter()
+            .find(|p| p.scheme() == reference.scheme)
+            .ok_or_else(|| AppError::Configuration(format!(
+                "{} uses unsupported secret scheme {}",
+                key, reference.scheme
+            )))?;
+
+        let secret = provider
+            .fetch(&reference.id)
+            .await
+            .map_err(|e| {
+                warn!(key = key, scheme = %reference.scheme, error = %e, "Secret fetch failed");
+                AppError::Configuration(format!(
+                    "{} could not be loaded from {}",
+                    key, reference.scheme
+                ))
+            })?
+            .ok_or_else(|| AppError::Configuration(format!(
+                "{} references a missing {} secret",
+                key, reference.scheme
+            )))?;
+
+        match &reference.field {
+            None => Ok(secret),
+            Some(field) => extract_field(key, &secret, field),
+        }
+    }
+}
+
+/// Select `field` from a JSON object secret
+///
+/// ADVANTAGE: Parse errors are replaced, so no secret text leaks via serde
+fn extract_field(key: &str, secret: &str, field: &str) -> AppResult<String> {
+    let malformed = || AppError::Configuration(format!(
+        "{} secret is malformed: expected JSON object with field {}",
+        key, field
+    ));
+
+    let json: serde_json::Value = serde_json::from_str(secret).map_err(|_| malformed())?;
+
+    match json.get(field) {
+        Some(serde_json::Value::String(s)) => Ok(s.clone()),
+        Some(v @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_))) => Ok(v.to_string()),
+        _ => Err(malformed()),
+    }
+}
+
+/// Secret that re-resolves through a `SecretStore` when its TTL lapses
+///
+/// ADVANTAGE: Rotated credentials are picked up without a cold start
+#[derive(Clone)]
+pub struct SecretHandle {
+    key: String,
+    raw: String,
+    store: Option<Arc<SecretStore>>,
+}
+
+impl SecretHandle {
+    /// Handle resolving `raw` for `key` through `store`
+    pub fn new(key: impl Into<String>, raw: impl Into<String>, store: Arc<SecretStore>) -> Self {
+        Self {
+            key: key.into(),
+            raw: raw.into(),
+            store: Some(store),
+        }
+    }
+
+    /// Handle for a fixed value that is never resolved
+    pub fn literal(value: impl Into<String>) -> Self {
+        Self {
+            key: String::new(),
+            raw: value.into(),
+            store: None,
+        }
+    }
+
+    /// Current secret value
+    pub async fn get(&self) -> AppResult<String> {
+        match &self.store {
+            Some(store) => store.resolve(&self.key, &self.raw).await,
+            None => Ok(self.raw.clone()),
+        }
+    }
+}
+
+impl From<&str> for SecretHandle {
+    fn from(value: &str) -> Self {
+        Self::literal(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Provider returning a fixed secret and counting fetches
+    struct CountingProvider {
+        value: Option<&'static str>,
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SecretsProvider for CountingProvider {
+        fn scheme(&self) -> &'static str {
+            "ssm"
+        }
+
+        async fn fetch(&self, _id: &str) -> AppResult<Option<String>> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(self.value.map(str::to_string))
+        }
+    }
+
+    fn counting_store(value: Option<&'static str>, ttl: Duration) -> (Arc<CountingProvider>, SecretStore) {
+        let provider = Arc::new(CountingProvider { value, fetches: AtomicUsize::new(0) });
+        let store = SecretStore::new(vec![provider.clone()], ttl);
+        (provider, store)
+    }
+
+    #[test]
+    fn test_reference_parsing() {
+        assert_eq!(SecretReference::parse("KEY", "postgresql://u:p@host/db").unwrap(), None);
+        assert_eq!(SecretReference::parse("KEY", "sk_test_123").unwrap(), None);
+
+        let reference = SecretReference::parse(
+            "KEY",
+            "secretsmanager:arn:aws:secretsmanager:us-east-1:123456789012:secret:db#password",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(reference.scheme, "secretsmanager");
+        assert_eq!(reference.id, "arn:aws:secretsmanager:us-east-1:123456789012:secret:db");
+        assert_eq!(reference.field.as_deref(), Some("password"));
+
+        let err = SecretReference::parse("STRIPE_API_KEY", "ssm:").unwrap_err();
+        assert!(matches!(err, AppError::Configuration(ref m) if m.contains("STRIPE_API_KEY")));
+    }
+
+    #[tokio::test]
+    async fn test_store_caches_until_ttl() {
+        let (provider, store) = counting_store(Some("sk_live_abc"), Duration::from_secs(60));
+
+        assert_eq!(store.resolve("STRIPE_API_KEY", "ssm:/stripe").await.unwrap(), "sk_live_abc");
+        assert_eq!(store.resolve("STRIPE_API_KEY", "ssm:/stripe").await.unwrap(), "sk_live_abc");
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 1);
+
+        let (provider, store) = counting_store(Some("sk_live_abc"), Duration::ZERO);
+        store.resolve("STRIPE_API_KEY", "ssm:/stripe").await.unwrap();
+        store.resolve("STRIPE_API_KEY", "ssm:/stripe").await.unwrap();
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 2);
+    }
+
+    /// Provider whose `slow` secret never arrives
+    struct StalledProvider;
+
+    #[async_trait]
+    impl SecretsProvider for StalledProvider {
+        fn scheme(&self) -> &'static str {
+            "ssm"
+        }
+
+        async fn fetch(&self, id: &str) -> AppResult<Option<String>> {
+            if id == "/slow" {
+                std::future::pending::<()>().await;
+            }
+            Ok(Some(format!("value of {}", id)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_fetch_does_not_block_other_keys() {
+        let store = Arc::new(SecretStore::new(vec![Arc::new(StalledProvider)], DEFAULT_CACHE_TTL));
+        let stalled = Arc::clone(&store);
+        tokio::spawn(async move { stalled.resolve("SLOW", "ssm:/slow").await });
+        tokio::task::yield_now().await;
+
+        let value = tokio::time::timeout(Duration::from_secs(1), store.resolve("FAST", "ssm:/fast"))
+            .await
+            .expect("resolve blocked behind another key's fetch");
+        assert_eq!(value.unwrap(), "value of /fast");
+    }
+
+    #[tokio::test]
+    async fn test_errors_name_key_but_not_value() {
+        let (_, store) = counting_store(None, DEFAULT_CACHE_TTL);
+        let err = store.resolve("STRIPE_API_KEY", "ssm:/stripe").await.unwrap_err();
+        assert!(matches!(err, AppError::Configuration(ref m) if m.contains("STRIPE_API_KEY")));
+
+        let (_, store) = counting_store(Some("not-json-sk_live_abc"), DEFAULT_CACHE_TTL);
+        let err = store.resolve("DATABASE_URL", "ssm:/db#url").await.unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("DATABASE_URL"));
+        assert!(!message.contains("sk_live_abc"));
+    }
+}
@@ -0,0 +1,170 @@
+//! # Subscription Renewals
+//!
+//! A scheduled job loads subscriptions whose `next_billing_at` has passed
+//! and moves each one step on:
+//!
+//! - cancelled, season over or grace period over: the subscription ends
+//! - otherwise: the saved payment method is charged off-session for the
+//!   next period; a decline makes it past due and schedules a dunning retry
+//!
+//! The renewal transaction ID is claimed on the subscription before the
+//! charge, so a job that dies mid-renewal re-charges under the same
+//! idempotency key instead of billing the player twice. A cancellation that
+//! lands in between still ends the subscription; the released renewal is
+//! then failed by the `reap-pending` job.
+//!
+//! ADVANTAGE: Failures are per subscription - one bad row never blocks the batch
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::subscription::DueStep;
+use crate::models::{Subscription, Transaction, TransactionStatus};
+use crate::services::{Database, PaymentService};
+
+/// Outcome counts of one renewal run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingReport {
+    pub due: usize,
+    pub renewed: usize,
+    /// Declined and scheduled for a dunning retry
+    pub retried: usize,
+    /// Cancelled, expired or out of grace
+    pub ended: usize,
+    /// Errored; left due for the next run
+    pub failed: usize,
+}
+
+impl BillingReport {
+    fn add(&mut self, other: Self) {
+        self.due += other.due;
+        self.renewed += other.renewed;
+        self.retried += other.retried;
+        self.ended += other.ended;
+        self.failed += other.failed;
+    }
+}
+
+/// Renews due subscriptions against a payment service
+pub struct SubscriptionBiller {
+    db: Arc<dyn Database>,
+    payments: Arc<PaymentService>,
+    batch_size: i32,
+}
+
+impl SubscriptionBiller {
+    pub fn new(db: Arc<dyn Database>, payments: Arc<PaymentService>) -> Self {
+        Self { db, payments, batch_size: 50 }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: i32) -> Self {
+        self.batch_size = batch_size.clamp(1, 1000);
+        self
+    }
+
+    /// Renew one batch of subscriptions due at `now`
+    pub async fn bill_once(&self, now: DateTime<Utc>) -> AppResult<BillingReport> {
+        let due = self.db.get_due_subscriptions(now, self.batch_size).await?;
+        let mut report = BillingReport { due: due.len(), ..Default::default() };
+
+        for subscription in due {
+            let subscription_id = subscription.subscription_id;
+            match self.bill(subscription, now).await {
+                Ok(saved) if !saved.status.is_live() => report.ended += 1,
+                Ok(saved) if saved.renewal_attempts > 0 => report.retried += 1,
+                Ok(_) => report.renewed += 1,
+                Err(e) => {
+                    error!(subscription_id = %subscription_id, error = %e, "Subscription renewal failed");
+                    report.failed += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Bill batches until one comes back short, at most `max_batches`
+    pub async fn drain(&self, max_batches: usize) -> AppResult<BillingReport> {
+        let mut report = BillingReport::default();
+        let now = Utc::now();
+
+        for _ in 0..max_batches {
+            let batch = self.bill_once(now).await?;
+            report.add(batch);
+            // Errored rows stay due; stop rather than fetch them again
+            if batch.due < self.batch_size as usize || batch.failed > 0 {
+                break;
+            }
+        }
+
+        info!(?report, "Subscriptions renewed");
+        Ok(report)
+    }
+
+    async fn bill(&self, subscription: Subscription, now: DateTime<Utc>) -> AppResult<Subscription> {
+        let plan = self
+            .db
+            .get_subscription_plan(&subscription.plan_id)
+            .await?
+            .ok_or_else(|| AppError::Internal(format!("Plan {} not found", subscription.plan_id)))?;
+
+        let amount_cents = match subscription.due_step(&plan, now) {
+            DueStep::End(ended) => return self.db.update_subscription(&ended).await,
+            DueStep::Charge { amount_cents } => amount_cents,
+        };
+
+        let subscription = match subscription.renewal_transaction_id {
+            Some(_) => subscription,
+            None => self.db.update_subscription(&subscription.claim_renewal(Uuid::new_v4())).await?,
+        };
+        let transaction_id = subscription
+            .renewal_transaction_id
+            .ok_or_else(|| AppError::Internal("Renewal was not claimed".into()))?;
+
+        let tx = match self.db.get_transaction(transaction_id).await? {
+            Some(tx) => tx,
+            None => {
+                let (_, paid_until) = subscription.next_period(&plan)?;
+                let mut new_tx = plan.charge(&subscription, amount_cents, paid_until);
+                new_tx.transaction_id = transaction_id;
+                self.db.insert_transaction(&new_tx).await?
+            }
+        };
+        let tx = charge_saved_method(self.db.as_ref(), &self.payments, tx, &subscription.payment_method).await?;
+
+        let saved = if tx.status == TransactionStatus::Completed {
+            subscription.renewed(&plan, &tx)?
+        } else {
+            warn!(subscription_id = %subscription.subscription_id, attempts = subscription.renewal_attempts + 1, "Renewal declined");
+            subscription.renewal_failed(&plan, now)
+        };
+        self.db.update_subscription(&saved).await
+    }
+}
+
+/// Settle pending `tx` against a saved payment method; a transaction
+/// covered entirely by credit completes without a charge
+pub(crate) async fn charge_saved_method(
+    db: &dyn Database,
+    payments: &PaymentService,
+    tx: Transaction,
+    payment_method: &str,
+) -> AppResult<Transaction> {
+    if tx.status != TransactionStatus::Pending {
+        return Ok(tx);
+    }
+    if tx.price_cents == 0 {
+        return db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, None).await;
+    }
+
+    let result = payments
+        .charge_off_session(tx.transaction_id, tx.player_id, payment_method, tx.price_cents, &tx.currency)
+        .await?;
+    let status = if result.success { TransactionStatus::Completed } else { TransactionStatus::Failed };
+    db.update_transaction_status(tx.transaction_id, status, Some(&result.processor_id)).await
+}
@@ -0,0 +1,367 @@
+//! # Webhook Dispatcher
+//!
+//! Status changes write one `webhook_deliveries` row per matching
+//! subscription in the same database transaction. The dispatcher claims due
+//! deliveries and POSTs each payload to its subscription's URL:
+//!
+//! - 2xx: the delivery is marked delivered
+//! - anything else, or no response: it is retried with exponential backoff
+//! - failed `max_attempts` times: it is marked dead until redelivered by hand
+//!
+//! Every request is signed so the game server can check it came from us:
+//!
+//! ```text
+//! X-Webhook-Id: <delivery id>
+//! X-Webhook-Event: purchase.completed
+//! X-Webhook-Timestamp: <unix seconds>
+//! X-Webhook-Signature: v1=<hex HMAC-SHA256(secret, "<timestamp>.<body>")>
+//! ```
+//!
+//! Destinations are checked again before each send, and the client only
+//! connects to public addresses and never follows redirects, so a
+//! subscription cannot be turned on internal services by changing its DNS.
+//!
+//! ADVANTAGE: The timestamp is signed too, so receivers can reject replays
+//! ADVANTAGE: Same claim/lease/backoff shape as the outbox dispatcher
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::errors::AppResult;
+use crate::models::WebhookDispatch;
+use crate::models::webhook::{check_destination, is_public_address};
+use crate::services::outbox::{backoff_delay, DispatchReport, DEFAULT_CLAIM_LEASE, DEFAULT_RETRY_BACKOFF};
+use crate::services::Database;
+
+/// Delivery ID, stable across retries
+pub const ID_HEADER: &str = "X-Webhook-Id";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+/// Unix seconds when the request was signed
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// How long a receiver gets to answer
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Recommended receiver tolerance for `verify_signature`
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_secs(300);
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn mac(secret: &str, timestamp: i64, body: &[u8]) -> HmacSha256 {
+    // HMAC accepts keys of any length
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC key of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// `X-Webhook-Signature` value for `body` sent at `timestamp`
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!("v1={}", hex::encode(mac(secret, timestamp, body).finalize().into_bytes()))
+}
+
+/// Receiver-side check: the signature matches and `timestamp` is within
+/// `tolerance` of `now`
+///
+/// ADVANTAGE: Constant-time comparison - game servers written in Rust can use this directly
+pub fn verify_signature(
+    secret: &str,
+    timestamp: i64,
+    body: &[u8],
+    signature: &str,
+    now: i64,
+    tolerance: Duration,
+) -> bool {
+    let fresh = now.abs_diff(timestamp) <= tolerance.as_secs();
+    let valid = signature
+        .strip_prefix("v1=")
+        .and_then(|digest| hex::decode(digest).ok())
+        .is_some_and(|digest| mac(secret, timestamp, body).verify_slice(&digest).is_ok());
+
+    fresh && valid
+}
+
+/// DNS resolver refusing names with any non-public address, so a host
+/// cannot be re-pointed between `check_destination` and the connection
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<_> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            if let Some(address) = addresses.iter().find(|a| !is_public_address(a.ip())) {
+                return Err(format!("{} resolves to non-public address {}", name.as_str(), address.ip()).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Client for receivers: public addresses only, redirects not followed
+fn receiver_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .expect("webhook HTTP client")
+}
+
+/// Sends claimed webhook deliveries to their game servers
+pub struct WebhookDispatcher {
+    db: Arc<dyn Database>,
+    http: reqwest::Client,
+    /// Skip destination checks - local stand-in receivers in tests only
+    allow_private: bool,
+    batch_size: i32,
+    max_attempts: i32,
+    retry_backoff: Duration,
+    claim_lease: Duration,
+    timeout: Duration,
+}
+
+impl WebhookDispatcher {
+    pub fn new(db: Arc<dyn Database>) -> Self {
+        Self {
+            db,
+            http: receiver_client(),
+            allow_private: false,
+            batch_size: 50,
+            max_attempts: 10,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            claim_lease: DEFAULT_CLAIM_LEASE,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: i32) -> Self {
+        self.batch_size = batch_size.clamp(1, 1000);
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: i32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn with_retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Receiver timeout - kept below the claim lease so a slow receiver
+    /// cannot get the same delivery sent twice at once
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout.min(self.claim_lease);
+        self
+    }
+
+    /// Deliver to local receivers, which production never allows
+    #[cfg(test)]
+    fn with_private_destinations(mut self) -> Self {
+        self.http = reqwest::Client::new();
+        self.allow_private = true;
+        self
+    }
+
+    /// POST one delivery, returning the response status
+    async fn send(&self, dispatch: &WebhookDispatch) -> Result<u16, String> {
+        if !self.allow_private {
+            check_destination(&dispatch.url).await.map_err(|e| e.to_string())?;
+        }
+        let delivery = &dispatch.delivery;
+        let body = delivery.payload.to_string().into_bytes();
+        let timestamp = Utc::now().timestamp();
+        let signature = sign(dispatch.secret.expose(), timestamp, &body);
+
+        let response = self
+            .http
+            .post(&dispatch.url)
+            .timeout(self.timeout)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(ID_HEADER, delivery.delivery_id.to_string())
+            .header(EVENT_HEADER, &delivery.event_type)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, signature)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(response.status().as_u16())
+    }
+
+    /// Claim and send one batch of due deliveries
+    pub async fn dispatch_once(&self) -> AppResult<DispatchReport> {
+        let dispatches = self.db.claim_webhook_deliveries(self.batch_size, self.claim_lease).await?;
+        let mut report = DispatchReport { claimed: dispatches.len(), ..Default::default() };
+
+        for dispatch in &dispatches {
+            let delivery = &dispatch.delivery;
+            let (status_code, error) = match self.send(dispatch).await {
+                Ok(status) if (200..300).contains(&status) => {
+                    self.db.mark_webhook_delivered(delivery.delivery_id, i32::from(status)).await?;
+                    report.delivered += 1;
+                    continue;
+                }
+                Ok(status) => (Some(i32::from(status)), format!("Receiver responded with {}", status)),
+                Err(e) => (None, e),
+            };
+
+            if delivery.attempts >= self.max_attempts {
+                error!(delivery_id = %delivery.delivery_id, attempts = delivery.attempts, error = %error, "Webhook delivery is dead");
+                self.db.mark_webhook_failed(delivery.delivery_id, status_code, &error, None).await?;
+                report.dead += 1;
+            } else {
+                let delay = backoff_delay(self.retry_backoff, delivery.attempts);
+                warn!(delivery_id = %delivery.delivery_id, attempts = delivery.attempts, retry_in = ?delay, error = %error, "Webhook delivery failed");
+                self.db.mark_webhook_failed(delivery.delivery_id, status_code, &error, Some(delay)).await?;
+                report.retried += 1;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Dispatch batches until one comes back short, at most `max_batches`
+    pub async fn drain(&self, max_batches: usize) -> AppResult<DispatchReport> {
+        let mut report = DispatchReport::default();
+
+        for _ in 0..max_batches {
+            let batch = self.dispatch_once().await?;
+            report.add(batch);
+            if batch.claimed < self.batch_size as usize {
+                break;
+            }
+        }
+
+        info!(?report, "Webhooks dispatched");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::models::{
+        CatalogItemRequest, CreateWebhookRequest, EventType, NewTransaction, NewWebhookSubscription, TransactionStatus,
+        WebhookDeliveryStatus,
+    };
+    use crate::services::InMemoryDatabase;
+    use crate::test_support::HttpStandIn;
+
+    async fn completed_purchase(db: &InMemoryDatabase, title_id: &str) {
+        let item_id = format!("{}_sword", title_id);
+        let listing: CatalogItemRequest =
+            serde_json::from_value(json!({"kind": "consumable", "title_id": title_id})).unwrap();
+        db.upsert_catalog_item(&item_id, &listing).await.unwrap();
+        let tx = db.insert_transaction(&NewTransaction::new(
+            uuid::Uuid::new_v4(), item_id, "Sword".into(), 999, "USD".into(), 1, serde_json::Value::Null,
+        )).await.unwrap();
+        db.update_transaction_status(tx.transaction_id, TransactionStatus::Completed, Some("pi_1")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_deliveries_are_signed_for_subscribed_titles() {
+        let receiver = HttpStandIn::start(|_| (204, serde_json::Value::Null)).await;
+        let db = Arc::new(InMemoryDatabase::new());
+        let subscription = db.create_webhook_subscription(&NewWebhookSubscription::new(
+            "starfall".into(),
+            CreateWebhookRequest {
+                url: format!("{}/hooks", receiver.endpoint),
+                event_types: vec![EventType::PurchaseCompleted],
+            },
+        )).await.unwrap();
+
+        completed_purchase(&db, "starfall").await;
+        // Other titles' purchases are not delivered
+        completed_purchase(&db, "moonrise").await;
+
+        let report = WebhookDispatcher::new(db.clone()).with_private_destinations().drain(5).await.unwrap();
+        assert_eq!(report, DispatchReport { claimed: 1, delivered: 1, ..Default::default() });
+
+        let requests = receiver.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.path, "/hooks");
+        assert_eq!(request.header(EVENT_HEADER), Some("purchase.completed"));
+        assert_eq!(request.json()["eventType"], "purchase.completed");
+
+        let timestamp: i64 = request.header(TIMESTAMP_HEADER).unwrap().parse().unwrap();
+        let signature = request.header(SIGNATURE_HEADER).unwrap();
+        let secret = subscription.secret.expose();
+        let now = Utc::now().timestamp();
+        assert!(verify_signature(secret, timestamp, &request.body, signature, now, DEFAULT_TOLERANCE));
+        assert!(!verify_signature(secret, timestamp, b"{}", signature, now, DEFAULT_TOLERANCE));
+        assert!(!verify_signature(secret, timestamp, &request.body, signature, now + 3600, DEFAULT_TOLERANCE));
+
+        let log = db.get_webhook_deliveries(subscription.subscription_id, 10).await.unwrap();
+        assert_eq!(log[0].status, WebhookDeliveryStatus::Delivered);
+        assert_eq!(log[0].last_status_code, Some(204));
+        assert_eq!(request.header(ID_HEADER), Some(log[0].delivery_id.to_string().as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_failing_receiver_goes_dead_until_redelivered() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        // Fails twice, then recovers
+        let receiver = HttpStandIn::start(move |_| match counter.fetch_add(1, Ordering::SeqCst) {
+            0 | 1 => (503, json!({"error": "maintenance"})),
+            _ => (200, json!({"ok": true})),
+        })
+        .await;
+        let db = Arc::new(InMemoryDatabase::new());
+        let subscription = db.create_webhook_subscription(&NewWebhookSubscription::new(
+            "starfall".into(),
+            CreateWebhookRequest { url: receiver.endpoint.clone(), event_types: vec![EventType::PurchaseCompleted] },
+        )).await.unwrap();
+        completed_purchase(&db, "starfall").await;
+
+        let dispatcher = WebhookDispatcher::new(db.clone())
+            .with_private_destinations()
+            .with_max_attempts(2)
+            .with_retry_backoff(Duration::ZERO);
+        assert_eq!(dispatcher.dispatch_once().await.unwrap().retried, 1);
+        assert_eq!(dispatcher.dispatch_once().await.unwrap().dead, 1);
+        assert_eq!(dispatcher.dispatch_once().await.unwrap().claimed, 0);
+
+        let dead = db.get_webhook_deliveries(subscription.subscription_id, 10).await.unwrap().remove(0);
+        assert_eq!(dead.status, WebhookDeliveryStatus::Dead);
+        assert_eq!(dead.last_status_code, Some(503));
+
+        let redelivered = db.redeliver_webhook(dead.delivery_id).await.unwrap();
+        assert_eq!((redelivered.status, redelivered.attempts), (WebhookDeliveryStatus::Pending, 0));
+        assert_eq!(dispatcher.dispatch_once().await.unwrap().delivered, 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_private_destinations_are_not_sent_to() {
+        let receiver = HttpStandIn::start(|_| (200, serde_json::Value::Null)).await;
+        let db = Arc::new(InMemoryDatabase::new());
+        // Stored directly, as if the host's DNS changed after creation
+        let subscription = db.create_webhook_subscription(&NewWebhookSubscription::new(
+            "starfall".into(),
+            CreateWebhookRequest {
+                url: receiver.endpoint.replace("http://", "https://"),
+                event_types: vec![EventType::PurchaseCompleted],
+            },
+        )).await.unwrap();
+        completed_purchase(&db, "starfall").await;
+
+        let report = WebhookDispatcher::new(db.clone()).dispatch_once().await.unwrap();
+        assert_eq!(report.retried, 1);
+        assert!(receiver.requests().is_empty());
+        let log = db.get_webhook_deliveries(subscription.subscription_id, 10).await.unwrap();
+        assert!(log[0].last_error.as_deref().unwrap().contains("public addresses"));
+    }
+}
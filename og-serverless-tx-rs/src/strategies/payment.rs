@@ -7,11 +7,15 @@
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
 use std::time::Duration;
 use tracing::{info, warn, instrument};
 use uuid::Uuid;
 
 use crate::errors::{AppError, AppResult};
+use crate::services::secrets::SecretHandle;
+use crate::telemetry;
 
 /// Payment request data
 /// 
@@ -24,6 +28,10 @@ pub struct PaymentRequest {
     pub player_id: Uuid,
     pub transaction_id: Uuid,
     pub idempotency_key: String,
+    /// Key-value pairs stored on the processor's charge (Stripe `metadata`)
+    /// 
+    /// ADVANTAGE: `request_id` here lets support find a charge from a ticket
+    pub metadata: BTreeMap<String, String>,
 }
 
 /// Payment result from processor
@@ -93,8 +101,32 @@ pub trait PaymentStrategy: Send + Sync {
     /// ADVANTAGE: Return type is guaranteed - no undefined/null surprises
     async fn process_payment(&self, request: PaymentRequest) -> AppResult<PaymentResult>;
     
-    /// Refund a payment
-    async fn refund_payment(&self, processor_id: &str, amount_cents: i64) -> AppResult<PaymentResult>;
+    /// Refund a payment; a repeated `idempotency_key` refunds once
+    async fn refund_payment(&self, processor_id: &str, amount_cents: i64, idempotency_key: &str) -> AppResult<PaymentResult>;
+    
+    /// Charge a saved payment method with the player away (subscription renewals)
+    /// 
+    /// Strategies that cannot store payment methods reject every charge.
+    async fn charge_off_session(&self, payment_method: &str, request: PaymentRequest) -> AppResult<PaymentResult> {
+        let _ = (payment_method, request);
+        Err(AppError::Payment(format!("{} cannot charge saved payment methods", self.name())))
+    }
+    
+    /// Look up the charge sent under `idempotency_key`, `None` when the
+    /// processor never received one
+    /// 
+    /// Strategies that cannot look charges up report every outcome as unknown.
+    async fn find_payment(&self, idempotency_key: &str) -> AppResult<Option<PaymentResult>> {
+        let _ = idempotency_key;
+        Err(AppError::Unavailable(format!("{} cannot look up charges", self.name())))
+    }
+    
+    /// Check the processor is reachable with the current credentials
+    /// 
+    /// Strategies without a remote processor are always healthy.
+    async fn health_check(&self) -> AppResult<()> {
+        Ok(())
+    }
     
     /// Get strategy name for logging
     fn name(&self) -> &'static str;
@@ -108,15 +140,16 @@ pub trait PaymentStrategy: Send + Sync {
 /// 
 /// ADVANTAGE: API key is stored securely in struct, not global state
 /// ADVANTAGE: Each instance can have different configuration
+/// ADVANTAGE: Key is a `SecretHandle` - rotated keys are picked up when warm
 pub struct StripePaymentStrategy {
-    api_key: String,
+    api_key: SecretHandle,
     // In production, you'd have a reqwest::Client here
 }
 
 impl StripePaymentStrategy {
-    pub fn new(api_key: &str) -> Self {
+    pub fn new(api_key: impl Into<SecretHandle>) -> Self {
         Self {
-            api_key: api_key.to_string(),
+            api_key: api_key.into(),
         }
     }
 }
@@ -127,12 +160,19 @@ impl PaymentStrategy for StripePaymentStrategy {
     /// 
     /// ADVANTAGE: #[instrument] provides automatic tracing
     /// ADVANTAGE: All error paths return typed errors
-    #[instrument(skip(self, request), fields(strategy = "stripe"))]
+    #[instrument(skip(self, request), fields(strategy = "stripe", otel.kind = "client", peer.service = "stripe"))]
     async fn process_payment(&self, request: PaymentRequest) -> AppResult<PaymentResult> {
+        let api_key = self.api_key.get().await?;
+        
+        // ADVANTAGE: Trace context rides along, so the charge joins the request's trace
+        let _trace_headers = telemetry::outbound_headers();
+        
         info!(
             amount = request.amount_cents,
             currency = %request.currency,
             player_id = %request.player_id,
+            livemode = api_key.starts_with("sk_live_"),
+            request_id = request.metadata.get("request_id").map(String::as_str),
             "Processing Stripe payment"
         );
         
@@ -146,11 +186,11 @@ impl PaymentStrategy for StripePaymentStrategy {
         }
         
         // Simulate Stripe API call
-        // In production: use reqwest to call Stripe API
+        // In production: use reqwest to call Stripe API, sending `_trace_headers`
         tokio::time::sleep(Duration::from_millis(50)).await;
         
         // Simulate success for amounts under $1000
-        let processor_id = format!("pi_{}", Uuid::new_v4().to_string().replace("-", "")[..24].to_string());
+        let processor_id = format!("pi_{}", &Uuid::new_v4().simple().to_string()[..24]);
         
         if request.amount_cents < 100_000 {
             info!(processor_id = %processor_id, "Payment successful");
@@ -165,21 +205,82 @@ impl PaymentStrategy for StripePaymentStrategy {
         }
     }
     
-    #[instrument(skip(self), fields(strategy = "stripe"))]
-    async fn refund_payment(&self, processor_id: &str, amount_cents: i64) -> AppResult<PaymentResult> {
+    /// Confirm an off-session PaymentIntent against a saved `pm_` method
+    #[instrument(skip(self, request), fields(strategy = "stripe", otel.kind = "client", peer.service = "stripe"))]
+    async fn charge_off_session(&self, payment_method: &str, request: PaymentRequest) -> AppResult<PaymentResult> {
+        if !payment_method.starts_with("pm_") {
+            return Err(AppError::Payment("Saved payment method must be a Stripe pm_ ID".into()));
+        }
+        info!(
+            amount = request.amount_cents,
+            player_id = %request.player_id,
+            idempotency_key = %request.idempotency_key,
+            "Processing off-session Stripe payment"
+        );
+        
+        if request.amount_cents <= 0 {
+            return Err(AppError::Payment("Amount must be positive".into()));
+        }
+        
+        // Simulate POST /v1/payment_intents with off_session=true, confirm=true
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        
+        let processor_id = format!("pi_{}", &Uuid::new_v4().simple().to_string()[..24]);
+        if request.amount_cents < 100_000 {
+            Ok(PaymentResult::success(processor_id))
+        } else {
+            warn!(processor_id = %processor_id, "Off-session payment declined");
+            Ok(PaymentResult::failure(
+                processor_id,
+                "authentication_required",
+                "The saved payment method requires the player to authenticate.",
+            ))
+        }
+    }
+    
+    #[instrument(skip(self), fields(strategy = "stripe", otel.kind = "client", peer.service = "stripe"))]
+    async fn refund_payment(&self, processor_id: &str, amount_cents: i64, idempotency_key: &str) -> AppResult<PaymentResult> {
         info!(
             processor_id = %processor_id,
             amount = amount_cents,
+            idempotency_key = %idempotency_key,
             "Processing Stripe refund"
         );
         
         // Simulate refund
         tokio::time::sleep(Duration::from_millis(30)).await;
         
-        let refund_id = format!("re_{}", Uuid::new_v4().to_string().replace("-", "")[..24].to_string());
+        let refund_id = format!("re_{}", &Uuid::new_v4().simple().to_string()[..24]);
         Ok(PaymentResult::success(refund_id))
     }
     
+    /// Search PaymentIntents by the idempotency key stored in their metadata
+    #[instrument(skip(self), fields(strategy = "stripe", otel.kind = "client", peer.service = "stripe"))]
+    async fn find_payment(&self, idempotency_key: &str) -> AppResult<Option<PaymentResult>> {
+        let _api_key = self.api_key.get().await?;
+        
+        // Simulate GET /v1/payment_intents/search?query=metadata['idempotency_key']:'...'
+        // In production: map the intent's status to a result, `None` when the search is empty
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        Ok(None)
+    }
+    
+    /// Resolve the key and ping the API
+    /// 
+    /// ADVANTAGE: A rotated-away or missing key shows up in readiness, not in a purchase
+    #[instrument(skip(self), fields(strategy = "stripe", otel.kind = "client", peer.service = "stripe"))]
+    async fn health_check(&self) -> AppResult<()> {
+        let api_key = self.api_key.get().await?;
+        if api_key.is_empty() {
+            return Err(AppError::Configuration("STRIPE_API_KEY is empty".into()));
+        }
+        
+        // Simulate GET /v1/balance
+        // In production: use reqwest to call Stripe API
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        Ok(())
+    }
+    
     fn name(&self) -> &'static str {
         "stripe"
     }
@@ -189,6 +290,9 @@ impl PaymentStrategy for StripePaymentStrategy {
 // MOCK PAYMENT STRATEGY (for testing)
 // ============================================================================
 
+/// Saved payment method the mock strategy always declines off-session
+pub const MOCK_DECLINING_PAYMENT_METHOD: &str = "pm_card_chargeDeclined";
+
 /// Mock payment processor for testing
 /// 
 /// ADVANTAGE: Same interface as real processor - tests are realistic
@@ -199,6 +303,8 @@ pub struct MockPaymentStrategy {
     failure_rate: f64,
     /// Simulated processing delay
     delay: Duration,
+    /// Results by idempotency key, replayed like a real processor's
+    charges: Mutex<HashMap<String, PaymentResult>>,
 }
 
 impl MockPaymentStrategy {
@@ -206,6 +312,7 @@ impl MockPaymentStrategy {
         Self {
             failure_rate: 0.0,
             delay: Duration::from_millis(10),
+            charges: Mutex::default(),
         }
     }
     
@@ -214,6 +321,7 @@ impl MockPaymentStrategy {
         Self {
             failure_rate: failure_rate.clamp(0.0, 1.0),
             delay: Duration::from_millis(10),
+            charges: Mutex::default(),
         }
     }
 }
@@ -242,19 +350,42 @@ impl PaymentStrategy for MockPaymentStrategy {
         // This allows predictable test scenarios
         let should_fail = request.player_id.as_bytes()[0] as f64 / 255.0 < self.failure_rate;
         
-        if should_fail {
-            Ok(PaymentResult::failure(
+        let result = if should_fail {
+            PaymentResult::failure(
                 processor_id,
                 "mock_decline",
                 "Mock payment declined for testing",
-            ))
+            )
         } else {
-            Ok(PaymentResult::success(processor_id))
+            PaymentResult::success(processor_id)
+        };
+        
+        // ADVANTAGE: A repeated idempotency key answers with the first charge
+        let mut charges = self.charges.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(charges.entry(request.idempotency_key).or_insert(result).clone())
+    }
+    
+    #[instrument(skip(self, request), fields(strategy = "mock"))]
+    async fn charge_off_session(&self, payment_method: &str, request: PaymentRequest) -> AppResult<PaymentResult> {
+        if payment_method == MOCK_DECLINING_PAYMENT_METHOD {
+            tokio::time::sleep(self.delay).await;
+            return Ok(PaymentResult::failure(
+                format!("mock_{}", Uuid::new_v4()),
+                "mock_decline",
+                "Mock saved payment method declined for testing",
+            ));
         }
+        self.process_payment(request).await
+    }
+    
+    #[instrument(skip(self), fields(strategy = "mock"))]
+    async fn find_payment(&self, idempotency_key: &str) -> AppResult<Option<PaymentResult>> {
+        let charges = self.charges.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(charges.get(idempotency_key).cloned())
     }
     
     #[instrument(skip(self), fields(strategy = "mock"))]
-    async fn refund_payment(&self, processor_id: &str, _amount_cents: i64) -> AppResult<PaymentResult> {
+    async fn refund_payment(&self, processor_id: &str, _amount_cents: i64, _idempotency_key: &str) -> AppResult<PaymentResult> {
         tokio::time::sleep(self.delay).await;
         
         let refund_id = format!("mock_refund_{}", Uuid::new_v4());
@@ -284,6 +415,7 @@ mod tests {
             player_id: Uuid::new_v4(),
             transaction_id: Uuid::new_v4(),
             idempotency_key: Uuid::new_v4().to_string(),
+            metadata: BTreeMap::new(),
         };
         
         let result = strategy.process_payment(request).await.unwrap();
@@ -0,0 +1,226 @@
+//! # Distributed Tracing
+//!
+//! With `--features otel`, every `tracing` span - the router's `request`
+//! span, the `#[instrument]` spans on database and payment calls - is
+//! exported over OTLP to `OTEL_EXPORTER_OTLP_ENDPOINT` (e.g. the ADOT
+//! collector layer, which forwards to X-Ray).
+//!
+//! The request span joins the invocation's X-Ray trace, taken from the
+//! `X-Amzn-Trace-Id` header or the `_X_AMZN_TRACE_ID` variable the Lambda
+//! runtime sets. Outbound processor calls carry the context onward in both
+//! `X-Amzn-Trace-Id` and W3C `traceparent` headers.
+//!
+//! Without the feature every function here is a no-op.
+//!
+//! ADVANTAGE: Existing `#[instrument]` spans become trace spans - no second API
+//! ADVANTAGE: Default builds carry no OpenTelemetry code at all
+
+use lambda_http::http::HeaderMap;
+
+#[cfg(feature = "otel")]
+pub mod xray;
+
+/// Trace header set by API Gateway and understood by X-Ray
+pub const XRAY_HEADER: &str = "X-Amzn-Trace-Id";
+
+/// Variable the Lambda runtime sets to the invocation's trace header
+pub const XRAY_ENV_VAR: &str = "_X_AMZN_TRACE_ID";
+
+/// Make `span` a child of the caller's trace, if one is known
+///
+/// The request header wins; the Lambda environment is the fallback.
+pub fn set_remote_parent(span: &tracing::Span, request_headers: &HeaderMap) {
+    #[cfg(feature = "otel")]
+    otel::set_remote_parent(span, request_headers);
+
+    #[cfg(not(feature = "otel"))]
+    let _ = (span, request_headers);
+}
+
+/// Headers that carry the current span's context to an outbound HTTP call
+pub fn outbound_headers() -> Vec<(String, String)> {
+    #[cfg(feature = "otel")]
+    return otel::outbound_headers();
+
+    #[cfg(not(feature = "otel"))]
+    Vec::new()
+}
+
+/// Export buffered spans before Lambda freezes the container
+pub async fn flush() {
+    #[cfg(feature = "otel")]
+    otel::flush().await;
+}
+
+#[cfg(feature = "otel")]
+pub use otel::{layer, layer_for};
+
+#[cfg(feature = "otel")]
+mod otel {
+    use lambda_http::http::HeaderMap;
+    use opentelemetry::propagation::{Extractor, TextMapCompositePropagator, TextMapPropagator};
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry::Context;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+    use std::collections::HashMap;
+    use std::sync::OnceLock;
+    use tracing::Subscriber;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    use super::xray::XrayPropagator;
+    use super::{XRAY_ENV_VAR, XRAY_HEADER};
+    use crate::errors::{AppError, AppResult};
+
+    /// Provider behind the installed layer, kept for flushing
+    static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+    /// OTLP layer, or `None` when `OTEL_EXPORTER_OTLP_ENDPOINT` is unset
+    ///
+    /// The exporter reads the standard `OTEL_EXPORTER_OTLP_*` variables.
+    pub fn layer<S>(service_name: &'static str) -> AppResult<Option<impl Layer<S>>>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        if std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_none() {
+            return Ok(None);
+        }
+
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .build()
+            .map_err(|e| AppError::Configuration(format!("OTLP exporter: {}", e)))?;
+
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder().with_service_name(service_name).build())
+            .build();
+
+        let layer = layer_for(&provider, service_name);
+        let _ = PROVIDER.set(provider);
+        Ok(Some(layer))
+    }
+
+    /// Layer exporting through `provider` - tests pass an in-memory exporter
+    pub fn layer_for<S>(provider: &SdkTracerProvider, service_name: &'static str) -> impl Layer<S> + use<S>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name))
+    }
+
+    pub fn set_remote_parent(span: &tracing::Span, request_headers: &HeaderMap) {
+        let header = request_headers
+            .get(XRAY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .or_else(|| std::env::var(XRAY_ENV_VAR).ok());
+
+        let Some(header) = header else {
+            return;
+        };
+
+        // HashMap extraction lowercases the key
+        let carrier = HashMap::from([(XRAY_HEADER.to_lowercase(), header)]);
+        let parent = XrayPropagator.extract_with_context(&Context::new(), &carrier as &dyn Extractor);
+        let _ = span.set_parent(parent);
+    }
+
+    pub fn outbound_headers() -> Vec<(String, String)> {
+        let propagator = TextMapCompositePropagator::new(vec![
+            Box::new(XrayPropagator),
+            Box::new(TraceContextPropagator::new()),
+        ]);
+
+        let mut carrier = HashMap::new();
+        propagator.inject_context(&tracing::Span::current().context(), &mut carrier);
+
+        let mut headers: Vec<(String, String)> = carrier.into_iter().collect();
+        headers.sort();
+        headers
+    }
+
+    pub async fn flush() {
+        let Some(provider) = PROVIDER.get() else {
+            return;
+        };
+
+        // The batch processor exports on its own thread; don't block a worker
+        let provider = provider.clone();
+        let _ = tokio::task::spawn_blocking(move || provider.force_flush()).await;
+    }
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TraceId;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+    use std::sync::Arc;
+    use tracing::instrument::WithSubscriber;
+    use tracing::Instrument;
+    use tracing_subscriber::layer::SubscriberExt;
+    use uuid::Uuid;
+
+    use crate::services::PaymentService;
+    use crate::strategies::payment::MockPaymentStrategy;
+
+    const TRACE_HEADER: &str = "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1";
+
+    #[tokio::test]
+    async fn test_processor_spans_join_the_invocation_trace() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry().with(layer_for(&provider, "test"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(XRAY_HEADER, TRACE_HEADER.parse().unwrap());
+        let service = PaymentService::new(Arc::new(MockPaymentStrategy::new()));
+
+        let outbound = async {
+            let span = tracing::info_span!("request");
+            set_remote_parent(&span, &headers);
+
+            async {
+                service
+                    .process_purchase(Uuid::new_v4(), Uuid::new_v4(), 1000, "USD", None)
+                    .await
+                    .unwrap();
+                outbound_headers()
+            }
+            .instrument(span)
+            .await
+        }
+        .with_subscriber(subscriber)
+        .await;
+
+        provider.force_flush().unwrap();
+        let spans = exporter.get_finished_spans().unwrap();
+        let expected = TraceId::from_hex("5759e988bd862e3fe1be46a994272793").unwrap();
+
+        let names: Vec<&str> = spans.iter().map(|s| s.name.as_ref()).collect();
+        assert!(names.contains(&"request"));
+        assert!(names.contains(&"process_purchase"));
+        assert!(names.contains(&"process_payment"));
+        assert!(spans.iter().all(|s| s.span_context.trace_id() == expected));
+
+        let xray = outbound.iter().find(|(k, _)| k.eq_ignore_ascii_case(XRAY_HEADER)).map(|(_, v)| v.as_str());
+        assert!(xray.is_some_and(|v| v.starts_with("Root=1-5759e988-bd862e3fe1be46a994272793;Parent=")));
+        assert!(outbound.iter().any(|(k, v)| k == "traceparent" && v.contains("5759e988bd862e3fe1be46a994272793")));
+    }
+
+    #[test]
+    fn test_xray_header_round_trip() {
+        let span_context = xray::parse_header(TRACE_HEADER).unwrap();
+        assert!(span_context.is_sampled());
+        assert_eq!(xray::format_header(&span_context), TRACE_HEADER);
+
+        assert!(xray::parse_header("Root=1-5759e988-bd862e3fe1be46a994272793").is_none());
+        assert!(!xray::parse_header(&TRACE_HEADER.replace("Sampled=1", "Sampled=0")).unwrap().is_sampled());
+    }
+}
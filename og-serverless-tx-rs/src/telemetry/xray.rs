@@ -0,0 +1,86 @@
+//! # X-Ray Trace Header Propagator
+//!
+//! Reads and writes `X-Amzn-Trace-Id`:
+//!
+//! ```text
+//! Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1
+//! ```
+//!
+//! The root's epoch and random parts together are the 128-bit OpenTelemetry
+//! trace ID, so X-Ray and OTLP backends see the same trace.
+
+use opentelemetry::propagation::text_map_propagator::FieldIter;
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+use opentelemetry::Context;
+use std::sync::LazyLock;
+
+use super::XRAY_HEADER;
+
+static FIELDS: LazyLock<[String; 1]> = LazyLock::new(|| [XRAY_HEADER.to_lowercase()]);
+
+/// `TextMapPropagator` for the X-Ray trace header
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XrayPropagator;
+
+impl TextMapPropagator for XrayPropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        let span = cx.span();
+        let span_context = span.span_context();
+        if !span_context.is_valid() {
+            return;
+        }
+
+        injector.set(XRAY_HEADER, format_header(span_context));
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        match extractor.get(XRAY_HEADER).and_then(parse_header) {
+            Some(span_context) => cx.with_remote_span_context(span_context),
+            None => cx.clone(),
+        }
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(FIELDS.as_slice())
+    }
+}
+
+/// `Root=1-{8 hex}-{24 hex};Parent={16 hex};Sampled={0|1}`
+pub fn format_header(span_context: &SpanContext) -> String {
+    let trace_id = format!("{:032x}", span_context.trace_id());
+    format!(
+        "Root=1-{}-{};Parent={:016x};Sampled={}",
+        &trace_id[..8],
+        &trace_id[8..],
+        span_context.span_id(),
+        if span_context.is_sampled() { 1 } else { 0 }
+    )
+}
+
+/// Parse a trace header; `None` if the root or parent is missing or malformed
+///
+/// A missing `Sampled` defers the decision to us, and we sample.
+pub fn parse_header(header: &str) -> Option<SpanContext> {
+    let mut trace_id = None;
+    let mut span_id = None;
+    let mut flags = TraceFlags::SAMPLED;
+
+    for part in header.split(';') {
+        match part.trim().split_once('=')? {
+            ("Root", root) => {
+                let (epoch, random) = root.strip_prefix("1-")?.split_once('-')?;
+                if epoch.len() != 8 || random.len() != 24 {
+                    return None;
+                }
+                trace_id = TraceId::from_hex(&format!("{}{}", epoch, random)).ok();
+            }
+            ("Parent", parent) if parent.len() == 16 => span_id = SpanId::from_hex(parent).ok(),
+            ("Sampled", "0") => flags = TraceFlags::NOT_SAMPLED,
+            _ => {}
+        }
+    }
+
+    let span_context = SpanContext::new(trace_id?, span_id?, flags, true, TraceState::NONE);
+    span_context.is_valid().then_some(span_context)
+}
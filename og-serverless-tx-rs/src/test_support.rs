@@ -0,0 +1,148 @@
+//! # Test Support
+//!
+//! ADVANTAGE: Local HTTP stand-ins exercise real clients without AWS access
+//! ADVANTAGE: Compiled only for tests - nothing here ships in the binary
+
+use lambda_http::{Body, Request, Response};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// `POST` request carrying `body` as JSON, for calling handlers directly
+pub fn post(body: Value) -> Request {
+    lambda_http::http::Request::builder()
+        .method("POST")
+        .body(Body::Text(body.to_string()))
+        .unwrap()
+}
+
+/// JSON body of a handler response
+pub fn body(response: &Response<Body>) -> Value {
+    let Body::Text(body) = response.body() else { panic!("expected a text body") };
+    serde_json::from_str(body).unwrap()
+}
+
+/// Request captured by an `HttpStandIn`
+#[derive(Debug, Clone)]
+pub struct StandInRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl StandInRequest {
+    /// First header value with `name` (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Body parsed as JSON, `Value::Null` if empty or invalid
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or(Value::Null)
+    }
+}
+
+type Responder = Arc<dyn Fn(&StandInRequest) -> (u16, Value) + Send + Sync>;
+
+/// Minimal HTTP/1.1 server answering every request with JSON
+///
+/// ADVANTAGE: Keep-alive aware, so SDK clients with pooled connections work
+pub struct HttpStandIn {
+    pub endpoint: String,
+    requests: Arc<Mutex<Vec<StandInRequest>>>,
+}
+
+impl HttpStandIn {
+    /// Bind to an ephemeral localhost port and serve `responder`
+    pub async fn start<F>(responder: F) -> Self
+    where
+        F: Fn(&StandInRequest) -> (u16, Value) + Send + Sync + 'static,
+    {
+        let responder: Responder = Arc::new(responder);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let recorded = Arc::clone(&recorded);
+                let responder = Arc::clone(&responder);
+
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    loop {
+                        // Read until a full request (headers + body) is buffered
+                        let (request, consumed) = loop {
+                            if let Some(parsed) = parse_http_request(&buf) {
+                                break parsed;
+                            }
+                            let mut chunk = [0u8; 4096];
+                            match socket.read(&mut chunk).await {
+                                Ok(0) | Err(_) => return,
+                                Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                            }
+                        };
+                        buf.drain(..consumed);
+
+                        let (status, body) = responder(&request);
+                        let body = body.to_string();
+                        recorded.lock().unwrap().push(request);
+
+                        let http = format!(
+                            "HTTP/1.1 {} Stand-In\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                            status,
+                            body.len(),
+                            body
+                        );
+                        if socket.write_all(http.as_bytes()).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        Self { endpoint, requests }
+    }
+
+    /// Requests received so far, in arrival order
+    pub fn requests(&self) -> Vec<StandInRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Parse one buffered request, returning it with the bytes consumed
+fn parse_http_request(buf: &[u8]) -> Option<(StandInRequest, usize)> {
+    let header_end = buf.windows(4).position(|w| w == b"\r\n\r\n")? + 4;
+    let head = std::str::from_utf8(&buf[..header_end]).ok()?;
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    if buf.len() < header_end + content_length {
+        return None;
+    }
+
+    let body = buf[header_end..header_end + content_length].to_vec();
+    Some((StandInRequest { method, path, headers, body }, header_end + content_length))
+}